@@ -0,0 +1,20 @@
+// The distance fields of baked ops, which are only declared when the
+// profile supports baking (see `ShaderBuilder::load_header`). Each was
+// sampled on a grid that spans the box from `lower` to `upper`.
+layout (binding = BAKE_TEXTURE_UNIT) uniform sampler3D baked[BAKE_CAPACITY];
+
+float sdf_baked(in sampler3D volume, in vec3 p, in vec3 lower, in vec3 upper)
+{
+    // The first and last grid points lie on the faces of the box, while
+    // texels are sampled at their centers.
+    vec3 size = vec3(textureSize(volume, 0));
+    vec3 c = clamp(p, lower, upper);
+    vec3 uvw = ((c - lower) / (upper - lower) * (size - 1.0) + 0.5) / size;
+    float d = texture(volume, uvw).r;
+
+    // The surface lies inside of the box, so the distance to a point
+    // outside of it is at least the distance to the box, combined with
+    // the distance from there to the surface.
+    vec3 q = p - c;
+    return dot(q, q) > 0.0 ? sqrt(dot(q, q) + max(d, 0.0) * max(d, 0.0)) : d;
+}
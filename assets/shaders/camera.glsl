@@ -0,0 +1,34 @@
+// Generates the primary ray for the current pixel (plus a sub-pixel
+// `offset`) from the preview's virtual camera.
+ray generate_ray(in vec2 offset)
+{
+    // uv-coordinates in the range [-1..1]
+    vec2 uv = ((u_picking || u_probing ? u_pick_uv : vs_texcoord) + offset) * 2.0 - 1.0;
+
+    const float pi = 3.14159265359;
+    float fovx = pi * u_fov / 360.0;
+    float ulen = tan(fovx);
+    float vlen = ulen / u_aspect;
+
+    vec3 camera_up = u_camera_up;
+    vec2 cam_uv = uv;
+    vec3 camera_right = normalize(cross(camera_up, u_camera_front));
+
+    // Orthographic rays all travel in the same direction, starting
+    // from a plane perpendicular to the camera.
+    if (u_orthographic)
+    {
+        vec3 ortho_up = cross(u_camera_front, camera_right);
+        vec3 ro = u_camera_position
+                + camera_right * cam_uv.x * u_ortho_scale
+                + ortho_up * cam_uv.y * u_ortho_scale / u_aspect;
+        return ray(ro, u_camera_front);
+    }
+
+    vec3 pixel = u_camera_position + u_camera_front + camera_right * cam_uv.x * ulen + camera_up * cam_uv.y * vlen;
+
+    vec3 ro = u_camera_position;
+    vec3 rd = normalize(pixel - u_camera_position);
+
+    return ray(ro, rd);
+}
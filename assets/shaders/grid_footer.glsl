@@ -0,0 +1,19 @@
+}
+
+void main()
+{
+    uvec3 cell = gl_GlobalInvocationID;
+    if (any(greaterThanEqual(cell, u_grid_resolution)))
+    {
+        return;
+    }
+
+    // Grids with a single point along an axis sample the box's minimum.
+    vec3 t = vec3(cell) / vec3(max(u_grid_resolution - 1u, uvec3(1u)));
+    vec3 p = mix(u_grid_min, u_grid_max, t);
+
+    uint index = cell.x + u_grid_resolution.x * (cell.y + u_grid_resolution.y * cell.z);
+    vec2 hit = map(p);
+    distances[index] = hit.y;
+    ids[index] = hit.x;
+}
@@ -0,0 +1,27 @@
+// The `#version` directive and the declaration of the `params` array
+// are prepended by the application. Grid evaluation requires shader
+// storage buffers, so it's only available from GLSL 4.30 onward.
+
+layout (local_size_x = 4, local_size_y = 4, local_size_z = 4) in;
+
+// The box that the grid spans, and the number of points along each
+// of its axes, where the first and last points lie on the box's faces.
+uniform vec3 u_grid_min;
+uniform vec3 u_grid_max;
+uniform uvec3 u_grid_resolution;
+
+// The distance at each point of the grid, where the x-coordinate
+// varies fastest and the z-coordinate slowest.
+layout (std430, binding = 1) buffer distances_block
+{
+    float distances[];
+};
+
+// The ID of the object that each distance belongs to, in the same
+// order as the distances.
+layout (std430, binding = 2) buffer ids_block
+{
+    float ids[];
+};
+
+#include "map_header.glsl"
@@ -0,0 +1,12 @@
+// The code that comes right before the generated body of the `map(..)`
+// function, which is shared by every kind of shader that evaluates the
+// shader graph.
+
+#include "sdf.glsl"
+
+// The cone-tracing scale, which is set by the render op.
+float cone_scale = 0.0;
+
+vec2 map(in vec3 p)
+{
+    // start of generated code
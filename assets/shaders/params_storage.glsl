@@ -0,0 +1,7 @@
+// The SSBO that will contain a parameter vector for each op in
+// the graph. Note that according to the spec, there can only be
+// one array of variable size per SSBO.
+layout (std430, binding = 0) buffer params_block
+{
+    vec4 params[];
+};
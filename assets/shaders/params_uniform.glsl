@@ -0,0 +1,8 @@
+// Without SSBOs (i.e. before GLSL 4.30, or on GLSL ES), the parameter
+// vectors live in a uniform buffer of fixed size instead. Binding layout qualifiers
+// require GLSL 4.20, so the block's binding point is assigned by the
+// application after the program is linked.
+layout (std140) uniform params_block
+{
+    vec4 params[PARAMETER_CAPACITY];
+};
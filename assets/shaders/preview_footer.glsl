@@ -0,0 +1,189 @@
+}
+
+#include "raymarch.glsl"
+
+#include "shading.glsl"
+
+#include "camera.glsl"
+
+const uint GIZMOS_NONE = 0u;
+const uint GIZMOS_AXES = 1u;
+const uint GIZMOS_AXES_AND_GRID = 2u;
+
+// Returns the distance between the ray `r` and the segment that
+// runs from the origin along `axis` (with length `len`), as well
+// as the distance along the ray to the closest point.
+vec2 ray_axis_distance(in ray r, in vec3 axis, float len)
+{
+    float b = dot(r.d, axis);
+    float d = dot(r.d, r.o);
+    float e = dot(axis, r.o);
+    float denom = 1.0 - b * b;
+
+    float s = denom > 0.0001 ? (e - b * d) / denom : 0.0;
+    s = clamp(s, 0.0, len);
+    float t = max(dot(axis * s - r.o, r.d), 0.0);
+
+    return vec2(length(r.o + r.d * t - axis * s), t);
+}
+
+// Draws a world-space axis tripod and (optionally) a floor grid on
+// top of `color`, respecting the depth of the surface that was hit.
+vec3 draw_gizmos(in ray r, in result res, in vec3 color)
+{
+    float depth = res.id >= 0.0 ? res.total_distance : u_max_trace_distance;
+
+    if (u_gizmos == GIZMOS_AXES_AND_GRID && abs(r.d.y) > 0.0001)
+    {
+        float t = -r.o.y / r.d.y;
+        if (t > 0.0 && t < depth)
+        {
+            vec2 p = (r.o + r.d * t).xz;
+            vec2 grid = abs(fract(p - 0.5) - 0.5) / fwidth(p);
+            float line = 1.0 - min(min(grid.x, grid.y), 1.0);
+            float fade = exp(-t * 0.1);
+            color = mix(color, vec3(0.5), line * fade * 0.5);
+        }
+    }
+
+    const vec3 axes[3] = vec3[3](vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0));
+    for (int i = 0; i < 3; ++i)
+    {
+        vec2 hit = ray_axis_distance(r, axes[i], 1.0);
+
+        // Keep the lines roughly the same width on screen.
+        float width = 0.0025 * hit.y;
+        if (hit.x < width && hit.y < depth)
+        {
+            color = axes[i];
+        }
+    }
+    return color;
+}
+
+// Returns the offset of the `i`th sample within a pixel, in units
+// of pixels: 2x uses a diagonal pattern and 4x uses a rotated grid.
+vec2 sample_offset(in uint i)
+{
+    if (u_samples == 2u)
+    {
+        return i == 0u ? vec2(-0.25, -0.25) : vec2(0.25, 0.25);
+    }
+    else if (u_samples == 4u)
+    {
+        const vec2 rotated_grid[4] = vec2[](vec2(0.125, 0.375),
+                                           vec2(0.375, -0.125),
+                                           vec2(-0.125, -0.375),
+                                           vec2(-0.375, 0.125));
+        return rotated_grid[i];
+    }
+    return vec2(0.0);
+}
+
+vec3 draw_sample(in ray r, in result res)
+{
+    const vec3 background = vec3(0.0);
+    vec3 color = background;
+    // Rays that hit a surface carry the (non-negative) ID of the object
+    // that they hit: for now, every object is shaded the same way.
+    if (res.id >= 0.0)
+    {
+        color = shading(r, res);
+        if (u_isolines)
+        {
+            color = draw_isolines(r, res, color);
+        }
+    }
+    else
+    {
+        // Blend in the surface that the ray narrowly missed, which
+        // anti-aliases silhouette edges when cone tracing.
+        if (res.coverage > 0.0)
+        {
+            result edge = res;
+            edge.id = 0.0;
+            edge.total_distance = res.closest_distance;
+            color = mix(background, shading(r, edge), res.coverage);
+        }
+    }
+
+    if (u_gizmos != GIZMOS_NONE)
+    {
+        color = draw_gizmos(r, res, color);
+    }
+    return color;
+}
+
+void main()
+{
+    // The size of a single pixel in texture coordinates.
+    vec2 pixel = fwidth(vs_texcoord);
+
+    // Picking and probing cast a single ray, so cone tracing is
+    // disabled for them.
+    const float pi = 3.14159265359;
+    pixel_angle = u_picking || u_probing ? 0.0 : 2.0 * tan(pi * u_fov / 360.0) * pixel.x;
+
+    // Picking and probing only march the ray through the center of the
+    // pixel.
+    if (u_picking || u_probing)
+    {
+        ray r = generate_ray(vec2(0.0));
+        result res = raymarch(r);
+
+        // When picking, output the ID of the object that was hit (or -1.0
+        // if the ray missed).
+        if (u_picking)
+        {
+            o_color = vec4(res.id, 0.0, 0.0, 1.0);
+            return;
+        }
+
+        // When probing, the first pixel holds the hit position and the
+        // distance along the ray (or -1.0 if the ray missed), while the
+        // second pixel holds the surface normal and the step count.
+        vec3 hit = r.o + r.d * res.total_distance;
+        bool missed = res.id < 0.0;
+        if (gl_FragCoord.x < 1.0)
+        {
+            o_color = vec4(hit, missed ? -1.0 : res.total_distance);
+        }
+        else
+        {
+            vec3 n = missed ? vec3(0.0) : calculate_normal(hit);
+            o_color = vec4(n, float(res.total_steps));
+        }
+        return;
+    }
+
+    // Supersample the pixel by averaging several jittered rays.
+    // When accumulating over several frames, also jitter each ray
+    // within its pixel, which anti-aliases the result for free.
+    vec2 jitter = vec2(0.0);
+    if (u_jitter)
+    {
+        vec2 seed = gl_FragCoord.xy + float(u_frame) * vec2(2.39, 4.87);
+        jitter = vec2(hash(seed), hash(seed.yx)) - 0.5;
+    }
+
+    vec3 color = vec3(0.0);
+    for (uint i = 0u; i < u_samples; ++i)
+    {
+        ray r_sample = generate_ray((sample_offset(i) + jitter) * pixel);
+        result res = raymarch(r_sample);
+        color += draw_sample(r_sample, res);
+
+        // Only the steps of the first sample are recorded, which is the ray
+        // through the center of the pixel unless the pixel is supersampled
+        // or jittered. The step image is only declared when the profile
+        // supports it (see `step_image.glsl`).
+#ifdef STEP_IMAGE_UNIT
+        if (u_recording_steps && i == 0u)
+        {
+            imageStore(u_step_image, ivec2(gl_FragCoord.xy), uvec4(uint(res.total_steps)));
+        }
+#endif
+    }
+
+    o_color = vec4(color / float(u_samples), 1.0);
+}
@@ -0,0 +1,66 @@
+// The `#version` directive and the declaration of the `params` array
+// are prepended by the application, depending on the GLSL version that
+// it targets. Uniforms are set by the application before every draw (or
+// are zero by default), since GLSL ES doesn't allow initializers on them.
+
+INTERSTAGE_LOCATION(0) in vec2 vs_texcoord;
+
+layout (location = 0) out vec4 o_color;
+
+uniform vec3 u_camera_position;
+uniform vec3 u_camera_front;
+uniform vec3 u_camera_up;
+uniform bool u_orthographic;
+uniform float u_ortho_scale;
+uniform uint u_shading;
+uniform float u_time;
+uniform float u_aspect;
+uniform uint u_gizmos;
+uniform float u_fov;
+uniform float u_max_trace_distance;
+uniform bool u_picking;
+uniform bool u_probing;
+uniform bool u_isolines;
+uniform uint u_samples;
+uniform bool u_jitter;
+uniform uint u_frame;
+uniform float u_steps_min;
+uniform float u_steps_max;
+uniform bool u_steps_log;
+uniform vec2 u_pick_uv;
+
+const int MAX_STEPS = 256;
+const float MIN_HIT_DISTANCE = 0.001;
+
+struct ray
+{
+    vec3 o;
+    vec3 d;
+};
+
+struct result
+{
+    float id;
+    float total_distance;
+    int total_steps;
+
+    // The fraction of the pixel's cone that is covered by the surface,
+    // and the distance along the ray where the surface came closest
+    float coverage;
+    float closest_distance;
+};
+
+mat3 lookat(in vec3 t, in vec3 p)
+{
+    vec3 k = normalize(t - p);
+    vec3 i = cross(k, vec3(0.0, 1.0, 0.0));
+    vec3 j = cross(i, k);
+    return mat3(i, j, k);
+}
+
+// The angle that a single pixel subtends, which (together with the
+// render op's `cone_scale`) determines how close a ray must get to
+// the surface to count as a hit.
+float pixel_angle = 0.0;
+
+#include "map_header.glsl"
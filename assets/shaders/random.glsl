@@ -0,0 +1,20 @@
+// Returns a pseudo-random number in the range [0..1).
+float hash(in vec2 seed)
+{
+    return fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// Returns a random, cosine-weighted direction in the hemisphere
+// around `n`.
+vec3 cosine_direction(in vec3 n, in vec2 seed)
+{
+    float u = hash(seed);
+    float v = hash(seed + vec2(17.0, 59.0));
+    float a = 6.2831853 * v;
+    float r = sqrt(u);
+
+    vec3 up = abs(n.y) < 0.9 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 t = normalize(cross(n, up));
+    vec3 b = cross(n, t);
+    return normalize(t * r * cos(a) + b * r * sin(a) + n * sqrt(1.0 - u));
+}
@@ -0,0 +1,83 @@
+// Sphere tracing against `map(..)`, along with the surface normals and
+// ambient occlusion that shading relies on.
+
+vec3 calculate_normal(in vec3 p)
+{
+    const vec3 e = vec3(0.001, 0.0, 0.0);
+    vec3 n = vec3(map(p + e.xyy).y - map(p - e.xyy).y,	// Gradient x
+                  map(p + e.yxy).y - map(p - e.yxy).y,	// Gradient y
+                  map(p + e.yyx).y - map(p - e.yyx).y); // Gradient z
+
+    return normalize(n);
+}
+
+#include "random.glsl"
+
+float ambient_occlusion(in vec3 p, in vec3 n)
+{
+    // When accumulating over several frames, march along a
+    // different random direction each frame.
+    if (u_jitter)
+    {
+        n = cosine_direction(n, gl_FragCoord.xy + float(u_frame) * vec2(7.23, 3.17));
+    }
+
+    const float attenuation = 0.5;
+    float ao;
+    float accum = 0.0;
+    float scale = 1.0;
+    for(int step = 0; step < 5; step++)
+    {
+        float hr = 0.01 + 0.02 * float(step * step);
+        vec3 aopos = n * hr + p;
+
+        float dist = map(aopos).y;
+        ao = -(dist - hr);
+        accum += ao * scale;
+        scale *= attenuation;
+    }
+    ao = 1.0 - clamp(accum, 0.0, 1.0);
+
+    return ao;
+}
+
+result raymarch(in ray r)
+{
+    result res = result(-1.0, 0.0, 0, 0.0, 0.0);
+    float closest = 1e10;
+    for (int i = 0; i < MAX_STEPS; ++i)
+    {
+        vec3 p = r.o + r.d * res.total_distance;
+        vec2 hit_info = map(p);
+        float hit_id = hit_info.x;
+        float hit_dist = hit_info.y;
+
+        // The radius of the pixel's cone at the current distance,
+        // which is zero unless cone tracing is enabled.
+        float radius = cone_scale * pixel_angle * res.total_distance;
+        if (radius > 0.0 && hit_dist / radius < closest)
+        {
+            closest = hit_dist / radius;
+            res.closest_distance = res.total_distance;
+        }
+        res.total_distance += hit_dist;
+
+        if (hit_dist < max(MIN_HIT_DISTANCE, radius))
+        {
+            res.id = hit_id;
+            break;
+        }
+
+        if(res.total_distance > u_max_trace_distance)
+        {
+            res.total_distance = 0.0;
+            break;
+        }
+
+        res.total_steps++;
+    }
+
+    // Rays that narrowly missed the surface partially cover it.
+    res.coverage = res.id >= 0.0 ? 1.0 : 1.0 - clamp(closest - 1.0, 0.0, 1.0);
+    return res;
+}
@@ -0,0 +1,7 @@
+// Returns the signed distance from `p` to a rectangle that is centered at
+// the origin with half-extents `b` and corner radius `r`.
+float rounded_rectangle(in vec2 p, in vec2 b, in float r)
+{
+    vec2 q = abs(p) - b + r;
+    return length(max(q, 0.0)) + min(max(q.x, q.y), 0.0) - r;
+}
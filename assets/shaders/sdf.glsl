@@ -0,0 +1,63 @@
+// Domain operators, boolean operators, and signed-distance functions for
+// each of the primitives that ops can generate.
+
+vec3 domain_twist(in vec3 p, float t)
+{
+    float c = cos(t * p.y);
+    float s = sin(t * p.y);
+    mat2  m = mat2(c, -s, s, c);
+    vec3  q = vec3(m * p.xz, p.y);
+    return q;
+}
+
+vec3 domain_bend(in vec3 p, float t)
+{
+    float c = cos(t * p.y);
+    float s = sin(t * p.y);
+    mat2 m = mat2(c, -s, s, c);
+    vec3 q = vec3(m * p.xy, p.z);
+    return q;
+}
+
+float op_union(float a, float b)
+{
+    return min(a, b);
+}
+
+float op_subtract(float a, float b)
+{
+    return max(-a, b);
+}
+
+float op_intersect(float a, float b)
+{
+    return max(a, b);
+}
+
+float op_smooth_min(float a, float b, float k)
+{
+    float h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}
+
+float sdf_sphere(in vec3 p, in vec3 center, float radius)
+{
+    return length(center - p) - radius;
+}
+
+float sdf_box(in vec3 p, in vec3 b)
+{
+  vec3 d = abs(p) - b;
+  return min(max(d.x, max(d.y, d.z)), 0.0) + length(max(d, 0.0));
+}
+
+float sdf_plane(in vec3 p, in float h)
+{
+    return p.y - h;
+}
+
+float sdf_torus(in vec3 p, in vec2 t)
+{
+    vec2 d = vec2(length(p.xz)- t.x, p.y);
+    return length(d) - t.y;
+}
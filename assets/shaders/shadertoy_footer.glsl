@@ -0,0 +1,30 @@
+
+// The entry point that Shadertoy calls for every pixel, which sets the
+// inputs of the preview's `main()` (renamed to `preview_main()` when the
+// shader is exported) from Shadertoy's own inputs. Dragging the mouse
+// orbits the camera around the point that it looks at.
+void mainImage(out vec4 fragColor, in vec2 fragCoord)
+{
+    vs_texcoord = fragCoord / iResolution.xy;
+    u_time = iTime;
+    u_aspect = iResolution.x / iResolution.y;
+    u_frame = uint(iFrame);
+
+    const float pi = 3.14159265359;
+    vec3 front = normalize(CAMERA_FOCUS - CAMERA_POSITION);
+    float yaw = atan(front.z, front.x);
+    float pitch = asin(front.y);
+    if (iMouse.z > 0.0)
+    {
+        vec2 offset = (iMouse.xy - abs(iMouse.zw)) / iResolution.xy;
+        yaw += offset.x * 2.0 * pi;
+        pitch = clamp(pitch + offset.y * pi, -0.49 * pi, 0.49 * pi);
+    }
+
+    u_camera_front = vec3(cos(yaw) * cos(pitch), sin(pitch), sin(yaw) * cos(pitch));
+    u_camera_position = CAMERA_FOCUS - u_camera_front * distance(CAMERA_FOCUS, CAMERA_POSITION);
+    u_camera_up = vec3(0.0, 1.0, 0.0);
+
+    preview_main();
+    fragColor = o_color;
+}
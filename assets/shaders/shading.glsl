@@ -0,0 +1,122 @@
+// Shading models for surfaces that were hit by a ray, which rely on
+// `map(..)`, `raymarch(..)`, and `calculate_normal(..)`.
+
+const uint SHADING_DEPTH = 0u;
+const uint SHADING_STEPS = 1u;
+const uint SHADING_AMBIENT_OCCLUSION = 2u;
+const uint SHADING_NORMALS = 3u;
+const uint SHADING_DIFFUSE = 4u;
+const uint SHADING_PATH_TRACED = 5u;
+
+const int PATH_BOUNCES = 3;
+const vec3 PATH_ALBEDO = vec3(0.7);
+
+// Returns the light arriving from the sky along direction `d`.
+vec3 sky(in vec3 d)
+{
+    return mix(vec3(0.3, 0.3, 0.35), vec3(1.0), clamp(d.y * 0.5 + 0.5, 0.0, 1.0));
+}
+
+// Traces a single light path that starts at the surface point `p`
+// with normal `n` and bounces diffusely around the scene until it
+// escapes to the sky.
+vec3 path_trace(in vec3 p, in vec3 n, in vec2 seed)
+{
+    vec3 throughput = vec3(1.0);
+    for (int bounce = 0; bounce < PATH_BOUNCES; ++bounce)
+    {
+        vec3 d = cosine_direction(n, seed + float(bounce) * vec2(31.7, 11.3));
+        throughput *= PATH_ALBEDO;
+
+        ray bounced = ray(p + n * MIN_HIT_DISTANCE * 10.0, d);
+        result res = raymarch(bounced);
+        if (res.id < 0.0)
+        {
+            return throughput * sky(d);
+        }
+        p = bounced.o + bounced.d * res.total_distance;
+        n = calculate_normal(p);
+    }
+    return vec3(0.0);
+}
+
+vec3 shading(in ray r, in result res)
+{
+    vec3 hit = r.o + r.d * res.total_distance;
+    if (u_shading == SHADING_DEPTH)
+    {
+        float depth = hit.z / u_max_trace_distance;
+        return vec3(pow(depth, 0.5));
+    }
+    else if (u_shading == SHADING_STEPS)
+    {
+        // Map the step count into the range set by the user,
+        // optionally on a logarithmic scale.
+        float steps = max(float(res.total_steps) - u_steps_min, 0.0);
+        float range = max(u_steps_max - u_steps_min, 1.0);
+        float pct = u_steps_log ? log(1.0 + steps) / log(1.0 + range) : steps / range;
+        pct = clamp(pct, 0.0, 1.0);
+        const vec3 c_a = vec3(0.0, 0.0, 1.0);
+        const vec3 c_b = vec3(0.0, 1.0, 1.0);
+        const vec3 c_c = vec3(1.0, 1.0, 0.0);
+        const vec3 c_d = vec3(1.0, 0.0, 0.0);
+
+        const float a = 0.00;
+        const float b = 0.33;
+        const float c = 0.66;
+        const float d = 1.00;
+
+        vec3 color = mix(c_a, c_b, smoothstep(a, b, pct));
+        color = mix(color, c_c, smoothstep(b, c, pct));
+        color = mix(color, c_d, smoothstep(c, d, pct));
+        return color;
+    }
+    else
+    {
+        // calculate normals
+        vec3 n = calculate_normal(hit);
+        if (u_shading == SHADING_AMBIENT_OCCLUSION)
+        {
+            float ao = ambient_occlusion(hit, n);
+            return vec3(pow(ao, 3.0));
+        }
+        else if (u_shading == SHADING_NORMALS)
+        {
+            return n * 0.5 + 0.5;
+        }
+        else if (u_shading == SHADING_PATH_TRACED)
+        {
+            vec2 seed = gl_FragCoord.xy + float(u_frame) * vec2(5.71, 9.13);
+            return path_trace(hit, n, seed);
+        }
+        else
+        {
+            const vec3 l = vec3(0.0, 2.0, 3.0);
+            vec3 to_light = normalize(l - hit);
+            float d = max(0.0, dot(n, to_light));
+            float ao = ambient_occlusion(hit, n);
+            return vec3(d * pow(ao, 3.0));
+        }
+    }
+}
+
+const float ISOLINE_OFFSET = 0.25;
+const float ISOLINE_SPACING = 0.02;
+
+vec3 draw_isolines(in ray r, in result res, in vec3 color)
+{
+    // Sample the field a fixed distance above the surface: for an
+    // exact distance field, this always returns `ISOLINE_OFFSET`,
+    // which falls halfway between two bands, so any lines that
+    // appear mark regions where the field is poorly behaved.
+    vec3 hit = r.o + r.d * res.total_distance;
+    vec3 n = calculate_normal(hit);
+    float d = map(hit + n * ISOLINE_OFFSET).y / ISOLINE_SPACING;
+
+    float stripe = mod(floor(d), 2.0);
+    float band = abs(fract(d) - 0.5);
+    float line = smoothstep(0.4, 0.45, band);
+
+    color *= mix(0.8, 1.0, stripe);
+    return mix(color, vec3(1.0, 0.5, 0.0), line);
+}
@@ -0,0 +1,6 @@
+// The number of steps that the ray through each pixel of the preview took,
+// which is only declared when the profile supports building histograms of
+// them (see `ShaderBuilder::load_header`), and only written while
+// `u_recording_steps` is set (see `steps.rs`).
+layout (binding = STEP_IMAGE_UNIT, r32ui) uniform writeonly uimage2D u_step_image;
+uniform bool u_recording_steps;
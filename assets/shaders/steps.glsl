@@ -0,0 +1,61 @@
+// The `#version` directive and the defines below are prepended by the
+// application (see `steps.rs`). Reduces the number of steps that the ray
+// through each pixel of the preview took into a histogram, along with
+// their sum and maximum.
+
+layout (local_size_x = 16, local_size_y = 16) in;
+
+layout (binding = STEP_IMAGE_UNIT, r32ui) uniform readonly uimage2D u_step_image;
+
+// The number of pixels in the image, which may be smaller than the
+// number of invocations.
+uniform uvec2 u_resolution;
+
+layout (std430, binding = STEP_HISTOGRAM_BINDING) buffer histogram_block
+{
+    uint total_steps;
+    uint max_steps;
+    uint bins[STEP_HISTOGRAM_BINS];
+};
+
+shared uint local_bins[STEP_HISTOGRAM_BINS];
+shared uint local_total;
+shared uint local_max;
+
+void main()
+{
+    // Each work group builds its own histogram in shared memory first,
+    // which keeps most of the atomics off of the buffer.
+    uint index = gl_LocalInvocationIndex;
+    if (index < uint(STEP_HISTOGRAM_BINS))
+    {
+        local_bins[index] = 0u;
+    }
+    if (index == 0u)
+    {
+        local_total = 0u;
+        local_max = 0u;
+    }
+    barrier();
+
+    if (all(lessThan(gl_GlobalInvocationID.xy, u_resolution)))
+    {
+        uint steps = imageLoad(u_step_image, ivec2(gl_GlobalInvocationID.xy)).r;
+        uint bin = min(steps * uint(STEP_HISTOGRAM_BINS) / uint(STEP_HISTOGRAM_RANGE),
+                       uint(STEP_HISTOGRAM_BINS - 1));
+        atomicAdd(local_bins[bin], 1u);
+        atomicAdd(local_total, steps);
+        atomicMax(local_max, steps);
+    }
+    barrier();
+
+    if (index < uint(STEP_HISTOGRAM_BINS))
+    {
+        atomicAdd(bins[index], local_bins[index]);
+    }
+    if (index == 0u)
+    {
+        atomicAdd(total_steps, local_total);
+        atomicMax(max_steps, local_max);
+    }
+}
@@ -0,0 +1,29 @@
+use constants;
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that holds the directory that assets (shaders,
+/// fonts, icons, op definitions, and presets) are loaded from, if it isn't
+/// the default one. It is set from the command line (`--assets=<dir>`)
+/// before any assets are loaded, but it can also be set by hand.
+const ASSETS_VARIABLE: &str = "SDFPERF_ASSETS";
+
+/// Loads assets from the directory at `path` from now on. This should be
+/// called at startup, before any other threads are spawned.
+pub fn set_directory(path: &Path) {
+    env::set_var(ASSETS_VARIABLE, path);
+}
+
+/// Returns the directory that assets are loaded from.
+pub fn get_directory() -> PathBuf {
+    env::var_os(ASSETS_VARIABLE)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(constants::ASSETS_PATH))
+}
+
+/// Returns the path of the asset at `relative`, which is relative to the
+/// assets directory (i.e. `shaders` or `fonts/font_8x8.png`).
+pub fn get_path(relative: &str) -> PathBuf {
+    get_directory().join(relative)
+}
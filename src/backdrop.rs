@@ -0,0 +1,130 @@
+use cgmath::Vector2;
+
+use bounds::Rect;
+use color::Color;
+use constants;
+use interaction::InteractionState;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Describes what part of a backdrop the user is currently
+/// dragging.
+#[derive(Copy, Clone, PartialEq)]
+pub enum BackdropDrag {
+    /// The backdrop (and any ops that sit on top of it) is
+    /// being moved
+    Move,
+
+    /// The backdrop is being resized from its lower-right corner
+    Resize,
+}
+
+/// A resizable, colored rectangle that is drawn behind a group
+/// of ops. Dragging a backdrop by its title bar moves all of the
+/// ops that are contained within it.
+pub struct Backdrop {
+    /// The bounding box of the entire backdrop
+    pub bounds_body: Rect,
+
+    /// The bounding box of the backdrop's title bar
+    pub bounds_title: Rect,
+
+    /// The bounding box of the backdrop's resize handle
+    pub bounds_handle: Rect,
+
+    /// The title that will be drawn in the backdrop's title bar
+    pub title: String,
+
+    /// The index into `constants::BACKDROP_COLORS` of the color
+    /// that will be used to draw this backdrop
+    pub color_index: usize,
+
+    /// The current interaction state of the backdrop
+    pub state: InteractionState,
+}
+
+impl Backdrop {
+    pub fn new(position: Vector2<f32>, size: Vector2<f32>) -> Backdrop {
+        // Increment counter.
+        let count = COUNTER.fetch_add(1, Ordering::SeqCst);
+
+        let mut backdrop = Backdrop {
+            bounds_body: Rect::new(position, size),
+            bounds_title: Rect::default(),
+            bounds_handle: Rect::default(),
+            title: format!("backdrop_{}", count),
+            color_index: 0,
+            state: InteractionState::Deselected,
+        };
+        backdrop.rebuild_bounds();
+        backdrop
+    }
+
+    /// Returns the color that this backdrop will be drawn with.
+    pub fn get_color(&self) -> Color {
+        Color::from_hex(
+            constants::BACKDROP_COLORS[self.color_index % constants::BACKDROP_COLORS.len()],
+            1.0,
+        )
+    }
+
+    /// Switches to the next color in the backdrop palette.
+    pub fn cycle_color(&mut self) {
+        self.color_index = (self.color_index + 1) % constants::BACKDROP_COLORS.len();
+    }
+
+    /// Returns the part of the backdrop (if any) that the user can
+    /// start dragging from `point`.
+    pub fn get_drag_region(&self, point: &Vector2<f32>) -> Option<BackdropDrag> {
+        if self.bounds_handle.inside(point) {
+            return Some(BackdropDrag::Resize);
+        }
+        if self.bounds_title.inside(point) {
+            return Some(BackdropDrag::Move);
+        }
+        None
+    }
+
+    /// Returns `true` if `other` lies entirely inside of this backdrop
+    /// and `false` otherwise.
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.bounds_body.contains(other)
+    }
+
+    /// Translates the backdrop in the network editor by an amount
+    /// `offset`.
+    pub fn translate(&mut self, offset: &Vector2<f32>) {
+        self.bounds_body.translate(offset);
+        self.bounds_title.translate(offset);
+        self.bounds_handle.translate(offset);
+    }
+
+    /// Resizes the backdrop by an amount `delta`, keeping the upper-left
+    /// corner fixed.
+    pub fn resize(&mut self, delta: &Vector2<f32>) {
+        let mut size = self.bounds_body.get_size() + delta;
+        size.x = size.x.max(constants::BACKDROP_MIN_SIZE.x);
+        size.y = size.y.max(constants::BACKDROP_MIN_SIZE.y);
+
+        self.bounds_body.set_size(&size);
+        self.rebuild_bounds();
+    }
+
+    /// Rebuilds the title bar and resize handle bounding boxes
+    /// from the backdrop's body.
+    fn rebuild_bounds(&mut self) {
+        let upper_left = *self.bounds_body.get_upper_left();
+        let size = *self.bounds_body.get_size();
+
+        self.bounds_title = Rect::new(
+            upper_left,
+            Vector2::new(size.x, constants::BACKDROP_TITLE_HEIGHT),
+        );
+        self.bounds_handle = Rect::new(
+            upper_left + size - constants::BACKDROP_HANDLE_SIZE,
+            constants::BACKDROP_HANDLE_SIZE,
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use cgmath::{Matrix4, Vector4};
+
+use error::Error;
+
+use std::ops::Range;
+
+/// A handle to a buffer of vertex (or instance) data that was created by
+/// a `Backend`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BufferHandle(pub u32);
+
+/// A handle to a description of how vertex data is laid out across one
+/// or more buffers (i.e. a vertex array object).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LayoutHandle(pub u32);
+
+/// A handle to a shader program that was created by a `Backend`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProgramHandle(pub u32);
+
+/// A handle to a texture that can be sampled by a shader program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(pub u32);
+
+/// How often the contents of a buffer are expected to change.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BufferUsage {
+    /// The contents are uploaded once, when the buffer is created
+    Static,
+
+    /// The contents are replaced (in part) every now and then, but the
+    /// buffer is never resized
+    Dynamic,
+
+    /// The contents are replaced several times per frame, and the buffer
+    /// can be resized (see `Backend::resize_buffer`)
+    Stream,
+}
+
+/// Whether the attributes at a binding point advance once per vertex or
+/// once per instance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StepMode {
+    Vertex,
+    Instance,
+}
+
+/// A single vertex attribute, which is made up of `components` floats
+/// that start `offset` floats into each element of the buffer that is
+/// bound to `binding`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub components: u32,
+    pub offset: u32,
+    pub binding: u32,
+}
+
+/// How the vertices of a draw call are assembled into primitives.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Topology {
+    Triangles,
+    TriangleStrip,
+}
+
+/// The value of a uniform, i.e. an input to a shader program that is the
+/// same for every vertex and fragment of a draw call.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Vec4(Vector4<f32>),
+    Mat4(Matrix4<f32>),
+}
+
+/// The operations that the network editor's renderer needs from a graphics
+/// API: creating buffers and shader programs, binding textures, and issuing
+/// draw calls. The renderer only talks to the GPU through this trait, so
+/// that other APIs can be supported next to OpenGL (see `GlBackend`).
+///
+/// Resources are referred to by handles, which are only meaningful to the
+/// backend that created them. Textures are still created by `Texture`, as
+/// is everything that the preview draws (which is specific to OpenGL 4.3).
+pub trait Backend {
+    /// Sets up the state that every draw call relies on, i.e. alpha
+    /// blending and the conversion of linear colors to sRGB.
+    fn init_state(&self);
+
+    /// Creates a buffer that holds `capacity` floats, whose initial
+    /// contents are `data` (if any).
+    fn create_buffer(
+        &self,
+        capacity: usize,
+        data: Option<&[f32]>,
+        usage: BufferUsage,
+    ) -> BufferHandle;
+
+    /// Replaces the contents of `buffer` with `data`, starting at its first
+    /// element. `data` must fit in the buffer.
+    fn update_buffer(&self, buffer: BufferHandle, data: &[f32]);
+
+    /// Reallocates a `BufferUsage::Stream` buffer so that it holds
+    /// `capacity` floats. Its previous contents are discarded, without
+    /// waiting on any draw calls that still use them.
+    fn resize_buffer(&self, buffer: BufferHandle, capacity: usize);
+
+    fn delete_buffer(&self, buffer: BufferHandle);
+
+    /// Creates a vertex layout from a set of `attributes`, along with the
+    /// step mode of each binding point that they refer to (by index).
+    fn create_layout(&self, attributes: &[VertexAttribute], bindings: &[StepMode]) -> LayoutHandle;
+
+    /// Sources the elements at `binding` of `layout` from `buffer`, where
+    /// each element is `stride` floats long.
+    fn set_vertex_buffer(
+        &self,
+        layout: LayoutHandle,
+        binding: u32,
+        buffer: BufferHandle,
+        stride: u32,
+    );
+
+    fn delete_layout(&self, layout: LayoutHandle);
+
+    /// Compiles and links a new shader program.
+    fn create_program(&self, vs_src: String, fs_src: String) -> Result<ProgramHandle, Error>;
+
+    /// Uses `program` for subsequent draw calls.
+    fn bind_program(&self, program: ProgramHandle);
+
+    fn unbind_program(&self);
+
+    /// Sets the uniform `name` of `program`. Uniforms that aren't used by
+    /// the program are ignored.
+    fn set_uniform(&self, program: ProgramHandle, name: &str, value: UniformValue);
+
+    fn delete_program(&self, program: ProgramHandle);
+
+    /// Binds `texture` to the texture unit `unit`.
+    fn bind_texture(&self, unit: u32, texture: TextureHandle);
+
+    /// Draws the `vertices` of `layout`, `instances` times.
+    fn draw(&self, layout: LayoutHandle, topology: Topology, vertices: Range<u32>, instances: u32);
+}
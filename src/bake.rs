@@ -0,0 +1,221 @@
+use cgmath::Vector3;
+use gl::{self, types::*};
+use uuid::Uuid;
+
+use constants;
+use graph::Graph;
+use grid::SampleGrid;
+use operator::Op;
+use shader_builder::ShaderBuilder;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_void;
+
+/// An op whose distance field has been baked, as far as the shader builder
+/// is concerned: its code is replaced with a fetch from one of the 3D
+/// textures in `baked[..]` (see `map_header.glsl`).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BakedOp {
+    /// The UUID of the op
+    pub uuid: Uuid,
+
+    /// The index of the texture that holds the op's distance field
+    pub slot: usize,
+
+    /// The corner of the box that the texture spans with the smallest
+    /// coordinates
+    pub min: Vector3<f32>,
+
+    /// The corner of the box that the texture spans with the largest
+    /// coordinates
+    pub max: Vector3<f32>,
+}
+
+impl BakedOp {
+    /// Returns the code that replaces the code of the op, whose name is
+    /// `name`.
+    pub fn get_code(&self, name: &str) -> String {
+        let vec3 = |v: Vector3<f32>| format!("vec3({:?}, {:?}, {:?})", v.x, v.y, v.z);
+        format!(
+            "float {} = sdf_baked(baked[{}], p, {}, {});",
+            name,
+            self.slot,
+            vec3(self.min),
+            vec3(self.max)
+        )
+    }
+}
+
+/// Returns a hash of everything that the distance field of the sub-graph
+/// that ends at the op at `root` depends on, which is its structure (see
+/// `ShaderBuilder::get_signature`) along with the values of its parameters.
+pub fn get_signature<E>(graph: &Graph<Op, E>, root: usize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    ShaderBuilder::get_signature(graph, root).hash(&mut hasher);
+    for node in graph.traverse(root).into_iter().filter_map(|index| graph.get_node(index)) {
+        for value in node.data.params.get_data().iter() {
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// The distance field of a single baked op, sampled on a grid and stored
+/// in a 3D texture with a single channel.
+struct Volume {
+    /// The OpenGL handle to the texture
+    texture: GLuint,
+
+    /// The UUID of the op
+    uuid: Uuid,
+
+    /// The signature (see `get_signature`) of the op's sub-graph when it
+    /// was baked
+    signature: u64,
+
+    /// The corner of the grid with the smallest coordinates
+    min: Vector3<f32>,
+
+    /// The corner of the grid with the largest coordinates
+    max: Vector3<f32>,
+}
+
+impl Volume {
+    fn new(uuid: Uuid, signature: u64, grid: &SampleGrid, distances: &[f32]) -> Volume {
+        let mut texture = 0;
+        unsafe {
+            gl::CreateTextures(gl::TEXTURE_3D, 1, &mut texture);
+            gl::TextureParameteri(texture, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TextureParameteri(texture, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TextureParameteri(texture, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TextureParameteri(texture, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TextureParameteri(texture, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+
+            // The distances are ordered like the texels, with x varying fastest.
+            let (x, y, z) = (
+                grid.resolution.x as GLsizei,
+                grid.resolution.y as GLsizei,
+                grid.resolution.z as GLsizei,
+            );
+            gl::TextureStorage3D(texture, 1, gl::R32F, x, y, z);
+            gl::TextureSubImage3D(
+                texture,
+                0,
+                0,
+                0,
+                0,
+                x,
+                y,
+                z,
+                gl::RED,
+                gl::FLOAT,
+                distances.as_ptr() as *const c_void,
+            );
+        }
+
+        Volume {
+            texture,
+            uuid,
+            signature,
+            min: grid.min,
+            max: grid.max,
+        }
+    }
+}
+
+impl Drop for Volume {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
+
+/// The distance fields of every baked op (see `Op::baked`), at most
+/// `BAKE_CAPACITY` of them. Once an op is baked, the preview and its
+/// thumbnails sample its texture instead of evaluating its sub-graph,
+/// which is much cheaper for large, static parts of a graph. The texture
+/// is only resampled when the sub-graph (or any of its parameters) changes.
+pub struct Bakes {
+    volumes: Vec<Volume>,
+}
+
+impl Bakes {
+    pub fn new() -> Bakes {
+        Bakes {
+            volumes: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the op with `uuid` has been baked, and its
+    /// sub-graph hasn't changed since then (i.e. it still has `signature`).
+    pub fn is_current(&self, uuid: Uuid, signature: u64) -> bool {
+        self.volumes
+            .iter()
+            .any(|volume| volume.uuid == uuid && volume.signature == signature)
+    }
+
+    /// Stores the `distances` that the distance field of the op with `uuid`
+    /// was sampled at, at every point of `grid` (see `SampleGrid::evaluate`),
+    /// replacing any earlier bake of the same op.
+    pub fn insert(&mut self, uuid: Uuid, signature: u64, grid: &SampleGrid, distances: &[f32]) {
+        let volume = Volume::new(uuid, signature, grid, distances);
+        match self.volumes.iter().position(|volume| volume.uuid == uuid) {
+            Some(slot) => self.volumes[slot] = volume,
+            None => self.volumes.push(volume),
+        }
+    }
+
+    /// Forgets the bake of the op with `uuid` (if there is one).
+    pub fn remove(&mut self, uuid: Uuid) {
+        self.volumes.retain(|volume| volume.uuid != uuid);
+    }
+
+    /// Forgets the bakes of any ops that aren't in `uuids` (i.e. that were
+    /// deleted or are no longer baked), returning `true` if there were any.
+    pub fn retain(&mut self, uuids: &[Uuid]) -> bool {
+        let count = self.volumes.len();
+        self.volumes.retain(|volume| uuids.contains(&volume.uuid));
+        self.volumes.len() != count
+    }
+
+    /// Returns every baked op, along with the slot that its texture is
+    /// bound to (see `bind`).
+    pub fn get_ops(&self) -> Vec<BakedOp> {
+        self.volumes
+            .iter()
+            .enumerate()
+            .map(|(slot, volume)| BakedOp {
+                uuid: volume.uuid,
+                slot,
+                min: volume.min,
+                max: volume.max,
+            })
+            .collect()
+    }
+
+    /// Binds the texture in each slot to the texture unit `BAKE_TEXTURE_UNIT`
+    /// plus the slot.
+    pub fn bind(&self) {
+        for (slot, volume) in self.volumes.iter().enumerate() {
+            unsafe {
+                gl::BindTextureUnit(constants::BAKE_TEXTURE_UNIT + slot as GLuint, volume.texture);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_baked_code() {
+    let op = BakedOp {
+        uuid: Uuid::nil(),
+        slot: 2,
+        min: Vector3::new(-1.0, -0.5, -1.0),
+        max: Vector3::new(1.0, 0.5, 1.0),
+    };
+    assert_eq!(
+        op.get_code("sphere_0"),
+        "float sphere_0 = sdf_baked(baked[2], p, vec3(-1.0, -0.5, -1.0), vec3(1.0, 0.5, 1.0));"
+    );
+}
@@ -0,0 +1,254 @@
+use serde_json::{self, Value};
+
+use cli::Benchmark;
+use preview;
+
+use std::cmp::Ordering;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The columns of a CSV report, which has one row per benchmark run.
+const CSV_HEADER: &str = "timestamp,project,width,height,frames,cpu_mean,cpu_median,cpu_max,\
+                          gpu_mean,gpu_median,gpu_max,steps_mean,steps_max,hit_ratio";
+
+/// A summary of a set of measurements.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Statistics {
+    pub min: f32,
+    pub mean: f32,
+    pub median: f32,
+    pub max: f32,
+}
+
+impl Statistics {
+    /// Summarizes `samples`, which are all zero if there aren't any.
+    pub fn new(samples: &[f32]) -> Statistics {
+        if samples.is_empty() {
+            return Statistics {
+                min: 0.0,
+                mean: 0.0,
+                median: 0.0,
+                max: 0.0,
+            };
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let middle = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 0 {
+            (sorted[middle - 1] + sorted[middle]) * 0.5
+        } else {
+            sorted[middle]
+        };
+
+        Statistics {
+            min: sorted[0],
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            median,
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "min": self.min,
+            "mean": self.mean,
+            "median": self.median,
+            "max": self.max,
+        })
+    }
+}
+
+/// How long a single benchmarked frame took, in milliseconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FrameTiming {
+    /// The time spent on the CPU issuing the frame's draw calls
+    pub cpu: f32,
+
+    /// The time that the GPU spent raymarching the frame
+    pub gpu: f32,
+}
+
+/// The results of benchmarking a network (see `Preview::benchmark`).
+pub struct Report {
+    /// The network that was benchmarked
+    pub project: PathBuf,
+
+    /// When the benchmark was run (see `preview::get_timestamp`)
+    pub timestamp: String,
+
+    /// The resolution that each frame was raymarched at
+    pub width: u32,
+    pub height: u32,
+
+    /// The timings of each frame, in the order they were rendered
+    pub frames: Vec<FrameTiming>,
+
+    /// The number of steps that the raymarcher took along each ray of a
+    /// `BENCHMARK_PROBE_GRID` by `BENCHMARK_PROBE_GRID` grid of rays that
+    /// were cast through the image
+    pub steps: Vec<u32>,
+
+    /// The number of those rays that hit the surface
+    pub hits: u32,
+}
+
+impl Report {
+    pub fn new(benchmark: &Benchmark) -> Report {
+        Report {
+            project: benchmark.project.clone(),
+            timestamp: preview::get_timestamp(),
+            width: benchmark.width,
+            height: benchmark.height,
+            frames: Vec::new(),
+            steps: Vec::new(),
+            hits: 0,
+        }
+    }
+
+    pub fn get_cpu(&self) -> Statistics {
+        let samples: Vec<f32> = self.frames.iter().map(|frame| frame.cpu).collect();
+        Statistics::new(&samples)
+    }
+
+    pub fn get_gpu(&self) -> Statistics {
+        let samples: Vec<f32> = self.frames.iter().map(|frame| frame.gpu).collect();
+        Statistics::new(&samples)
+    }
+
+    pub fn get_steps(&self) -> Statistics {
+        let samples: Vec<f32> = self.steps.iter().map(|&steps| steps as f32).collect();
+        Statistics::new(&samples)
+    }
+
+    /// Returns the fraction of the grid's rays that hit the surface.
+    pub fn get_hit_ratio(&self) -> f32 {
+        if self.steps.is_empty() {
+            return 0.0;
+        }
+        self.hits as f32 / self.steps.len() as f32
+    }
+
+    /// Returns a one-line summary of the report, for the console.
+    pub fn get_summary(&self) -> String {
+        let (cpu, gpu, steps) = (self.get_cpu(), self.get_gpu(), self.get_steps());
+        format!(
+            "{} frames at {}x{}: GPU {:.2} ms (median {:.2}, max {:.2}), \
+             CPU {:.2} ms, {:.1} steps per ray (max {})",
+            self.frames.len(),
+            self.width,
+            self.height,
+            gpu.mean,
+            gpu.median,
+            gpu.max,
+            cpu.mean,
+            steps.mean,
+            steps.max
+        )
+    }
+
+    pub fn to_json(&self) -> Value {
+        let frames: Vec<Value> = self.frames
+            .iter()
+            .map(|frame| json!({ "cpu": frame.cpu, "gpu": frame.gpu }))
+            .collect();
+
+        json!({
+            "timestamp": self.timestamp,
+            "project": self.project.display().to_string(),
+            "width": self.width,
+            "height": self.height,
+            "cpu": self.get_cpu().to_json(),
+            "gpu": self.get_gpu().to_json(),
+            "steps": self.get_steps().to_json(),
+            "hit_ratio": self.get_hit_ratio(),
+            "frames": frames,
+        })
+    }
+
+    /// Returns the report as a single row of a CSV file (see `CSV_HEADER`),
+    /// without the timings of the individual frames.
+    pub fn to_csv_row(&self) -> String {
+        let (cpu, gpu, steps) = (self.get_cpu(), self.get_gpu(), self.get_steps());
+        let project = self.project.display().to_string().replace('"', "\"\"");
+        format!(
+            "{},\"{}\",{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.2},{},{:.4}",
+            self.timestamp,
+            project,
+            self.width,
+            self.height,
+            self.frames.len(),
+            cpu.mean,
+            cpu.median,
+            cpu.max,
+            gpu.mean,
+            gpu.median,
+            gpu.max,
+            steps.mean,
+            steps.max,
+            self.get_hit_ratio()
+        )
+    }
+
+    /// Saves the report to the file at `path`. If its extension is `.csv`,
+    /// the report is appended to it as a new row (so that a single file
+    /// tracks every run), and the header is written first if the file is
+    /// new. Otherwise, the file is replaced with the full report as JSON.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let is_csv = path.extension().map_or(false, |extension| extension == "csv");
+        if !is_csv {
+            let text = serde_json::to_string_pretty(&self.to_json())
+                .map_err(|error| error.to_string())?;
+            return File::create(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|error| error.to_string());
+        }
+
+        let is_new = !path.exists();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| {
+                if is_new {
+                    writeln!(file, "{}", CSV_HEADER)?;
+                }
+                writeln!(file, "{}", self.to_csv_row())
+            })
+            .map_err(|error| error.to_string())
+    }
+}
+
+#[test]
+fn test_statistics() {
+    let statistics = Statistics::new(&[4.0, 1.0, 3.0, 2.0]);
+    assert_eq!(statistics.min, 1.0);
+    assert_eq!(statistics.mean, 2.5);
+    assert_eq!(statistics.median, 2.5);
+    assert_eq!(statistics.max, 4.0);
+
+    assert_eq!(Statistics::new(&[3.0, 1.0, 2.0]).median, 2.0);
+    assert_eq!(Statistics::new(&[]).max, 0.0);
+}
+
+#[test]
+fn test_csv_row() {
+    let report = Report {
+        project: PathBuf::from("blob.json"),
+        timestamp: "2018-03-21_17-45-09".to_string(),
+        width: 640,
+        height: 360,
+        frames: vec![
+            FrameTiming { cpu: 0.5, gpu: 2.0 },
+            FrameTiming { cpu: 1.5, gpu: 4.0 },
+        ],
+        steps: vec![10, 30],
+        hits: 1,
+    };
+
+    let row = report.to_csv_row();
+    assert_eq!(row.split(',').count(), CSV_HEADER.split(',').count());
+    assert!(row.starts_with("2018-03-21_17-45-09,\"blob.json\",640,360,2,1.0000,"));
+    assert!(row.ends_with(",20.00,30,0.5000"));
+}
@@ -99,6 +99,38 @@ impl Rect {
         false
     }
 
+    /// Returns `true` if `other` lies entirely inside of this rectangle
+    /// and `false` otherwise.
+    pub fn contains(&self, other: &Rect) -> bool {
+        other.upper_left.x >= self.upper_left.x
+            && other.upper_left.y >= self.upper_left.y
+            && (other.upper_left.x + other.size.x) <= (self.upper_left.x + self.size.x)
+            && (other.upper_left.y + other.size.y) <= (self.upper_left.y + self.size.y)
+    }
+
+    /// Returns `true` if this rectangle and `other` overlap (or touch)
+    /// and `false` otherwise.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        other.upper_left.x <= self.upper_left.x + self.size.x
+            && other.upper_left.y <= self.upper_left.y + self.size.y
+            && self.upper_left.x <= other.upper_left.x + other.size.x
+            && self.upper_left.y <= other.upper_left.y + other.size.y
+    }
+
+    /// Returns the smallest rectangle that contains both this rectangle
+    /// and `other`.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let upper_left = Vector2::new(
+            self.upper_left.x.min(other.upper_left.x),
+            self.upper_left.y.min(other.upper_left.y),
+        );
+        let lower_right = Vector2::new(
+            (self.upper_left.x + self.size.x).max(other.upper_left.x + other.size.x),
+            (self.upper_left.y + self.size.y).max(other.upper_left.y + other.size.y),
+        );
+        Rect::new(upper_left, lower_right - upper_left)
+    }
+
     pub fn centroid(&self) -> Vector2<f32> {
         Vector2::new(
             self.upper_left.x + self.size.x * 0.5,
@@ -142,3 +174,21 @@ impl Default for Rect {
         }
     }
 }
+
+#[test]
+fn test_rect_union() {
+    let a = Rect::new(Vector2::new(-10.0, 0.0), Vector2::new(20.0, 10.0));
+    let b = Rect::new(Vector2::new(5.0, -5.0), Vector2::new(10.0, 10.0));
+    let union = a.union(&b);
+    assert_eq!(*union.get_upper_left(), Vector2::new(-10.0, -5.0));
+    assert_eq!(*union.get_size(), Vector2::new(25.0, 15.0));
+}
+
+#[test]
+fn test_rect_intersects() {
+    let a = Rect::new(Vector2::new(0.0, 0.0), Vector2::new(10.0, 10.0));
+    let b = Rect::new(Vector2::new(5.0, 5.0), Vector2::new(10.0, 10.0));
+    let c = Rect::new(Vector2::new(20.0, 0.0), Vector2::new(10.0, 10.0));
+    assert!(a.intersects(&b) && b.intersects(&a));
+    assert!(!a.intersects(&c));
+}
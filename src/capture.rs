@@ -0,0 +1,87 @@
+use constants;
+use video::VideoFormat;
+
+use std::path::{Path, PathBuf};
+
+/// Describes what changes from one captured frame to the next.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CaptureMode {
+    /// The camera makes one full orbit around its focus point, while time
+    /// stands still
+    Turntable,
+
+    /// Time advances across the timeline's loop range, while the camera
+    /// stands still
+    Timeline,
+}
+
+impl CaptureMode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CaptureMode::Turntable => "turntable",
+            CaptureMode::Timeline => "timeline",
+        }
+    }
+}
+
+/// Options that control how frame sequences of the preview are captured,
+/// which are parsed from the command line (see `cli::Options`).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CaptureSettings {
+    /// The number of frames in each sequence (`--capture-frames=<n>`)
+    pub frames: u32,
+
+    /// The resolution of each frame, which doesn't depend on the size of
+    /// the preview window (`--capture-size=<width>x<height>`)
+    pub width: u32,
+    pub height: u32,
+
+    /// The playback rate of captured videos, in frames per second
+    /// (`--capture-fps=<n>`)
+    pub fps: u32,
+
+    /// The format that sequences are encoded as with ffmpeg, or `None` to
+    /// save them as numbered PNGs instead (`--capture-video=<mp4|webm>`)
+    pub video: Option<VideoFormat>,
+}
+
+impl CaptureSettings {
+    pub fn new() -> CaptureSettings {
+        CaptureSettings {
+            frames: constants::CAPTURE_FRAME_COUNT,
+            width: constants::CAPTURE_WIDTH,
+            height: constants::CAPTURE_HEIGHT,
+            fps: constants::CAPTURE_FPS,
+            video: None,
+        }
+    }
+
+    /// Switches between saving sequences as numbered PNGs and encoding them
+    /// as each of the video formats.
+    pub fn cycle_video(&mut self) {
+        self.video = match self.video {
+            None => Some(VideoFormat::Mp4),
+            Some(VideoFormat::Mp4) => Some(VideoFormat::Webm),
+            Some(VideoFormat::Webm) => None,
+        };
+        match self.video {
+            Some(format) => console!("Capturing {} videos", format.get_extension()),
+            None => console!("Capturing PNG frames"),
+        }
+    }
+}
+
+/// Returns the path of the frame at `index` within `directory`. Frames are
+/// numbered from zero with (at least) four digits, i.e. `frame_0007.png`,
+/// which is what tools like `ffmpeg -i frame_%04d.png` expect.
+pub fn get_frame_path(directory: &Path, index: u32) -> PathBuf {
+    directory.join(format!("frame_{:04}.png", index))
+}
+
+#[test]
+fn test_get_frame_path() {
+    assert_eq!(
+        get_frame_path(Path::new("frames"), 7),
+        Path::new("frames").join("frame_0007.png")
+    );
+}
@@ -0,0 +1,550 @@
+use cgmath::Vector2;
+use clap::{App, AppSettings, Arg, ArgMatches, Error};
+
+use capture::CaptureSettings;
+use constants;
+use gl_debug::{DebugOptions, Severity};
+use pacing::FrameSettings;
+use profile::Profile;
+use video::VideoFormat;
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The things that can be exported from the command line (see `Export`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+    Glsl,
+    Shadertoy,
+    Isf,
+    Wgsl,
+    Spirv,
+    Dot,
+    Svg,
+    Png,
+    Screenshot,
+    Turntable,
+    Timeline,
+    Mesh,
+    Volume,
+}
+
+impl ExportFormat {
+    pub fn all() -> [ExportFormat; 13] {
+        [
+            ExportFormat::Glsl,
+            ExportFormat::Shadertoy,
+            ExportFormat::Isf,
+            ExportFormat::Wgsl,
+            ExportFormat::Spirv,
+            ExportFormat::Dot,
+            ExportFormat::Svg,
+            ExportFormat::Png,
+            ExportFormat::Screenshot,
+            ExportFormat::Turntable,
+            ExportFormat::Timeline,
+            ExportFormat::Mesh,
+            ExportFormat::Volume,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ExportFormat::Glsl => "glsl",
+            ExportFormat::Shadertoy => "shadertoy",
+            ExportFormat::Isf => "isf",
+            ExportFormat::Wgsl => "wgsl",
+            ExportFormat::Spirv => "spirv",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Screenshot => "screenshot",
+            ExportFormat::Turntable => "turntable",
+            ExportFormat::Timeline => "timeline",
+            ExportFormat::Mesh => "mesh",
+            ExportFormat::Volume => "volume",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        ExportFormat::all()
+            .iter()
+            .find(|format| format.name() == name)
+            .cloned()
+    }
+
+    /// Returns `true` if the export needs the preview's shader program,
+    /// rather than just the shader graph.
+    pub fn uses_preview(&self) -> bool {
+        match *self {
+            ExportFormat::Screenshot | ExportFormat::Turntable | ExportFormat::Timeline => true,
+            _ => false,
+        }
+    }
+}
+
+/// An export that is written without opening the network editor (the
+/// `export` subcommand), i.e. `sdfperf export shadertoy blob.json`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Export {
+    /// What to export
+    pub format: ExportFormat,
+
+    /// The network to export from
+    pub project: PathBuf,
+
+    /// Where to write the export, if not to the same path as the
+    /// corresponding hotkey (`--output=<path>`)
+    pub output: Option<PathBuf>,
+}
+
+/// A benchmark of a network that runs without opening the network editor
+/// (the `bench` subcommand), i.e. `sdfperf bench blob.json --frames=200`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Benchmark {
+    /// The network to benchmark
+    pub project: PathBuf,
+
+    /// The number of frames that are timed (`--frames=<n>`)
+    pub frames: u32,
+
+    /// The resolution that each frame is raymarched at, which doesn't
+    /// depend on the size of the preview window (`--size=<width>x<height>`)
+    pub width: u32,
+    pub height: u32,
+
+    /// Where to write the report, if not to `BENCHMARK_REPORT_PATH`
+    /// (`--output=<path>`)
+    pub output: Option<PathBuf>,
+}
+
+/// Everything that can be configured from the command line.
+pub struct Options {
+    /// The network to open at startup, if any
+    pub project: Option<PathBuf>,
+
+    /// The initial size of the window (`--size=<width>x<height>`)
+    pub window_size: Vector2<f32>,
+
+    /// The directory that assets are loaded from (`--assets=<dir>`), if
+    /// not the default one
+    pub assets: Option<PathBuf>,
+
+    /// The number of samples per pixel that the window is created with
+    /// (`--samples=<n>`), which glutin requires to be a power of two (or
+    /// zero, to disable MSAA)
+    pub samples: u16,
+
+    /// The GLSL version that shader graphs are generated for
+    /// (`--glsl=<version>`), if it shouldn't be detected
+    pub profile: Option<Profile>,
+
+    /// Whether and how messages from the driver are reported
+    pub debug: DebugOptions,
+
+    /// How frame sequences of the preview are captured
+    pub capture: CaptureSettings,
+
+    /// How often the window is redrawn
+    pub frame: FrameSettings,
+
+    /// The export to write (without opening the network editor), if any
+    pub export: Option<Export>,
+
+    /// The benchmark to run (without opening the network editor), if any
+    pub benchmark: Option<Benchmark>,
+}
+
+/// Parses a resolution like `1280x720`, where both dimensions must be
+/// between 1 and `EXPORT_MAX_RESOLUTION`.
+pub fn parse_size(text: &str) -> Option<(u32, u32)> {
+    let mut dimensions = text.splitn(2, 'x').map(|value| value.trim().parse::<u32>());
+    match (dimensions.next(), dimensions.next()) {
+        (Some(Ok(width)), Some(Ok(height))) => {
+            let max = constants::EXPORT_MAX_RESOLUTION as u32;
+            if width > 0 && height > 0 && width <= max && height <= max {
+                Some((width, height))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The validators below are used by clap, which reports the message if
+/// a value is invalid.
+fn validate_size(value: String) -> Result<(), String> {
+    parse_size(&value)
+        .map(|_| ())
+        .ok_or_else(|| format!("invalid size: {} (expected i.e. 1280x720)", value))
+}
+
+fn validate_samples(value: String) -> Result<(), String> {
+    match value.parse::<u16>() {
+        Ok(n) if n == 0 || n.is_power_of_two() => Ok(()),
+        _ => Err(format!("invalid sample count: {} (expected 0, 1, 2, 4, 8, ...)", value)),
+    }
+}
+
+fn validate_fps_cap(value: String) -> Result<(), String> {
+    value
+        .parse::<u32>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a whole number of frames per second, not {}", value))
+}
+
+fn validate_count(value: String) -> Result<(), String> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("expected a positive whole number, not {}", value)),
+    }
+}
+
+fn validate_seconds(value: String) -> Result<(), String> {
+    match value.parse::<f32>() {
+        Ok(seconds) if seconds > 0.0 => Ok(()),
+        _ => Err(format!("expected a positive number of seconds, not {}", value)),
+    }
+}
+
+/// Returns the description of every argument and subcommand.
+fn build_app<'a, 'b>() -> App<'a, 'b> {
+    let export_formats: Vec<&str> = ExportFormat::all()
+        .iter()
+        .map(|format| format.name())
+        .collect();
+
+    App::new("sdfperf")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("A node-based editor for signed-distance fields")
+        .arg(
+            Arg::with_name("project")
+                .value_name("PROJECT")
+                .help("The network to open at startup (i.e. blob.json)")
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("size")
+                .long("size")
+                .value_name("WIDTHxHEIGHT")
+                .help("The initial size of the window")
+                .validator(validate_size),
+        )
+        .arg(
+            Arg::with_name("assets")
+                .long("assets")
+                .value_name("DIR")
+                .help("The directory that shaders, fonts, icons, ops, and presets are loaded from"),
+        )
+        .arg(
+            Arg::with_name("samples")
+                .long("samples")
+                .value_name("N")
+                .help("The number of samples per pixel (MSAA) of the window")
+                .validator(validate_samples),
+        )
+        .arg(
+            Arg::with_name("vsync")
+                .long("vsync")
+                .value_name("on|off")
+                .help("Whether frames are synchronized to the display's refresh rate")
+                .possible_values(&["on", "off"]),
+        )
+        .arg(
+            Arg::with_name("fps-cap")
+                .long("fps-cap")
+                .value_name("N")
+                .help("The most frames that are drawn per second (0 for no limit)")
+                .validator(validate_fps_cap),
+        )
+        .arg(
+            Arg::with_name("glsl")
+                .long("glsl")
+                .value_name("VERSION")
+                .help("The GLSL version that shader graphs are generated for")
+                .possible_values(&["330", "410", "430", "450", "es310"]),
+        )
+        .arg(
+            Arg::with_name("gl-debug")
+                .long("gl-debug")
+                .help("Reports messages from the driver's debug output"),
+        )
+        .arg(
+            Arg::with_name("gl-debug-severity")
+                .long("gl-debug-severity")
+                .value_name("SEVERITY")
+                .help("The least severe debug messages that are reported")
+                .possible_values(&["notification", "low", "medium", "high"]),
+        )
+        .arg(
+            Arg::with_name("gl-debug-break")
+                .long("gl-debug-break")
+                .help("Aborts as soon as the driver reports an error (implies --gl-debug)"),
+        )
+        .arg(
+            Arg::with_name("capture-frames")
+                .long("capture-frames")
+                .value_name("N")
+                .help("The number of frames in captured sequences")
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("capture-duration")
+                .long("capture-duration")
+                .value_name("SECONDS")
+                .help("The length of captured sequences (overrides --capture-frames)")
+                .validator(validate_seconds),
+        )
+        .arg(
+            Arg::with_name("capture-fps")
+                .long("capture-fps")
+                .value_name("N")
+                .help("The frame rate of captured videos")
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("capture-size")
+                .long("capture-size")
+                .value_name("WIDTHxHEIGHT")
+                .help("The resolution of captured frames")
+                .validator(validate_size),
+        )
+        .arg(
+            Arg::with_name("capture-video")
+                .long("capture-video")
+                .value_name("FORMAT")
+                .help("Encodes captured sequences as videos with ffmpeg")
+                .possible_values(&["mp4", "webm"]),
+        )
+        .subcommand(
+            App::new("export")
+                .about("Writes an export of a network and exits, without opening the editor")
+                .arg(
+                    Arg::with_name("format")
+                        .value_name("FORMAT")
+                        .help("What to export")
+                        .possible_values(&export_formats)
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("project")
+                        .value_name("PROJECT")
+                        .help("The network to export from")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .value_name("PATH")
+                        .help("Where to write the export (defaults to the path of its hotkey)"),
+                ),
+        )
+        .subcommand(
+            App::new("bench")
+                .about("Times how long a network takes to render and writes a report")
+                .arg(
+                    Arg::with_name("project")
+                        .value_name("PROJECT")
+                        .help("The network to benchmark")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("frames")
+                        .long("frames")
+                        .value_name("N")
+                        .help("The number of frames that are timed")
+                        .validator(validate_count),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .value_name("WIDTHxHEIGHT")
+                        .help("The resolution that frames are rendered at")
+                        .validator(validate_size),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .value_name("PATH")
+                        .help("Where to write the report (as CSV rows if it ends in .csv)"),
+                ),
+        )
+}
+
+fn get_size(matches: &ArgMatches, name: &str) -> Option<(u32, u32)> {
+    matches.value_of(name).and_then(parse_size)
+}
+
+/// The values of the arguments below have already been checked by their
+/// validators, so they can't fail to parse.
+fn get_number<T: ::std::str::FromStr>(matches: &ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).and_then(|value| value.parse().ok())
+}
+
+impl Options {
+    /// Parses the command line `args`, where the first one is the name of
+    /// the executable. Returns clap's error (which also covers `--help` and
+    /// `--version`) if the arguments are invalid, so that it can be shown
+    /// to the user.
+    pub fn parse<I, T>(args: I) -> Result<Options, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = build_app().get_matches_from_safe(args)?;
+
+        let window_size = get_size(&matches, "size")
+            .map(|(width, height)| Vector2::new(width as f32, height as f32))
+            .unwrap_or(constants::WINDOW_RESOLUTION);
+
+        let break_on_error = matches.is_present("gl-debug-break");
+        let debug = DebugOptions {
+            enabled: matches.is_present("gl-debug") || break_on_error,
+            min_severity: matches
+                .value_of("gl-debug-severity")
+                .and_then(Severity::from_name)
+                .unwrap_or(Severity::Low),
+            break_on_error,
+        };
+
+        let mut capture = CaptureSettings::new();
+        if let Some(frames) = get_number(&matches, "capture-frames") {
+            capture.frames = frames;
+        }
+        if let Some(fps) = get_number(&matches, "capture-fps") {
+            capture.fps = fps;
+        }
+        if let Some((width, height)) = get_size(&matches, "capture-size") {
+            capture.width = width;
+            capture.height = height;
+        }
+        if let Some(seconds) = get_number::<f32>(&matches, "capture-duration") {
+            capture.frames = ((seconds * capture.fps as f32).round() as u32).max(1);
+        }
+        capture.video = matches
+            .value_of("capture-video")
+            .and_then(VideoFormat::from_name);
+
+        let mut frame = FrameSettings::new();
+        if let Some(vsync) = matches.value_of("vsync") {
+            frame.vsync = vsync == "on";
+        }
+        if let Some(fps) = get_number(&matches, "fps-cap") {
+            frame.set_fps_cap(fps);
+        }
+
+        let export = matches.subcommand_matches("export").map(|export| Export {
+            format: export
+                .value_of("format")
+                .and_then(ExportFormat::from_name)
+                .unwrap_or(ExportFormat::Glsl),
+            project: PathBuf::from(export.value_of("project").unwrap_or_default()),
+            output: export.value_of("output").map(PathBuf::from),
+        });
+
+        let benchmark = matches.subcommand_matches("bench").map(|bench| {
+            let (width, height) = get_size(bench, "size")
+                .unwrap_or((constants::BENCHMARK_WIDTH, constants::BENCHMARK_HEIGHT));
+            Benchmark {
+                project: PathBuf::from(bench.value_of("project").unwrap_or_default()),
+                frames: get_number(bench, "frames").unwrap_or(constants::BENCHMARK_FRAME_COUNT),
+                width,
+                height,
+                output: bench.value_of("output").map(PathBuf::from),
+            }
+        });
+
+        Ok(Options {
+            project: matches.value_of("project").map(PathBuf::from),
+            window_size,
+            assets: matches.value_of("assets").map(PathBuf::from),
+            samples: get_number(&matches, "samples").unwrap_or(constants::WINDOW_MULTISAMPLES),
+            profile: matches.value_of("glsl").and_then(Profile::from_name),
+            debug,
+            capture,
+            frame,
+            export,
+            benchmark,
+        })
+    }
+}
+
+impl Benchmark {
+    /// Returns the path that the report should be written to.
+    pub fn get_output(&self) -> &Path {
+        self.output
+            .as_ref()
+            .map(|output| output.as_path())
+            .unwrap_or_else(|| Path::new(constants::BENCHMARK_REPORT_PATH))
+    }
+}
+
+impl Export {
+    /// Returns the path that the export should be written to, which is
+    /// `default` unless another one was given.
+    pub fn get_output<'a>(&'a self, default: &'a str) -> &'a Path {
+        self.output
+            .as_ref()
+            .map(|output| output.as_path())
+            .unwrap_or_else(|| Path::new(default))
+    }
+}
+
+#[test]
+fn test_parse_options() {
+    let parse = |args: &[&str]| Options::parse(["sdfperf"].iter().chain(args.iter()).cloned());
+
+    let options = parse(&[]).unwrap();
+    assert_eq!(options.project, None);
+    assert_eq!(options.samples, constants::WINDOW_MULTISAMPLES);
+    assert_eq!(options.window_size, constants::WINDOW_RESOLUTION);
+    assert!(!options.debug.enabled);
+    assert_eq!(options.capture, CaptureSettings::new());
+    assert_eq!(options.export, None);
+
+    let options = parse(&[
+        "--samples=4",
+        "blob.json",
+        "--size=800x600",
+        "--gl-debug-break",
+        "--gl-debug-severity=high",
+        "--capture-duration=2.5",
+        "--capture-fps=24",
+        "--capture-size=1280x720",
+    ]).unwrap();
+    assert_eq!(options.project, Some(PathBuf::from("blob.json")));
+    assert_eq!(options.samples, 4);
+    assert_eq!(options.window_size, Vector2::new(800.0, 600.0));
+    assert!(options.debug.enabled && options.debug.break_on_error);
+    assert_eq!(options.debug.min_severity, Severity::High);
+    assert_eq!(options.capture.frames, 60);
+    assert_eq!((options.capture.width, options.capture.height), (1280, 720));
+
+    let options = parse(&["--vsync=off", "--fps-cap=0"]).unwrap();
+    assert!(!options.frame.vsync);
+    assert_eq!(options.frame.fps_cap, None);
+
+    assert!(parse(&["--samples=3"]).is_err());
+    assert!(parse(&["--capture-size=0x720"]).is_err());
+    assert!(parse(&["--glsl=120"]).is_err());
+
+    let options = parse(&["export", "shadertoy", "blob.json", "--output=blob.glsl"]).unwrap();
+    let export = options.export.unwrap();
+    assert_eq!(export.format, ExportFormat::Shadertoy);
+    assert_eq!(export.project, PathBuf::from("blob.json"));
+    assert_eq!(export.get_output(constants::EXPORT_SHADERTOY_PATH), Path::new("blob.glsl"));
+    assert!(parse(&["export", "blob.json"]).is_err());
+
+    let options = parse(&["bench", "blob.json", "--frames=10", "--size=640x360"]).unwrap();
+    let benchmark = options.benchmark.unwrap();
+    assert_eq!(benchmark.project, PathBuf::from("blob.json"));
+    assert_eq!((benchmark.frames, benchmark.width, benchmark.height), (10, 640, 360));
+    assert_eq!(benchmark.get_output(), Path::new(constants::BENCHMARK_REPORT_PATH));
+    assert!(parse(&["bench", "blob.json", "--frames=0"]).is_err());
+}
@@ -0,0 +1,88 @@
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// The commands that copy their standard input to the system clipboard,
+/// in the order that they are tried.
+#[cfg(target_os = "macos")]
+const COPY_COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "windows")]
+const COPY_COMMANDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const COPY_COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// The commands that print the contents of the system clipboard, in the
+/// order that they are tried.
+#[cfg(target_os = "macos")]
+const PASTE_COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+#[cfg(target_os = "windows")]
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"],
+];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// Returns the error that is reported when none of `commands` exist.
+fn get_missing_error(commands: &[&[&str]]) -> String {
+    let names: Vec<&str> = commands.iter().map(|command| command[0]).collect();
+    format!("no clipboard tool was found (tried {})", names.join(", "))
+}
+
+/// Places `text` on the system clipboard, with the first of the platform's
+/// clipboard tools that is installed.
+pub fn set_text(text: &str) -> Result<(), String> {
+    for command in COPY_COMMANDS.iter() {
+        // Some tools (i.e. `xclip`) keep running in the background to serve
+        // the clipboard, so their output isn't waited on.
+        let spawned = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(format!("failed to run {}: {}", command[0], error)),
+        };
+
+        // Closing the standard input (by dropping it) lets the tool know
+        // that it has all of the text.
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(|error| error.to_string())?;
+        }
+
+        let status = child.wait().map_err(|error| error.to_string())?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{} failed ({})", command[0], status))
+        };
+    }
+    Err(get_missing_error(COPY_COMMANDS))
+}
+
+/// Returns the text on the system clipboard, with the first of the
+/// platform's clipboard tools that is installed.
+pub fn get_text() -> Result<String, String> {
+    for command in PASTE_COMMANDS.iter() {
+        let output = match Command::new(command[0]).args(&command[1..]).output() {
+            Ok(output) => output,
+            Err(ref error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(format!("failed to run {}: {}", command[0], error)),
+        };
+        return if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        };
+    }
+    Err(get_missing_error(PASTE_COMMANDS))
+}
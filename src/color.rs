@@ -2,6 +2,15 @@ use std::ops::{Add, AddAssign, Sub, SubAssign};
 
 use cgmath::Vector4;
 
+/// Converts a single sRGB-encoded color channel to linear space.
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Color {
     pub r: f32,
@@ -33,6 +42,19 @@ impl Color {
     pub fn black() -> Color {
         Color::mono(0.0, 1.0)
     }
+
+    /// Returns this color converted from sRGB (which is how every color in
+    /// the UI is specified, i.e. as hex codes picked by eye) to linear space,
+    /// which is what shaders should output when rendering to an sRGB
+    /// framebuffer. Alpha is left unchanged.
+    pub fn to_linear(self) -> Color {
+        Color::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        )
+    }
 }
 
 impl Add for Color {
@@ -104,3 +126,14 @@ fn test_white_hex() {
 fn test_black_hex() {
     assert_eq!(Color::from_hex(0x000000, 1.0), Color::black());
 }
+
+#[test]
+fn test_to_linear() {
+    assert_eq!(Color::white().to_linear(), Color::white());
+    assert_eq!(Color::black().to_linear(), Color::black());
+
+    // Mid-gray in sRGB is much darker in linear space.
+    let gray = Color::mono(0.5, 0.5).to_linear();
+    assert!((gray.r - 0.214).abs() < 1e-3);
+    assert_eq!(gray.a, 0.5);
+}
@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use constants;
+
+thread_local! {
+    /// The most recent messages that were logged, oldest first
+    static MESSAGES: RefCell<VecDeque<String>> = RefCell::new(VecDeque::new());
+}
+
+/// Logs an informational message, which is printed to stderr and shown in
+/// the on-screen console unless it is filtered out (see `logging.rs`).
+/// Usage is identical to `println!(..)`.
+macro_rules! console {
+    ($($arg:tt)*) => (info!($($arg)*));
+}
+
+/// Appends each line of `message` to the console, discarding the oldest
+/// messages once the console is full.
+pub fn push(message: &str) {
+    MESSAGES.with(|messages| {
+        let mut messages = messages.borrow_mut();
+        for line in message.lines() {
+            messages.push_back(line.to_string());
+        }
+        while messages.len() > constants::CONSOLE_CAPACITY {
+            messages.pop_front();
+        }
+    });
+}
+
+/// Returns (at most) the `count` most recent messages, oldest first.
+pub fn get_recent(count: usize) -> Vec<String> {
+    MESSAGES.with(|messages| {
+        let messages = messages.borrow();
+        let skip = messages.len().saturating_sub(count);
+        messages.iter().skip(skip).cloned().collect()
+    })
+}
+
+/// Returns the total number of messages currently held by the console.
+pub fn len() -> usize {
+    MESSAGES.with(|messages| messages.borrow().len())
+}
+
+#[test]
+fn test_console_capacity() {
+    for i in 0..constants::CONSOLE_CAPACITY + 2 {
+        push(&format!("message {}", i));
+    }
+    assert_eq!(len(), constants::CONSOLE_CAPACITY);
+    assert_eq!(
+        get_recent(1),
+        vec![format!("message {}", constants::CONSOLE_CAPACITY + 1)]
+    );
+}
@@ -4,28 +4,240 @@ use cgmath::Vector2;
 pub const WINDOW_RESOLUTION: Vector2<f32> = Vector2 { x: 1400.0, y: 700.0 };
 pub const WINDOW_MULTISAMPLES: u16 = 8;
 pub const WINDOW_TITLE: &str = "signed-distance fields";
+pub const WINDOW_VSYNC: bool = true;
+pub const WINDOW_FPS_CAP: u32 = 60;
+pub const WINDOW_FPS_CAPS: [u32; 4] = [30, 60, 144, 0];
 
 // Preview region
 pub const PREVIEW_RESOLUTION: Vector2<f32> = Vector2{ x: 300.0, y: 300.0 };
+pub const PREVIEW_RENDER_SCALES: [f32; 3] = [0.5, 1.0, 2.0];
+pub const PREVIEW_DEFAULT_RENDER_SCALE: usize = 1;
+pub const PREVIEW_SAMPLE_COUNTS: [u32; 3] = [1, 2, 4];
+pub const PREVIEW_MAX_ACCUMULATED_FRAMES: u32 = 256;
+pub const PREVIEW_INTERACTIVE_SCALES: [f32; 3] = [1.0, 0.5, 0.25];
+pub const PREVIEW_DEFAULT_INTERACTIVE_SCALE: usize = 1;
+pub const PREVIEW_IDLE_DELAY: f32 = 1.0;
+pub const PREVIEW_SPLIT_HANDLE_WIDTH: f32 = 6.0;
 pub const PREVIEW_ROTATION_SENSITIVITY: f32 = 0.25;
-pub const PREVIEW_TRANSLATION_SENSITIVITY: f32 = 0.01;
+pub const PREVIEW_TRANSLATION_SENSITIVITY: f32 = 0.002;
+pub const PREVIEW_HOME_DISTANCE: f32 = 5.0;
+pub const PREVIEW_MIN_DISTANCE: f32 = 0.1;
+pub const PREVIEW_FLY_SPEED: f32 = 2.0;
+pub const PREVIEW_MIN_FLY_SPEED: f32 = 0.1;
+pub const PREVIEW_FOV: f32 = 50.0;
+pub const PREVIEW_MIN_FOV: f32 = 10.0;
+pub const PREVIEW_MAX_FOV: f32 = 120.0;
+pub const PREVIEW_FOV_INCREMENT: f32 = 5.0;
+pub const PREVIEW_MAX_TRACE_DISTANCE: f32 = 64.0;
+pub const PREVIEW_TRACE_DISTANCE_INCREMENT: f32 = 8.0;
+pub const PREVIEW_TURNTABLE_SPEED: f32 = 20.0;
+pub const PREVIEW_TURNTABLE_SPEED_INCREMENT: f32 = 5.0;
+pub const PREVIEW_MAX_STEPS: f32 = 256.0;
+pub const PREVIEW_STEPS_INCREMENT: f32 = 8.0;
+pub const PREVIEW_TILE_SIZE: i32 = 64;
+pub const PREVIEW_TILE_BUDGET_MS: f32 = 8.0;
 
 // Interface controls
 pub const ZOOM_INCREMENT: f32 = 0.05;
+pub const PANEL_CORNER_RADIUS: f32 = 4.0;
 
 // Network
 pub const NETWORK_BACKGROUND_COLOR: u32 = 0x2B2B2B;
 pub const NETWORK_BACKGROUND_ALPHA: f32 = 1.0;
+pub const NETWORK_GRID_LINE_WIDTH: f32 = 1.0;
+pub const NETWORK_CULL_PADDING: f32 = 32.0;
+pub const NETWORK_GRID_SPACING: f32 = 20.0;
+pub const NETWORK_MIN_ZOOM: f32 = 0.25;
+pub const NETWORK_MAX_ZOOM: f32 = 4.0;
+
+// Exporting
+pub const EXPORT_PNG_PATH: &str = "network.png";
+pub const EXPORT_SVG_PATH: &str = "network.svg";
+pub const EXPORT_DOT_PATH: &str = "network.dot";
+pub const EXPORT_GLSL_PATH: &str = "shader.glsl";
+pub const EXPORT_SHADERTOY_PATH: &str = "shadertoy.glsl";
+pub const EXPORT_SPIRV_PATH: &str = "shader.frag.spv";
+pub const SPIRV_COMPILER: &str = "glslc";
+pub const EXPORT_WGSL_PATH: &str = "shader.wgsl";
+pub const EXPORT_ISF_PATH: &str = "shader.fs";
+pub const WGSL_TRANSLATOR: &str = "naga";
+pub const EXPORT_MARGIN: f32 = 40.0;
+pub const EXPORT_SCALE: f32 = 2.0;
+pub const EXPORT_MAX_RESOLUTION: f32 = 8192.0;
+pub const SVG_FONT_SIZE: f32 = 12.0;
+
+// Frame sequences
+pub const CAPTURE_FRAME_COUNT: u32 = 60;
+pub const CAPTURE_WIDTH: u32 = 512;
+pub const CAPTURE_HEIGHT: u32 = 512;
+pub const CAPTURE_FPS: u32 = 30;
+pub const VIDEO_ENCODER: &str = "ffmpeg";
+
+// Benchmarks
+pub const BENCHMARK_REPORT_PATH: &str = "benchmark.json";
+pub const BENCHMARK_FRAME_COUNT: u32 = 100;
+pub const BENCHMARK_WARMUP_FRAMES: u32 = 10;
+pub const BENCHMARK_WIDTH: u32 = 1280;
+pub const BENCHMARK_HEIGHT: u32 = 720;
+pub const BENCHMARK_PROBE_GRID: u32 = 16;
+
+// Grid evaluation
+pub const GRID_EXTENT: f32 = 8.0;
+pub const GRID_RESOLUTION: u32 = 64;
+
+// Meshes
+pub const EXPORT_GLTF_PATH: &str = "mesh.glb";
+pub const MESH_RESOLUTION: u32 = 128;
+pub const MESH_COLOR: u32 = 0xB0B0B0;
+
+// Volumes
+pub const EXPORT_VOLUME_PATH: &str = "volume.bin";
+pub const EXPORT_VOX_PATH: &str = "volume.vox";
+pub const VOLUME_RESOLUTION: u32 = 128;
+
+// Baking
+pub const BAKE_CAPACITY: usize = 8;
+pub const BAKE_RESOLUTION: u32 = 64;
+pub const BAKE_TEXTURE_UNIT: u32 = 8;
+
+// Step histograms
+pub const STEP_IMAGE_UNIT: u32 = 0;
+pub const STEP_HISTOGRAM_BINDING: u32 = 2;
+pub const STEP_HISTOGRAM_BINS: usize = 32;
+pub const STEP_HISTOGRAM_RANGE: u32 = 256;
+
+// Bounding sphere hierarchies
+pub const HIERARCHY_MIN_LEAVES: usize = 4;
+pub const HIERARCHY_LEAF_SIZE: usize = 2;
+
+// Shadows
+pub const SHADOW_OFFSET: Vector2<f32> = Vector2 { x: 0.0, y: 4.0 };
+pub const SHADOW_BLUR: f32 = 8.0;
+pub const SHADOW_ALPHA: f32 = 0.35;
 
 // Operators
 pub const OPERATOR_SIZE: Vector2<f32> = Vector2 { x: 100.0, y: 50.0 };
 pub const OPERATIVE_SLOT_SIZE: Vector2<f32> = Vector2 { x: 12.0, y: 12.0 };
 pub const OPERATOR_ICON_SIZE: Vector2<f32> = Vector2 { x: 40.0, y: 40.0 };
 pub const OPERATOR_ICON_OFFSET: Vector2<f32> = Vector2 { x: 4.0, y: 4.0 };
+pub const OPERATOR_ERROR_COLOR: u32 = 0xB23A3A;
+pub const OPERATOR_SELECTION_WIDTH: f32 = 6.0;
+pub const OPERATOR_CORNER_RADIUS: f32 = 6.0;
+pub const OPERATOR_BORDER_WIDTH: f32 = 1.5;
+pub const OPERATOR_SLOT_CORNER_RADIUS: f32 = 3.0;
+
+// Edges
+pub const EDGE_LINE_WIDTH: f32 = 2.0;
+pub const EDGE_ARROW_SIZE: f32 = 8.0;
+pub const EDGE_CURVE_LOD: usize = 20;
+pub const EDGE_MIN_TANGENT_LENGTH: f32 = 40.0;
+pub const EDGE_SELECTION_DISTANCE: f32 = 6.0;
+pub const EDGE_ROUTING_PADDING: f32 = 8.0;
+
+// Knife
+pub const KNIFE_LINE_WIDTH: f32 = 2.0;
+pub const KNIFE_POINT_SPACING: f32 = 4.0;
+pub const KNIFE_MAX_POINTS: usize = 250;
+
+// Backdrops
+pub const BACKDROP_SIZE: Vector2<f32> = Vector2 { x: 300.0, y: 200.0 };
+pub const BACKDROP_MIN_SIZE: Vector2<f32> = Vector2 { x: 120.0, y: 60.0 };
+pub const BACKDROP_TITLE_HEIGHT: f32 = 24.0;
+pub const BACKDROP_TITLE_PADDING: f32 = 8.0;
+pub const BACKDROP_HANDLE_SIZE: Vector2<f32> = Vector2 { x: 12.0, y: 12.0 };
+pub const BACKDROP_COLORS: [u32; 4] = [0x515151, 0x8F719D, 0xA8B6C5, 0xC77832];
+pub const BACKDROP_ALPHA: f32 = 0.25;
+
+// Console
+pub const CONSOLE_CAPACITY: usize = 64;
+pub const CONSOLE_VISIBLE_MESSAGES: usize = 12;
+pub const CONSOLE_MAX_LINE_LENGTH: usize = 64;
+pub const CONSOLE_PADDING: Vector2<f32> = Vector2 { x: 8.0, y: 8.0 };
+
+// Status bar
+pub const STATUS_BAR_HEIGHT: f32 = 24.0;
+pub const STATUS_BAR_PADDING: f32 = 8.0;
+
+// Performance overlay
+pub const PERF_QUERY_COUNT: usize = 4;
+pub const PERF_HISTORY_LENGTH: usize = 120;
+pub const PERF_PANEL_WIDTH: f32 = 240.0;
+pub const PERF_GRAPH_HEIGHT: f32 = 60.0;
+pub const PERF_HISTOGRAM_HEIGHT: f32 = 40.0;
+pub const PERF_PADDING: Vector2<f32> = Vector2 { x: 8.0, y: 8.0 };
+pub const PERF_BUDGET_MS: f32 = 16.7;
+pub const PERF_COST_SMOOTHING: f32 = 0.25;
+pub const PERF_COLORS: [u32; 3] = [0xA8B6C5, 0xC77832, 0x8F719D];
+
+// Timeline
+pub const TIMELINE_HEIGHT: f32 = 20.0;
+pub const TIMELINE_DURATION: f32 = 10.0;
+pub const TIMELINE_MIN_LOOP_LENGTH: f32 = 0.1;
+
+// Custom ops
+pub const CUSTOM_OP_PATH: &str = "custom";
+
+// Assets (the paths below are relative to this directory)
+pub const ASSETS_PATH: &str = "assets";
+
+// Op definitions
+pub const OP_DEFINITIONS_PATH: &str = "ops";
+
+// Projects
+pub const PROJECT_PATH: &str = "network.json";
+pub const RECENT_PROJECTS_PATH: &str = "recent.txt";
+pub const RECENT_PROJECTS_CAPACITY: usize = 8;
+pub const RECENT_PANEL_WIDTH: f32 = 420.0;
+pub const RECENT_PANEL_MAX_CHARS: usize = 48;
+
+// Presets
+pub const PRESETS_PATH: &str = "presets";
+pub const PRESET_PANEL_WIDTH: f32 = 240.0;
+pub const PRESET_PANEL_PADDING: Vector2<f32> = Vector2 { x: 8.0, y: 4.0 };
+
+// Examples (bundled networks that are listed in the gallery)
+pub const EXAMPLES_PATH: &str = "examples";
+pub const GALLERY_PANEL_WIDTH: f32 = 240.0;
+
+// Recently used ops
+pub const RECENT_OPS_CAPACITY: usize = 5;
+pub const RECENT_OPS_PADDING: f32 = 6.0;
+
+// Thumbnails
+pub const THUMBNAIL_RESOLUTION: u32 = 64;
+pub const THUMBNAIL_BUILDS_PER_FRAME: usize = 4;
 
 // Parameters
 pub const PARAMETER_CAPACITY: usize = 4;
 pub const PARAMETER_SSBO_CAPACITY: usize = 256;
+pub const PARAMETER_RING_REGIONS: usize = 3;
+pub const PARAMETER_FENCE_TIMEOUT: u64 = 1_000_000_000;
+
+// Program cache
+pub const PROGRAM_CACHE_PATH: &str = "cache/programs";
+
+// Renderer
+pub const RENDERER_INSTANCE_CAPACITY: usize = 1024;
+pub const RENDERER_LINE_INITIAL_CAPACITY: usize = 1024;
+
+// Shaders
+pub const SHADER_PATH: &str = "shaders";
+pub const SHADER_OVERRIDE_PATH: &str = "overrides/shaders";
+pub const SHADER_LIBRARIES: &[&str] = &["sdf.glsl"];
+pub const SHADER_WATCH_INTERVAL_MS: u64 = 500;
+
+// Text
+pub const FONT_PATH: &str = "fonts/font_8x8.png";
+pub const FONT_CELL_SIZE: Vector2<f32> = Vector2 { x: 8.0, y: 8.0 };
+pub const FONT_GLYPH_SIZE: Vector2<f32> = Vector2 { x: 6.0, y: 8.0 };
+pub const FONT_LINE_SPACING: f32 = 2.0;
+pub const FONT_SCALE: f32 = 2.0;
+pub const TEXT_GLYPH_CAPACITY: usize = 1024;
+
+// Tooltips
+pub const TOOLTIP_DELAY: f32 = 0.5;
+pub const TOOLTIP_PADDING: Vector2<f32> = Vector2 { x: 8.0, y: 8.0 };
+pub const TOOLTIP_OFFSET: Vector2<f32> = Vector2 { x: 0.0, y: 8.0 };
 
 
 
@@ -0,0 +1,256 @@
+use constants;
+use operator::Parameters;
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The code that each new custom op starts out with, which shades its
+/// input domain with a sphere of adjustable radius.
+static DEFAULT_CODE: &str = "\
+// The code of a custom op, where `NAME` is replaced with the name of the op,
+// `INPUT_A` and `INPUT_B` with the names of its inputs, and `INDEX` with the
+// index of its parameters (i.e. `params[INDEX].x`). Ops that are connected to
+// a domain operator can use its position `p_INPUT_A` and scale `s_INPUT_A`.
+// Unless the code sets `id_NAME`, the op is shaded as an object of its own.
+// @inputs 1
+// @param radius 1.0 0.1 4.0 0.1
+float NAME = sdf_sphere(p_INPUT_A, vec3(0.0), params[INDEX].x) * s_INPUT_A;
+";
+
+/// The user-supplied GLSL code of a custom op, which lives in a file of its
+/// own inside of `CUSTOM_OP_PATH`, so that it can be edited externally. Lines
+/// that start with `//` are comments, which aren't part of the generated code,
+/// except for the directives that declare the number of inputs that the op
+/// takes and each of the parameters that it uses (up to four, which map to the
+/// components of `params[INDEX]`):
+///
+/// ```text
+/// // @inputs 2
+/// // @param blend 0.5 0.0 1.0 0.1
+/// float NAME = mix(INPUT_A, INPUT_B, params[INDEX].x);
+/// float id_NAME = id_INPUT_A;
+/// ```
+///
+/// Each parameter lists its name, default value, minimum, maximum, and
+/// step size.
+#[derive(Clone)]
+pub struct CustomCode {
+    /// The name of the file that holds the code, inside of `CUSTOM_OP_PATH`
+    pub file_name: String,
+
+    /// The code template, which uses the same placeholders as the templates
+    /// of the built-in op families
+    pub template: String,
+
+    /// The number of inputs that the op takes (at most two)
+    pub inputs: usize,
+
+    /// The names, default values, minimums, maximums, and step sizes of the
+    /// parameters that the code uses
+    params: Vec<(String, [f32; 4])>,
+}
+
+impl CustomCode {
+    /// Parses the contents of the file named `file_name`, returning an error
+    /// if any of its directives are malformed.
+    pub fn parse(file_name: &str, text: &str) -> Result<CustomCode, String> {
+        let mut custom = CustomCode {
+            file_name: file_name.to_string(),
+            template: String::new(),
+            inputs: 1,
+            params: Vec::new(),
+        };
+
+        let mut code = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if !trimmed.starts_with("//") {
+                if !trimmed.is_empty() {
+                    code.push(line);
+                }
+                continue;
+            }
+
+            let mut tokens = trimmed.trim_start_matches("//").split_whitespace();
+            match tokens.next() {
+                Some("@inputs") => match tokens.next().and_then(|token| token.parse().ok()) {
+                    Some(inputs) if inputs <= 2 => custom.inputs = inputs,
+                    _ => return Err(format!("line {}: expected 0, 1, or 2 inputs", number + 1)),
+                },
+                Some("@param") => {
+                    let name = tokens.next();
+                    let values = tokens
+                        .map(|token| token.parse::<f32>().ok())
+                        .collect::<Option<Vec<_>>>();
+                    match (name, values) {
+                        (Some(name), Some(ref values)) if values.len() == 4 => custom
+                            .params
+                            .push((name.to_string(), [values[0], values[1], values[2], values[3]])),
+                        _ => {
+                            return Err(format!(
+                                "line {}: expected a name, default, minimum, maximum, and step",
+                                number + 1
+                            ))
+                        }
+                    }
+                    if custom.params.len() > constants::PARAMETER_CAPACITY {
+                        return Err(format!(
+                            "line {}: ops can have at most {} parameters",
+                            number + 1,
+                            constants::PARAMETER_CAPACITY
+                        ));
+                    }
+                }
+                Some(token) if token.starts_with('@') => {
+                    return Err(format!("line {}: unknown directive {}", number + 1, token))
+                }
+                // Skip ordinary comments.
+                _ => (),
+            }
+        }
+        custom.template = code.join("\n");
+        Ok(custom)
+    }
+
+    /// Returns the path of the file named `file_name`.
+    fn get_path(file_name: &str) -> PathBuf {
+        Path::new(constants::CUSTOM_OP_PATH).join(file_name)
+    }
+
+    /// Loads the code in the file named `file_name`.
+    pub fn load(file_name: &str) -> Result<CustomCode, String> {
+        let path = CustomCode::get_path(file_name);
+        let mut text = String::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        CustomCode::parse(file_name, &text)
+            .map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Loads the code in the file named `file_name`, falling back to the
+    /// default code (and logging why) if it can't be loaded.
+    pub fn open(file_name: &str) -> CustomCode {
+        CustomCode::load(file_name).unwrap_or_else(|error| {
+            error!("Failed to load custom op: {}", error);
+            CustomCode::parse(file_name, DEFAULT_CODE).unwrap()
+        })
+    }
+
+    /// Returns the contents of the file named `file_name`.
+    pub fn read_source(file_name: &str) -> Result<String, String> {
+        let path = CustomCode::get_path(file_name);
+        let mut text = String::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        Ok(text)
+    }
+
+    /// Returns the name of the first file (i.e. `custom_0.glsl`, then
+    /// `custom_1.glsl`, and so on) that starts with `stem` and doesn't
+    /// exist yet.
+    fn get_free_file_name(stem: &str) -> String {
+        (0..)
+            .map(|count| format!("{}_{}.glsl", stem, count))
+            .find(|file_name| !CustomCode::get_path(file_name).exists())
+            .unwrap()
+    }
+
+    /// Writes `text` to the file named `file_name`.
+    fn write(file_name: &str, text: &str) -> Result<(), String> {
+        let path = CustomCode::get_path(file_name);
+        fs::create_dir_all(constants::CUSTOM_OP_PATH)
+            .and_then(|_| File::create(&path))
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Creates a new file with the default code, named after the first
+    /// custom op that doesn't have a file yet.
+    pub fn create() -> CustomCode {
+        let file_name = CustomCode::get_free_file_name("custom");
+        match CustomCode::write(&file_name, DEFAULT_CODE) {
+            Ok(_) => console!(
+                "Created custom op: edit {} to change its code",
+                CustomCode::get_path(&file_name).display()
+            ),
+            Err(error) => error!("Failed to create custom op {}", error),
+        }
+        CustomCode::parse(&file_name, DEFAULT_CODE).unwrap()
+    }
+
+    /// Returns the code `text` of a custom op that was shared from another
+    /// network (i.e. pasted from the clipboard), where it lived in the file
+    /// named `file_name`. That file is reused if it doesn't exist yet or
+    /// already holds the same code, and otherwise the code is written to a
+    /// new file, so that the existing one isn't overwritten.
+    pub fn import(file_name: &str, text: &str) -> Result<CustomCode, String> {
+        // Shared code can't be written outside of `CUSTOM_OP_PATH`.
+        let file_name = Path::new(file_name)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("invalid file name: {}", file_name))?;
+
+        let custom = CustomCode::parse(file_name, text)?;
+        match CustomCode::read_source(file_name) {
+            Ok(ref existing) if existing == text => Ok(custom),
+            Ok(_) => {
+                let stem = file_name.trim_end_matches(".glsl");
+                let file_name = CustomCode::get_free_file_name(stem);
+                CustomCode::write(&file_name, text)?;
+                CustomCode::parse(&file_name, text)
+            }
+            Err(_) => {
+                CustomCode::write(file_name, text)?;
+                Ok(custom)
+            }
+        }
+    }
+
+    /// Returns `true` if this code was loaded from the file at `path`.
+    pub fn is_loaded_from(&self, path: &Path) -> bool {
+        path.file_name().map_or(false, |name| name == &self.file_name[..])
+            && path.parent().map_or(false, |parent| parent.ends_with(constants::CUSTOM_OP_PATH))
+    }
+
+    /// Returns the parameters that the code declares, with their default
+    /// values.
+    pub fn get_default_params(&self) -> Parameters {
+        let mut data = [0.0; constants::PARAMETER_CAPACITY];
+        let mut names = [""; constants::PARAMETER_CAPACITY];
+        let mut min = [0.0; constants::PARAMETER_CAPACITY];
+        let mut max = [0.0; constants::PARAMETER_CAPACITY];
+        let mut step = [0.0; constants::PARAMETER_CAPACITY];
+
+        for (i, &(ref name, values)) in self.params.iter().enumerate() {
+            // Parameter names are shared by every copy of an op, so they
+            // live for the remainder of the program.
+            names[i] = Box::leak(name.clone().into_boxed_str());
+            data[i] = values[0];
+            min[i] = values[1];
+            max[i] = values[2];
+            step[i] = values[3];
+        }
+        Parameters::new(data, names, 0, min, max, step)
+    }
+}
+
+#[test]
+fn test_parse_custom_code() {
+    let text = "// A smooth blend.\n// @inputs 2\n// @param blend 0.5 0 1 0.1\n\n\
+                float NAME = mix(INPUT_A, INPUT_B, params[INDEX].x);\n";
+    let custom = CustomCode::parse("blend.glsl", text).unwrap();
+    assert_eq!(custom.inputs, 2);
+    assert_eq!(custom.template, "float NAME = mix(INPUT_A, INPUT_B, params[INDEX].x);");
+
+    let params = custom.get_default_params();
+    assert_eq!(params.get_names()[0], "blend");
+    assert_eq!(params.get_data()[0], 0.5);
+    assert_eq!(params.get_max()[0], 1.0);
+
+    assert!(CustomCode::parse("blend.glsl", "// @inputs 3\n").is_err());
+    assert!(CustomCode::parse("blend.glsl", "// @param blend 0.5\n").is_err());
+    assert!(CustomCode::parse("blend.glsl", DEFAULT_CODE).is_ok());
+}
@@ -0,0 +1,112 @@
+use graph::Graph;
+use operator::{ConnectionType, Op};
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Escapes the characters in `text` that have a special meaning inside of
+/// a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns the name of the classification of a connection, as it appears
+/// in the `connection` attribute of each edge.
+fn get_connection_name(connection_type: &ConnectionType) -> &'static str {
+    match *connection_type {
+        ConnectionType::Direct => "direct",
+        ConnectionType::Indirect => "indirect",
+        ConnectionType::Invalid => "invalid",
+    }
+}
+
+/// Returns a GraphViz (DOT) description of the ops in `graph`, where the
+/// active render op (if any) is at `render`. Each op is labeled with its
+/// name and family, and carries `family` and `type` attributes (i.e.
+/// `primitive` and `sphere`). Each edge carries a `connection` attribute
+/// that classifies it as direct or indirect (which is also drawn dashed,
+/// like in the network editor), along with the `input` of the destination
+/// op that it connects to, since the order of inputs matters for some ops
+/// (i.e. subtraction).
+pub fn generate<E>(graph: &Graph<Op, E>, render: Option<usize>) -> String {
+    let mut lines = vec![
+        "digraph network {".to_string(),
+        "    rankdir=LR;".to_string(),
+        "    node [shape=box, style=rounded, fontname=\"monospace\"];".to_string(),
+    ];
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let op = &node.data;
+        let peripheries = if render == Some(index) { ", peripheries=2" } else { "" };
+        lines.push(format!(
+            "    op{} [label=\"{}\\n({})\", family=\"{}\", type=\"{}\"{}];",
+            index,
+            escape(&op.name),
+            escape(op.family.to_string()),
+            op.family.get_family_name(),
+            escape(op.family.to_string()),
+            peripheries
+        ));
+    }
+
+    for (src, edges) in graph.edges.iter().enumerate() {
+        for &dst in edges.outputs.iter() {
+            let connection_type = graph.nodes[src]
+                .data
+                .family
+                .get_connection_type(graph.nodes[dst].data.family);
+            let style = match connection_type {
+                ConnectionType::Indirect => ", style=dashed",
+                _ => "",
+            };
+            let input = graph.edges[dst]
+                .inputs
+                .iter()
+                .position(|&other| other == src)
+                .unwrap_or(0);
+            lines.push(format!(
+                "    op{} -> op{} [connection=\"{}\", input={}{}];",
+                src,
+                dst,
+                get_connection_name(&connection_type),
+                input,
+                style
+            ));
+        }
+    }
+
+    lines.push("}".to_string());
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// Writes the DOT description `text` to the file at `path`.
+pub fn save(text: &str, path: &Path) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(text.as_bytes())
+}
+
+#[test]
+fn test_generate() {
+    use operator::{DomainType, OpFamily, PrimitiveType};
+    use shader_builder::make_graph;
+
+    let families = [
+        OpFamily::Domain(DomainType::Root),
+        OpFamily::Primitive(PrimitiveType::Sphere),
+        OpFamily::Primitive(PrimitiveType::Render),
+    ];
+    let graph = make_graph(&families, &[(0, 1), (1, 2)]);
+
+    let text = generate(&graph, Some(2));
+    assert!(text.starts_with("digraph network {\n"));
+    assert!(text.contains(
+        "    op1 [label=\"sphere_1\\n(sphere)\", family=\"primitive\", type=\"sphere\"];"
+    ));
+    assert!(text.contains("op2 [label=\"render_2\\n(render)\""));
+    assert!(text.contains("peripheries=2"));
+    assert!(text.contains("    op0 -> op1 [connection=\"indirect\", input=0, style=dashed];"));
+    assert!(text.contains("    op1 -> op2 [connection=\"direct\", input=0];"));
+}
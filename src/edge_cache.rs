@@ -0,0 +1,197 @@
+use cgmath::Vector2;
+
+use bounds::Rect;
+
+use std::collections::HashMap;
+
+/// The line data that a single edge is drawn with.
+pub struct EdgeGeometry {
+    /// The sampled curve (4 floats per vertex, see `DrawParams::Line`)
+    pub curve: Vec<f32>,
+
+    /// The arrowhead halfway along the curve, which is empty if the curve
+    /// has no direction there
+    pub arrow: Vec<f32>,
+
+    /// The point halfway along the curve
+    pub midpoint: Vector2<f32>,
+
+    /// The bounding box of the curve
+    pub bounds: Rect,
+}
+
+/// An entry in the cache, along with the inputs that its geometry was
+/// generated from.
+struct Entry {
+    /// The centroid of the output slot that the edge leaves from
+    from: Vector2<f32>,
+
+    /// The centroid of the input slot that the edge enters
+    to: Vector2<f32>,
+
+    /// Whether or not the edge is routed around the bodies of other ops
+    routed: bool,
+
+    /// Whether or not the edge has been drawn since the last call to
+    /// `EdgeCache::begin`
+    used: bool,
+
+    geometry: EdgeGeometry,
+}
+
+/// The geometry of every edge in the network, which is only regenerated
+/// when an edge's endpoints move. Edges that are routed around other ops
+/// are also regenerated whenever any op moves.
+pub struct EdgeCache {
+    /// The bounds of the body of every op when the current frame began
+    bodies: Vec<Rect>,
+
+    /// Whether or not any of `bodies` changed since the previous frame
+    bodies_changed: bool,
+
+    /// The cached geometry, indexed by the ops at either end of each edge
+    entries: HashMap<(usize, usize), Entry>,
+}
+
+impl EdgeCache {
+    pub fn new() -> EdgeCache {
+        EdgeCache {
+            bodies: Vec::new(),
+            bodies_changed: true,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Starts a new frame, where `bodies` are the bounds of the body of
+    /// every op.
+    pub fn begin<I>(&mut self, bodies: I)
+    where
+        I: Iterator<Item = Rect> + Clone,
+    {
+        self.bodies_changed = bodies.clone().count() != self.bodies.len()
+            || bodies.clone().zip(self.bodies.iter()).any(|(a, b)| a != *b);
+        if self.bodies_changed {
+            self.bodies.clear();
+            self.bodies.extend(bodies);
+        }
+
+        for entry in self.entries.values_mut() {
+            entry.used = false;
+        }
+    }
+
+    /// Marks the edge from op `src` to op `dst` as drawn during this frame
+    /// and returns `true` if its geometry has to be regenerated (see
+    /// `insert`), which is the case if it doesn't exist yet or if it was
+    /// generated from different inputs.
+    pub fn mark(
+        &mut self,
+        src: usize,
+        dst: usize,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+        routed: bool,
+    ) -> bool {
+        let bodies_changed = self.bodies_changed;
+        match self.entries.get_mut(&(src, dst)) {
+            Some(entry) => {
+                entry.used = true;
+                entry.from != from || entry.to != to || entry.routed != routed
+                    || (routed && bodies_changed)
+            }
+            None => true,
+        }
+    }
+
+    /// Stores the `geometry` of the edge from op `src` to op `dst`, which
+    /// was generated from the given inputs (see `mark`).
+    pub fn insert(
+        &mut self,
+        src: usize,
+        dst: usize,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+        routed: bool,
+        geometry: EdgeGeometry,
+    ) {
+        let entry = Entry {
+            from,
+            to,
+            routed,
+            used: true,
+            geometry,
+        };
+        self.entries.insert((src, dst), entry);
+    }
+
+    /// Returns the geometry of the edge from op `src` to op `dst` (if it
+    /// exists).
+    pub fn get(&self, src: usize, dst: usize) -> Option<&EdgeGeometry> {
+        self.entries.get(&(src, dst)).map(|entry| &entry.geometry)
+    }
+
+    /// Returns the geometry of the edge from op `src` to op `dst` as it was
+    /// last drawn, but only if it was generated for the same endpoints
+    /// (otherwise, the ops may have been re-indexed or moved since then).
+    pub fn get_current(
+        &self,
+        src: usize,
+        dst: usize,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+    ) -> Option<&EdgeGeometry> {
+        self.entries
+            .get(&(src, dst))
+            .filter(|entry| entry.from == from && entry.to == to)
+            .map(|entry| &entry.geometry)
+    }
+
+    /// Forgets the geometry of any edges that weren't drawn during this
+    /// frame (i.e. edges that were removed).
+    pub fn end(&mut self) {
+        self.entries.retain(|_, entry| entry.used);
+    }
+}
+
+#[test]
+fn test_edge_cache() {
+    let geometry = || EdgeGeometry {
+        curve: Vec::new(),
+        arrow: Vec::new(),
+        midpoint: Vector2::new(0.0, 0.0),
+        bounds: Rect::default(),
+    };
+    let from = Vector2::new(0.0, 0.0);
+    let to = Vector2::new(100.0, 0.0);
+    let body = Rect::new(Vector2::new(0.0, 0.0), Vector2::new(10.0, 10.0));
+
+    let mut cache = EdgeCache::new();
+    cache.begin(vec![body].into_iter());
+    assert!(cache.mark(0, 1, from, to, true));
+    cache.insert(0, 1, from, to, true, geometry());
+    assert!(cache.mark(1, 2, from, to, false));
+    cache.insert(1, 2, from, to, false, geometry());
+    cache.end();
+
+    // Nothing moved.
+    cache.begin(vec![body].into_iter());
+    assert!(!cache.mark(0, 1, from, to, true));
+    assert!(!cache.mark(1, 2, from, to, false));
+
+    // An op moved, which only affects routed edges.
+    let moved = Rect::new(Vector2::new(5.0, 0.0), Vector2::new(10.0, 10.0));
+    cache.begin(vec![moved].into_iter());
+    assert!(cache.mark(0, 1, from, to, true));
+    assert!(!cache.mark(1, 2, from, to, false));
+
+    // The endpoints of an edge moved.
+    assert!(cache.mark(1, 2, from, from, false));
+
+    // Edges that aren't drawn are forgotten.
+    cache.begin(vec![moved].into_iter());
+    cache.mark(1, 2, from, to, false);
+    cache.end();
+    assert!(cache.get(0, 1).is_none());
+    assert!(cache.get(1, 2).is_some());
+    assert!(cache.get_current(1, 2, from, from).is_none());
+}
@@ -0,0 +1,105 @@
+use image::ImageError;
+
+use shader_builder::BuildError;
+use shader_error::ShaderError;
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Anything that can go wrong while setting up the editor or loading the
+/// files that it depends on, which is reported to the user rather than
+/// aborting the process.
+#[derive(Debug)]
+pub enum Error {
+    /// The file or directory at the path couldn't be read
+    Io(PathBuf, io::Error),
+
+    /// The image at the path couldn't be decoded
+    Image(PathBuf, ImageError),
+
+    /// An asset that the editor can't do without (i.e. the icon that is
+    /// shared by custom ops) doesn't exist
+    MissingAsset(PathBuf),
+
+    /// A shader file couldn't be loaded, or one of its `#include`s couldn't
+    /// be expanded
+    Source(String),
+
+    /// A built-in shader program failed to compile or link
+    Shader(ShaderError),
+
+    /// A shader graph couldn't be turned into shader code
+    Build(BuildError),
+
+    /// The window or its OpenGL context couldn't be created
+    Window(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            Error::Image(ref path, ref error) => write!(f, "{}: {}", path.display(), error),
+            Error::MissingAsset(ref path) => write!(f, "missing asset {}", path.display()),
+            Error::Source(ref message) => write!(f, "{}", message),
+            Error::Shader(ref error) => write!(f, "{}", error),
+            Error::Build(ref error) => write!(f, "{}", error),
+            Error::Window(ref message) => write!(f, "failed to create a window: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io(..) => "couldn't read a file",
+            Error::Image(..) => "couldn't decode an image",
+            Error::MissingAsset(_) => "missing asset",
+            Error::Source(_) => "couldn't load a shader",
+            Error::Shader(_) => "couldn't compile a shader",
+            Error::Build(_) => "couldn't build a shader graph",
+            Error::Window(_) => "couldn't create a window",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(_, ref error) => Some(error),
+            Error::Image(_, ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ShaderError> for Error {
+    fn from(error: ShaderError) -> Error {
+        Error::Shader(error)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(error: BuildError) -> Error {
+        Error::Build(error)
+    }
+}
+
+#[test]
+fn test_display() {
+    let error = Error::MissingAsset(PathBuf::from("assets/custom.png"));
+    assert_eq!(error.to_string(), "missing asset assets/custom.png");
+
+    let error = Error::from(BuildError::MissingInputs("union_3".to_string()));
+    assert_eq!(error.to_string(), "union_3 is missing inputs");
+}
+
+#[test]
+fn test_source() {
+    use std::error::Error as StdError;
+
+    let inner = io::Error::new(io::ErrorKind::NotFound, "not found");
+    let error = Error::Io(PathBuf::from("assets"), inner);
+    assert_eq!(error.source().unwrap().to_string(), "not found");
+    assert!(Error::MissingAsset(PathBuf::from("assets")).source().is_none());
+}
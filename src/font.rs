@@ -0,0 +1,127 @@
+use gl;
+use cgmath::{Vector2, Zero};
+
+use constants;
+use error::Error;
+use texture::Texture;
+
+use std::path::Path;
+
+/// The first character stored in the glyph atlas.
+const FIRST_CHARACTER: u32 = 32;
+
+/// The last character stored in the glyph atlas, which is also
+/// used as a fallback for any character that is not in the atlas.
+const LAST_CHARACTER: u32 = 127;
+
+/// A fixed-width bitmap font, where each printable ASCII character
+/// occupies a single cell of a grid-based glyph atlas.
+pub struct Font {
+    /// The texture that contains all of the glyphs
+    atlas: Texture,
+
+    /// The size (in pixels) of a single cell in the atlas
+    cell_size: Vector2<f32>,
+
+    /// The size (in pixels) of the region of each cell that is
+    /// actually occupied by a glyph
+    glyph_size: Vector2<f32>,
+
+    /// The number of cells along each row of the atlas
+    columns: usize,
+}
+
+impl Font {
+    /// Loads the glyph atlas at `path`.
+    pub fn new(path: &Path) -> Result<Font, Error> {
+        let atlas = Texture::new(path)?;
+
+        // Glyphs should stay crisp when they are scaled up.
+        atlas.set_filter(gl::NEAREST, gl::NEAREST);
+
+        let columns = (atlas.get_resolution().x / constants::FONT_CELL_SIZE.x) as usize;
+
+        Ok(Font {
+            atlas,
+            cell_size: constants::FONT_CELL_SIZE,
+            glyph_size: constants::FONT_GLYPH_SIZE,
+            columns,
+        })
+    }
+
+    /// Returns the texture that contains all of the glyphs.
+    pub fn get_atlas(&self) -> &Texture {
+        &self.atlas
+    }
+
+    /// Returns the distance between the tops of two successive lines
+    /// of text drawn at `scale`.
+    pub fn get_line_height(&self, scale: f32) -> f32 {
+        (self.glyph_size.y + constants::FONT_LINE_SPACING) * scale
+    }
+
+    /// Returns the size (width and height) of the region that `text`
+    /// will occupy when drawn at `scale`.
+    pub fn measure(&self, text: &str, scale: f32) -> Vector2<f32> {
+        let mut size = Vector2::zero();
+        for (i, line) in text.lines().enumerate() {
+            let width = line.chars().count() as f32 * self.glyph_size.x * scale;
+            size.x = if width > size.x { width } else { size.x };
+            size.y = i as f32 * self.get_line_height(scale) + self.glyph_size.y * scale;
+        }
+        size
+    }
+
+    /// Lays out `text` so that its upper-left corner is at `origin`,
+    /// returning the vertex data (positions followed by texture
+    /// coordinates) for two triangles per glyph.
+    pub fn layout(&self, text: &str, origin: &Vector2<f32>, scale: f32) -> Vec<f32> {
+        let mut vertices = Vec::with_capacity(text.len() * 24);
+        let size = self.glyph_size * scale;
+
+        for (row, line) in text.lines().enumerate() {
+            let y = origin.y + row as f32 * self.get_line_height(scale);
+
+            for (column, character) in line.chars().enumerate() {
+                let x = origin.x + column as f32 * size.x;
+
+                let (uv_ul, uv_lr) = self.get_texcoords(character);
+                vertices.extend_from_slice(&[
+                    // First triangle
+                    x, y, uv_ul.x, uv_ul.y,
+                    x + size.x, y, uv_lr.x, uv_ul.y,
+                    x, y + size.y, uv_ul.x, uv_lr.y,
+
+                    // Second triangle
+                    x + size.x, y, uv_lr.x, uv_ul.y,
+                    x + size.x, y + size.y, uv_lr.x, uv_lr.y,
+                    x, y + size.y, uv_ul.x, uv_lr.y,
+                ]);
+            }
+        }
+        vertices
+    }
+
+    /// Returns the upper-left and lower-right texture coordinates of
+    /// the glyph that corresponds to `character`.
+    fn get_texcoords(&self, character: char) -> (Vector2<f32>, Vector2<f32>) {
+        let mut code = character as u32;
+        if !(FIRST_CHARACTER..=LAST_CHARACTER).contains(&code) {
+            code = LAST_CHARACTER;
+        }
+        let index = (code - FIRST_CHARACTER) as usize;
+
+        let resolution = self.atlas.get_resolution();
+        let cell = Vector2::new(
+            (index % self.columns) as f32 * self.cell_size.x,
+            (index / self.columns) as f32 * self.cell_size.y,
+        );
+
+        let ul = Vector2::new(cell.x / resolution.x, cell.y / resolution.y);
+        let lr = Vector2::new(
+            (cell.x + self.glyph_size.x) / resolution.x,
+            (cell.y + self.glyph_size.y) / resolution.y,
+        );
+        (ul, lr)
+    }
+}
@@ -0,0 +1,153 @@
+/// The indentation of each line of generated code inside of the `map(..)`
+/// function.
+const INDENT: &str = "    ";
+
+/// How generated GLSL is laid out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Style {
+    /// Indented code, where the code of each op is preceded by a comment
+    /// with the op's name (i.e. `// op: sphere_3`)
+    Pretty,
+
+    /// Code without comments or unnecessary whitespace, i.e. for export,
+    /// where the code of each op takes up a single line
+    Minified,
+}
+
+/// Returns `true` if `c` can be part of an identifier or number.
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Returns `true` if `c` can be part of an operator that spans several
+/// characters (i.e. `<=` or `--`).
+fn is_operator(c: char) -> bool {
+    "+-*/%<>=!&|^".contains(c)
+}
+
+/// Returns `code` without any comments.
+fn strip_comments(code: &str) -> String {
+    let mut stripped = String::new();
+    let mut chars = code.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some(&'/')) => {
+                while chars.peek().map_or(false, |&next| next != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some(&'*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                stripped.push(' ');
+            }
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Returns `code` without comments or unnecessary whitespace. Preprocessor
+/// directives are kept on lines of their own, while everything else is
+/// joined into as few lines as possible.
+pub fn minify(code: &str) -> String {
+    let mut minified = String::new();
+    let mut last: Option<char> = None;
+
+    for line in strip_comments(code).lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            if last.is_some() {
+                minified.push('\n');
+            }
+            minified.push_str(line);
+            minified.push('\n');
+            last = None;
+            continue;
+        }
+
+        // Whitespace is only kept where removing it would merge two tokens
+        // (i.e. `in vec3` or `a - -b`), and line breaks count as whitespace.
+        let mut space = true;
+        for c in line.chars() {
+            if c.is_whitespace() {
+                space = true;
+                continue;
+            }
+            if let Some(previous) = last {
+                let merges = (is_word(previous) && is_word(c))
+                    || (is_operator(previous) && is_operator(c));
+                if space && merges {
+                    minified.push(' ');
+                }
+            }
+            minified.push(c);
+            last = Some(c);
+            space = false;
+        }
+    }
+    if last.is_some() {
+        minified.push('\n');
+    }
+    minified
+}
+
+/// Returns the non-empty lines of `code`, without the indentation that
+/// all of them have in common, while keeping any indentation inside of
+/// the code (i.e. of the body of an `if` statement).
+pub fn dedent(code: &str) -> String {
+    let lines: Vec<&str> = code.lines().filter(|line| !line.trim().is_empty()).collect();
+    let indent = lines
+        .iter()
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let lines: Vec<&str> = lines.iter().map(|line| line[indent..].trim_end()).collect();
+    lines.join("\n")
+}
+
+/// Lays out the (dedented) generated `code` of the op named `name` in
+/// `style`, ending with a newline.
+pub fn format_op(name: &str, code: &str, style: Style) -> String {
+    match style {
+        Style::Pretty => {
+            let mut formatted = format!("{}// op: {}\n", INDENT, name);
+            for line in code.lines() {
+                formatted.push_str(INDENT);
+                formatted.push_str(line);
+                formatted.push('\n');
+            }
+            formatted
+        }
+        Style::Minified => minify(code),
+    }
+}
+
+#[test]
+fn test_minify() {
+    let code = "#version 430\n// A comment.\nfloat a = b - -c; /* x */\n\n\
+                vec3 f(in vec3 p)\n{\n    return p * 2.0;\n}\n";
+    assert_eq!(minify(code), "#version 430\nfloat a=b- -c;vec3 f(in vec3 p){return p*2.0;}\n");
+}
+
+#[test]
+fn test_format_op() {
+    let code = "\n        float a = 1.0;\n        if (a > 0.0)\n            a = 2.0;";
+    let code = &dedent(code);
+    assert_eq!(code, "float a = 1.0;\nif (a > 0.0)\n    a = 2.0;");
+    assert_eq!(
+        format_op("a_0", code, Style::Pretty),
+        "    // op: a_0\n    float a = 1.0;\n    if (a > 0.0)\n        a = 2.0;\n"
+    );
+    assert_eq!(format_op("a_0", code, Style::Minified), "float a=1.0;if(a>0.0)a=2.0;\n");
+}
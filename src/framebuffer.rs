@@ -0,0 +1,173 @@
+use gl;
+use gl::types::*;
+use cgmath::{Vector2, Vector4};
+use image;
+
+use texture::Texture;
+
+use std::path::Path;
+
+/// A wrapper around an OpenGL framebuffer object (FBO) with a single
+/// color attachment, which can be sampled like any other texture
+/// after it has been rendered to.
+pub struct Framebuffer {
+    /// The OpenGL handle to the FBO
+    id: GLuint,
+
+    /// The texture that will be rendered to
+    color_attachment: Texture,
+}
+
+impl Framebuffer {
+    /// Creates a new framebuffer with an sRGB color attachment of the
+    /// specified dimensions, which stores linear colors with more
+    /// precision in the darks than a plain 8-bit attachment.
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer::with_format(width, height, gl::SRGB8_ALPHA8)
+    }
+
+    /// Creates a new framebuffer with a color attachment of the
+    /// specified dimensions and internal format.
+    pub fn with_format(width: u32, height: u32, format: GLenum) -> Framebuffer {
+        let color_attachment = Texture::empty_with_format(width, height, format);
+
+        let mut id = 0;
+        unsafe {
+            gl::CreateFramebuffers(1, &mut id);
+            gl::NamedFramebufferTexture(id, gl::COLOR_ATTACHMENT0, color_attachment.get_id(), 0);
+
+            let status = gl::CheckNamedFramebufferStatus(id, gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                error!("Framebuffer is incomplete - status: {}", status);
+            }
+        }
+
+        Framebuffer {
+            id,
+            color_attachment,
+        }
+    }
+
+    /// Returns the texture that this framebuffer renders to.
+    pub fn get_color_attachment(&self) -> &Texture {
+        &self.color_attachment
+    }
+
+    /// Returns the resolution (width and height) of the framebuffer.
+    pub fn get_resolution(&self) -> &Vector2<f32> {
+        self.color_attachment.get_resolution()
+    }
+
+    /// Reads back the color of the pixel at `x` and `y`, where `(0, 0)`
+    /// is the lower-left corner of the framebuffer.
+    pub fn read_pixel(&self, x: i32, y: i32) -> Vector4<f32> {
+        let mut pixel = Vector4::new(0.0, 0.0, 0.0, 0.0);
+        unsafe {
+            gl::NamedFramebufferReadBuffer(self.id, gl::COLOR_ATTACHMENT0);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::ReadPixels(
+                x,
+                y,
+                1,
+                1,
+                gl::RGBA,
+                gl::FLOAT,
+                &mut pixel as *mut Vector4<f32> as *mut GLvoid,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        pixel
+    }
+
+    /// Reads back the entire color attachment as tightly packed, 8-bit
+    /// RGBA rows, starting with the bottom row of the framebuffer.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let resolution = self.get_resolution();
+        let (width, height) = (resolution.x as i32, resolution.y as i32);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        unsafe {
+            gl::NamedFramebufferReadBuffer(self.id, gl::COLOR_ATTACHMENT0);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width,
+                height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut GLvoid,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        pixels
+    }
+
+    /// Saves the color attachment to a PNG at `path`.
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let resolution = self.get_resolution();
+        let (width, height) = (resolution.x as u32, resolution.y as u32);
+
+        // OpenGL returns the rows bottom to top, while PNGs store them
+        // top to bottom.
+        let pixels = self.read_pixels();
+        let flipped: Vec<u8> = pixels
+            .chunks(width as usize * 4)
+            .rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect();
+
+        image::save_buffer(path, &flipped, width, height, image::RGBA(8))
+            .map_err(|error| error.to_string())
+    }
+
+    /// Copies the color attachment into the color attachment of `other`,
+    /// stretching it if their resolutions differ. Linear colors are encoded
+    /// along the way if `other` stores sRGB colors.
+    pub fn blit_to(&self, other: &Framebuffer) {
+        let src = self.get_resolution();
+        let dst = other.get_resolution();
+        unsafe {
+            gl::BlitNamedFramebuffer(
+                self.id,
+                other.id,
+                0,
+                0,
+                src.x as i32,
+                src.y as i32,
+                0,
+                0,
+                dst.x as i32,
+                dst.y as i32,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR,
+            );
+        }
+    }
+
+    /// Makes this framebuffer the current render target and resizes
+    /// the viewport to cover it.
+    pub fn bind(&self) {
+        let resolution = self.get_resolution();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, resolution.x as i32, resolution.y as i32);
+        }
+    }
+
+    /// Restores the default framebuffer as the current render target.
+    /// Note that the caller is responsible for restoring the viewport.
+    pub fn unbind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
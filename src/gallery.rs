@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A network that ships with the application (inside of `EXAMPLES_PATH`),
+/// which demonstrates an op family or a common trick. Examples are saved
+/// in the same format as any other network (see `Project`).
+pub struct Example {
+    /// The name that is shown in the gallery
+    pub name: String,
+
+    /// The path of the file that holds the network
+    pub path: PathBuf,
+}
+
+/// Returns the name of the example in the file at `path`, which is the
+/// file's name without its extension or leading number, and with spaces
+/// instead of underscores (i.e. `03_smooth_blending.json` becomes
+/// `smooth blending`). The numbers only determine the order of the gallery.
+pub fn get_name(path: &Path) -> String {
+    let stem = path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    stem.trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches('_')
+        .replace('_', " ")
+}
+
+/// Lists all of the examples in the directory at `path`, ordered by the
+/// names of their files.
+pub fn load_all(path: &Path) -> Vec<Example> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| Example {
+            name: get_name(&path),
+            path,
+        })
+        .collect()
+}
+
+#[test]
+fn test_get_name() {
+    assert_eq!(get_name(Path::new("examples/03_smooth_blending.json")), "smooth blending");
+    assert_eq!(get_name(Path::new("blob.json")), "blob");
+}
@@ -0,0 +1,215 @@
+use gl::{self, types::*};
+
+use backend::{Backend, BufferHandle, BufferUsage, LayoutHandle, ProgramHandle, StepMode,
+              TextureHandle, Topology, UniformValue, VertexAttribute};
+use error::Error;
+use program::Program;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// Returns the size of `count` floats, in bytes.
+fn get_size(count: usize) -> GLsizeiptr {
+    (count * mem::size_of::<GLfloat>()) as GLsizeiptr
+}
+
+/// Implements `Backend` with OpenGL's direct state access functions, like
+/// the rest of the editor. Each handle holds the name of the underlying
+/// object (i.e. a `BufferHandle` holds the name of a buffer object).
+pub struct GlBackend {
+    /// The programs that were created through this backend, indexed by
+    /// their names, which also hold the locations of their uniforms
+    programs: RefCell<HashMap<GLuint, Program>>,
+}
+
+impl GlBackend {
+    pub fn new() -> GlBackend {
+        GlBackend {
+            programs: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl Backend for GlBackend {
+    fn init_state(&self) {
+        unsafe {
+            // Enable alpha blending.
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            // Shaders output (and blend) linear colors, which are converted
+            // to sRGB when they are written to an sRGB framebuffer.
+            gl::Enable(gl::FRAMEBUFFER_SRGB);
+        }
+    }
+
+    fn create_buffer(
+        &self,
+        capacity: usize,
+        data: Option<&[f32]>,
+        usage: BufferUsage,
+    ) -> BufferHandle {
+        let data = data.map_or(ptr::null(), |data| data.as_ptr() as *const c_void);
+        let mut buffer = 0;
+        unsafe {
+            gl::CreateBuffers(1, &mut buffer);
+            match usage {
+                BufferUsage::Static => {
+                    gl::NamedBufferData(buffer, get_size(capacity), data, gl::STATIC_DRAW)
+                }
+                BufferUsage::Dynamic => gl::NamedBufferStorage(
+                    buffer,
+                    get_size(capacity),
+                    data,
+                    gl::DYNAMIC_STORAGE_BIT,
+                ),
+
+                // Unlike the others, these buffers use mutable storage so
+                // that they can be reallocated.
+                BufferUsage::Stream => {
+                    gl::NamedBufferData(buffer, get_size(capacity), data, gl::STREAM_DRAW)
+                }
+            }
+        }
+        BufferHandle(buffer)
+    }
+
+    fn update_buffer(&self, buffer: BufferHandle, data: &[f32]) {
+        unsafe {
+            gl::NamedBufferSubData(
+                buffer.0,
+                0,
+                get_size(data.len()),
+                data.as_ptr() as *const c_void,
+            );
+        }
+    }
+
+    fn resize_buffer(&self, buffer: BufferHandle, capacity: usize) {
+        // Re-specifying the data store "orphans" the old one, which the
+        // driver keeps around until any pending draw calls are done with it.
+        unsafe {
+            gl::NamedBufferData(buffer.0, get_size(capacity), ptr::null(), gl::STREAM_DRAW);
+        }
+    }
+
+    fn delete_buffer(&self, buffer: BufferHandle) {
+        unsafe {
+            gl::DeleteBuffers(1, &buffer.0);
+        }
+    }
+
+    fn create_layout(&self, attributes: &[VertexAttribute], bindings: &[StepMode]) -> LayoutHandle {
+        let mut vao = 0;
+        unsafe {
+            gl::CreateVertexArrays(1, &mut vao);
+            for attribute in attributes.iter() {
+                gl::EnableVertexArrayAttrib(vao, attribute.location);
+                gl::VertexArrayAttribFormat(
+                    vao,
+                    attribute.location,
+                    attribute.components as GLint,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    attribute.offset * mem::size_of::<GLfloat>() as GLuint,
+                );
+                gl::VertexArrayAttribBinding(vao, attribute.location, attribute.binding);
+            }
+            for (binding, step_mode) in bindings.iter().enumerate() {
+                if *step_mode == StepMode::Instance {
+                    gl::VertexArrayBindingDivisor(vao, binding as GLuint, 1);
+                }
+            }
+        }
+        LayoutHandle(vao)
+    }
+
+    fn set_vertex_buffer(
+        &self,
+        layout: LayoutHandle,
+        binding: u32,
+        buffer: BufferHandle,
+        stride: u32,
+    ) {
+        unsafe {
+            gl::VertexArrayVertexBuffer(
+                layout.0,
+                binding,
+                buffer.0,
+                0,
+                (stride as usize * mem::size_of::<GLfloat>()) as GLsizei,
+            );
+        }
+    }
+
+    fn delete_layout(&self, layout: LayoutHandle) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &layout.0);
+        }
+    }
+
+    fn create_program(&self, vs_src: String, fs_src: String) -> Result<ProgramHandle, Error> {
+        let program = Program::new(vs_src, fs_src)?;
+        let handle = ProgramHandle(program.id);
+        self.programs.borrow_mut().insert(program.id, program);
+        Ok(handle)
+    }
+
+    fn bind_program(&self, program: ProgramHandle) {
+        unsafe {
+            gl::UseProgram(program.0);
+        }
+    }
+
+    fn unbind_program(&self) {
+        unsafe {
+            gl::UseProgram(0);
+        }
+    }
+
+    fn set_uniform(&self, program: ProgramHandle, name: &str, value: UniformValue) {
+        let programs = self.programs.borrow();
+        let program = match programs.get(&program.0) {
+            Some(program) => program,
+            None => return,
+        };
+        match value {
+            UniformValue::Int(value) => program.uniform_1i(name, value),
+            UniformValue::UInt(value) => program.uniform_1ui(name, value),
+            UniformValue::Float(value) => program.uniform_1f(name, value),
+            UniformValue::Vec4(ref value) => program.uniform_4f(name, value),
+            UniformValue::Mat4(ref value) => program.uniform_matrix_4f(name, value),
+        }
+    }
+
+    fn delete_program(&self, program: ProgramHandle) {
+        // Dropping the program deletes it.
+        self.programs.borrow_mut().remove(&program.0);
+    }
+
+    fn bind_texture(&self, unit: u32, texture: TextureHandle) {
+        unsafe {
+            gl::BindTextureUnit(unit, texture.0);
+        }
+    }
+
+    fn draw(&self, layout: LayoutHandle, topology: Topology, vertices: Range<u32>, instances: u32) {
+        let mode = match topology {
+            Topology::Triangles => gl::TRIANGLES,
+            Topology::TriangleStrip => gl::TRIANGLE_STRIP,
+        };
+        let count = (vertices.end - vertices.start) as GLsizei;
+        unsafe {
+            gl::BindVertexArray(layout.0);
+            if instances == 1 {
+                gl::DrawArrays(mode, vertices.start as GLint, count);
+            } else {
+                gl::DrawArraysInstanced(mode, vertices.start as GLint, count, instances as GLsizei);
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use gl::{self, types::*};
+use log::LogLevel;
+
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::process;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether or not the application should abort as soon as the driver
+/// reports an error (see `DebugOptions::break_on_error`).
+static BREAK_ON_ERROR: AtomicBool = AtomicBool::new(false);
+
+/// The severity of an OpenGL debug message, from least to most severe.
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub enum Severity {
+    Notification,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn all() -> [Severity; 4] {
+        [
+            Severity::Notification,
+            Severity::Low,
+            Severity::Medium,
+            Severity::High,
+        ]
+    }
+
+    /// Parses a severity from its (lowercase) name, i.e. `"medium"`.
+    pub fn from_name(name: &str) -> Option<Severity> {
+        match name {
+            "notification" => Some(Severity::Notification),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            _ => None,
+        }
+    }
+
+    pub fn from_gl(severity: GLenum) -> Severity {
+        match severity {
+            gl::DEBUG_SEVERITY_HIGH => Severity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => Severity::Medium,
+            gl::DEBUG_SEVERITY_LOW => Severity::Low,
+            _ => Severity::Notification,
+        }
+    }
+
+    pub fn to_gl(self) -> GLenum {
+        match self {
+            Severity::High => gl::DEBUG_SEVERITY_HIGH,
+            Severity::Medium => gl::DEBUG_SEVERITY_MEDIUM,
+            Severity::Low => gl::DEBUG_SEVERITY_LOW,
+            Severity::Notification => gl::DEBUG_SEVERITY_NOTIFICATION,
+        }
+    }
+}
+
+/// Options that control whether and how messages from the driver's
+/// `KHR_debug` output are reported, which are parsed from the command line
+/// (see `cli::Options`).
+pub struct DebugOptions {
+    /// Whether or not debug output is enabled (`--gl-debug`)
+    pub enabled: bool,
+
+    /// The least severe messages that will be reported
+    /// (`--gl-debug-severity=<notification|low|medium|high>`)
+    pub min_severity: Severity,
+
+    /// Whether or not to abort as soon as an error is reported, so that
+    /// an attached debugger stops on the offending GL call
+    /// (`--gl-debug-break`, which implies `--gl-debug`)
+    pub break_on_error: bool,
+}
+
+/// Returns a short, human-readable name for the source of a debug message.
+fn source_name(source: GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+/// Returns a short, human-readable name for the type of a debug message.
+fn type_name(gltype: GLenum) -> &'static str {
+    match gltype {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        _ => "other",
+    }
+}
+
+/// Logs a single message from the driver, at the level that matches its
+/// severity.
+extern "system" fn callback(
+    source: GLenum,
+    gltype: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    _length: GLsizei,
+    message: *const GLchar,
+    _user_param: *mut c_void,
+) {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    let severity = Severity::from_gl(severity);
+    let level = match severity {
+        Severity::Notification => LogLevel::Debug,
+        Severity::Low => LogLevel::Info,
+        Severity::Medium => LogLevel::Warn,
+        Severity::High => LogLevel::Error,
+    };
+    log!(
+        level,
+        "GL {:?} ({}, {}, id {}): {}",
+        severity,
+        type_name(gltype),
+        source_name(source),
+        id,
+        message
+    );
+
+    // Unwinding out of a callback that was invoked by the driver isn't
+    // allowed, so abort instead of panicking.
+    if gltype == gl::DEBUG_TYPE_ERROR && BREAK_ON_ERROR.load(Ordering::Relaxed) {
+        process::abort();
+    }
+}
+
+/// Registers a callback that reports the driver's debug messages, if
+/// debug output was requested and `KHR_debug` is available. This must be
+/// called after the OpenGL function pointers have been loaded.
+pub fn enable(options: &DebugOptions) {
+    if !options.enabled {
+        return;
+    }
+    if !gl::DebugMessageCallback::is_loaded() {
+        warn!("OpenGL debug output (KHR_debug) is not available");
+        return;
+    }
+
+    BREAK_ON_ERROR.store(options.break_on_error, Ordering::Relaxed);
+    unsafe {
+        // Report messages on the thread (and during the call) that caused
+        // them, so that they show up next to the offending call.
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+
+        // Let the driver filter out messages below the minimum severity.
+        gl::DebugMessageControl(
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            0,
+            ptr::null(),
+            gl::FALSE,
+        );
+        for severity in Severity::all().iter() {
+            if *severity >= options.min_severity {
+                gl::DebugMessageControl(
+                    gl::DONT_CARE,
+                    gl::DONT_CARE,
+                    severity.to_gl(),
+                    0,
+                    ptr::null(),
+                    gl::TRUE,
+                );
+            }
+        }
+
+        gl::DebugMessageCallback(callback, ptr::null());
+    }
+
+    console!(
+        "OpenGL debug output enabled (minimum severity: {:?})",
+        options.min_severity
+    );
+}
@@ -0,0 +1,227 @@
+use cgmath::Vector3;
+use serde_json::{self, Value};
+
+use color::Color;
+use mesh::Mesh;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The magic number at the start of a binary glTF file (`glTF`).
+const GLB_MAGIC: u32 = 0x4654_6C67;
+
+/// The types of the two chunks of a binary glTF file (`JSON` and `BIN`).
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+
+/// The component types and buffer targets that glTF borrows from OpenGL.
+const GLTF_FLOAT: u32 = 5126;
+const GLTF_UNSIGNED_INT: u32 = 5125;
+const GLTF_ARRAY_BUFFER: u32 = 34962;
+const GLTF_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[
+        value as u8,
+        (value >> 8) as u8,
+        (value >> 16) as u8,
+        (value >> 24) as u8,
+    ]);
+}
+
+fn push_vectors(bytes: &mut Vec<u8>, vectors: &[Vector3<f32>]) {
+    for vector in vectors.iter() {
+        push_u32(bytes, vector.x.to_bits());
+        push_u32(bytes, vector.y.to_bits());
+        push_u32(bytes, vector.z.to_bits());
+    }
+}
+
+fn vector_to_json(vector: Vector3<f32>) -> Value {
+    json!([vector.x, vector.y, vector.z])
+}
+
+/// Describes the contents of a binary glTF file that holds `mesh`, which
+/// is stored in a single buffer as its positions, followed by its normals,
+/// followed by the indices of each of its `objects` (see
+/// `Mesh::split_by_object`). The mesh is a single node named `name`, with
+/// a primitive for each object. Each primitive has its own material of the
+/// (sRGB-encoded) `color`, which is named after the object (see `names`).
+fn describe(
+    mesh: &Mesh,
+    objects: &[(i32, Vec<u32>)],
+    name: &str,
+    names: &HashMap<i32, String>,
+    color: Color,
+) -> Value {
+    let vertex_bytes = mesh.positions.len() * 12;
+    let index_bytes = mesh.indices.len() * 4;
+    let (min, max) = mesh
+        .get_bounds()
+        .unwrap_or((Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)));
+    let color = color.to_linear();
+
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    let mut accessors = vec![
+        json!({
+            "bufferView": 0,
+            "componentType": GLTF_FLOAT,
+            "count": mesh.positions.len(),
+            "type": "VEC3",
+            "min": vector_to_json(min),
+            "max": vector_to_json(max),
+        }),
+        json!({
+            "bufferView": 1,
+            "componentType": GLTF_FLOAT,
+            "count": mesh.normals.len(),
+            "type": "VEC3",
+        }),
+    ];
+    let mut offset = 0;
+    for (material, (object, indices)) in objects.iter().enumerate() {
+        primitives.push(json!({
+            "attributes": { "POSITION": 0, "NORMAL": 1 },
+            "indices": accessors.len(),
+            "material": material,
+        }));
+        materials.push(json!({
+            "name": names.get(object).map_or(name, |name| &name[..]),
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [color.r, color.g, color.b, 1.0],
+                "metallicFactor": 0.0,
+                "roughnessFactor": 0.5,
+            },
+        }));
+        accessors.push(json!({
+            "bufferView": 2,
+            "byteOffset": offset,
+            "componentType": GLTF_UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        offset += indices.len() * 4;
+    }
+
+    json!({
+        "asset": { "version": "2.0", "generator": "sdfperf" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": name, "mesh": 0 }],
+        "meshes": [{ "name": name, "primitives": primitives }],
+        "materials": materials,
+        "buffers": [{ "byteLength": vertex_bytes * 2 + index_bytes }],
+        "bufferViews": [
+            {
+                "buffer": 0,
+                "byteOffset": 0,
+                "byteLength": vertex_bytes,
+                "target": GLTF_ARRAY_BUFFER,
+            },
+            {
+                "buffer": 0,
+                "byteOffset": vertex_bytes,
+                "byteLength": vertex_bytes,
+                "target": GLTF_ARRAY_BUFFER,
+            },
+            {
+                "buffer": 0,
+                "byteOffset": vertex_bytes * 2,
+                "byteLength": index_bytes,
+                "target": GLTF_ELEMENT_ARRAY_BUFFER,
+            },
+        ],
+        "accessors": accessors,
+    })
+}
+
+/// Returns a binary glTF 2.0 (`.glb`) file that holds `mesh`, which can be
+/// opened by most game engines and 3D viewers. See `describe` for how the
+/// scene is laid out.
+pub fn generate(mesh: &Mesh, name: &str, names: &HashMap<i32, String>, color: Color) -> Vec<u8> {
+    let objects = mesh.split_by_object();
+
+    // Both chunks must be padded to a multiple of four bytes: the JSON
+    // chunk with spaces and the binary chunk with zeros.
+    let mut json = serde_json::to_string(&describe(mesh, &objects, name, names, color))
+        .unwrap_or_default()
+        .into_bytes();
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+
+    let mut binary = Vec::new();
+    push_vectors(&mut binary, &mesh.positions);
+    push_vectors(&mut binary, &mesh.normals);
+    for (_, indices) in objects.iter() {
+        for &index in indices.iter() {
+            push_u32(&mut binary, index);
+        }
+    }
+
+    let length = 12 + 8 + json.len() + 8 + binary.len();
+    let mut bytes = Vec::with_capacity(length);
+    push_u32(&mut bytes, GLB_MAGIC);
+    push_u32(&mut bytes, 2);
+    push_u32(&mut bytes, length as u32);
+    push_u32(&mut bytes, json.len() as u32);
+    push_u32(&mut bytes, GLB_CHUNK_JSON);
+    bytes.extend_from_slice(&json);
+    push_u32(&mut bytes, binary.len() as u32);
+    push_u32(&mut bytes, GLB_CHUNK_BIN);
+    bytes.extend_from_slice(&binary);
+    bytes
+}
+
+/// Writes the binary glTF file `bytes` to `path`.
+pub fn save(bytes: &[u8], path: &Path) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)
+}
+
+#[test]
+fn test_generate() {
+    let mesh = Mesh {
+        positions: vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+        ],
+        normals: vec![Vector3::new(0.0, 0.0, 1.0); 4],
+        indices: vec![0, 1, 2, 1, 3, 2],
+        objects: vec![2, 1],
+    };
+    let mut names = HashMap::new();
+    names.insert(1, "sphere_1".to_string());
+    let bytes = generate(&mesh, "render", &names, Color::white());
+
+    let read_u32 = |offset: usize| {
+        bytes[offset..offset + 4]
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | byte as u32)
+    };
+    assert_eq!(&bytes[0..4], b"glTF");
+    assert_eq!(read_u32(8) as usize, bytes.len());
+
+    let json_length = read_u32(12) as usize;
+    assert_eq!(json_length % 4, 0);
+    let json: Value = serde_json::from_slice(&bytes[20..20 + json_length]).unwrap();
+    assert_eq!(json["accessors"][0]["count"], json!(4));
+    assert_eq!(json["accessors"][0]["max"], json!([1.0, 1.0, 0.0]));
+
+    // Each object gets its own primitive and material, in order of their
+    // IDs, and objects without a name are named after the mesh.
+    assert_eq!(json["meshes"][0]["primitives"][1]["indices"], json!(3));
+    assert_eq!(json["accessors"][3]["byteOffset"], json!(12));
+    assert_eq!(json["materials"][0]["name"], json!("sphere_1"));
+    assert_eq!(json["materials"][1]["name"], json!("render"));
+
+    // Four positions and normals, plus six indices.
+    assert_eq!(read_u32(20 + json_length) as usize, 4 * 12 * 2 + 6 * 4);
+    assert_eq!(&bytes[24 + json_length..28 + json_length], b"BIN\0");
+}
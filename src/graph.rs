@@ -157,8 +157,9 @@ impl<N: Connected, E> Graph<N, E> {
             // the edge connecting its last input with `b` with
             // the new edge.
             if self.nodes[dst].data.get_number_of_available_inputs() == 0 {
-                let old = self.edges[dst].inputs.pop().unwrap();
-                self.remove_edge(old, dst);
+                if let Some(old) = self.edges[dst].inputs.pop() {
+                    self.remove_edge(old, dst);
+                }
             } else {
 
             }
@@ -170,28 +171,44 @@ impl<N: Connected, E> Graph<N, E> {
             self.edges[src].outputs.push(dst);
             self.edges[dst].inputs.push(src);
         } else {
-            println!("Connection failed");
+            warn!("Connection failed");
         }
     }
 
     /// Performs a post-order traversal of the graph, returning
     /// the node indices in the proper order.
-    pub fn traverse(&mut self, root: usize) -> Vec<usize> {
+    pub fn traverse(&self, root: usize) -> Vec<usize> {
+        self.traverse_until(root, |_| false)
+    }
+
+    /// Like `traverse`, but doesn't look past the inputs of any node for
+    /// which `stop` returns `true`, so the nodes that only feed into those
+    /// nodes are left out.
+    pub fn traverse_until<F>(&self, root: usize, stop: F) -> Vec<usize>
+    where
+        F: Fn(usize) -> bool,
+    {
         let mut indices = Vec::new();
         let mut visited = Vec::new();
 
         // Traverse the graph, starting at the root.
         visited.push(root);
-        self.recurse(root, &mut indices, &mut visited);
+        self.recurse(root, &stop, &mut indices, &mut visited);
 
         indices
     }
 
     /// Examine a `root` op's inputs and recurse backwards until
-    /// reaching a leaf node (i.e. an op with no other inputs).
-    fn recurse(&self, root: usize, indices: &mut Vec<usize>, visited: &mut Vec<usize>) {
-        for index in self.edges[root].inputs.iter() {
-            self.recurse(*index, indices, visited);
+    /// reaching a leaf node (i.e. an op with no other inputs), or
+    /// a node where `stop` returns `true`.
+    fn recurse<F>(&self, root: usize, stop: &F, indices: &mut Vec<usize>, visited: &mut Vec<usize>)
+    where
+        F: Fn(usize) -> bool,
+    {
+        if !stop(root) {
+            for index in self.edges[root].inputs.iter() {
+                self.recurse(*index, stop, indices, visited);
+            }
         }
 
         // Finally, push back the root index: note that
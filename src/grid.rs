@@ -0,0 +1,180 @@
+use cgmath::Vector3;
+use gl::{self, types::*};
+
+use program::Program;
+
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// The number of invocations along each axis of a work group, which
+/// matches the `local_size_*` qualifiers in `grid_header.glsl`.
+const WORK_GROUP_SIZE: u32 = 4;
+
+/// A regular grid of points that span an axis-aligned box, at which the
+/// distance field of a shader graph can be evaluated on the GPU (i.e. for
+/// mesh extraction or volume export).
+#[derive(Copy, Clone, Debug)]
+pub struct SampleGrid {
+    /// The corner of the box with the smallest coordinates
+    pub min: Vector3<f32>,
+
+    /// The corner of the box with the largest coordinates
+    pub max: Vector3<f32>,
+
+    /// The number of points along each axis, where the first and last
+    /// points lie on the faces of the box
+    pub resolution: Vector3<u32>,
+}
+
+impl SampleGrid {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>, resolution: Vector3<u32>) -> SampleGrid {
+        SampleGrid {
+            min,
+            max,
+            resolution,
+        }
+    }
+
+    /// Returns the total number of points in the grid.
+    pub fn get_point_count(&self) -> usize {
+        (self.resolution.x * self.resolution.y * self.resolution.z) as usize
+    }
+
+    /// Returns the distance between neighboring points along each axis.
+    pub fn get_spacing(&self) -> Vector3<f32> {
+        let spacing = |extent: f32, count: u32| extent / (count.max(2) - 1) as f32;
+        let extent = self.max - self.min;
+        Vector3::new(
+            spacing(extent.x, self.resolution.x),
+            spacing(extent.y, self.resolution.y),
+            spacing(extent.z, self.resolution.z),
+        )
+    }
+
+    /// Returns the cell (i.e. the coordinates along each axis) of the point
+    /// at `index` within the distances returned by `evaluate`, where the
+    /// x-coordinate varies fastest and the z-coordinate slowest.
+    pub fn get_cell(&self, index: usize) -> Vector3<u32> {
+        let index = index as u32;
+        Vector3::new(
+            index % self.resolution.x,
+            (index / self.resolution.x) % self.resolution.y,
+            index / (self.resolution.x * self.resolution.y),
+        )
+    }
+
+    /// Returns the position of the point at `cell`.
+    pub fn get_point(&self, cell: Vector3<u32>) -> Vector3<f32> {
+        let spacing = self.get_spacing();
+        Vector3::new(
+            self.min.x + spacing.x * cell.x as f32,
+            self.min.y + spacing.y * cell.y as f32,
+            self.min.z + spacing.z * cell.z as f32,
+        )
+    }
+
+    /// Evaluates the distance field at every point of the grid with
+    /// `program` (see `ShaderBuilder::build_grid_program`), returning the
+    /// distances in the order described by `get_cell`. The buffer that
+    /// holds the op parameters must already be bound (see
+    /// `Preview::bind_transforms`). This blocks until the GPU is done.
+    pub fn evaluate(&self, program: &Program) -> Vec<f32> {
+        self.evaluate_with_ids(program).0
+    }
+
+    /// Like `evaluate`, but also returns the ID of the object that each
+    /// distance belongs to (i.e. the generator op that is closest).
+    pub fn evaluate_with_ids(&self, program: &Program) -> (Vec<f32>, Vec<f32>) {
+        let mut distances = vec![0.0f32; self.get_point_count()];
+        let mut ids = vec![-1.0f32; self.get_point_count()];
+        let size = (distances.len() * mem::size_of::<f32>()) as GLsizeiptr;
+
+        let groups = |count: u32| (count + WORK_GROUP_SIZE - 1) / WORK_GROUP_SIZE;
+
+        program.bind();
+        program.uniform_3f("u_grid_min", &self.min);
+        program.uniform_3f("u_grid_max", &self.max);
+        program.uniform_3ui("u_grid_resolution", &self.resolution);
+        unsafe {
+            let mut buffers = [0; 2];
+            gl::CreateBuffers(2, buffers.as_mut_ptr());
+            for (binding, &buffer) in buffers.iter().enumerate() {
+                gl::NamedBufferStorage(buffer, size, ptr::null(), 0);
+                gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding as GLuint + 1, buffer);
+            }
+
+            gl::DispatchCompute(
+                groups(self.resolution.x),
+                groups(self.resolution.y),
+                groups(self.resolution.z),
+            );
+
+            // Make sure that the results are written before reading them back.
+            gl::MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+            gl::GetNamedBufferSubData(buffers[0], 0, size, distances.as_mut_ptr() as *mut c_void);
+            gl::GetNamedBufferSubData(buffers[1], 0, size, ids.as_mut_ptr() as *mut c_void);
+
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 1, 0);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 2, 0);
+            gl::DeleteBuffers(2, buffers.as_ptr());
+        }
+        program.unbind();
+
+        (distances, ids)
+    }
+
+    /// Returns the corners of the box that encloses all of the points
+    /// inside of the surface, according to the evaluated `distances`, or
+    /// `None` if there aren't any. The box is grown by a single cell in
+    /// every direction (within the grid), since the surface may extend
+    /// past the innermost points.
+    pub fn get_bounds(&self, distances: &[f32]) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        let mut cells = distances
+            .iter()
+            .enumerate()
+            .filter(|&(_, &distance)| distance <= 0.0)
+            .map(|(index, _)| self.get_cell(index));
+
+        let first = cells.next()?;
+        let (lower, upper) = cells.fold((first, first), |(lower, upper), cell| {
+            (
+                Vector3::new(lower.x.min(cell.x), lower.y.min(cell.y), lower.z.min(cell.z)),
+                Vector3::new(upper.x.max(cell.x), upper.y.max(cell.y), upper.z.max(cell.z)),
+            )
+        });
+
+        let last = self.resolution - Vector3::new(1, 1, 1);
+        let lower = Vector3::new(
+            lower.x.saturating_sub(1),
+            lower.y.saturating_sub(1),
+            lower.z.saturating_sub(1),
+        );
+        let upper = Vector3::new(
+            (upper.x + 1).min(last.x),
+            (upper.y + 1).min(last.y),
+            (upper.z + 1).min(last.z),
+        );
+        Some((self.get_point(lower), self.get_point(upper)))
+    }
+}
+
+#[test]
+fn test_grid() {
+    let grid = SampleGrid::new(
+        Vector3::new(-1.0, -1.0, -1.0),
+        Vector3::new(1.0, 1.0, 1.0),
+        Vector3::new(5, 5, 5),
+    );
+    assert_eq!(grid.get_point_count(), 125);
+    assert_eq!(grid.get_cell(1 + 5 * (2 + 5 * 3)), Vector3::new(1, 2, 3));
+    assert_eq!(grid.get_point(Vector3::new(0, 2, 4)), Vector3::new(-1.0, 0.0, 1.0));
+
+    // A single point inside of the surface, at the center of the grid.
+    let mut distances = vec![1.0; grid.get_point_count()];
+    assert!(grid.get_bounds(&distances).is_none());
+    distances[2 + 5 * (2 + 5 * 2)] = -0.1;
+    let (min, max) = grid.get_bounds(&distances).unwrap();
+    assert_eq!(min, Vector3::new(-0.5, -0.5, -0.5));
+    assert_eq!(max, Vector3::new(0.5, 0.5, 0.5));
+}
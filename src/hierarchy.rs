@@ -0,0 +1,364 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+
+use constants;
+use graph::Graph;
+use operator::{DomainType, Op, OpFamily, PrimitiveType};
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A sphere that encloses the surface of part of a shader graph.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3<f32>, radius: f32) -> Sphere {
+        Sphere { center, radius }
+    }
+
+    /// Returns a sphere that encloses everything, which is used for parts
+    /// of a graph whose bounds are unknown.
+    pub fn unbounded() -> Sphere {
+        Sphere::new(Vector3::zero(), 1e10)
+    }
+
+    /// Returns the smallest sphere that encloses both this sphere and
+    /// `other`.
+    pub fn merge(&self, other: &Sphere) -> Sphere {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+        Sphere::new(center, radius)
+    }
+}
+
+/// Returns the radius of the sphere around the origin of its domain that
+/// encloses the surface of a generator of `family` (see `get_code_template`),
+/// or `None` if it isn't known (or the surface is infinite).
+fn get_local_radius(family: OpFamily) -> Option<f32> {
+    match family {
+        OpFamily::Primitive(PrimitiveType::Sphere) => Some(1.0),
+        OpFamily::Primitive(PrimitiveType::Box) => Some(3.0f32.sqrt()),
+        OpFamily::Primitive(PrimitiveType::Torus) => Some(1.5),
+        _ => None,
+    }
+}
+
+/// Returns the value of `s_NAME` of the domain op at `index`, i.e. the
+/// product of the scales of every transform leading up to it, or `None`
+/// if any of the ops along the way aren't transforms.
+fn get_scale<E>(graph: &Graph<Op, E>, index: usize) -> Option<f32> {
+    let op = &graph.get_node(index)?.data;
+    match op.family {
+        OpFamily::Domain(DomainType::Root) => Some(1.0),
+        OpFamily::Domain(DomainType::Transform) => {
+            let input = *graph.edges[index].inputs.first()?;
+            Some(op.params.get_data()[3] * get_scale(graph, input)?)
+        }
+        _ => None,
+    }
+}
+
+/// Maps `sphere` from the domain produced by the op at `index` to the
+/// domain of the `map(..)` function, or returns `None` if the domain isn't
+/// produced by a chain of transforms (other domain ops can warp space
+/// arbitrarily).
+fn to_world<E>(graph: &Graph<Op, E>, index: usize, sphere: Sphere) -> Option<Sphere> {
+    let op = &graph.get_node(index)?.data;
+    match op.family {
+        OpFamily::Domain(DomainType::Root) => Some(sphere),
+        OpFamily::Domain(DomainType::Transform) => {
+            // Each transform computes `p_INPUT_A / s_NAME + t_NAME`.
+            let params = op.params.get_data();
+            let translation = Vector3::new(params[0], params[1], params[2]);
+            let scale = get_scale(graph, index)?;
+            let input = *graph.edges[index].inputs.first()?;
+            let sphere = Sphere::new(
+                (sphere.center - translation) * scale,
+                sphere.radius * scale.abs(),
+            );
+            to_world(graph, input, sphere)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the sphere that encloses the surface of the generator at
+/// `index`, or `None` if it can't be bounded.
+pub fn get_bounds<E>(graph: &Graph<Op, E>, index: usize) -> Option<Sphere> {
+    let radius = get_local_radius(graph.get_node(index)?.data.family)?;
+    let domain = *graph.edges[index].inputs.first()?;
+    to_world(graph, domain, Sphere::new(Vector3::zero(), radius))
+}
+
+/// A node of the tree of bounding spheres within a cluster.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Node {
+    /// A node whose sphere encloses the two child nodes at these indices
+    Branch(usize, usize),
+
+    /// A node whose sphere encloses the generators at these op indices
+    Leaf(Vec<usize>),
+}
+
+/// A group of unions that combine many branches of a graph into a single
+/// distance field, along with a tree of bounding spheres around the
+/// generators among those branches.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cluster {
+    /// The index of the union whose result is the result of the cluster
+    pub root: usize,
+
+    /// The indices of the unions inside of the cluster, other than `root`
+    pub members: Vec<usize>,
+
+    /// The indices of the ops that feed into the cluster, but can't be
+    /// bounded, which are always evaluated
+    pub unbounded: Vec<usize>,
+
+    /// The tree of bounding spheres, where the first node is the root of
+    /// the tree and children always come after their parents
+    pub nodes: Vec<Node>,
+
+    /// The index of the parameter vector that holds the sphere of the first
+    /// node (see `Hierarchy::refit`)
+    pub offset: usize,
+}
+
+impl Cluster {
+    /// Returns the index of the parameter vector that holds the sphere of
+    /// the node at `index`.
+    pub fn get_param_index(&self, index: usize) -> usize {
+        self.offset + index
+    }
+
+    /// Returns `true` if the op at `index` is part of this cluster, and is
+    /// generated along with its root rather than on its own.
+    pub fn contains(&self, index: usize) -> bool {
+        self.members.contains(&index) || self.nodes.iter().any(|node| match *node {
+            Node::Leaf(ref leaves) => leaves.contains(&index),
+            _ => false,
+        })
+    }
+}
+
+/// Returns `true` if the op at `index` can be merged into a cluster.
+fn is_union<E>(graph: &Graph<Op, E>, index: usize) -> bool {
+    let op = &graph.nodes[index].data;
+    op.family == OpFamily::Primitive(PrimitiveType::Union) && !op.baked
+}
+
+/// Returns `true` if the op at `index` only feeds into a single union,
+/// which can then evaluate it on demand.
+fn feeds_union<E>(graph: &Graph<Op, E>, index: usize) -> bool {
+    let outputs = &graph.edges[index].outputs;
+    outputs.len() == 1 && is_union(graph, outputs[0])
+}
+
+/// Builds a tree out of `leaves` (op indices and the centers of their
+/// bounds), appending its nodes to `nodes` and returning the index of its
+/// root. Each branch splits its generators in half along the axis where
+/// their centers are spread out the most.
+fn build_nodes(leaves: &mut [(usize, Vector3<f32>)], nodes: &mut Vec<Node>) -> usize {
+    let index = nodes.len();
+    nodes.push(Node::Leaf(leaves.iter().map(|&(op, _)| op).collect()));
+    if leaves.len() <= constants::HIERARCHY_LEAF_SIZE {
+        return index;
+    }
+
+    let (mut lower, mut upper) = (leaves[0].1, leaves[0].1);
+    for &(_, center) in leaves.iter() {
+        lower = Vector3::new(lower.x.min(center.x), lower.y.min(center.y), lower.z.min(center.z));
+        upper = Vector3::new(upper.x.max(center.x), upper.y.max(center.y), upper.z.max(center.z));
+    }
+    let extent = upper - lower;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    leaves.sort_by(|a, b| a.1[axis].partial_cmp(&b.1[axis]).unwrap_or(Ordering::Equal));
+
+    let middle = leaves.len() / 2;
+    let (left, right) = leaves.split_at_mut(middle);
+    let left = build_nodes(left, nodes);
+    let right = build_nodes(right, nodes);
+    nodes[index] = Node::Branch(left, right);
+    index
+}
+
+/// The clusters of unions in a network, each with a hierarchy of bounding
+/// spheres, so that the generated `map(..)` function can skip every
+/// branch of a large union that is further away than the closest surface
+/// found so far. The tree of each cluster is built from the positions of
+/// its generators when the structure of the network changes, while the
+/// spheres themselves are recomputed every frame (see `refit`) and stored
+/// after the op parameters, so that moving a generator doesn't require a
+/// rebuild.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hierarchy {
+    pub clusters: Vec<Cluster>,
+
+    /// The signature (see `get_signature`) of the network that the
+    /// hierarchy was built for
+    signature: u64,
+}
+
+impl Hierarchy {
+    pub fn new() -> Hierarchy {
+        Hierarchy {
+            clusters: Vec::new(),
+            signature: 0,
+        }
+    }
+
+    /// Returns a hash of everything about `graph` that the hierarchy is
+    /// built from, except for the parameters of its ops.
+    pub fn get_signature<E>(graph: &Graph<Op, E>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (node, edges) in graph.nodes.iter().zip(graph.edges.iter()) {
+            node.data.family.to_string().hash(&mut hasher);
+            node.data.baked.hash(&mut hasher);
+            edges.inputs.hash(&mut hasher);
+            edges.outputs.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Returns `true` if the hierarchy was built for a network with the
+    /// same structure as `graph`.
+    pub fn is_current<E>(&self, graph: &Graph<Op, E>) -> bool {
+        self.signature == Hierarchy::get_signature(graph)
+    }
+
+    /// Builds the hierarchy for every cluster of unions in `graph` that
+    /// combines at least `HIERARCHY_MIN_LEAVES` generators that can be
+    /// bounded. The spheres are stored right after the parameters of the
+    /// last op, as long as they fit.
+    pub fn build<E>(graph: &Graph<Op, E>) -> Hierarchy {
+        let mut clusters = Vec::new();
+        let mut offset = graph.nodes.len();
+
+        for root in 0..graph.nodes.len() {
+            if !is_union(graph, root) || feeds_union(graph, root) {
+                continue;
+            }
+
+            // Gather the unions that only feed into this one, along with
+            // everything that feeds into them.
+            let mut members = Vec::new();
+            let mut leaves = Vec::new();
+            let mut unbounded = Vec::new();
+            let mut stack = vec![root];
+            while let Some(index) = stack.pop() {
+                for &input in graph.edges[index].inputs.iter() {
+                    if is_union(graph, input) && feeds_union(graph, input) {
+                        members.push(input);
+                        stack.push(input);
+                        continue;
+                    }
+                    let op = &graph.nodes[input].data;
+                    let bounded = op.family.is_generator() && !op.baked;
+                    let bounds = if bounded && feeds_union(graph, input) {
+                        get_bounds(graph, input)
+                    } else {
+                        None
+                    };
+                    match bounds {
+                        Some(sphere) => leaves.push((input, sphere.center)),
+                        None => unbounded.push(input),
+                    }
+                }
+            }
+            if leaves.len() < constants::HIERARCHY_MIN_LEAVES {
+                continue;
+            }
+
+            let mut nodes = Vec::new();
+            build_nodes(&mut leaves, &mut nodes);
+            if offset + nodes.len() > constants::PARAMETER_SSBO_CAPACITY {
+                warn!("Too many ops to bound the union {}", graph.nodes[root].data.name);
+                continue;
+            }
+
+            clusters.push(Cluster {
+                root,
+                members,
+                unbounded,
+                offset,
+                nodes,
+            });
+            offset += clusters.last().unwrap().nodes.len();
+        }
+
+        Hierarchy {
+            clusters,
+            signature: Hierarchy::get_signature(graph),
+        }
+    }
+
+    /// Returns the cluster whose result is the result of the union at
+    /// `index`, if there is one.
+    pub fn get_cluster(&self, index: usize) -> Option<&Cluster> {
+        self.clusters.iter().find(|cluster| cluster.root == index)
+    }
+
+    /// Computes the sphere of every node from the current parameters of the
+    /// ops in `graph`, and appends them to `data` as parameter vectors
+    /// (with the center in `xyz` and the radius in `w`).
+    pub fn refit<E>(&self, graph: &Graph<Op, E>, data: &mut Vec<f32>) {
+        for cluster in self.clusters.iter() {
+            let mut spheres = vec![Sphere::unbounded(); cluster.nodes.len()];
+
+            // Children always come after their parents.
+            for (index, node) in cluster.nodes.iter().enumerate().rev() {
+                spheres[index] = match *node {
+                    Node::Branch(left, right) => spheres[left].merge(&spheres[right]),
+                    Node::Leaf(ref leaves) => leaves
+                        .iter()
+                        .map(|&leaf| get_bounds(graph, leaf).unwrap_or_else(Sphere::unbounded))
+                        .fold(None, |merged: Option<Sphere>, sphere| {
+                            Some(merged.map_or(sphere, |merged| merged.merge(&sphere)))
+                        })
+                        .unwrap_or_else(Sphere::unbounded),
+                };
+            }
+
+            // Each cluster's spheres start at its offset.
+            data.resize(cluster.offset * constants::PARAMETER_CAPACITY, 0.0);
+            for sphere in spheres.iter() {
+                data.extend_from_slice(&[
+                    sphere.center.x,
+                    sphere.center.y,
+                    sphere.center.z,
+                    sphere.radius,
+                ]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_merge() {
+    let a = Sphere::new(Vector3::new(-1.0, 0.0, 0.0), 1.0);
+    let b = Sphere::new(Vector3::new(2.0, 0.0, 0.0), 2.0);
+    assert_eq!(a.merge(&b), Sphere::new(Vector3::new(1.0, 0.0, 0.0), 3.0));
+
+    // A sphere that encloses the other one is kept as it is.
+    let c = Sphere::new(Vector3::new(2.5, 0.0, 0.0), 0.5);
+    assert_eq!(b.merge(&c), b);
+    assert_eq!(c.merge(&b), b);
+}
@@ -2,6 +2,8 @@ use cgmath::{Vector2, Zero};
 
 use bounds::Rect;
 
+use std::time::{Duration, SystemTime};
+
 pub struct MouseInfo {
     /// The current position of the mouse
     pub curr: Vector2<f32>,
@@ -26,6 +28,10 @@ pub struct MouseInfo {
 
     /// The scroll status of the mouse
     pub scroll: f32,
+
+    /// A flag denoting whether or not the control
+    /// key is currently held down
+    pub ctrl: bool,
 }
 
 impl MouseInfo {
@@ -38,6 +44,7 @@ impl MouseInfo {
             rdown: false,
             mdown: false,
             scroll: 1.0,
+            ctrl: false,
         }
     }
 
@@ -45,6 +52,61 @@ impl MouseInfo {
         self.curr - self.last
     }
 }
+/// A simple timer that measures how long the mouse has been
+/// hovering over the same item (for example, an op in the
+/// network editor).
+pub struct HoverTimer {
+    /// The index of the item that the mouse is currently
+    /// hovering over (if there is one)
+    target: Option<usize>,
+
+    /// The time at which the mouse started hovering over
+    /// `target`
+    start: SystemTime,
+}
+
+impl HoverTimer {
+    pub fn new() -> HoverTimer {
+        HoverTimer {
+            target: None,
+            start: SystemTime::now(),
+        }
+    }
+
+    /// Sets the item that the mouse is currently hovering over,
+    /// restarting the timer if it differs from the previous one.
+    pub fn update(&mut self, target: Option<usize>) {
+        if target != self.target {
+            self.target = target;
+            self.start = SystemTime::now();
+        }
+    }
+
+    /// Returns the index of the item that the mouse is currently
+    /// hovering over (if there is one).
+    pub fn get_target(&self) -> Option<usize> {
+        self.target
+    }
+
+    /// Returns the number of seconds that the mouse has been
+    /// hovering over the current target.
+    pub fn get_elapsed_seconds(&self) -> f32 {
+        let elapsed = self.start.elapsed().unwrap_or(Duration::from_secs(0));
+        let milliseconds = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+
+        (milliseconds as f32) / 1000.0
+    }
+
+    /// Returns the current target if the mouse has been hovering
+    /// over it for at least `delay` seconds.
+    pub fn expired(&self, delay: f32) -> Option<usize> {
+        match self.target {
+            Some(target) if self.get_elapsed_seconds() >= delay => Some(target),
+            _ => None,
+        }
+    }
+}
+
 pub enum InteractionState {
     Deselected,
     Selected,
@@ -0,0 +1,133 @@
+use constants;
+use graph::Graph;
+use operator::Op;
+use shadertoy;
+
+/// Maps the inputs that a shader which follows Shadertoy's conventions
+/// expects to the ones that ISF hosts provide. ISF doesn't have an
+/// equivalent of `iMouse`, so the camera can't be orbited.
+static INPUT_DEFINES: &str = "#define iTime TIME
+#define iResolution vec3(RENDERSIZE, 1.0)
+#define iMouse vec4(0.0)
+#define iFrame FRAMEINDEX";
+
+/// A published input of an ISF shader, which controls a single (named)
+/// component of an op's parameters.
+pub struct Input {
+    /// The name of the input, which is also the name of the uniform that
+    /// holds its value (i.e. `smooth_minimum_3_exponent`)
+    pub name: String,
+
+    /// The name that hosts display for the input (i.e. `smooth_minimum_3 exponent`)
+    pub label: String,
+
+    /// The index of the op's parameters within the `params` array
+    pub index: usize,
+
+    /// The component of the op's parameters that the input controls
+    pub component: usize,
+
+    /// The current value of the component, along with its range
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Returns `name` with any characters that can't be part of a GLSL
+/// identifier replaced by underscores.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Returns an input for each adjustable component of the parameters of the
+/// ops at `indices` within `graph`, i.e. each one that has a name as well
+/// as a range of values.
+pub fn get_inputs<E>(graph: &Graph<Op, E>, indices: &[usize]) -> Vec<Input> {
+    let mut inputs = Vec::new();
+    for node in indices.iter().filter_map(|&index| graph.get_node(index)) {
+        let params = node.data.get_params();
+        for (component, name) in params.get_names().iter().enumerate() {
+            if name.is_empty() || params.get_min()[component] >= params.get_max()[component] {
+                continue;
+            }
+            inputs.push(Input {
+                name: sanitize(&format!("{}_{}", node.data.name, name)),
+                label: format!("{} {}", node.data.name, name),
+                index: params.get_index(),
+                component,
+                default: params.get_data()[component],
+                min: params.get_min()[component],
+                max: params.get_max()[component],
+            });
+        }
+    }
+    inputs
+}
+
+/// Returns the JSON description of a shader that publishes `inputs`,
+/// which ISF expects in a comment at the start of the shader.
+fn get_description(inputs: &[Input]) -> String {
+    let entries: Vec<String> = inputs
+        .iter()
+        .map(|input| {
+            format!(
+                "        {{ \"NAME\": \"{}\", \"LABEL\": \"{}\", \"TYPE\": \"float\", \
+                 \"DEFAULT\": {}, \"MIN\": {}, \"MAX\": {} }}",
+                input.name,
+                input.label,
+                shadertoy::float(input.default),
+                shadertoy::float(input.min),
+                shadertoy::float(input.max)
+            )
+        })
+        .collect();
+
+    format!(
+        "/*{{\n    \"DESCRIPTION\": \"A signed distance field, exported from sdfperf\",\n    \
+         \"ISFVSN\": \"2\",\n    \"CATEGORIES\": [\"Generator\"],\n    \
+         \"INPUTS\": [\n{}\n    ]\n}}*/",
+        entries.join(",\n")
+    )
+}
+
+/// Turns the shader `fs_src` for the shader graph that ends at the op at
+/// `root` within `graph`, which follows Shadertoy's conventions and leaves
+/// it to the caller of `mainImage(..)` to fill in the op parameters (see
+/// `shadertoy::get_prelude`), into an ISF shader. Each adjustable component
+/// of the op parameters becomes a published input, while the others keep
+/// their current values.
+pub fn convert<E>(fs_src: &str, graph: &Graph<Op, E>, root: usize) -> String {
+    let indices = graph.traverse(root);
+    let inputs = get_inputs(graph, &indices);
+
+    let mut assignments = String::new();
+    for node in indices.iter().filter_map(|&index| graph.get_node(index)) {
+        let params = node.data.get_params();
+        let components: Vec<String> = (0..constants::PARAMETER_CAPACITY)
+            .map(|component| {
+                inputs
+                    .iter()
+                    .find(|input| input.index == params.get_index() && input.component == component)
+                    .map_or_else(
+                        || shadertoy::float(params.get_data()[component]),
+                        |input| input.name.clone(),
+                    )
+            })
+            .collect();
+        assignments.push_str(&format!(
+            "    params[{}] = vec4({});\n",
+            params.get_index(),
+            components.join(", ")
+        ));
+    }
+
+    format!(
+        "{}\n\n{}\n\n{}\nvoid main()\n{{\n{}\n    mainImage(gl_FragColor, gl_FragCoord.xy);\n}}\n",
+        get_description(&inputs),
+        INPUT_DEFINES,
+        fs_src,
+        assignments
+    )
+}
@@ -0,0 +1,154 @@
+use preprocessor::Source;
+
+/// A function that is defined at the top level of a GLSL source.
+struct Function {
+    /// The name of the function
+    name: String,
+
+    /// The (0-based) index of the function's first line, including any
+    /// comments that directly precede it
+    first: usize,
+
+    /// The (0-based) index of the function's last line (its closing brace)
+    last: usize,
+}
+
+/// Returns the name of the function whose definition starts on `line`, if
+/// it does, i.e. `float sdf_sphere(in vec3 p, ...)`.
+fn parse_signature(line: &str) -> Option<&str> {
+    if line.starts_with(|c: char| c.is_whitespace()) {
+        return None;
+    }
+    let mut words = line.splitn(2, ' ');
+    let return_type = words.next()?;
+    let rest = words.next()?.trim_start();
+    if return_type.starts_with("//") || return_type.starts_with('#') {
+        return None;
+    }
+
+    let name = &rest[..rest.find('(')?];
+    if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Finds each of the functions defined at the top level of `lines`.
+fn parse_functions(lines: &[&str]) -> Vec<Function> {
+    let mut functions = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some(name) = parse_signature(lines[i]) {
+            // Attach any comments that directly precede the function.
+            let mut first = i;
+            while first > 0 && lines[first - 1].trim_start().starts_with("//") {
+                first -= 1;
+            }
+
+            let mut last = i;
+            while last < lines.len() && !lines[last].starts_with('}') {
+                last += 1;
+            }
+            functions.push(Function {
+                name: name.to_string(),
+                first,
+                last: last.min(lines.len() - 1),
+            });
+            i = last;
+        }
+        i += 1;
+    }
+    functions
+}
+
+/// Returns `true` if `code` calls the function called `name`.
+fn references(code: &str, name: &str) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    code.match_indices(name).any(|(start, _)| {
+        let before = code[..start].chars().next_back();
+        let after = code[start + name.len()..].trim_start().chars().next();
+        !before.map_or(false, is_identifier) && after == Some('(')
+    })
+}
+
+/// Removes the functions defined in any of the `libraries` (i.e. helper
+/// files that were included into `source`) that are never called, either
+/// by `source` itself, by any of the `other` sources, or by the functions
+/// that are kept. This keeps the shaders generated from small graphs small,
+/// no matter how large the helper libraries grow.
+pub fn strip_unused(source: &Source, libraries: &[&str], other: &[&str]) -> Source {
+    let lines: Vec<&str> = source.code.lines().collect();
+    let functions: Vec<Function> = parse_functions(&lines)
+        .into_iter()
+        .filter(|function| {
+            source
+                .locate(function.first + 1)
+                .map_or(false, |(name, _)| libraries.contains(&name))
+        })
+        .collect();
+
+    // The library function (if any) that each line belongs to.
+    let mut owners = vec![None; lines.len()];
+    for (i, function) in functions.iter().enumerate() {
+        for owner in owners[function.first..function.last + 1].iter_mut() {
+            *owner = Some(i);
+        }
+    }
+
+    // Everything that isn't a library function is always kept.
+    let mut code: Vec<&str> = lines
+        .iter()
+        .zip(owners.iter())
+        .filter(|&(_, owner)| owner.is_none())
+        .map(|(&line, _)| line)
+        .collect();
+    code.extend(other.iter().cloned());
+    let mut code = code.join("\n");
+
+    // Keep adding the functions that are called by the code that is kept,
+    // until there are no new ones.
+    let mut needed = vec![false; functions.len()];
+    loop {
+        let mut changed = false;
+        for (i, function) in functions.iter().enumerate() {
+            if !needed[i] && references(&code, &function.name) {
+                needed[i] = true;
+                changed = true;
+                code.push('\n');
+                code.push_str(&lines[function.first..function.last + 1].join("\n"));
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    source.retain(|line| owners[line].map_or(true, |i| needed[i]))
+}
+
+#[test]
+fn test_strip_unused() {
+    use preprocessor;
+    use std::io;
+
+    let read = |name: &str| match name {
+        "lib.glsl" => Ok("// Unused.\nfloat a(float x)\n{\n    return x;\n}\n\n\
+                          float b(float x)\n{\n    return c(x);\n}\n\n\
+                          float c(float x)\n{\n    return x;\n}\n"
+            .to_string()),
+        _ => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+    };
+    let source = preprocessor::process_with("main.glsl", "#include \"lib.glsl\"", &read).unwrap();
+
+    let stripped = strip_unused(&source, &["lib.glsl"], &["float y = b (1.0);"]);
+    assert!(!stripped.code.contains("float a("));
+    assert!(!stripped.code.contains("// Unused."));
+    assert!(stripped.code.contains("float b("));
+    assert!(stripped.code.contains("float c("));
+    assert_eq!(stripped.locate(1), Some(("lib.glsl", 6)));
+
+    // Functions are only removed from libraries.
+    let stripped = strip_unused(&source, &[], &[]);
+    assert_eq!(stripped.code, source.code);
+}
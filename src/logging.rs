@@ -0,0 +1,238 @@
+use log::{self, LogLevel, LogLevelFilter, LogMetadata, LogRecord};
+
+use console;
+
+use std::env;
+
+/// The environment variable that holds the log filter, which is a comma
+/// separated list of the lowest level to log (i.e. `warn`), optionally
+/// followed by a level per subsystem (i.e. `warn,codegen=debug,gl=trace`).
+/// A subsystem on its own (i.e. `gl`) logs everything from it.
+const LOG_VARIABLE: &str = "SDFPERF_LOG";
+
+/// The filter that is used if `LOG_VARIABLE` isn't set (or is invalid).
+const DEFAULT_FILTER: &str = "info";
+
+/// The subsystem that each module logs to, if not the default (`app`).
+const SUBSYSTEMS: &[(&str, &[&str])] = &[
+    (
+        "codegen",
+        &[
+            "custom",
+            "formatter",
+            "isf",
+            "library",
+            "op_definition",
+            "preprocessor",
+            "shader_builder",
+            "shadertoy",
+            "spirv",
+            "wgsl",
+        ],
+    ),
+    (
+        "gl",
+        &[
+            "font",
+            "framebuffer",
+            "gl_debug",
+            "grid",
+            "pacing",
+            "params_buffer",
+            "perf",
+            "preview",
+            "profile",
+            "program",
+            "renderer",
+            "steps",
+            "texture",
+            "thumbnail",
+        ],
+    ),
+    (
+        "graph",
+        &[
+            "backdrop",
+            "bake",
+            "edge_cache",
+            "graph",
+            "hierarchy",
+            "network",
+            "operator",
+            "preset",
+            "routing",
+            "timeline",
+        ],
+    ),
+    (
+        "io",
+        &[
+            "assets",
+            "benchmark",
+            "capture",
+            "clipboard",
+            "dot",
+            "gallery",
+            "gltf",
+            "mesh",
+            "project",
+            "recent",
+            "svg",
+            "video",
+            "volume",
+            "watcher",
+        ],
+    ),
+];
+
+/// The subsystem of messages that were logged from any other module of
+/// this crate.
+const DEFAULT_SUBSYSTEM: &str = "app";
+
+/// Returns the subsystem that a message with `target` belongs to. Messages
+/// are logged with the path of the module they came from by default (i.e.
+/// `sdfperf::shader_builder`), which is mapped to a subsystem (`codegen`).
+/// Targets that name a subsystem already are returned as they are, and
+/// those of other crates are reduced to the name of the crate.
+fn get_subsystem(target: &str) -> &str {
+    let is_subsystem = |name: &str| {
+        name == DEFAULT_SUBSYSTEM || SUBSYSTEMS.iter().any(|&(subsystem, _)| subsystem == name)
+    };
+    if is_subsystem(target) {
+        return target;
+    }
+
+    let mut path = target.split("::");
+    let krate = path.next().unwrap_or(target);
+    if krate != module_path!().split("::").next().unwrap_or("") {
+        return krate;
+    }
+    match path.next() {
+        Some(module) => SUBSYSTEMS
+            .iter()
+            .find(|&&(_, modules)| modules.contains(&module))
+            .map_or(DEFAULT_SUBSYSTEM, |&(subsystem, _)| subsystem),
+        None => DEFAULT_SUBSYSTEM,
+    }
+}
+
+/// Decides which messages are logged, based on their level and subsystem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    /// The lowest level that is logged from subsystems that aren't listed
+    /// in `subsystems`
+    default: LogLevelFilter,
+
+    /// The lowest level that is logged from each subsystem
+    subsystems: Vec<(String, LogLevelFilter)>,
+}
+
+impl Filter {
+    /// Parses a filter in the format of `LOG_VARIABLE`.
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter {
+            default: LogLevelFilter::Info,
+            subsystems: Vec::new(),
+        };
+        let parse_level = |level: &str| {
+            level
+                .parse::<LogLevelFilter>()
+                .map_err(|_| format!("invalid log level `{}`", level))
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let name = parts.next().unwrap_or(directive);
+            match parts.next() {
+                Some(level) => filter.subsystems.push((name.to_string(), parse_level(level)?)),
+                None => match parse_level(name) {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter.subsystems.push((name.to_string(), LogLevelFilter::Trace)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Returns the most verbose level that any subsystem logs at.
+    pub fn get_max_level(&self) -> LogLevelFilter {
+        self.subsystems
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, |a, b| a.max(b))
+    }
+
+    /// Returns `true` if messages at `level` are logged from `subsystem`.
+    pub fn is_enabled(&self, subsystem: &str, level: LogLevel) -> bool {
+        let threshold = self.subsystems
+            .iter()
+            .rev()
+            .find(|(name, _)| name == subsystem)
+            .map_or(self.default, |&(_, level)| level);
+        level <= threshold
+    }
+}
+
+/// Writes messages to stderr and to the on-screen console.
+struct Logger {
+    filter: Filter,
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        self.filter.is_enabled(get_subsystem(metadata.target()), metadata.level())
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        eprintln!("[{:<5} {}] {}", record.level(), get_subsystem(record.target()), message);
+        console::push(&message);
+    }
+}
+
+/// Installs the logger, with the filter in `LOG_VARIABLE`. This should be
+/// called once at startup, before anything is logged.
+pub fn init() {
+    let spec = env::var(LOG_VARIABLE).unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    let (filter, error) = match Filter::parse(&spec) {
+        Ok(filter) => (filter, None),
+        Err(error) => (Filter::parse(DEFAULT_FILTER).unwrap(), Some(error)),
+    };
+
+    let _ = log::set_logger(|max_level| {
+        max_level.set(filter.get_max_level());
+        Box::new(Logger { filter })
+    });
+    if let Some(error) = error {
+        warn!("Ignoring {}: {}", LOG_VARIABLE, error);
+    }
+}
+
+#[test]
+fn test_get_subsystem() {
+    let krate = module_path!().split("::").next().unwrap();
+    assert_eq!(get_subsystem(&format!("{}::shader_builder", krate)), "codegen");
+    assert_eq!(get_subsystem(&format!("{}::program", krate)), "gl");
+    assert_eq!(get_subsystem(&format!("{}::network", krate)), "graph");
+    assert_eq!(get_subsystem(&format!("{}::project", krate)), "io");
+    assert_eq!(get_subsystem(krate), "app");
+    assert_eq!(get_subsystem("io"), "io");
+    assert_eq!(get_subsystem("glutin::api::glx"), "glutin");
+}
+
+#[test]
+fn test_filter() {
+    let filter = Filter::parse("warn, codegen=debug,gl").unwrap();
+    assert!(filter.is_enabled("codegen", LogLevel::Debug));
+    assert!(!filter.is_enabled("codegen", LogLevel::Trace));
+    assert!(filter.is_enabled("gl", LogLevel::Trace));
+    assert!(filter.is_enabled("io", LogLevel::Warn));
+    assert!(!filter.is_enabled("io", LogLevel::Info));
+    assert_eq!(filter.get_max_level(), LogLevelFilter::Trace);
+
+    assert_eq!(Filter::parse("").unwrap().get_max_level(), LogLevelFilter::Info);
+    assert!(Filter::parse("codegen=loud").is_err());
+}
@@ -4,23 +4,79 @@
 #![allow(unreachable_code)]
 #![feature(vec_remove_item)]
 extern crate cgmath;
+extern crate clap;
 extern crate gl;
 extern crate glutin;
 extern crate image;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
 extern crate uuid;
 
+#[macro_use]
+mod console;
+mod assets;
+mod backend;
+mod capture;
+mod cli;
+mod custom;
+mod dot;
+mod edge_cache;
+mod error;
 mod bounds;
+mod backdrop;
+mod bake;
+mod benchmark;
+mod clipboard;
 mod color;
 mod constants;
+mod font;
+mod formatter;
+mod framebuffer;
+mod gallery;
+mod gl_backend;
+mod gl_debug;
+mod gltf;
 mod graph;
+mod grid;
+mod hierarchy;
 mod interaction;
+mod isf;
+mod library;
+mod logging;
+mod mesh;
 mod network;
+mod op_definition;
 mod operator;
+mod pacing;
+mod params_buffer;
+mod perf;
+mod preprocessor;
+mod preset;
 mod preview;
+mod profile;
 mod program;
+mod project;
+mod recent;
 mod renderer;
+mod routing;
 mod shader_builder;
+mod shader_error;
+mod shadertoy;
+mod spirv;
+mod status;
+mod steps;
+mod svg;
 mod texture;
+mod thumbnail;
+mod timeline;
+mod video;
+mod view;
+mod volume;
+mod watcher;
+mod wgsl;
 
 // TODO:
 // - Limit generators (i.e. sphere) to ONE output, since
@@ -31,46 +87,295 @@ mod texture;
 //   generator is duplicated. This would mean that transforms
 //   should be their own family of operator as well.
 
+use capture::CaptureMode;
+use cli::{Benchmark, Export, ExportFormat, Options};
 use color::Color;
+use error::Error;
+use gl_debug::DebugOptions;
 use interaction::{MouseInfo, Panel};
 use operator::{DomainType, Op, OpFamily, Parameters, PrimitiveType};
 use network::Network;
-use preview::Shading;
+use preview::{Movement, Shading};
+use profile::Profile;
 use program::Program;
 use renderer::Renderer;
-use shader_builder::ShaderBuilder;
+use shader_builder::{ShaderBuilder, Target};
+use watcher::Watcher;
 
 use glutin::GlContext;
 use cgmath::{Vector2, Vector3, Vector4, Zero};
 
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
 fn clear() {
     unsafe {
-        let clear = Color::from_hex(constants::NETWORK_BACKGROUND_COLOR, constants::NETWORK_BACKGROUND_ALPHA);
+        let clear = Color::from_hex(constants::NETWORK_BACKGROUND_COLOR, constants::NETWORK_BACKGROUND_ALPHA)
+            .to_linear();
         gl::ClearColor(clear.r, clear.g, clear.b, clear.a);
         gl::Clear(gl::COLOR_BUFFER_BIT);
     }
 }
 
-fn main() {
-    let mut events_loop = glutin::EventsLoop::new();
+/// Creates the main window at `size` with `samples` samples per pixel. If
+/// the driver doesn't support that many, the sample count is halved until
+/// it does, falling back to no multisampling at all. Hidden windows are
+/// used for exporting from the command line, which still needs an OpenGL
+/// context.
+fn create_window(
+    events_loop: &glutin::EventsLoop,
+    size: Vector2<f32>,
+    visible: bool,
+    vsync: bool,
+    samples: u16,
+    debug_options: &DebugOptions,
+) -> Result<glutin::GlWindow, Error> {
     let window = glutin::WindowBuilder::new()
-        .with_dimensions(constants::WINDOW_RESOLUTION.x as u32, constants::WINDOW_RESOLUTION.y as u32)
+        .with_dimensions(size.x as u32, size.y as u32)
+        .with_visibility(visible)
         .with_title(constants::WINDOW_TITLE);
-    let context = glutin::ContextBuilder::new().with_multisampling(constants::WINDOW_MULTISAMPLES);
-    let gl_window = glutin::GlWindow::new(window, context, &events_loop).unwrap();
-    unsafe { gl_window.make_current() }.unwrap();
+
+    let mut samples = samples;
+    loop {
+        let context = glutin::ContextBuilder::new()
+            .with_multisampling(samples)
+            .with_vsync(vsync)
+            .with_srgb(true)
+            .with_gl_debug_flag(debug_options.enabled);
+
+        // macOS only provides OpenGL 4.1 through a core profile context,
+        // which has to be requested explicitly.
+        #[cfg(target_os = "macos")]
+        let context = context
+            .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (4, 1)))
+            .with_gl_profile(glutin::GlProfile::Core);
+
+        match glutin::GlWindow::new(window.clone(), context, events_loop) {
+            Ok(gl_window) => return Ok(gl_window),
+            Err(error) => {
+                if samples == 0 {
+                    return Err(Error::Window(error.to_string()));
+                }
+                warn!(
+                    "Failed to create a window with {}x MSAA ({}), retrying with {}x",
+                    samples,
+                    error,
+                    samples / 2
+                );
+                samples /= 2;
+            }
+        }
+    }
+}
+
+/// Loads the network of `export` and writes the export, with the same
+/// defaults as the corresponding hotkey. Returns `false` if the network
+/// can't be loaded, or if the export needs a render op and there isn't one.
+/// Any other failures are reported by the export itself.
+fn run_export(export: &Export, network: &mut Network, builder: &mut ShaderBuilder) -> bool {
+    if !network.load_project(&export.project) {
+        return false;
+    }
+
+    let format = export.format;
+    match format {
+        ExportFormat::Dot => {
+            network.export_dot(export.get_output(constants::EXPORT_DOT_PATH));
+            return true;
+        }
+        ExportFormat::Svg => {
+            network.export_svg(export.get_output(constants::EXPORT_SVG_PATH));
+            return true;
+        }
+        ExportFormat::Png => {
+            network.export_png(export.get_output(constants::EXPORT_PNG_PATH));
+            return true;
+        }
+        _ => (),
+    }
+
+    let root = match network.render_id {
+        Some(root) => root,
+        None => {
+            error!("{} doesn't have a render op", export.project.display());
+            return false;
+        }
+    };
+    if format.uses_preview() {
+        network.build_preview_now(builder);
+    }
+
+    let graph = &network.graph;
+    let snapshot = network.preview.get_snapshot();
+    match format {
+        ExportFormat::Glsl => {
+            builder.export(graph, root, export.get_output(constants::EXPORT_GLSL_PATH))
+        }
+        ExportFormat::Shadertoy => builder.export_shadertoy(
+            graph,
+            root,
+            &snapshot,
+            export.get_output(constants::EXPORT_SHADERTOY_PATH),
+        ),
+        ExportFormat::Isf => builder.export_isf(
+            graph,
+            root,
+            &snapshot,
+            export.get_output(constants::EXPORT_ISF_PATH),
+        ),
+        ExportFormat::Wgsl => builder.export_wgsl(
+            graph,
+            root,
+            &snapshot,
+            export.get_output(constants::EXPORT_WGSL_PATH),
+        ),
+        ExportFormat::Spirv => {
+            builder.export_spirv(graph, root, export.get_output(constants::EXPORT_SPIRV_PATH))
+        }
+        ExportFormat::Mesh => {
+            network.export_gltf(builder, root, export.get_output(constants::EXPORT_GLTF_PATH))
+        }
+        ExportFormat::Volume => {
+            let path = export.get_output(constants::EXPORT_VOLUME_PATH);
+            network.export_volume(builder, root, path, &path.with_extension("vox"));
+        }
+        ExportFormat::Screenshot => match export.output {
+            Some(ref path) => network.save_preview(path),
+            None => network.save_preview_screenshot(),
+        },
+        ExportFormat::Turntable | ExportFormat::Timeline => {
+            let mode = if format == ExportFormat::Turntable {
+                CaptureMode::Turntable
+            } else {
+                CaptureMode::Timeline
+            };
+            match export.output {
+                Some(ref path) => network.capture_frames_to(mode, path),
+                None => network.capture_frames(mode),
+            }
+        }
+        ExportFormat::Dot | ExportFormat::Svg | ExportFormat::Png => (),
+    }
+    true
+}
+
+/// Loads the network of `benchmark` and benchmarks its preview with the
+/// same optimizations (baked ops and bounding sphere hierarchies) as the
+/// network editor. Returns `false` if the network can't be loaded, if it
+/// doesn't have a render op, or if the benchmark fails.
+fn run_benchmark(
+    benchmark: &Benchmark,
+    network: &mut Network,
+    builder: &mut ShaderBuilder,
+) -> bool {
+    if !network.load_project(&benchmark.project) {
+        return false;
+    }
+    if network.render_id.is_none() {
+        error!("{} doesn't have a render op", benchmark.project.display());
+        return false;
+    }
+
+    network.update_bakes(builder);
+    network.update_hierarchy(builder);
+    network.build_preview_now(builder);
+    network.benchmark(benchmark)
+}
+
+/// Reports an `error` that the editor can't recover from (i.e. when the
+/// assets directory is missing at startup) and exits.
+fn exit_with(error: Error) -> ! {
+    error!("{}", error);
+    std::process::exit(1)
+}
+
+fn main() {
+    logging::init();
+    let options = Options::parse(std::env::args_os()).unwrap_or_else(|error| error.exit());
+    if let Some(ref assets) = options.assets {
+        assets::set_directory(assets);
+    }
+
+    // Exports and benchmarks from the command line still need an OpenGL
+    // context, so they use a window that is never shown.
+    let mut events_loop = glutin::EventsLoop::new();
+    let visible = options.export.is_none() && options.benchmark.is_none();
+    let gl_window = create_window(
+        &events_loop,
+        options.window_size,
+        visible,
+        options.frame.vsync,
+        options.samples,
+        &options.debug,
+    ).unwrap_or_else(|error| exit_with(error));
+    unsafe { gl_window.make_current() }
+        .unwrap_or_else(|error| exit_with(Error::Window(error.to_string())));
     gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
+    gl_debug::enable(&options.debug);
+    program::enable_parallel_compile(|symbol| gl_window.get_proc_address(symbol) as *const _);
 
     // Keep track of the current window size and interaction state
-    let mut current_size = Vector2::new(constants::WINDOW_RESOLUTION.x, constants::WINDOW_RESOLUTION.y);
+    let mut current_size = options.window_size;
     let mut mouse = MouseInfo::new();
 
     // Main objects
-    let mut network = Network::new(current_size);
-    let mut builder = ShaderBuilder::new();
+    op_definition::load_all(&assets::get_path(constants::OP_DEFINITIONS_PATH));
+    let profile = options.profile.unwrap_or_else(Profile::detect);
+    let mut network =
+        Network::new(current_size, profile).unwrap_or_else(|error| exit_with(error));
+    network.capture_settings = options.capture;
+    network.frame_settings = options.frame;
+    let mut builder = ShaderBuilder::new(profile).unwrap_or_else(|error| exit_with(error));
+
+    if let Some(ref export) = options.export {
+        let succeeded = run_export(export, &mut network, &mut builder);
+        std::process::exit(if succeeded { 0 } else { 1 });
+    }
+    if let Some(ref benchmark) = options.benchmark {
+        let succeeded = run_benchmark(benchmark, &mut network, &mut builder);
+        std::process::exit(if succeeded { 0 } else { 1 });
+    }
+    if let Some(ref path) = options.project {
+        network.load_project(path);
+    }
+
+    // Watch the shader files (and the user's overrides of them), along with
+    // the code of custom ops, so that edits to them show up in the preview
+    // without restarting.
+    let proxy = events_loop.create_proxy();
+    let shader_path = assets::get_path(constants::SHADER_PATH);
+    let watcher = Watcher::new(
+        &[
+            shader_path.as_path(),
+            Path::new(constants::SHADER_OVERRIDE_PATH),
+            Path::new(constants::CUSTOM_OP_PATH),
+        ],
+        Duration::from_millis(constants::SHADER_WATCH_INTERVAL_MS),
+        move || {
+            let _ = proxy.wakeup();
+        },
+    );
+
+    // The first frame is always drawn, since there is nothing to present yet.
+    let mut first_frame = true;
 
     loop {
-        events_loop.poll_events(|event| {
+        // When nothing in the network is changing (and no shaders are being
+        // compiled), block until the next event arrives rather than redrawing
+        // the same frame over and over.
+        let mut events = Vec::new();
+        if !first_frame && !network.is_animating() && !builder.is_building() {
+            events_loop.run_forever(|event| {
+                events.push(event);
+                glutin::ControlFlow::Break
+            });
+        }
+        first_frame = false;
+        let frame_start = Instant::now();
+        events_loop.poll_events(|event| events.push(event));
+
+        for event in events {
             match event {
                 glutin::Event::WindowEvent { event, .. } => match event {
                     glutin::WindowEvent::Closed => (),
@@ -78,15 +383,15 @@ fn main() {
                     glutin::WindowEvent::Resized(w, h) => {
                         current_size = Vector2 { x: w as f32, y: h as f32 };
                         gl_window.resize(w, h);
+                        network.resize(&current_size);
                     }
 
                     glutin::WindowEvent::MouseMoved { position, .. } => {
                         mouse.last = mouse.curr;
-                        mouse.curr = Vector2::new(position.0 as f32, position.1 as f32);
-
-                        // Zero center and zoom.
-                        mouse.curr -= current_size * 0.5;
-                        //mouse.curr *= mouse.scroll;
+                        mouse.curr = network
+                            .renderer
+                            .get_view()
+                            .window_to_screen(&Vector2::new(position.0 as f32, position.1 as f32));
 
                         network.handle_interaction(&mouse);
                     }
@@ -119,10 +424,45 @@ fn main() {
                             mouse.ldown = false;
                             mouse.rdown = false;
                             mouse.mdown = false;
+
+                            // Let the network know that any drags or pending
+                            // connections have ended.
+                            network.handle_interaction(&mouse);
                         }
                     }
 
                     glutin::WindowEvent::KeyboardInput { input, .. } => {
+                        // Keep track of the control key, which modifies
+                        // some mouse interactions.
+                        match input.virtual_keycode {
+                            Some(glutin::VirtualKeyCode::LControl)
+                            | Some(glutin::VirtualKeyCode::RControl) => {
+                                mouse.ctrl = input.state == glutin::ElementState::Pressed;
+                            }
+                            _ => (),
+                        }
+
+                        // Keep track of the keys that move the preview camera
+                        // while it is in fly mode.
+                        if !input.modifiers.shift && !input.modifiers.ctrl {
+                            let held = input.state == glutin::ElementState::Pressed;
+                            match input.virtual_keycode {
+                                Some(glutin::VirtualKeyCode::W) => {
+                                    network.preview.set_movement(Movement::Forward, held)
+                                }
+                                Some(glutin::VirtualKeyCode::S) => {
+                                    network.preview.set_movement(Movement::Backward, held)
+                                }
+                                Some(glutin::VirtualKeyCode::A) => {
+                                    network.preview.set_movement(Movement::Left, held)
+                                }
+                                Some(glutin::VirtualKeyCode::D) => {
+                                    network.preview.set_movement(Movement::Right, held)
+                                }
+                                _ => (),
+                            }
+                        }
+
                         if let glutin::ElementState::Pressed = input.state {
                             if let Some(key) = input.virtual_keycode {
                                 if input.modifiers.shift && key != glutin::VirtualKeyCode::LShift {
@@ -155,6 +495,9 @@ fn main() {
                                         glutin::VirtualKeyCode::R => {
                                             Some(OpFamily::Primitive(PrimitiveType::Render))
                                         }
+                                        glutin::VirtualKeyCode::C => {
+                                            Some(OpFamily::Primitive(PrimitiveType::Custom))
+                                        }
                                         glutin::VirtualKeyCode::Key1 => {
                                             Some(OpFamily::Domain(DomainType::Root))
                                         }
@@ -167,24 +510,236 @@ fn main() {
                                         glutin::VirtualKeyCode::Key4 => {
                                             Some(OpFamily::Domain(DomainType::Bend))
                                         }
-                                        _ => None,
+                                        // Op types that were defined in data files
+                                        // refer to keys by name (i.e. "K").
+                                        _ => op_definition::find_by_shortcut(&format!("{:?}", key)),
                                     };
                                     if let Some(family) = family {
                                         network.add_op(
                                             family,
-                                            mouse.curr - constants::OPERATOR_SIZE * 0.5,
+                                            network.renderer.get_view().to_world(&mouse.curr)
+                                                - constants::OPERATOR_SIZE * 0.5,
                                             constants::OPERATOR_SIZE,
                                         );
                                     }
+                                } else if input.modifiers.ctrl {
+                                    // If the `ctrl` modifier is down, save (or export) the
+                                    // network, pick a recent one (or an example) to load,
+                                    // copy and paste ops, bake the selected op, capture a
+                                    // sequence of frames of the preview, or change the
+                                    // frame rate limit.
+                                    match key {
+                                        glutin::VirtualKeyCode::S => network.save_project(),
+                                        glutin::VirtualKeyCode::C => network.copy_selected(),
+                                        glutin::VirtualKeyCode::V => {
+                                            let position =
+                                                network.renderer.get_view().to_world(&mouse.curr);
+                                            network.paste(position)
+                                        }
+                                        glutin::VirtualKeyCode::F12 => network
+                                            .export_png(Path::new(constants::EXPORT_PNG_PATH)),
+                                        glutin::VirtualKeyCode::O => {
+                                            network.toggle_recent_projects()
+                                        }
+                                        glutin::VirtualKeyCode::G => network.toggle_gallery(),
+                                        glutin::VirtualKeyCode::B => {
+                                            network.toggle_bake_selected()
+                                        }
+                                        glutin::VirtualKeyCode::T => {
+                                            network.capture_frames(CaptureMode::Turntable)
+                                        }
+                                        glutin::VirtualKeyCode::R => {
+                                            network.capture_frames(CaptureMode::Timeline)
+                                        }
+                                        glutin::VirtualKeyCode::M => {
+                                            network.capture_settings.cycle_video()
+                                        }
+                                        glutin::VirtualKeyCode::F => {
+                                            network.frame_settings.cycle_fps_cap()
+                                        }
+                                        _ => (),
+                                    }
                                 } else {
                                     // Handle other key commands.
                                     match key {
                                         glutin::VirtualKeyCode::Delete => network.delete_selected(),
+                                        glutin::VirtualKeyCode::G => {
+                                            let position =
+                                                network.renderer.get_view().to_world(&mouse.curr);
+                                            network.add_backdrop(position, constants::BACKDROP_SIZE)
+                                        }
+                                        glutin::VirtualKeyCode::C => {
+                                            network.cycle_backdrop_color()
+                                        }
                                         glutin::VirtualKeyCode::H => {
                                             mouse.scroll = 1.0;
                                             network.preview.home();
+                                            network.home_view(&mouse);
                                         }
                                         glutin::VirtualKeyCode::P => network.toggle_preview(),
+                                        glutin::VirtualKeyCode::T => network.toggle_thumbnails(),
+                                        glutin::VirtualKeyCode::R => {
+                                            network.reroute_selected_edge()
+                                        }
+                                        glutin::VirtualKeyCode::L => network.toggle_presets(),
+                                        glutin::VirtualKeyCode::X => network.preview.cycle_gizmos(),
+                                        glutin::VirtualKeyCode::Comma => network
+                                            .preview
+                                            .adjust_fov(-constants::PREVIEW_FOV_INCREMENT),
+                                        glutin::VirtualKeyCode::Period => network
+                                            .preview
+                                            .adjust_fov(constants::PREVIEW_FOV_INCREMENT),
+                                        glutin::VirtualKeyCode::Semicolon => {
+                                            network.preview.adjust_max_trace_distance(
+                                                -constants::PREVIEW_TRACE_DISTANCE_INCREMENT,
+                                            )
+                                        }
+                                        glutin::VirtualKeyCode::Apostrophe => {
+                                            network.preview.adjust_max_trace_distance(
+                                                constants::PREVIEW_TRACE_DISTANCE_INCREMENT,
+                                            )
+                                        }
+                                        glutin::VirtualKeyCode::J => {
+                                            network.preview.toggle_turntable()
+                                        }
+                                        glutin::VirtualKeyCode::LBracket => {
+                                            network.preview.adjust_turntable_speed(
+                                                -constants::PREVIEW_TURNTABLE_SPEED_INCREMENT,
+                                            )
+                                        }
+                                        glutin::VirtualKeyCode::RBracket => {
+                                            network.preview.adjust_turntable_speed(
+                                                constants::PREVIEW_TURNTABLE_SPEED_INCREMENT,
+                                            )
+                                        }
+                                        glutin::VirtualKeyCode::B => {
+                                            network.preview.toggle_isolines()
+                                        }
+                                        glutin::VirtualKeyCode::I => {
+                                            network.preview.toggle_probing()
+                                        }
+                                        glutin::VirtualKeyCode::V => {
+                                            network.preview.toggle_camera_mode()
+                                        }
+                                        glutin::VirtualKeyCode::O => {
+                                            network.preview.cycle_render_scale()
+                                        }
+                                        glutin::VirtualKeyCode::M => {
+                                            network.preview.cycle_sample_count()
+                                        }
+                                        glutin::VirtualKeyCode::N => {
+                                            network.preview.cycle_interactive_scale()
+                                        }
+                                        glutin::VirtualKeyCode::Y => {
+                                            network.preview.toggle_quad_view()
+                                        }
+                                        glutin::VirtualKeyCode::Z => {
+                                            network.toggle_compare_selected()
+                                        }
+                                        glutin::VirtualKeyCode::Space => {
+                                            network.timeline.toggle_playing()
+                                        }
+                                        glutin::VirtualKeyCode::Home => {
+                                            network.timeline.set_loop_start()
+                                        }
+                                        glutin::VirtualKeyCode::End => {
+                                            network.timeline.set_loop_end()
+                                        }
+                                        glutin::VirtualKeyCode::E => {
+                                            network.preview.toggle_accumulation()
+                                        }
+                                        glutin::VirtualKeyCode::F => {
+                                            network.toggle_fullscreen_preview()
+                                        }
+                                        glutin::VirtualKeyCode::K => {
+                                            network.save_selected_as_preset()
+                                        }
+                                        glutin::VirtualKeyCode::Grave => network.toggle_console(),
+                                        glutin::VirtualKeyCode::F1 => network.toggle_perf(),
+                                        glutin::VirtualKeyCode::F12 => {
+                                            network.save_preview_screenshot()
+                                        }
+                                        glutin::VirtualKeyCode::F11 => network
+                                            .export_svg(Path::new(constants::EXPORT_SVG_PATH)),
+                                        glutin::VirtualKeyCode::F4 => network
+                                            .export_dot(Path::new(constants::EXPORT_DOT_PATH)),
+                                        glutin::VirtualKeyCode::F5 => {
+                                            if let Some(root) = network.render_id {
+                                                builder.export_isf(
+                                                    &network.graph,
+                                                    root,
+                                                    &network.preview.get_snapshot(),
+                                                    Path::new(constants::EXPORT_ISF_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F6 => {
+                                            if let Some(root) = network.render_id {
+                                                network.estimate_bounds(&builder, root);
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F2 => {
+                                            if let Some(root) = network.render_id {
+                                                network.export_volume(
+                                                    &builder,
+                                                    root,
+                                                    Path::new(constants::EXPORT_VOLUME_PATH),
+                                                    Path::new(constants::EXPORT_VOX_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F3 => {
+                                            if let Some(root) = network.render_id {
+                                                network.export_gltf(
+                                                    &builder,
+                                                    root,
+                                                    Path::new(constants::EXPORT_GLTF_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F7 => {
+                                            if let Some(root) = network.render_id {
+                                                builder.export_wgsl(
+                                                    &network.graph,
+                                                    root,
+                                                    &network.preview.get_snapshot(),
+                                                    Path::new(constants::EXPORT_WGSL_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F8 => {
+                                            if let Some(root) = network.render_id {
+                                                builder.export_spirv(
+                                                    &network.graph,
+                                                    root,
+                                                    Path::new(constants::EXPORT_SPIRV_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F9 => {
+                                            if let Some(root) = network.render_id {
+                                                builder.export_shadertoy(
+                                                    &network.graph,
+                                                    root,
+                                                    &network.preview.get_snapshot(),
+                                                    Path::new(constants::EXPORT_SHADERTOY_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::F10 => {
+                                            if let Some(root) = network.render_id {
+                                                builder.export(
+                                                    &network.graph,
+                                                    root,
+                                                    Path::new(constants::EXPORT_GLSL_PATH),
+                                                );
+                                            }
+                                        }
+                                        glutin::VirtualKeyCode::Q => network.add_recent_op(
+                                            network.renderer.get_view().to_world(&mouse.curr)
+                                                - constants::OPERATOR_SIZE * 0.5,
+                                            constants::OPERATOR_SIZE,
+                                        ),
                                         glutin::VirtualKeyCode::Key1 => {
                                             network.preview.set_shading(Shading::Depth)
                                         }
@@ -200,6 +755,36 @@ fn main() {
                                         glutin::VirtualKeyCode::Key5 => {
                                             network.preview.set_shading(Shading::Diffuse)
                                         }
+                                        glutin::VirtualKeyCode::U => {
+                                            network.preview.set_shading(Shading::PathTraced)
+                                        }
+                                        glutin::VirtualKeyCode::Key6 => network
+                                            .preview
+                                            .adjust_steps_range(
+                                                -constants::PREVIEW_STEPS_INCREMENT,
+                                                0.0,
+                                            ),
+                                        glutin::VirtualKeyCode::Key7 => network
+                                            .preview
+                                            .adjust_steps_range(
+                                                constants::PREVIEW_STEPS_INCREMENT,
+                                                0.0,
+                                            ),
+                                        glutin::VirtualKeyCode::Key8 => network
+                                            .preview
+                                            .adjust_steps_range(
+                                                0.0,
+                                                -constants::PREVIEW_STEPS_INCREMENT,
+                                            ),
+                                        glutin::VirtualKeyCode::Key9 => network
+                                            .preview
+                                            .adjust_steps_range(
+                                                0.0,
+                                                constants::PREVIEW_STEPS_INCREMENT,
+                                            ),
+                                        glutin::VirtualKeyCode::Key0 => {
+                                            network.preview.toggle_steps_log()
+                                        }
                                         glutin::VirtualKeyCode::Equals => {
                                             network.increment_param(&Vector4::new(
                                                 0.0,
@@ -258,25 +843,92 @@ fn main() {
                 },
                 _ => (),
             }
-        });
+        }
 
         clear();
 
+        // Reload the shader files if any of them were edited.
+        let changes = watcher.get_changes();
+        if !changes.is_empty() {
+            for path in changes.iter() {
+                info!(target: "codegen", "Shader file changed: {}", path.display());
+            }
+            match builder.reload() {
+                Ok(()) => network.invalidate_programs(),
+                Err(error) => error!("Failed to reload shaders: {}", error),
+            }
+            network.reload_custom_ops(&changes);
+        }
+
+        // Re-sample any baked ops whose sub-graphs changed, and bound the
+        // branches of any large unions.
+        network.update_bakes(&mut builder);
+        network.update_hierarchy(&mut builder);
+
         // Check to see if the graph needs to be rebuilt.
         if network.dirty() {
             if let Some(root) = network.render_id {
-                let indices = network.graph.traverse(root);
-                let program = builder.build_sources(&network, indices);
-                network.preview.set_valid_program(program);
+                // The previous programs keep being drawn until the new ones
+                // have finished compiling in the background. The same goes
+                // for graphs that can't be built at all, which are usually
+                // halfway through an edit: the preview only falls back to
+                // the checkerboard once a build actually fails to compile.
+                let started = builder.start_build(&network.graph, root, Target::Valid).is_ok();
+                if !started {
+                    network.set_error_ops(Vec::new());
+                }
+                network.status.stale = !started;
+
+                // Build the graph that the active one is being compared against.
+                let started = network.compare_id.map_or(false, |compare| {
+                    builder.start_build(&network.graph, compare, Target::Compare).is_ok()
+                });
+                if !started {
+                    builder.cancel(Target::Compare);
+                    network.preview.set_compare_program(None);
+                }
                 network.clean();
             } else {
+                builder.cancel_all();
+                network.status.stale = false;
                 network.preview.set_valid_program(None);
             }
         }
 
+        // Swap in any programs that have finished compiling.
+        for build in builder.poll() {
+            match build.target {
+                Target::Valid => {
+                    network.set_error_ops(build.error_ops);
+                    network.status.build_time = build.build_time;
+                    network.preview.set_valid_program(build.program);
+                }
+                Target::Compare => network.preview.set_compare_program(build.program),
+            }
+        }
+
+        // Rebuild the thumbnail of each op, if necessary.
+        if network.thumbnails_dirty() {
+            network.rebuild_thumbnails(&builder);
+        }
+
         // Draw the graph (ops, connections, preview window, etc.).
         network.draw();
+        network.perf.record_frame(frame_start.elapsed());
 
-        gl_window.swap_buffers().unwrap();
+        if let Err(error) = gl_window.swap_buffers() {
+            // The context is gone (i.e. the GPU was reset), so there is
+            // nothing left to draw into.
+            exit_with(Error::Window(error.to_string()));
+        }
+
+        // Space out frames by hand, unless the frame rate isn't limited:
+        // otherwise, the network is redrawn as fast as possible while it
+        // is animating or shaders are compiling (unless vsync is on).
+        if let Some(interval) = network.frame_settings.get_frame_interval() {
+            if let Some(remaining) = interval.checked_sub(frame_start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
     }
 }
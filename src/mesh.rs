@@ -0,0 +1,281 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+
+use grid::SampleGrid;
+
+use std::collections::BTreeMap;
+use std::u32;
+
+/// The pairs of corners (numbered as in `get_corner`) that are joined by
+/// each of the 12 edges of a cell.
+const CELL_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Returns the offset of the corner of a cell at `corner`, where the first
+/// bit selects the x-coordinate, the second the y-coordinate, and the third
+/// the z-coordinate.
+fn get_corner(corner: usize) -> Vector3<u32> {
+    Vector3::new(
+        (corner & 1) as u32,
+        ((corner >> 1) & 1) as u32,
+        ((corner >> 2) & 1) as u32,
+    )
+}
+
+/// A triangle mesh of the surface of a distance field.
+pub struct Mesh {
+    /// The position of each vertex
+    pub positions: Vec<Vector3<f32>>,
+
+    /// The (unit length) surface normal at each vertex
+    pub normals: Vec<Vector3<f32>>,
+
+    /// The vertices of each triangle, counterclockwise when seen from
+    /// outside of the surface
+    pub indices: Vec<u32>,
+
+    /// The ID of the object that each triangle belongs to, or `-1` if it
+    /// doesn't belong to any
+    pub objects: Vec<i32>,
+}
+
+impl Mesh {
+    /// Extracts the surface from the `distances` that were evaluated over
+    /// `grid` (see `SampleGrid::evaluate`) with surface nets: every cell that
+    /// the surface passes through gets a single vertex (at the average of
+    /// the points where the surface crosses its edges), and the vertices of
+    /// the four cells around each crossed edge of the grid are joined by a
+    /// quad. Points where the distance is zero are considered inside. Each
+    /// quad belongs to the object at the inner end of its edge, according
+    /// to `ids` (see `SampleGrid::evaluate_with_ids`).
+    pub fn extract(grid: &SampleGrid, distances: &[f32], ids: &[f32]) -> Mesh {
+        let mut mesh = Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            objects: Vec::new(),
+        };
+
+        let resolution = grid.resolution;
+        if resolution.x < 2 || resolution.y < 2 || resolution.z < 2 {
+            return mesh;
+        }
+        let spacing = grid.get_spacing();
+        let point_index = |point: Vector3<u32>| {
+            (point.x + resolution.x * (point.y + resolution.y * point.z)) as usize
+        };
+        let distance_at = |point: Vector3<u32>| distances[point_index(point)];
+
+        // Place a vertex in every cell that the surface passes through.
+        let cells = resolution - Vector3::new(1, 1, 1);
+        let cell_index =
+            |cell: Vector3<u32>| (cell.x + cells.x * (cell.y + cells.y * cell.z)) as usize;
+        let mut vertices = vec![u32::MAX; (cells.x * cells.y * cells.z) as usize];
+        for z in 0..cells.z {
+            for y in 0..cells.y {
+                for x in 0..cells.x {
+                    let cell = Vector3::new(x, y, z);
+                    let mut values = [0.0; 8];
+                    for (corner, value) in values.iter_mut().enumerate() {
+                        *value = distance_at(cell + get_corner(corner));
+                    }
+                    let inside = values.iter().filter(|&&value| value <= 0.0).count();
+                    if inside == 0 || inside == 8 {
+                        continue;
+                    }
+
+                    let mut sum = Vector3::zero();
+                    let mut crossings = 0.0;
+                    for &(a, b) in CELL_EDGES.iter() {
+                        if (values[a] <= 0.0) != (values[b] <= 0.0) {
+                            let t = values[a] / (values[a] - values[b]);
+                            let (pa, pb) = (get_corner(a), get_corner(b));
+                            let offset = Vector3::new(
+                                pa.x as f32 + (pb.x as f32 - pa.x as f32) * t,
+                                pa.y as f32 + (pb.y as f32 - pa.y as f32) * t,
+                                pa.z as f32 + (pb.z as f32 - pa.z as f32) * t,
+                            );
+                            sum += offset;
+                            crossings += 1.0;
+                        }
+                    }
+                    let offset = sum / crossings;
+                    let origin = grid.get_point(cell);
+                    let position = Vector3::new(
+                        origin.x + offset.x * spacing.x,
+                        origin.y + offset.y * spacing.y,
+                        origin.z + offset.z * spacing.z,
+                    );
+
+                    // The gradient of the distance field (which points away
+                    // from the surface) is estimated from the differences
+                    // between opposite faces of the cell.
+                    let difference = |axis: usize| {
+                        (0..8)
+                            .map(|corner| {
+                                if (corner >> axis) & 1 == 1 {
+                                    values[corner]
+                                } else {
+                                    -values[corner]
+                                }
+                            })
+                            .sum::<f32>()
+                    };
+                    let gradient = Vector3::new(
+                        difference(0) / spacing.x,
+                        difference(1) / spacing.y,
+                        difference(2) / spacing.z,
+                    );
+                    let normal = if gradient.magnitude2() > 0.0 {
+                        gradient.normalize()
+                    } else {
+                        Vector3::unit_y()
+                    };
+
+                    vertices[cell_index(cell)] = mesh.positions.len() as u32;
+                    mesh.positions.push(position);
+                    mesh.normals.push(normal);
+                }
+            }
+        }
+
+        // Join the vertices of the cells around each edge of the grid that
+        // the surface crosses.
+        let axes = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+        for z in 0..resolution.z {
+            for y in 0..resolution.y {
+                for x in 0..resolution.x {
+                    let point = Vector3::new(x, y, z);
+                    for axis in 0..3 {
+                        // The other two axes, in the order that makes the quad
+                        // counterclockwise when seen from the positive end of
+                        // this one.
+                        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                        if point[axis] + 1 >= resolution[axis]
+                            || point[u] == 0
+                            || point[v] == 0
+                            || point[u] >= cells[u]
+                            || point[v] >= cells[v]
+                        {
+                            continue;
+                        }
+
+                        let start_inside = distance_at(point) <= 0.0;
+                        let end_inside = distance_at(point + axes[axis]) <= 0.0;
+                        if start_inside == end_inside {
+                            continue;
+                        }
+
+                        let base = point - axes[u] - axes[v];
+                        let quad = [
+                            vertices[cell_index(base)],
+                            vertices[cell_index(base + axes[u])],
+                            vertices[cell_index(base + axes[u] + axes[v])],
+                            vertices[cell_index(base + axes[v])],
+                        ];
+
+                        // The surface faces the positive end of the axis if
+                        // the edge leaves the inside there.
+                        let order = if start_inside {
+                            [0, 1, 2, 0, 2, 3]
+                        } else {
+                            [0, 2, 1, 0, 3, 2]
+                        };
+                        mesh.indices.extend(order.iter().map(|&corner| quad[corner]));
+
+                        let inner = if start_inside { point } else { point + axes[axis] };
+                        let object = ids[point_index(inner)] as i32;
+                        mesh.objects.extend_from_slice(&[object, object]);
+                    }
+                }
+            }
+        }
+
+        mesh
+    }
+
+    /// Returns the number of triangles in the mesh.
+    pub fn get_triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Returns the vertices of the triangles that belong to each object,
+    /// in order of the objects' IDs.
+    pub fn split_by_object(&self) -> Vec<(i32, Vec<u32>)> {
+        let mut objects: BTreeMap<i32, Vec<u32>> = BTreeMap::new();
+        for (triangle, &object) in self.indices.chunks(3).zip(self.objects.iter()) {
+            objects.entry(object).or_default().extend_from_slice(triangle);
+        }
+        objects.into_iter().collect()
+    }
+
+    /// Returns the corners of the box that encloses all of the vertices,
+    /// or `None` if the mesh is empty.
+    pub fn get_bounds(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        let first = *self.positions.first()?;
+        Some(self.positions.iter().fold((first, first), |(lower, upper), p| {
+            (
+                Vector3::new(lower.x.min(p.x), lower.y.min(p.y), lower.z.min(p.z)),
+                Vector3::new(upper.x.max(p.x), upper.y.max(p.y), upper.z.max(p.z)),
+            )
+        }))
+    }
+}
+
+#[test]
+fn test_extract() {
+    let grid = SampleGrid::new(
+        Vector3::new(-2.0, -2.0, -2.0),
+        Vector3::new(2.0, 2.0, 2.0),
+        Vector3::new(17, 17, 17),
+    );
+    let distances: Vec<f32> = (0..grid.get_point_count())
+        .map(|index| grid.get_point(grid.get_cell(index)).magnitude() - 0.9)
+        .collect();
+
+    // The two halves of the sphere are different objects.
+    let ids: Vec<f32> = (0..grid.get_point_count())
+        .map(|index| if grid.get_point(grid.get_cell(index)).x < 0.0 { 1.0 } else { 2.0 })
+        .collect();
+    let mesh = Mesh::extract(&grid, &distances, &ids);
+
+    assert!(mesh.get_triangle_count() > 0);
+    assert_eq!(mesh.positions.len(), mesh.normals.len());
+    for position in mesh.positions.iter() {
+        assert!((position.magnitude() - 0.9).abs() < 0.1);
+    }
+
+    // Every triangle should face away from the center of the sphere (whose
+    // radius is chosen so that none of the points lie on its surface, which
+    // would collapse the triangles around them).
+    for triangle in mesh.indices.chunks(3) {
+        let a = mesh.positions[triangle[0] as usize];
+        let b = mesh.positions[triangle[1] as usize];
+        let c = mesh.positions[triangle[2] as usize];
+        assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+    }
+
+    let (min, max) = mesh.get_bounds().unwrap();
+    assert!(min.x > -1.0 && max.x < 1.0);
+
+    let objects = mesh.split_by_object();
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0].1.len() + objects[1].1.len(), mesh.indices.len());
+    for (object, indices) in objects.iter() {
+        let side = if *object == 1 { -1.0 } else { 1.0 };
+        for &index in indices.iter() {
+            assert!(mesh.positions[index as usize].x * side > -grid.get_spacing().x);
+        }
+    }
+}
@@ -1,20 +1,58 @@
-use cgmath::{self, Vector2, Vector3, Vector4, Zero};
+use cgmath::{self, InnerSpace, Vector2, Vector3, Vector4, Zero};
+use gl;
 use uuid::Uuid;
 
+use assets;
+use backdrop::{Backdrop, BackdropDrag};
+use bake::{self, Bakes};
 use bounds::Rect;
+use capture::{self, CaptureMode, CaptureSettings};
+use cli::Benchmark;
+use clipboard;
 use color::Color;
+use console;
+use constants;
+use custom::CustomCode;
+use dot;
+use edge_cache::{EdgeCache, EdgeGeometry};
+use error::Error;
+use framebuffer::Framebuffer;
+use gallery::{self, Example};
+use gltf;
 use graph::{Connected, Graph};
-use interaction::{InteractionState, MouseInfo, Panel};
+use grid::SampleGrid;
+use hierarchy::Hierarchy;
+use mesh::Mesh;
+use interaction::{HoverTimer, InteractionState, MouseInfo, Panel};
 use operator::{ConnectionType, Connectivity, DomainType, Op, OpFamily, PrimitiveType};
-use preview::Preview;
-use renderer::{DrawParams, LineConnectivity, LineMode, Renderer};
+use pacing::FrameSettings;
+use perf::Performance;
+use preset::Preset;
+use preview::{self, Preview};
+use profile::Profile;
+use project::{Project, ProjectBackdrop, ProjectOp, Snippet};
+use recent::RecentProjects;
+use renderer::{DrawParams, Instance, Layer, LineConnectivity, LineMode, Renderer, Rounding};
+use routing::{self, Segment};
+use shader_builder::{ShaderBuilder, Target};
+use status::Status;
+use svg::Svg;
 use texture::Texture;
+use thumbnail::Thumbnails;
+use timeline::Timeline;
+use video::Encoder;
+use view::View;
+use volume;
 
 use std::cmp::max;
 use std::collections::HashMap;
 use std::io;
 use std::fs::{self, DirEntry};
-use std::path::Path;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::thread;
+use std::time::Duration;
 use std::ffi::OsStr;
 
 /// Palette:
@@ -29,63 +67,83 @@ use std::ffi::OsStr;
 /// Other:       0xFEC56D (yellow)
 ///
 pub struct Grid {
-    size: Vector2<f32>,
-    spacing: Vector2<usize>,
+    /// The distance between neighboring lines, in world space
+    spacing: Vector2<f32>,
+
+    /// The region that the lines currently cover
+    bounds: Rect,
+
     pub points_vertical: Vec<f32>,
     pub points_horizontal: Vec<f32>,
 }
 
 impl Grid {
-    pub fn new(size: Vector2<f32>, spacing: Vector2<usize>) -> Grid {
-        let mut points_vertical = Vec::new();
-        let mut points_horizontal = Vec::new();
+    pub fn new(spacing: Vector2<f32>) -> Grid {
+        Grid {
+            spacing,
+            bounds: Rect::default(),
+            points_vertical: Vec::new(),
+            points_horizontal: Vec::new(),
+        }
+    }
 
-        let lines_x = size.x as usize / spacing.x;
-        let lines_y = size.y as usize / spacing.y;
-        let spacing_x = size.x / lines_x as f32;
-        let spacing_y = size.y / lines_y as f32;
+    /// Rebuilds the lines so that they cover `bounds` (in world space),
+    /// unless they already do. The lines are placed at multiples of the
+    /// spacing, so that they move along with the network as it is panned.
+    pub fn cover(&mut self, bounds: &Rect) {
+        if self.bounds == *bounds {
+            return;
+        }
+        self.bounds = *bounds;
+        self.points_vertical.clear();
+        self.points_horizontal.clear();
 
-        let offset = size * 0.5;
+        let min = bounds.get_upper_left();
+        let max = min + bounds.get_size();
+        let first_x = (min.x / self.spacing.x).ceil() as i32;
+        let first_y = (min.y / self.spacing.y).ceil() as i32;
+        let last_x = (max.x / self.spacing.x).floor() as i32;
+        let last_y = (max.y / self.spacing.y).floor() as i32;
 
         // Draw vertical lines.
-        for i in 0..lines_x {
-            points_vertical.extend_from_slice(&[
-                i as f32 * spacing_x - offset.x,
-                -offset.y,
-                0.0,
-                0.0,
-                i as f32 * spacing_x - offset.x,
-                offset.y,
-                1.0,
-                1.0,
-            ]);
+        for i in first_x..last_x + 1 {
+            let x = i as f32 * self.spacing.x;
+            self.points_vertical
+                .extend_from_slice(&[x, min.y, 0.0, 0.0, x, max.y, 1.0, 1.0]);
         }
 
         // Draw horizontal lines.
-        for i in 0..lines_y {
-            points_horizontal.extend_from_slice(&[
-                -offset.x,
-                i as f32 * spacing_y - offset.y,
-                0.0,
-                0.0,
-                offset.x,
-                i as f32 * spacing_y - offset.y,
-                1.0,
-                1.0,
-            ]);
-        }
-
-        Grid {
-            size,
-            spacing,
-            points_vertical,
-            points_horizontal,
+        for i in first_y..last_y + 1 {
+            let y = i as f32 * self.spacing.y;
+            self.points_horizontal
+                .extend_from_slice(&[min.x, y, 0.0, 0.0, max.x, y, 1.0, 1.0]);
         }
     }
 }
 
 type Connection = usize;
 
+/// The rectangles that make up a group of ops that don't overlap one
+/// another (see `Network::draw_all_nodes`), gathered into batches that
+/// share the same textures.
+#[derive(Default)]
+struct NodeBand<'a> {
+    /// The area covered by each op in the band, including its shadow
+    extents: Vec<Rect>,
+
+    /// The bodies of the ops that are selected
+    selected: Vec<Rect>,
+
+    /// The body of each op
+    bodies: Vec<Instance>,
+
+    /// The input and output slots of each op
+    slots: Vec<Instance>,
+
+    /// The icon of each op, grouped by texture
+    icons: Vec<(&'a Texture, Vec<Instance>)>,
+}
+
 pub struct Network {
     /// An adjacency list representation of ops
     pub graph: Graph<Op, Connection>,
@@ -108,6 +166,47 @@ pub struct Network {
     /// The index of the render op (if there is one)
     pub render_id: Option<usize>,
 
+    /// The index of the second render op (if any), whose graph is drawn
+    /// alongside the active one in the preview for comparison
+    pub compare_id: Option<usize>,
+
+    /// The backdrops that are drawn behind groups of ops
+    pub backdrops: Vec<Backdrop>,
+
+    /// The source and destination indices of the currently selected
+    /// edge (if there is one)
+    pub edge_selection: Option<(usize, usize)>,
+
+    /// The index of the currently selected backdrop (if there is one)
+    pub backdrop_selection_id: Option<usize>,
+
+    /// The index of the backdrop that is currently being dragged (if
+    /// there is one), what part of it is being dragged, and the indices
+    /// of any ops that will move along with it
+    backdrop_drag: Option<(usize, BackdropDrag, Vec<usize>)>,
+
+    /// A flag that is set while the selected op is being dragged
+    /// around the network editor
+    dragging_op: bool,
+
+    /// A flag that is set while the network is being panned (by dragging
+    /// it with the middle mouse button)
+    panning: bool,
+
+    /// The scroll status of the mouse as of the last mouse event, which
+    /// is used to zoom the network by however much the wheel has moved
+    last_scroll: f32,
+
+    /// The points along the "knife" stroke that the user is currently
+    /// drawing (if any) - every connection that the stroke crosses is
+    /// removed when the stroke ends
+    knife_stroke: Vec<Vector2<f32>>,
+
+    /// The index of the op that the user is currently dragging a new
+    /// connection from (if there is one), along with the current
+    /// position of the mouse
+    pending_connection: Option<(usize, Vector2<f32>)>,
+
     /// A flag that controls whether or not the shader graph
     /// needs to be rebuilt
     dirty: bool,
@@ -116,13 +215,118 @@ pub struct Network {
     /// be drawn
     show_preview: bool,
 
+    /// The presets that are available in the library panel
+    presets: Vec<Preset>,
+
+    /// The index of the preset that will be dropped into the network
+    /// at the next click (if there is one)
+    armed_preset: Option<usize>,
+
+    /// A flag that controls whether or not the library panel will
+    /// be drawn
+    show_presets: bool,
+
+    /// The file that the network is saved to (and was loaded from)
+    project_path: PathBuf,
+
+    /// The networks that were most recently saved or loaded
+    recent_projects: RecentProjects,
+
+    /// A flag that controls whether or not the panel of recent networks
+    /// will be drawn, which is shown at startup
+    show_recent_projects: bool,
+
+    /// The networks that ship with the application
+    examples: Vec<Example>,
+
+    /// A flag that controls whether or not the gallery of examples will
+    /// be drawn, which is shown at startup if there aren't any recent
+    /// networks
+    show_gallery: bool,
+
+    /// The statistics shown in the status bar
+    pub status: Status,
+
+    /// The application's timeline, which drives time in the preview
+    pub timeline: Timeline,
+
+    /// A flag that is set while the user is dragging the playhead
+    scrubbing: bool,
+
+    /// The number of frames and resolution of captured frame sequences
+    pub capture_settings: CaptureSettings,
+
+    /// Whether the window is synchronized to the display, and how many
+    /// frames are drawn per second at most
+    pub frame_settings: FrameSettings,
+
+    /// The CPU and GPU timings of recent frames
+    pub perf: Performance,
+
+    /// The distance fields of the ops that are baked into 3D textures
+    bakes: Bakes,
+
+    /// The bounding spheres around the branches of large unions, which are
+    /// stored after the op parameters
+    hierarchy: Hierarchy,
+
+    /// The geometry of every edge, which is reused between frames
+    edge_cache: EdgeCache,
+
+    /// The order that edges are drawn in (see `draw_all_edges`), which is
+    /// gathered again each frame into the same allocation
+    edge_order: Vec<(bool, u32, usize, usize)>,
+
+    /// The parameters of every op, which are gathered again each frame
+    /// (see `gather_params`) into the same allocation
+    params_scratch: Vec<f32>,
+
+    /// A flag that controls whether or not the performance overlay will
+    /// be drawn
+    show_perf: bool,
+
+    /// A flag that controls whether or not the console will be
+    /// expanded to show recent messages
+    show_console: bool,
+
     /// A flag that controls whether or not ops will be snapped
     /// to a grid when dragged
     snapping: bool,
 
+    /// The UUIDs of the ops that caused the shader graph to fail
+    /// to compile (if any)
+    error_ops: Vec<Uuid>,
+
+    /// The op families that were most recently added to the network,
+    /// ordered from most to least recent
+    recent_families: Vec<OpFamily>,
+
+    /// The raymarched thumbnail of each op
+    thumbnails: Thumbnails,
+
+    /// A flag that controls whether or not the thumbnail programs
+    /// need to be rebuilt (i.e. after the topology of the graph changes)
+    thumbnails_dirty: bool,
+
+    /// The UUIDs of the ops whose thumbnail programs are waiting to be
+    /// rebuilt, which are built a few at a time (the last one first)
+    thumbnail_queue: Vec<Uuid>,
+
+    /// A flag that controls whether or not the thumbnails need to be
+    /// re-rendered (i.e. after parameters change)
+    thumbnails_stale: bool,
+
+    /// A flag that controls whether or not thumbnails will be drawn
+    /// in place of the op icons
+    show_thumbnails: bool,
+
     /// A map of asset names to textures, used to render various
     /// UI elements
     assets: HashMap<String, Texture>,
+
+    /// A timer that keeps track of how long the mouse has been
+    /// hovering over a particular op
+    hover: HoverTimer,
 }
 
 enum Pair<T> {
@@ -147,22 +351,84 @@ fn index_twice<T>(slc: &mut [T], a: usize, b: usize) -> Pair<&mut T> {
 }
 
 impl Network {
-    /// Constructs a new, empty network.
-    pub fn new(size: Vector2<f32>) -> Network {
+    /// Constructs a new, empty network, whose preview targets the
+    /// OpenGL feature level `profile`. This fails if any of the assets
+    /// that the editor needs (shaders, the font, and icons) can't be
+    /// loaded.
+    pub fn new(size: Vector2<f32>, profile: Profile) -> Result<Network, Error> {
         let mut network = Network {
             graph: Graph::new(),
-            renderer: Renderer::new(size),
-            preview: Preview::new(),
-            grid: Grid::new(size, Vector2::new(20, 20)),
+            renderer: Renderer::new(size)?,
+            preview: Preview::new(profile)?,
+            grid: Grid::new(Vector2::new(1.0, 1.0) * constants::NETWORK_GRID_SPACING),
             selection_id: None,
             render_id: None,
+            compare_id: None,
+            edge_selection: None,
+            backdrops: Vec::new(),
+            backdrop_selection_id: None,
+            backdrop_drag: None,
+            dragging_op: false,
+            panning: false,
+            last_scroll: 1.0,
+            knife_stroke: Vec::new(),
+            pending_connection: None,
             dirty: false,
             show_preview: true,
+            show_console: false,
+            status: Status::new(),
+            timeline: Timeline::new(),
+            scrubbing: false,
+            capture_settings: CaptureSettings::new(),
+            frame_settings: FrameSettings::new(),
+            perf: Performance::new(),
+            bakes: Bakes::new(),
+            hierarchy: Hierarchy::new(),
+            edge_cache: EdgeCache::new(),
+            edge_order: Vec::new(),
+            params_scratch: Vec::new(),
+            show_perf: false,
+            presets: Preset::load_all(&assets::get_path(constants::PRESETS_PATH)),
+            armed_preset: None,
+            show_presets: false,
+            project_path: PathBuf::from(constants::PROJECT_PATH),
+            recent_projects: RecentProjects::load(Path::new(constants::RECENT_PROJECTS_PATH)),
+            show_recent_projects: false,
+            examples: gallery::load_all(&assets::get_path(constants::EXAMPLES_PATH)),
+            show_gallery: false,
             snapping: true,
+            error_ops: Vec::new(),
+            recent_families: Vec::new(),
+            thumbnails: Thumbnails::new(),
+            thumbnails_dirty: false,
+            thumbnail_queue: Vec::new(),
+            thumbnails_stale: false,
+            show_thumbnails: true,
             assets: HashMap::new(),
+            hover: HoverTimer::new(),
         };
-        network.load_assets();
-        network
+        network.show_recent_projects = !network.recent_projects.is_empty();
+        network.show_gallery = !network.show_recent_projects && !network.examples.is_empty();
+        network.load_assets()?;
+        Ok(network)
+    }
+
+    /// Returns `true` if the network will look different on the next
+    /// frame even if no new events arrive (i.e. the timeline is playing,
+    /// the preview is changing, or a tooltip is about to appear) and
+    /// `false` otherwise. When this returns `false`, there is no need to
+    /// redraw until the next event. The dashes of indirect connections
+    /// only scroll while the network is being redrawn anyways, so that an
+    /// idle network doesn't keep the CPU busy.
+    pub fn is_animating(&self) -> bool {
+        let tooltip_pending = self.hover.get_target().is_some()
+            && self.hover.expired(constants::TOOLTIP_DELAY).is_none();
+
+        self.timeline.is_playing()
+            || self.preview.is_animating()
+            || tooltip_pending
+            || self.pending_connection.is_some()
+            || !self.knife_stroke.is_empty()
     }
 
     /// Returns `true` if the shader graph needs to be rebuilt and
@@ -171,419 +437,3218 @@ impl Network {
         self.dirty
     }
 
-    /// Sets the `dirty` flag to `false`.
-    pub fn clean(&mut self) {
-        self.dirty = false;
-    }
-
-    /// Toggles drawing of the preview window.
-    pub fn toggle_preview(&mut self) {
-        self.show_preview = !self.show_preview;
+    /// Shows or hides the library panel of presets.
+    pub fn toggle_presets(&mut self) {
+        self.show_presets = !self.show_presets;
+        self.armed_preset = None;
     }
 
-    /// Scales the distance field represented by the currently
-    /// selected op (if one exists).
-    pub fn increment_param(&mut self, values: &Vector4<f32>) {
-        if let Some(selected) = self.selection_id {
-            let node = self.graph.nodes.get_mut(selected).unwrap();
+    /// Returns the indices of the op at `root` and all of the ops upstream
+    /// of it, the upper-left corner of the group, and the connections
+    /// between them (as indices into the former), which are listed in the
+    /// order of each op's inputs.
+    fn get_upstream(&self, root: usize) -> (Vec<usize>, Vector2<f32>, Vec<(usize, usize)>) {
+        let indices = self.graph.traverse(root);
+
+        let mut origin = Vector2::new(::std::f32::MAX, ::std::f32::MAX);
+        for node in indices.iter().filter_map(|&index| self.graph.get_node(index)) {
+            let upper_left = node.data.bounds_body.get_upper_left();
+            origin.x = origin.x.min(upper_left.x);
+            origin.y = origin.y.min(upper_left.y);
+        }
 
-            let params = node.data.get_params_mut();
-            let data = params.get_data_mut();
-            data[0] += values.x;
-            data[1] += values.y;
-            data[2] += values.z;
-            data[3] += values.w;
+        let mut connections = Vec::new();
+        for (dst, &index) in indices.iter().enumerate() {
+            for input in self.graph.edges[index].inputs.iter() {
+                if let Some(src) = indices.iter().position(|other| other == input) {
+                    connections.push((src, dst));
+                }
+            }
         }
+        (indices, origin, connections)
     }
 
-    /// Deletes the currently selected op (if the selection is not empty).
-    pub fn delete_selected(&mut self) {
+    /// Saves the selected op, along with all of the ops upstream of it,
+    /// as a new preset in the library, which is named after the op. If
+    /// there already is a preset by that name, the name is numbered (i.e.
+    /// `sphere_3 2`), so that the existing preset isn't overwritten.
+    pub fn save_selected_as_preset(&mut self) {
         if let Some(selected) = self.selection_id {
-            // Before removing this vertex from the graph,
-            // check to see if it was connected to the root
-            // (if one exists). If so, then the shader
-            // graph needs to be rebuilt.
-            if let Some(id) = self.render_id {
-                for edge in self.graph.edges[selected].outputs.iter() {
-                    if *edge == id {
-                        self.dirty = true;
-                        self.render_id = None;
-                        break;
-                    }
-                }
+            let path = assets::get_path(constants::PRESETS_PATH);
+            let taken = |name: &str| {
+                let file_name = Preset::get_file_name(name);
+                path.join(&file_name).exists()
+                    || self.presets
+                        .iter()
+                        .any(|preset| Preset::get_file_name(&preset.name) == file_name)
+            };
+
+            let base = match self.graph.get_node(selected) {
+                Some(node) => &node.data.name,
+                None => return,
+            };
+            let mut name = base.clone();
+            let mut count = 1;
+            while taken(&name) {
+                count += 1;
+                name = format!("{} {}", base, count);
             }
 
-            // The last node in the graph's list of nodes
-            // will be moved, so its parameter index needs
-            // to be reset.
-            if let Some(node) = self.graph.nodes.last_mut() {
-                node.data.params.set_index(selected);
+            let preset = Preset {
+                name,
+                snippet: self.get_snippet(selected),
+            };
+            match preset.save(&path) {
+                Ok(file) => console!("Saved preset: {}", file.display()),
+                Err(error) => {
+                    error!("Failed to save preset {}: {}", preset.name, error);
+                    return;
+                }
             }
 
-            // Finally, remove the node and reset the selection.
-            self.graph.remove_node(selected);
-            self.selection_id = None;
+            // Keep the library panel in the same order as when it was loaded.
+            let index = self.presets
+                .binary_search_by(|other| other.name.cmp(&preset.name))
+                .unwrap_or_else(|index| index);
+            self.presets.insert(index, preset);
+            self.armed_preset = None;
         }
     }
 
-    /// Adds a new op of type `family` to the network at coordinates
-    /// `position` and dimensions `size`.
-    pub fn add_op(&mut self, family: OpFamily, position: Vector2<f32>, size: Vector2<f32>) {
-        // Create the operator.
-        let mut op = Op::new(family, position, size);
+    /// Adds all of the ops and connections in the preset at `index`
+    /// to the network, with the preset's upper-left corner at `position`.
+    fn instantiate_preset(&mut self, index: usize, position: Vector2<f32>) {
+        let snippet = self.presets[index].snippet.clone();
+        self.insert_snippet(&snippet, position);
+        console!("Added preset: {}", self.presets[index].name);
+    }
 
-        // We need to re-assign this op's parameter index so
-        // that the resulting shader code properly indexes into
-        // the SSBO of parameter data.
-        op.params.set_index(self.graph.nodes.len());
+    /// Returns the selected op (at `selected`), along with all of the ops
+    /// upstream of it, as a snippet whose positions are relative to the
+    /// upper-left corner of the group.
+    fn get_snippet(&self, selected: usize) -> Snippet {
+        let (indices, origin, connections) = self.get_upstream(selected);
+
+        let ops = indices
+            .iter()
+            .filter_map(|&index| self.graph.get_node(index))
+            .map(|node| {
+                let op = &node.data;
+                ProjectOp {
+                    name: op.name.clone(),
+                    family: op.family,
+                    position: op.bounds_body.get_upper_left() - origin,
+                    params: *op.params.get_data(),
+                    code: op.custom.as_ref().map(|custom| custom.file_name.clone()),
+                    baked: op.baked,
+                }
+            })
+            .collect();
 
-        // Add the operator to the current graph.
-        self.graph.add_node(op, 0);
+        Snippet {
+            ops,
+            connections,
+            sources: self.get_custom_sources(&indices),
+        }
     }
 
-    /// Adds a new connection between two ops.
-    pub fn add_connection(&mut self, a: usize, b: usize) {
-        self.graph.add_edge(a, b);
-
-        if let Pair::Both(node_a, node_b) = index_twice(&mut self.graph.nodes, a, b) {
-            // If we previously connected to a render op, then we
-            // know that the graph must be rebuilt.
-            if let Some(_) = self.render_id {
-                self.dirty = true;
-                println!("Active render node in-line: re-building graph");
+    /// Places the selected op, along with all of the ops upstream of it,
+    /// on the system clipboard as a JSON snippet (see `Snippet`).
+    pub fn copy_selected(&self) {
+        if let Some(selected) = self.selection_id {
+            let snippet = self.get_snippet(selected);
+            match clipboard::set_text(&snippet.serialize()) {
+                Ok(()) => console!("Copied {} ops to the clipboard", snippet.ops.len()),
+                Err(error) => error!("Failed to copy ops to the clipboard: {}", error),
             }
+        }
+    }
 
-            // If we are connecting to a render op, then the shader
-            // must be rebuilt.
-            if let OpFamily::Primitive(PrimitiveType::Render) = node_b.data.family {
-                self.render_id = Some(b);
-                self.dirty = true;
-                println!("Connected to render node: building graph");
+    /// Adds the ops in the JSON snippet on the system clipboard (copied
+    /// from this or another network) to the network, with the snippet's
+    /// upper-left corner at `position`.
+    pub fn paste(&mut self, position: Vector2<f32>) {
+        let snippet = match clipboard::get_text().and_then(|text| Snippet::parse(&text)) {
+            Ok(snippet) => snippet,
+            Err(error) => {
+                warn!("The clipboard doesn't hold any ops: {}", error);
+                return;
             }
+        };
+        self.insert_snippet(&snippet, position);
+        console!("Pasted {} ops from the clipboard", snippet.ops.len());
+    }
 
-            // Deselect both ops.
-            node_a.data.state = InteractionState::Deselected;
-            node_b.data.state = InteractionState::Deselected;
-        } else {
-            println!("Attempting to connect two ops with the same index - something is wrong here")
+    /// Adds the ops in `snippet` to the network, with the snippet's
+    /// upper-left corner at `position`.
+    fn insert_snippet(&mut self, snippet: &Snippet, position: Vector2<f32>) {
+        let base = self.graph.nodes.len();
+        for op in snippet.ops.iter() {
+            let custom = op.code
+                .as_ref()
+                .map(|file_name| Network::import_custom(file_name, &snippet.sources));
+            self.insert_op(op.family, position + op.position, constants::OPERATOR_SIZE, custom);
+            let node = self.graph.nodes.last_mut().unwrap();
+            *node.data.params.get_data_mut() = op.params;
+            node.data.baked = op.baked;
+        }
+        for &(src, dst) in snippet.connections.iter() {
+            self.add_connection(base + src, base + dst);
         }
     }
 
-    /// Handles all mouse events.
-    pub fn handle_interaction(&mut self, mouse: &MouseInfo) {
-        let mut connecting = false;
-        let mut src: Option<usize> = None;
-        let mut dst: Option<usize> = None;
+    /// Returns the code of the custom op whose file is named `file_name`,
+    /// which is taken from `sources` (the code that was saved or copied
+    /// along with the op) if possible.
+    fn import_custom(file_name: &str, sources: &[(String, String)]) -> CustomCode {
+        match sources.iter().find(|(name, _)| name == file_name) {
+            Some((_, text)) => CustomCode::import(file_name, text).unwrap_or_else(|error| {
+                error!("Failed to import custom op {}: {}", file_name, error);
+                CustomCode::open(file_name)
+            }),
+            None => CustomCode::open(file_name),
+        }
+    }
 
-        for (index, node) in self.graph.nodes.iter_mut().enumerate() {
-            if let InteractionState::ConnectSource = node.data.state {
-                if mouse.ldown {
-                    // If this operator is currently being connected to another:
-                    // 1) Set the `connecting` flag to `true`, as the user is
-                    //    performing a potential op connection
-                    // 2) Store its graph index as a potential connect source
-                    // 3) Skip the rest of this loop iteration
-                    connecting = true;
-                    src = Some(index);
+    /// Returns the code of every custom op among the ops at `indices`,
+    /// keyed by the name of the file that holds it.
+    fn get_custom_sources(&self, indices: &[usize]) -> Vec<(String, String)> {
+        let mut sources: Vec<(String, String)> = Vec::new();
+        for &index in indices.iter() {
+            if let Some(ref custom) = self.graph.nodes[index].data.custom {
+                if sources.iter().any(|(name, _)| *name == custom.file_name) {
                     continue;
-                } else {
-                    // Otherwise, deselect this op
-                    node.data.state = InteractionState::Deselected;
+                }
+                match CustomCode::read_source(&custom.file_name) {
+                    Ok(text) => sources.push((custom.file_name.clone(), text)),
+                    Err(error) => error!("Failed to read custom op: {}", error),
                 }
             }
+        }
+        sources
+    }
 
-            // Is the mouse inside of this op's bounding box?
-            if node.data.bounds_body.inside(&mouse.curr) {
-                // Is there an op currently selected?
-                if let Some(selected) = self.selection_id {
-                    // Is this op the selected op?
-                    if selected == index {
-                        // Is the mouse down?
-                        if mouse.ldown {
-                            // TODO: let mut velocity = ..;
-                            if self.snapping {
-                                // TODO
-                            }
-                            node.data.translate(&mouse.velocity());
-                        }
-                        continue;
-                    }
-                }
+    /// Shows or hides the panel of recently saved or loaded networks.
+    pub fn toggle_recent_projects(&mut self) {
+        if self.recent_projects.is_empty() {
+            console!("There aren't any recent networks");
+            return;
+        }
+        self.show_recent_projects = !self.show_recent_projects;
+        self.show_gallery = false;
+    }
 
-                // This op is not the selected op, but we are inside of it's
-                // bounding box. Is the mouse down?
-                if mouse.ldown {
-                    // Are we inside the bounds of this op's output slot?
-                    if node.data
-                        .bounds_output
-                        .inside_with_padding(&mouse.curr, 12.0)
-                    {
-                        // This op is now a potential connection source.
-                        node.data.state = InteractionState::ConnectSource;
+    /// Shows or hides the gallery of example networks.
+    pub fn toggle_gallery(&mut self) {
+        if self.examples.is_empty() {
+            console!("There aren't any examples");
+            return;
+        }
+        self.show_gallery = !self.show_gallery;
+        self.show_recent_projects = false;
+    }
 
-                        // Store the connection source index.
-                        src = Some(index);
-                    } else {
-                        // This op has been selected.
-                        node.data.state = InteractionState::Selected;
+    /// Makes `path` the file that the network is saved to, and moves it
+    /// to the front of the list of recent networks.
+    fn remember_project(&mut self, path: &Path) {
+        self.recent_projects.add(path);
+        self.project_path = self.recent_projects.get_paths()[0].clone();
+        self.recent_projects.save(Path::new(constants::RECENT_PROJECTS_PATH));
+    }
 
-                        // Store the selected UUID.
-                        self.selection_id = Some(index);
-                    }
-                } else {
-                    // Otherwise, the mouse is still inside the bounds of this op,
-                    // so we must be hovering over it.
-                    node.data.state = InteractionState::Hover;
-                }
+    /// Saves every op, connection, and backdrop in the network, along with
+    /// the preview's camera and view settings, to the file that it was
+    /// loaded from (or `PROJECT_PATH`, if it wasn't).
+    pub fn save_project(&mut self) {
+        let path = self.project_path.clone();
+        let ops = self.graph
+            .nodes
+            .iter()
+            .map(|node| ProjectOp {
+                name: node.data.name.clone(),
+                family: node.data.family,
+                position: *node.data.bounds_body.get_upper_left(),
+                params: *node.data.params.get_data(),
+                code: node.data.custom.as_ref().map(|custom| custom.file_name.clone()),
+                baked: node.data.baked,
+            })
+            .collect();
+
+        // Connections are listed in the order of each op's inputs.
+        let mut connections = Vec::new();
+        for (dst, edges) in self.graph.edges.iter().enumerate() {
+            for &src in edges.inputs.iter() {
+                connections.push((src, dst));
+            }
+        }
 
-            // The mouse is not inside of this op's bounding box.
-            } else {
-                // Is there an op currently selected?
-                if let Some(selected) = self.selection_id {
-                    // Is this op the selected op?
-                    if selected == index {
-                        // Is the mouse down?
-                        if mouse.ldown {
-                            // The user has clicked somewhere else in the
-                            // network, so reset the selection.
-                            self.selection_id = None;
-                        } else {
-                            // Keep this op selected.
-                            node.data.state = InteractionState::Selected;
-                        }
-                    } else {
-                        // Deselect the op.
-                        node.data.state = InteractionState::Deselected;
-                    }
-                } else {
-                    // Deselect the op.
-                    node.data.state = InteractionState::Deselected;
-                }
+        let backdrops = self.backdrops
+            .iter()
+            .map(|backdrop| ProjectBackdrop {
+                title: backdrop.title.clone(),
+                position: *backdrop.bounds_body.get_upper_left(),
+                size: *backdrop.bounds_body.get_size(),
+                color_index: backdrop.color_index,
+            })
+            .collect();
+
+        let indices: Vec<usize> = (0..self.graph.nodes.len()).collect();
+        let project = Project {
+            ops,
+            connections,
+            render: self.render_id,
+            backdrops,
+            settings: self.preview.get_settings(),
+            sources: self.get_custom_sources(&indices),
+        };
+        match project.save(&path) {
+            Ok(()) => {
+                console!("Saved network: {}", path.display());
+                self.remember_project(&path);
             }
+            Err(error) => error!("Failed to save network {}: {}", path.display(), error),
         }
+    }
 
-        // If the mouse is dragging from the output slot of one operator,
-        // check if a potential connection has happened (i.e. the mouse
-        // is now over an input slot of a different operator).
-        if connecting {
-            for (index, node) in self.graph.nodes.iter_mut().enumerate() {
-                // Is the mouse now inside of a different op's input slot region?
-                if node.data
-                    .bounds_input
-                    .inside_with_padding(&mouse.curr, 12.0)
-                {
-                    node.data.state = InteractionState::ConnectDestination;
-                    if let Some(src) = src {
-                        dst = Some(index);
+    /// Replaces the network with the one saved in the file at `path`,
+    /// leaving the current network untouched (and returning `false`) if it
+    /// fails to load.
+    pub fn load_project(&mut self, path: &Path) -> bool {
+        let project = match Project::load(path) {
+            Ok(project) => project,
+            Err(error) => {
+                error!("Failed to load network {}: {}", path.display(), error);
+                return false;
+            }
+        };
+
+        self.remember_project(path);
+        self.apply_project(&project);
+        console!("Loaded network: {}", path.display());
+        true
+    }
+
+    /// Replaces the network with the example at `index`. Examples aren't
+    /// added to the list of recent networks, and saving doesn't overwrite
+    /// them: the network is saved to `PROJECT_PATH` instead.
+    fn load_example(&mut self, index: usize) {
+        let path = self.examples[index].path.clone();
+        match Project::load(&path) {
+            Ok(project) => {
+                self.project_path = PathBuf::from(constants::PROJECT_PATH);
+                self.apply_project(&project);
+                console!("Loaded example: {}", self.examples[index].name);
+            }
+            Err(error) => error!("Failed to load example {}: {}", path.display(), error),
+        }
+    }
+
+    /// Replaces the ops, connections, and backdrops of the network (along
+    /// with the preview's settings) with the ones in `project`.
+    fn apply_project(&mut self, project: &Project) {
+        self.show_recent_projects = false;
+        self.show_gallery = false;
+
+        self.graph = Graph::new();
+        self.selection_id = None;
+        self.compare_id = None;
+        self.edge_selection = None;
+        self.backdrops.clear();
+        self.backdrop_selection_id = None;
+        self.backdrop_drag = None;
+        self.pending_connection = None;
+        self.error_ops.clear();
+        self.hover.update(None);
+
+        for op in project.ops.iter() {
+            let custom = op.code
+                .as_ref()
+                .map(|file_name| Network::import_custom(file_name, &project.sources));
+            self.insert_op(op.family, op.position, constants::OPERATOR_SIZE, custom);
+
+            let node = self.graph.nodes.last_mut().unwrap();
+            node.data.name = op.name.clone();
+            *node.data.params.get_data_mut() = op.params;
+            node.data.baked = op.baked;
+        }
+        for &(src, dst) in project.connections.iter() {
+            self.add_connection(src, dst);
+        }
+
+        for saved in project.backdrops.iter() {
+            let mut backdrop = Backdrop::new(saved.position, saved.size);
+            backdrop.title = saved.title.clone();
+            backdrop.color_index = saved.color_index;
+            self.backdrops.push(backdrop);
+        }
+
+        // Connecting ops activates the last render op that was connected to,
+        // which isn't necessarily the one that was active.
+        self.render_id = project.render;
+        self.preview.apply_settings(&project.settings);
+        self.dirty = true;
+        self.thumbnails_dirty = true;
+    }
+
+    /// Returns the bounding box of the row in the library panel that
+    /// corresponds to the preset at `index`. Row `0` is the panel's title.
+    fn preset_row_bounds(&self, index: usize) -> Rect {
+        let height = self.renderer.get_font().get_line_height(constants::FONT_SCALE)
+            + constants::PRESET_PANEL_PADDING.y * 2.0;
+
+        // Anchor the panel to the upper-right corner of the network editor.
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(
+            half_size.x - constants::PRESET_PANEL_WIDTH - constants::PRESET_PANEL_PADDING.x,
+            -half_size.y + constants::PRESET_PANEL_PADDING.x + height * index as f32,
+        );
+        Rect::new(upper_left, Vector2::new(constants::PRESET_PANEL_WIDTH, height))
+    }
+
+    /// Arms the preset that the user clicked on in the library panel, or
+    /// drops the armed preset into the network at the mouse position.
+    /// Returns `true` if the mouse event was consumed and `false` otherwise.
+    fn handle_presets(&mut self, mouse: &MouseInfo) -> bool {
+        let pressed = mouse.ldown && mouse.clicked == mouse.curr;
+        if !self.show_presets || !pressed {
+            return false;
+        }
+
+        for index in 0..self.presets.len() {
+            if self.preset_row_bounds(index + 1).inside(&mouse.curr) {
+                self.armed_preset = Some(index);
+                return true;
+            }
+        }
+
+        if let Some(index) = self.armed_preset.take() {
+            let position = self.renderer.get_view().to_world(&mouse.curr);
+            self.instantiate_preset(index, position);
+            return true;
+        }
+        false
+    }
+
+    /// Returns the bounding box of the row in the panel of recent networks
+    /// that corresponds to the network at `index`. Row `0` is the panel's
+    /// title.
+    fn recent_project_row_bounds(&self, index: usize) -> Rect {
+        let height = self.renderer.get_font().get_line_height(constants::FONT_SCALE)
+            + constants::PRESET_PANEL_PADDING.y * 2.0;
+
+        // Center the panel horizontally, near the top of the network editor.
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(
+            -constants::RECENT_PANEL_WIDTH * 0.5,
+            -half_size.y * 0.5 + height * index as f32,
+        );
+        Rect::new(upper_left, Vector2::new(constants::RECENT_PANEL_WIDTH, height))
+    }
+
+    /// Loads the network that the user clicked on in the panel of recent
+    /// networks. Clicking anywhere else hides the panel. Returns `true` if
+    /// the mouse event was consumed and `false` otherwise.
+    fn handle_recent_projects(&mut self, mouse: &MouseInfo) -> bool {
+        let pressed = mouse.ldown && mouse.clicked == mouse.curr;
+        if !self.show_recent_projects || !pressed {
+            return false;
+        }
+
+        let count = self.recent_projects.get_paths().len();
+        for index in 0..count {
+            if self.recent_project_row_bounds(index + 1).inside(&mouse.curr) {
+                let path = self.recent_projects.get_paths()[index].clone();
+                self.load_project(&path);
+                return true;
+            }
+        }
+
+        self.show_recent_projects = false;
+        false
+    }
+
+    /// Returns the bounding box of the row in the gallery that corresponds
+    /// to the example at `index`. Row `0` is the gallery's title.
+    fn gallery_row_bounds(&self, index: usize) -> Rect {
+        let height = self.renderer.get_font().get_line_height(constants::FONT_SCALE)
+            + constants::PRESET_PANEL_PADDING.y * 2.0;
+
+        // Center the gallery horizontally, like the panel of recent networks.
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(
+            -constants::GALLERY_PANEL_WIDTH * 0.5,
+            -half_size.y * 0.5 + height * index as f32,
+        );
+        Rect::new(upper_left, Vector2::new(constants::GALLERY_PANEL_WIDTH, height))
+    }
+
+    /// Loads the example that the user clicked on in the gallery. Clicking
+    /// anywhere else hides the gallery. Returns `true` if the mouse event
+    /// was consumed and `false` otherwise.
+    fn handle_gallery(&mut self, mouse: &MouseInfo) -> bool {
+        let pressed = mouse.ldown && mouse.clicked == mouse.curr;
+        if !self.show_gallery || !pressed {
+            return false;
+        }
+
+        for index in 0..self.examples.len() {
+            if self.gallery_row_bounds(index + 1).inside(&mouse.curr) {
+                self.load_example(index);
+                return true;
+            }
+        }
+
+        self.show_gallery = false;
+        false
+    }
+
+    /// Returns the bounding box of the timeline, which sits just above
+    /// the status bar.
+    fn timeline_bounds(&self) -> Rect {
+        let half_size = self.renderer.get_size() * 0.5;
+        Rect::new(
+            Vector2::new(
+                -half_size.x,
+                half_size.y - constants::STATUS_BAR_HEIGHT - constants::TIMELINE_HEIGHT,
+            ),
+            Vector2::new(half_size.x * 2.0, constants::TIMELINE_HEIGHT),
+        )
+    }
+
+    /// Moves the playhead to the mouse while the user is dragging inside
+    /// of the timeline. Returns `true` if the mouse event was consumed and
+    /// `false` otherwise.
+    fn scrub(&mut self, mouse: &MouseInfo) -> bool {
+        let bounds = self.timeline_bounds();
+        if !mouse.ldown {
+            self.scrubbing = false;
+            return false;
+        }
+        if mouse.clicked == mouse.curr && bounds.inside(&mouse.curr) {
+            self.scrubbing = true;
+        }
+        if self.scrubbing {
+            let x = (mouse.curr.x - bounds.get_upper_left().x) / bounds.get_size().x;
+            self.timeline.scrub(x * constants::TIMELINE_DURATION);
+        }
+        self.scrubbing
+    }
+
+    /// Expands or collapses the console.
+    pub fn toggle_console(&mut self) {
+        self.show_console = !self.show_console;
+    }
+
+    /// Shows or hides the performance overlay.
+    pub fn toggle_perf(&mut self) {
+        self.show_perf = !self.show_perf;
+    }
+
+    /// Sets the ops that will be highlighted as the cause of a shader
+    /// compilation failure.
+    pub fn set_error_ops(&mut self, error_ops: Vec<Uuid>) {
+        self.error_ops = error_ops;
+    }
+
+    /// Marks every shader program (i.e. the preview and thumbnails) as
+    /// needing to be rebuilt, i.e. after the shader files were reloaded.
+    pub fn invalidate_programs(&mut self) {
+        if self.render_id.is_some() {
+            self.dirty = true;
+        }
+        self.thumbnails_dirty = true;
+    }
+
+    /// Reloads the code of every custom op whose file is one of `changes`,
+    /// keeping the previous code of any op whose file fails to load.
+    pub fn reload_custom_ops(&mut self, changes: &[PathBuf]) {
+        let mut reloaded = false;
+        for node in self.graph.nodes.iter_mut() {
+            let file_name = match node.data.custom {
+                Some(ref custom) if changes.iter().any(|path| custom.is_loaded_from(path)) => {
+                    custom.file_name.clone()
+                }
+                _ => continue,
+            };
+            match CustomCode::load(&file_name) {
+                Ok(custom) => {
+                    node.data.set_custom(custom);
+                    reloaded = true;
+                }
+                Err(error) => error!("Failed to reload custom op {}: {}", node.data.name, error),
+            }
+        }
+        if reloaded {
+            self.invalidate_programs();
+        }
+    }
+
+    /// Sets the `dirty` flag to `false`.
+    pub fn clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Toggles drawing of the preview window.
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+    }
+
+    /// Expands the preview to cover the entire network editor (hiding
+    /// the network), or returns it to its original size.
+    pub fn toggle_fullscreen_preview(&mut self) {
+        let size = *self.renderer.get_size();
+        self.preview.toggle_fullscreen(&size);
+    }
+
+    /// Toggles drawing of op thumbnails.
+    pub fn toggle_thumbnails(&mut self) {
+        self.show_thumbnails = !self.show_thumbnails;
+        self.thumbnails_stale = true;
+    }
+
+    /// Returns `true` if any of the thumbnail programs need to be rebuilt
+    /// and `false` otherwise.
+    pub fn thumbnails_dirty(&self) -> bool {
+        (self.thumbnails_dirty || !self.thumbnail_queue.is_empty()) && self.show_thumbnails
+    }
+
+    /// Rebuilds the shader programs that render the thumbnails of (at most)
+    /// a few ops, so that compiling all of them is spread over several
+    /// frames. Ops that are onscreen are rebuilt first.
+    pub fn rebuild_thumbnails(&mut self, builder: &ShaderBuilder) {
+        if self.thumbnails_dirty {
+            let uuids: Vec<Uuid> = self.graph.nodes.iter().map(|node| node.data.uuid).collect();
+            self.thumbnails.retain(|uuid| uuids.contains(uuid));
+
+            let (visible, hidden): (Vec<_>, Vec<_>) = self.graph
+                .nodes
+                .iter()
+                .partition(|node| self.is_visible(&node.data.bounds_body));
+            self.thumbnail_queue = hidden
+                .iter()
+                .chain(visible.iter())
+                .map(|node| node.data.uuid)
+                .collect();
+            self.thumbnails_dirty = false;
+        }
+
+        for _ in 0..constants::THUMBNAIL_BUILDS_PER_FRAME {
+            let uuid = match self.thumbnail_queue.pop() {
+                Some(uuid) => uuid,
+                None => break,
+            };
+            let index = self.graph.nodes.iter().position(|node| node.data.uuid == uuid);
+            match index.and_then(|index| builder.build_thumbnail(self, index)) {
+                Some(program) => self.thumbnails.set_program(uuid, program),
+                None => self.thumbnails.remove(&uuid),
+            }
+        }
+        self.thumbnails_stale = true;
+    }
+
+    /// Samples the distance field of every baked op (see `Op::baked`) whose
+    /// sub-graph changed since it was last baked over a grid that spans its
+    /// surface (see `sample_surface`), which blocks until the GPU is done,
+    /// and hands the baked ops to `builder`. Ops that can't be baked (i.e.
+    /// because their sub-graph is incomplete) stop being baked, and only the
+    /// first `BAKE_CAPACITY` baked ops are baked at all.
+    pub fn update_bakes(&mut self, builder: &mut ShaderBuilder) {
+        let baked: Vec<(usize, Uuid)> = self.graph
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, node)| node.data.baked && node.data.family.is_bakeable())
+            .map(|(index, node)| (index, node.data.uuid))
+            .take(constants::BAKE_CAPACITY)
+            .collect();
+        let uuids: Vec<Uuid> = baked.iter().map(|&(_, uuid)| uuid).collect();
+        let mut changed = self.bakes.retain(&uuids);
+
+        for &(index, uuid) in baked.iter() {
+            let signature = bake::get_signature(&self.graph, index);
+            if self.bakes.is_current(uuid, signature) {
+                continue;
+            }
+
+            // The grid is evaluated with the current parameters.
+            self.gather_params();
+            match self.sample_surface(builder, index, constants::BAKE_RESOLUTION) {
+                Some((grid, distances, _)) => self.bakes.insert(uuid, signature, &grid, &distances),
+                None => {
+                    let op = &mut self.graph.nodes[index].data;
+                    error!("Failed to bake {}", op.name);
+                    op.baked = false;
+                    self.bakes.remove(uuid);
+                }
+            }
+            changed = true;
+        }
+
+        if changed {
+            builder.set_bakes(self.bakes.get_ops());
+            self.dirty = true;
+            self.thumbnails_dirty = true;
+        }
+        self.bakes.bind();
+    }
+
+    /// Rebuilds the bounding sphere hierarchy of the network's unions (see
+    /// `Hierarchy`) if the structure of the network changed since it was
+    /// last built, and hands it to `builder`.
+    pub fn update_hierarchy(&mut self, builder: &mut ShaderBuilder) {
+        if !self.hierarchy.is_current(&self.graph) {
+            self.hierarchy = Hierarchy::build(&self.graph);
+            builder.set_hierarchy(self.hierarchy.clone());
+            self.dirty = true;
+            self.thumbnails_dirty = true;
+        }
+    }
+
+    /// Evaluates the distance field of the shader graph that ends at the op
+    /// at `root` over a grid centered at the origin, and reports the box
+    /// that encloses its surface.
+    pub fn estimate_bounds(&self, builder: &ShaderBuilder, root: usize) {
+        let program = match builder.build_grid_program(&self.graph, root) {
+            Ok(program) => program,
+            Err(error) => {
+                error!("Failed to build grid evaluation program:\n{}", error);
+                return;
+            }
+        };
+
+        let extent = Vector3::new(1.0, 1.0, 1.0) * constants::GRID_EXTENT;
+        let resolution = Vector3::new(1, 1, 1) * constants::GRID_RESOLUTION;
+        let grid = SampleGrid::new(-extent, extent, resolution);
+
+        self.preview.bind_transforms();
+        let distances = grid.evaluate(&program);
+        match grid.get_bounds(&distances) {
+            Some((min, max)) => console!(
+                "Estimated bounds: ({:.2}, {:.2}, {:.2}) to ({:.2}, {:.2}, {:.2})",
+                min.x,
+                min.y,
+                min.z,
+                max.x,
+                max.y,
+                max.z
+            ),
+            None => warn!(
+                "The surface doesn't lie within {} units of the origin",
+                constants::GRID_EXTENT
+            ),
+        }
+    }
+
+    /// Evaluates the distance field (and object IDs) of the shader graph that
+    /// ends at the op at `root` over a grid of `resolution` points along each
+    /// axis, which spans the bounds of its surface. The bounds are estimated on a coarse
+    /// grid first (see `estimate_bounds`). Returns `None` (after reporting
+    /// why) if the program can't be built or the surface can't be found.
+    fn sample_surface(
+        &self,
+        builder: &ShaderBuilder,
+        root: usize,
+        resolution: u32,
+    ) -> Option<(SampleGrid, Vec<f32>, Vec<f32>)> {
+        let program = match builder.build_grid_program(&self.graph, root) {
+            Ok(program) => program,
+            Err(error) => {
+                error!("Failed to build grid evaluation program:\n{}", error);
+                return None;
+            }
+        };
+
+        let extent = Vector3::new(1.0, 1.0, 1.0) * constants::GRID_EXTENT;
+        let coarse = SampleGrid::new(
+            -extent,
+            extent,
+            Vector3::new(1, 1, 1) * constants::GRID_RESOLUTION,
+        );
+
+        self.preview.bind_transforms();
+        let (min, max) = match coarse.get_bounds(&coarse.evaluate(&program)) {
+            Some(bounds) => bounds,
+            None => {
+                warn!(
+                    "The surface doesn't lie within {} units of the origin",
+                    constants::GRID_EXTENT
+                );
+                return None;
+            }
+        };
+        let grid = SampleGrid::new(min, max, Vector3::new(1, 1, 1) * resolution);
+        let (distances, ids) = grid.evaluate_with_ids(&program);
+        Some((grid, distances, ids))
+    }
+
+    /// Extracts the surface of the shader graph that ends at the op at `root`
+    /// as a triangle mesh (see `sample_surface`) and writes it to `path` as
+    /// a binary glTF file. Each object (i.e. generator op) gets a material
+    /// of its own, which is named after the op. There aren't any material
+    /// ops yet, so every material has the same neutral color.
+    pub fn export_gltf(&self, builder: &ShaderBuilder, root: usize, path: &Path) {
+        let (grid, distances, ids) =
+            match self.sample_surface(builder, root, constants::MESH_RESOLUTION) {
+                Some(samples) => samples,
+                None => return,
+            };
+        let mesh = Mesh::extract(&grid, &distances, &ids);
+
+        let names: HashMap<i32, String> = self
+            .graph
+            .nodes
+            .iter()
+            .map(|node| (node.data.params.get_index() as i32, node.data.name.clone()))
+            .collect();
+        let name = &self.graph.nodes[root].data.name;
+        let color = Color::from_hex(constants::MESH_COLOR, 1.0);
+        let bytes = gltf::generate(&mesh, name, &names, color);
+        match gltf::save(&bytes, path) {
+            Ok(_) => console!(
+                "Exported a mesh with {} triangles to {}",
+                mesh.get_triangle_count(),
+                path.display()
+            ),
+            Err(error) => error!("Failed to export the mesh: {}", error),
+        }
+    }
+
+    /// Samples the distance field of the shader graph that ends at the op at
+    /// `root` (see `sample_surface`) and writes the distances to `path` as a
+    /// dense volume, along with the points inside of the surface to
+    /// `vox_path` as a MagicaVoxel model.
+    pub fn export_volume(
+        &self,
+        builder: &ShaderBuilder,
+        root: usize,
+        path: &Path,
+        vox_path: &Path,
+    ) {
+        let (grid, distances, _) =
+            match self.sample_surface(builder, root, constants::VOLUME_RESOLUTION) {
+                Some(samples) => samples,
+                None => return,
+            };
+
+        let bytes = volume::generate_volume(&grid, &distances);
+        match volume::save(&bytes, path) {
+            Ok(_) => console!(
+                "Exported a {0}x{0}x{0} volume to {1}",
+                constants::VOLUME_RESOLUTION,
+                path.display()
+            ),
+            Err(error) => error!("Failed to export the volume: {}", error),
+        }
+
+        let result = volume::generate_vox(&grid, &distances)
+            .and_then(|bytes| volume::save(&bytes, vox_path).map_err(|error| error.to_string()));
+        match result {
+            Ok(_) => console!("Exported the voxels to {}", vox_path.display()),
+            Err(error) => error!("Failed to export the voxels: {}", error),
+        }
+    }
+
+    /// Scales the distance field represented by the currently
+    /// selected op (if one exists).
+    pub fn increment_param(&mut self, values: &Vector4<f32>) {
+        if let Some(selected) = self.selection_id {
+            let node = self.graph.nodes.get_mut(selected).unwrap();
+
+            let params = node.data.get_params_mut();
+            let data = params.get_data_mut();
+            data[0] += values.x;
+            data[1] += values.y;
+            data[2] += values.z;
+            data[3] += values.w;
+
+            self.thumbnails_stale = true;
+        }
+    }
+
+    /// Deletes the currently selected op (if the selection is not empty).
+    pub fn delete_selected(&mut self) {
+        if let Some(selected) = self.selection_id {
+            // Before removing this vertex from the graph,
+            // check to see if it was connected to the root
+            // (if one exists). If so, then the shader
+            // graph needs to be rebuilt.
+            if let Some(id) = self.render_id {
+                for edge in self.graph.edges[selected].outputs.iter() {
+                    if *edge == id {
+                        self.dirty = true;
+                        self.render_id = None;
+                        break;
                     }
                 }
             }
+
+            // Stop comparing against the deleted op, or follow the
+            // op that is about to be moved into its place.
+            if self.compare_id == Some(selected) {
+                self.compare_id = None;
+                self.dirty = true;
+            } else if self.compare_id == Some(self.graph.nodes.len() - 1) {
+                self.compare_id = Some(selected);
+            }
+
+            // The last node in the graph's list of nodes
+            // will be moved, so its parameter index needs
+            // to be reset.
+            if let Some(node) = self.graph.nodes.last_mut() {
+                node.data.params.set_index(selected);
+            }
+
+            // Finally, remove the node and reset the selection.
+            self.graph.remove_node(selected);
+            self.selection_id = None;
+            self.edge_selection = None;
+            self.hover.update(None);
+            self.thumbnails_dirty = true;
+        } else if let Some((src, dst)) = self.edge_selection {
+            self.remove_connection(src, dst);
+            self.edge_selection = None;
+        } else if let Some(selected) = self.backdrop_selection_id {
+            self.backdrops.remove(selected);
+            self.backdrop_selection_id = None;
+        }
+    }
+
+    /// Marks the selected render op as the one that the active render op
+    /// is compared against in the preview, or unmarks it if it already is.
+    pub fn toggle_compare_selected(&mut self) {
+        if let Some(selected) = self.selection_id {
+            let family = self.graph.nodes[selected].data.family;
+            if let OpFamily::Primitive(PrimitiveType::Render) = family {
+                if self.render_id == Some(selected) {
+                    warn!("The active render op can't be compared against itself");
+                    return;
+                }
+                self.compare_id = if self.compare_id == Some(selected) {
+                    console!("Stopped comparing render ops");
+                    None
+                } else {
+                    console!("Comparing against the selected render op");
+                    Some(selected)
+                };
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Bakes the selected op into a 3D texture (see `update_bakes`), or
+    /// stops baking it if it already is.
+    pub fn toggle_bake_selected(&mut self) {
+        if let Some(selected) = self.selection_id {
+            let op = &mut self.graph.nodes[selected].data;
+            if !op.family.is_bakeable() {
+                console!("Only ops that produce a distance field can be baked");
+                return;
+            }
+            op.baked = !op.baked;
+            if op.baked {
+                console!("Baking {}", op.name);
+            } else {
+                console!("Stopped baking {}", op.name);
+            }
+        }
+    }
+
+    /// Adds a new backdrop to the network at coordinates `position` and
+    /// dimensions `size`.
+    pub fn add_backdrop(&mut self, position: Vector2<f32>, size: Vector2<f32>) {
+        self.backdrops.push(Backdrop::new(position, size));
+    }
+
+    /// Switches the currently selected backdrop (if there is one) to the
+    /// next color in the backdrop palette.
+    pub fn cycle_backdrop_color(&mut self) {
+        if let Some(selected) = self.backdrop_selection_id {
+            self.backdrops[selected].cycle_color();
+        }
+    }
+
+    /// Adds a new op of type `family` to the network at coordinates
+    /// `position` and dimensions `size`.
+    pub fn add_op(&mut self, family: OpFamily, position: Vector2<f32>, size: Vector2<f32>) {
+        // Each new custom op gets a file of its own, which holds its code.
+        let custom = match family {
+            OpFamily::Primitive(PrimitiveType::Custom) => Some(CustomCode::create()),
+            _ => None,
+        };
+        self.insert_op(family, position, size, custom);
+    }
+
+    /// Like `add_op`, but with the given `custom` code, if the op is a
+    /// custom op.
+    fn insert_op(
+        &mut self,
+        family: OpFamily,
+        position: Vector2<f32>,
+        size: Vector2<f32>,
+        custom: Option<CustomCode>,
+    ) {
+        // Create the operator.
+        let mut op = Op::new(family, position, size);
+        if let Some(custom) = custom {
+            op.set_custom(custom);
+        }
+
+        // We need to re-assign this op's parameter index so
+        // that the resulting shader code properly indexes into
+        // the SSBO of parameter data.
+        op.params.set_index(self.graph.nodes.len());
+
+        // Add the operator to the current graph.
+        self.graph.add_node(op, 0);
+        self.thumbnails_dirty = true;
+
+        // Move this op family to the front of the list of recently
+        // used op families.
+        self.recent_families.retain(|&recent| recent != family);
+        self.recent_families.insert(0, family);
+        self.recent_families.truncate(constants::RECENT_OPS_CAPACITY);
+    }
+
+    /// Adds a new op of the most recently used op family (if there is
+    /// one) to the network at coordinates `position` and dimensions `size`.
+    pub fn add_recent_op(&mut self, position: Vector2<f32>, size: Vector2<f32>) {
+        if let Some(&family) = self.recent_families.first() {
+            self.add_op(family, position, size);
+        }
+    }
+
+    /// Adds a new connection between two ops.
+    pub fn add_connection(&mut self, a: usize, b: usize) {
+        self.graph.add_edge(a, b);
+        self.thumbnails_dirty = true;
+
+        if let Pair::Both(node_a, node_b) = index_twice(&mut self.graph.nodes, a, b) {
+            // If we previously connected to a render op, then we
+            // know that the graph must be rebuilt.
+            if let Some(_) = self.render_id {
+                self.dirty = true;
+                console!("Active render node in-line: re-building graph");
+            }
+
+            // If we are connecting to a render op, then the shader
+            // must be rebuilt.
+            if let OpFamily::Primitive(PrimitiveType::Render) = node_b.data.family {
+                self.render_id = Some(b);
+                self.dirty = true;
+                console!("Connected to render node: building graph");
+            }
+
+            // Deselect both ops.
+            node_a.data.state = InteractionState::Deselected;
+            node_b.data.state = InteractionState::Deselected;
+        } else {
+            console!("Attempting to connect two ops with the same index - something is wrong here")
+        }
+    }
+
+    /// Removes the connection between ops `a` and `b`.
+    pub fn remove_connection(&mut self, a: usize, b: usize) {
+        self.graph.remove_edge(a, b);
+        self.thumbnails_dirty = true;
+
+        // The removed connection might have been part of the
+        // graph that leads to the active render op.
+        if self.render_id.is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /// Extends the knife stroke while the user is dragging with the right
+    /// mouse button and control held down, then removes every connection
+    /// that the stroke crosses once the mouse is released. Returns `true`
+    /// if the mouse event was consumed and `false` otherwise.
+    fn cut(&mut self, mouse: &MouseInfo) -> bool {
+        if mouse.rdown && (mouse.ctrl || !self.knife_stroke.is_empty()) {
+            // Only keep points that are far enough apart, so that the
+            // stroke fits inside of the renderer's line buffer.
+            let far_enough = match self.knife_stroke.last() {
+                Some(last) => (mouse.curr - last).magnitude() >= constants::KNIFE_POINT_SPACING,
+                None => true,
+            };
+            if far_enough && self.knife_stroke.len() < constants::KNIFE_MAX_POINTS {
+                self.knife_stroke.push(mouse.curr);
+            }
+            return true;
+        }
+
+        if self.knife_stroke.is_empty() {
+            return false;
+        }
+
+        let stroke_bounds = routing::get_bounds(&self.knife_stroke);
+        let mut severed = Vec::new();
+        for (src, edges) in self.graph.edges.iter().enumerate() {
+            for &dst in edges.outputs.iter() {
+                // Skip edges that were drawn away from the stroke.
+                let (from, to) = match self.endpoints_for_edge(src, dst) {
+                    Some(endpoints) => endpoints,
+                    None => continue,
+                };
+                if let Some(geometry) = self.edge_cache.get_current(src, dst, from, to) {
+                    if !geometry.bounds.intersects(&stroke_bounds) {
+                        continue;
+                    }
+                }
+
+                let segments = self.segments_for_edge(src, dst);
+                let points = routing::sample(&segments, constants::EDGE_CURVE_LOD);
+                if routing::intersects(&points, &self.knife_stroke) {
+                    severed.push((src, dst));
+                }
+            }
+        }
+
+        for &(src, dst) in severed.iter() {
+            self.remove_connection(src, dst);
+        }
+        if !severed.is_empty() {
+            self.edge_selection = None;
+            console!("Severed {} connection(s)", severed.len());
+        }
+
+        self.knife_stroke.clear();
+        true
+    }
+
+    /// Inserts the op at `index` into the first connection that passes
+    /// underneath it, i.e. the connection `a -> b` becomes `a -> op -> b`.
+    /// This only applies to ops with a single input and an output that
+    /// aren't already connected to anything.
+    fn splice(&mut self, index: usize) {
+        let (family, bounds) = match self.graph.get_node(index) {
+            Some(node) => (node.data.family, node.data.bounds_body),
+            None => return,
+        };
+        if family.get_input_capacity() != 1 || !family.has_outputs() {
+            return;
+        }
+        let edges = &self.graph.edges[index];
+        if !edges.inputs.is_empty() || !edges.outputs.is_empty() {
+            return;
+        }
+
+        let mut found = None;
+        'search: for (a, edges) in self.graph.edges.iter().enumerate() {
+            for &b in edges.outputs.iter() {
+                let (a_node, b_node) = match (self.graph.get_node(a), self.graph.get_node(b)) {
+                    (Some(a_node), Some(b_node)) => (a_node, b_node),
+                    _ => continue,
+                };
+                let a_family = a_node.data.family;
+                let b_family = b_node.data.family;
+                if !a_family.can_connect_to(family) || !family.can_connect_to(b_family) {
+                    continue;
+                }
+
+                // Test against the un-routed curve, since routed curves
+                // are bent around the op that was just dropped.
+                let a_centroid = a_node.data.bounds_output.centroid();
+                let b_centroid = b_node.data.bounds_input.centroid();
+                let segment = match a_family.get_connection_type(b_family) {
+                    ConnectionType::Direct => routing::direct(a_centroid, b_centroid),
+                    _ => routing::straight(a_centroid, b_centroid),
+                };
+                let points = routing::sample(&[segment], constants::EDGE_CURVE_LOD);
+                if points.iter().any(|point| bounds.inside(point)) {
+                    found = Some((a, b));
+                    break 'search;
+                }
+            }
+        }
+
+        if let Some((a, b)) = found {
+            // Remember which of `b`'s inputs this connection occupied, so
+            // that the order of the inputs (i.e. for subtraction) is preserved.
+            let slot = self.graph.edges[b]
+                .inputs
+                .iter()
+                .position(|&input| input == a)
+                .unwrap();
+
+            self.remove_connection(a, b);
+            self.add_connection(a, index);
+            self.add_connection(index, b);
+
+            let inputs = &mut self.graph.edges[b].inputs;
+            if let Some(last) = inputs.pop() {
+                inputs.insert(slot, last);
+            }
+            self.edge_selection = None;
+            console!("Spliced op with ID {} between ops with IDs: {}, {}", index, a, b);
+        }
+    }
+
+    /// Disconnects the currently selected edge (if there is one) from
+    /// its destination op, so that the next connection dragged out of
+    /// its source op's output slot replaces it.
+    pub fn reroute_selected_edge(&mut self) {
+        if let Some((src, dst)) = self.edge_selection {
+            self.remove_connection(src, dst);
+            self.edge_selection = None;
+
+            if let Some(node) = self.graph.get_node_mut(src) {
+                node.data.state = InteractionState::ConnectSource;
+                console!("Re-routing connection from {}", node.data.name);
+            }
+        }
+    }
+
+    /// Pans the network while it is being dragged with the middle mouse
+    /// button, and zooms it about the mouse when the wheel is scrolled
+    /// outside of the preview (which zooms its own camera instead).
+    /// Returns `true` if the mouse event was consumed and `false`
+    /// otherwise.
+    fn navigate(&mut self, mouse: &MouseInfo) -> bool {
+        let scroll = mouse.scroll - self.last_scroll;
+        self.last_scroll = mouse.scroll;
+
+        let over_preview = self.show_preview && self.preview.get_bounds().inside(&mouse.curr);
+        if scroll != 0.0 && !over_preview {
+            let zoom = self.renderer.get_view().get_zoom() * (1.0 + scroll);
+            self.renderer.get_view_mut().zoom_about(&mouse.curr, zoom);
+        }
+
+        if !mouse.mdown {
+            self.panning = false;
+        } else if mouse.clicked == mouse.curr && !over_preview {
+            self.panning = true;
+        }
+        if self.panning {
+            self.renderer.get_view_mut().pan(&mouse.velocity());
+        }
+        self.panning
+    }
+
+    /// Returns to the "home" view of the network, i.e. centered on the
+    /// origin and not zoomed.
+    pub fn home_view(&mut self, mouse: &MouseInfo) {
+        self.last_scroll = mouse.scroll;
+        self.renderer.get_view_mut().home();
+        self.status.zoom = 1.0;
+    }
+
+    /// Resizes the network editor to fill a window that is `size` pixels
+    /// wide and tall.
+    pub fn resize(&mut self, size: &Vector2<f32>) {
+        self.renderer.get_view_mut().resize(size);
+        unsafe {
+            gl::Viewport(0, 0, size.x as i32, size.y as i32);
+        }
+    }
+
+    /// Handles all mouse events. The positions of `mouse` are in screen
+    /// space, which is what the preview and the panels are hit-tested
+    /// against, while ops, edges, and backdrops are hit-tested against
+    /// the same positions in world space (see `View`).
+    pub fn handle_interaction(&mut self, mouse: &MouseInfo) {
+        // The network is hidden while the preview is fullscreen (and
+        // isn't zoomed by scrolling the preview's camera).
+        if self.preview.is_fullscreen() {
+            self.last_scroll = mouse.scroll;
+            self.pick_from_preview(mouse);
+            self.preview.handle_interaction(mouse);
+            return;
+        }
+
+        let panning = self.navigate(mouse);
+        self.status.zoom = self.renderer.get_view().get_zoom();
+        if panning {
+            return;
+        }
+        let screen = mouse;
+        let mouse = &self.renderer.get_view().to_world_mouse(screen);
+
+        // A backdrop that is being dragged takes precedence over
+        // everything else, since the mouse might pass over other ops.
+        if self.drag_backdrop(mouse) {
+            self.preview.handle_interaction(screen);
+            return;
+        }
+
+        // Dragging in the timeline scrubs through time.
+        if self.scrub(screen) {
+            return;
+        }
+
+        // Clicks inside of the panel of recent networks aren't passed on
+        // to the ops.
+        if self.handle_recent_projects(screen) {
+            return;
+        }
+
+        // The same goes for the gallery of examples.
+        if self.handle_gallery(screen) {
+            return;
+        }
+
+        // Clicks inside of the library panel (or that drop a preset into
+        // the network) aren't passed on to the ops.
+        if self.handle_presets(screen) {
+            return;
+        }
+
+        // Dragging with the right mouse button while holding control
+        // draws a "knife" stroke that severs connections.
+        if self.cut(mouse) {
+            return;
+        }
+
+        // If the user just dropped the selected op, try to splice it
+        // into any connection that it was dropped onto.
+        if !mouse.ldown && self.dragging_op {
+            self.dragging_op = false;
+            if let Some(selected) = self.selection_id {
+                self.splice(selected);
+            }
+        }
+
+        let mut connecting = false;
+        let mut src: Option<usize> = None;
+        let mut dst: Option<usize> = None;
+
+        for (index, node) in self.graph.nodes.iter_mut().enumerate() {
+            if let InteractionState::ConnectSource = node.data.state {
+                if mouse.ldown {
+                    // If this operator is currently being connected to another:
+                    // 1) Set the `connecting` flag to `true`, as the user is
+                    //    performing a potential op connection
+                    // 2) Store its graph index as a potential connect source
+                    // 3) Skip the rest of this loop iteration
+                    connecting = true;
+                    src = Some(index);
+                    continue;
+                } else {
+                    // Otherwise, deselect this op
+                    node.data.state = InteractionState::Deselected;
+                }
+            }
+
+            // Is the mouse inside of this op's bounding box?
+            if node.data.bounds_body.inside(&mouse.curr) {
+                // Is there an op currently selected?
+                if let Some(selected) = self.selection_id {
+                    // Is this op the selected op?
+                    if selected == index {
+                        // Is the mouse down?
+                        if mouse.ldown {
+                            // TODO: let mut velocity = ..;
+                            if self.snapping {
+                                // TODO
+                            }
+                            node.data.translate(&mouse.velocity());
+                            self.dragging_op = true;
+                        }
+                        continue;
+                    }
+                }
+
+                // This op is not the selected op, but we are inside of it's
+                // bounding box. Is the mouse down?
+                if mouse.ldown {
+                    // Are we inside the bounds of this op's output slot?
+                    if node.data
+                        .bounds_output
+                        .inside_with_padding(&mouse.curr, 12.0)
+                    {
+                        // This op is now a potential connection source.
+                        node.data.state = InteractionState::ConnectSource;
+
+                        // Store the connection source index.
+                        src = Some(index);
+                    } else {
+                        // This op has been selected.
+                        node.data.state = InteractionState::Selected;
+
+                        // Store the selected UUID.
+                        self.selection_id = Some(index);
+                    }
+                } else {
+                    // Otherwise, the mouse is still inside the bounds of this op,
+                    // so we must be hovering over it.
+                    node.data.state = InteractionState::Hover;
+                }
+
+            // The mouse is not inside of this op's bounding box.
+            } else {
+                // Is there an op currently selected?
+                if let Some(selected) = self.selection_id {
+                    // Is this op the selected op?
+                    if selected == index {
+                        // Is the mouse down?
+                        if mouse.ldown {
+                            // The user has clicked somewhere else in the
+                            // network, so reset the selection.
+                            self.selection_id = None;
+                        } else {
+                            // Keep this op selected.
+                            node.data.state = InteractionState::Selected;
+                        }
+                    } else {
+                        // Deselect the op.
+                        node.data.state = InteractionState::Deselected;
+                    }
+                } else {
+                    // Deselect the op.
+                    node.data.state = InteractionState::Deselected;
+                }
+            }
+        }
+
+        // If the mouse is dragging from the output slot of one operator,
+        // check if a potential connection has happened (i.e. the mouse
+        // is now over an input slot of a different operator).
+        if connecting {
+            for (index, node) in self.graph.nodes.iter_mut().enumerate() {
+                // Is the mouse now inside of a different op's input slot region?
+                if node.data
+                    .bounds_input
+                    .inside_with_padding(&mouse.curr, 12.0)
+                {
+                    node.data.state = InteractionState::ConnectDestination;
+                    if let Some(src) = src {
+                        dst = Some(index);
+                    }
+                }
+            }
+        }
+
+        // Keep track of the connection that is being dragged out (if any),
+        // so that it can be drawn before it is committed.
+        self.pending_connection = match (connecting, src) {
+            (true, Some(src)) => Some((src, mouse.curr)),
+            _ => None,
+        };
+
+        // Find the op (if any) that is under the mouse, whether or not any
+        // of the buttons are held. Ops take priority over the backdrops
+        // behind them.
+        let under_mouse = self.graph
+            .nodes
+            .iter()
+            .position(|node| node.data.bounds_body.inside(&mouse.curr));
+
+        // Tooltips are only shown while the mouse is hovering.
+        let hovered = if mouse.ldown || mouse.rdown || mouse.mdown {
+            None
+        } else {
+            under_mouse
+        };
+        self.hover.update(hovered);
+
+        // If the user just clicked somewhere that isn't an op, check whether
+        // they are starting to drag one of the backdrops.
+        let pressed = mouse.ldown && mouse.clicked == mouse.curr;
+        if pressed && !connecting {
+            if under_mouse.is_some() {
+                self.deselect_backdrop();
+            } else {
+                self.select_backdrop(mouse);
+            }
+
+            // If the mouse was pressed somewhere that isn't an op or a
+            // backdrop, check whether the user clicked on an edge.
+            if under_mouse.is_some() || self.backdrop_selection_id.is_some() {
+                self.edge_selection = None;
+            } else {
+                self.select_edge(mouse);
+            }
+        }
+
+        if let (Some(src), Some(dst)) = (src, dst) {
+            let src_family = self.graph.get_node(src).map(|node| node.data.family);
+            let dst_family = self.graph.get_node(dst).map(|node| node.data.family);
+
+            if let (Some(src_family), Some(dst_family)) = (src_family, dst_family) {
+                if dst_family.has_inputs() && src_family.can_connect_to(dst_family) {
+                    console!("Valid connection between ops with IDs: {}, {}", src, dst);
+                    self.add_connection(src, dst);
+                }
+            }
+        }
+
+        self.pick_from_preview(screen);
+        self.preview.handle_interaction(screen);
+    }
+
+    /// Selects the op whose primitive is under the mouse when the user
+    /// clicks inside of the preview (or probes the surface there, if
+    /// the preview is in probe mode).
+    fn pick_from_preview(&mut self, mouse: &MouseInfo) {
+        let pressed = mouse.ldown && mouse.clicked == mouse.curr;
+        if !pressed || !(self.show_preview || self.preview.is_fullscreen()) {
+            return;
+        }
+
+        // Grabbing the split line shouldn't change the selection.
+        if self.preview.is_over_split(&mouse.curr) {
+            return;
+        }
+
+        if self.preview.is_probing() {
+            self.preview.probe(&self.renderer, &mouse.curr);
+        } else if let Some(index) = self.preview.pick(&self.renderer, &mouse.curr) {
+            self.select_op(index);
+        }
+    }
+
+    /// Selects the op at `index`, deselecting all other ops.
+    pub fn select_op(&mut self, index: usize) {
+        if index >= self.graph.nodes.len() {
+            return;
+        }
+        for (other, node) in self.graph.nodes.iter_mut().enumerate() {
+            node.data.state = if other == index {
+                InteractionState::Selected
+            } else {
+                InteractionState::Deselected
+            };
+        }
+        self.selection_id = Some(index);
+        self.edge_selection = None;
+    }
+
+    /// Moves or resizes the backdrop that is currently being dragged
+    /// (if there is one). Returns `true` if the mouse event was consumed
+    /// and `false` otherwise.
+    fn drag_backdrop(&mut self, mouse: &MouseInfo) -> bool {
+        if !mouse.ldown {
+            // The mouse was released, so the drag has ended.
+            self.backdrop_drag = None;
+            return false;
+        }
+
+        if let Some((index, drag, ref ops)) = self.backdrop_drag {
+            let velocity = mouse.velocity();
+            match drag {
+                BackdropDrag::Move => {
+                    self.backdrops[index].translate(&velocity);
+                    for op in ops.iter() {
+                        if let Some(node) = self.graph.get_node_mut(*op) {
+                            node.data.translate(&velocity);
+                        }
+                    }
+                }
+                BackdropDrag::Resize => self.backdrops[index].resize(&velocity),
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Selects the edge closest to the mouse, if it is close enough.
+    /// Otherwise, the current edge selection is cleared.
+    fn select_edge(&mut self, mouse: &MouseInfo) {
+        let mut closest = None;
+        let mut closest_distance = constants::EDGE_SELECTION_DISTANCE;
+
+        for (src, edges) in self.graph.edges.iter().enumerate() {
+            for &dst in edges.outputs.iter() {
+                // Skip edges that were drawn far away from the mouse (or
+                // culled, since the mouse is always onscreen).
+                let (from, to) = match self.endpoints_for_edge(src, dst) {
+                    Some(endpoints) => endpoints,
+                    None => continue,
+                };
+                if let Some(geometry) = self.edge_cache.get_current(src, dst, from, to) {
+                    if !geometry.bounds.inside_with_padding(&mouse.curr, closest_distance) {
+                        continue;
+                    }
+                }
+
+                let segments = self.segments_for_edge(src, dst);
+                let points = routing::sample(&segments, constants::EDGE_CURVE_LOD);
+
+                let distance = routing::distance_to(&mouse.curr, &points);
+                if distance < closest_distance {
+                    closest = Some((src, dst));
+                    closest_distance = distance;
+                }
+            }
+        }
+        self.edge_selection = closest;
+    }
+
+    /// Clears the current backdrop selection, if any.
+    fn deselect_backdrop(&mut self) {
+        if let Some(selected) = self.backdrop_selection_id {
+            self.backdrops[selected].state = InteractionState::Deselected;
+        }
+        self.backdrop_selection_id = None;
+    }
+
+    /// Selects the top-most backdrop whose title bar or resize handle is
+    /// under the mouse and starts dragging it. If there isn't one, the
+    /// current backdrop selection is cleared.
+    fn select_backdrop(&mut self, mouse: &MouseInfo) {
+        self.deselect_backdrop();
+
+        // Backdrops that were added later are drawn on top, so
+        // search through them in reverse.
+        for (index, backdrop) in self.backdrops.iter_mut().enumerate().rev() {
+            if let Some(drag) = backdrop.get_drag_region(&mouse.curr) {
+                backdrop.state = InteractionState::Selected;
+
+                // Gather all of the ops that will move with this backdrop.
+                let ops = self.graph
+                    .nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, node)| backdrop.contains(&node.data.bounds_body))
+                    .map(|(op, _)| op)
+                    .collect();
+
+                self.backdrop_selection_id = Some(index);
+                self.backdrop_drag = Some((index, drag, ops));
+                break;
+            }
+        }
+    }
+
+    /// Draws all of the operators and edges that make
+    /// up this graph.
+    pub fn draw(&mut self) {
+        self.timeline.update();
+        self.preview.set_time(self.timeline.get_time());
+        self.preview.update();
+        self.perf.update();
+
+        // While the preview is fullscreen, it is the only thing drawn.
+        if self.preview.is_fullscreen() {
+            self.gather_params();
+            self.draw_preview();
+            self.draw_probe();
+            self.flush();
+            return;
+        }
+
+        self.draw_grid();
+        self.draw_all_backdrops();
+        self.draw_all_edges();
+        self.draw_pending_connection();
+        self.draw_knife_stroke();
+        self.draw_all_nodes();
+        self.draw_recent_ops();
+        self.draw_console();
+        self.draw_presets();
+        self.draw_recent_projects();
+        self.draw_gallery();
+        self.draw_timeline();
+        self.draw_status_bar();
+        self.draw_perf();
+
+        // The op parameters are needed by both the preview and
+        // the thumbnails.
+        self.gather_params();
+
+        if self.show_thumbnails && self.thumbnails_stale {
+            self.preview.bind_transforms();
+            self.thumbnails.render(&self.renderer);
+            self.thumbnails_stale = false;
+        }
+
+        if self.show_preview {
+            self.draw_preview();
+            self.draw_probe();
+        }
+
+        self.draw_tooltip();
+
+        // Everything is drawn one layer at a time, so the order of the
+        // calls above doesn't determine what ends up on top.
+        self.flush();
+    }
+
+    /// Draws everything that was submitted to the renderer this frame,
+    /// measuring how long it takes on the GPU.
+    fn flush(&mut self) {
+        self.perf.interface_timer.begin();
+        self.renderer.flush();
+        self.perf.interface_timer.end();
+    }
+
+    /// Returns the smallest rectangle that contains every op and backdrop
+    /// in the network, or `None` if the network is empty.
+    fn get_content_bounds(&self) -> Option<Rect> {
+        let ops = self.graph.get_nodes().iter().map(|node| node.data.bounds_body);
+        let backdrops = self.backdrops.iter().map(|backdrop| backdrop.bounds_body);
+        ops.chain(backdrops)
+            .fold(None, |bounds: Option<Rect>, other| match bounds {
+                Some(bounds) => Some(bounds.union(&other)),
+                None => Some(other),
+            })
+    }
+
+    /// Renders the entire network (without any of the panels or the
+    /// preview) into an offscreen framebuffer that is framed around its
+    /// contents and saves the result to `path` as a PNG.
+    pub fn export_png(&mut self, path: &Path) {
+        let bounds = match self.get_content_bounds() {
+            Some(bounds) => Rect::expanded_from(
+                &bounds,
+                &Vector2::new(constants::EXPORT_MARGIN, constants::EXPORT_MARGIN),
+            ),
+            None => {
+                console!("There is nothing in the network to export");
+                return;
+            }
+        };
+
+        // Render at a higher resolution than the screen, as long as the
+        // image doesn't get unreasonably large.
+        let size = bounds.get_size();
+        let scale = constants::EXPORT_SCALE
+            .min(constants::EXPORT_MAX_RESOLUTION / size.x)
+            .min(constants::EXPORT_MAX_RESOLUTION / size.y);
+        let (width, height) = ((size.x * scale) as u32, (size.y * scale) as u32);
+        let framebuffer = Framebuffer::new(width, height);
+
+        let view = *self.renderer.get_view();
+        let resolution = Vector2::new(width as f32, height as f32);
+        self.renderer.set_view(View::framing(&bounds, &resolution));
+        framebuffer.bind();
+        unsafe {
+            let clear = Color::from_hex(
+                constants::NETWORK_BACKGROUND_COLOR,
+                constants::NETWORK_BACKGROUND_ALPHA,
+            ).to_linear();
+            gl::ClearColor(clear.r, clear.g, clear.b, clear.a);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+
+        self.draw_all_backdrops();
+        self.draw_all_edges();
+        self.draw_all_nodes();
+        self.renderer.flush();
+
+        framebuffer.unbind();
+        self.renderer.set_view(view);
+        let window = *self.renderer.get_size();
+        unsafe {
+            gl::Viewport(0, 0, window.x as i32, window.y as i32);
+        }
+
+        match framebuffer.save_png(path) {
+            Ok(_) => console!("Exported the network to {}", path.display()),
+            Err(error) => error!("Failed to export the network: {}", error),
+        }
+    }
+
+    /// Builds the preview's program for the active render op and waits for
+    /// the driver to finish compiling it, rather than swapping it in once it
+    /// is ready (see `main`), i.e. when exporting from the command line.
+    pub fn build_preview_now(&mut self, builder: &mut ShaderBuilder) {
+        let root = match self.render_id {
+            Some(root) => root,
+            None => return,
+        };
+        if builder.start_build(&self.graph, root, Target::Valid).is_err() {
+            return;
+        }
+        while builder.is_building() {
+            for build in builder.poll() {
+                if build.target == Target::Valid {
+                    self.preview.set_valid_program(build.program);
+                }
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.clean();
+        self.gather_params();
+    }
+
+    /// Saves the current image of the preview as a PNG, named after the
+    /// current date and time (i.e. `preview_2018-03-21_17-45-09.png`).
+    pub fn save_preview_screenshot(&mut self) {
+        let path = PathBuf::from(format!("preview_{}.png", preview::get_timestamp()));
+        self.save_preview(&path);
+    }
+
+    /// Saves the current image of the preview to `path` as a PNG.
+    pub fn save_preview(&mut self, path: &Path) {
+        let result = self.preview.save_screenshot(&self.renderer, path);
+
+        let window = *self.renderer.get_size();
+        unsafe {
+            gl::Viewport(0, 0, window.x as i32, window.y as i32);
+        }
+
+        match result {
+            Ok(_) => console!("Saved the preview to {}", path.display()),
+            Err(error) => error!("Failed to save the preview: {}", error),
+        }
+    }
+
+    /// Renders a sequence of frames of the preview (see `Preview::capture`)
+    /// and either saves them as numbered PNGs in a new directory, or encodes
+    /// them as a video with ffmpeg, depending on the capture settings. Both
+    /// are named after the mode and the current date and time (i.e.
+    /// `turntable_2018-03-21_17-45-09/frame_0000.png` or
+    /// `timeline_2018-03-21_17-45-09.mp4`). Timeline sequences span the
+    /// loop range.
+    pub fn capture_frames(&mut self, mode: CaptureMode) {
+        let name = format!("{}_{}", mode.name(), preview::get_timestamp());
+        let path = match self.capture_settings.video {
+            Some(format) => PathBuf::from(format!("{}.{}", name, format.get_extension())),
+            None => PathBuf::from(name),
+        };
+        self.capture_frames_to(mode, &path);
+    }
+
+    /// Captures a sequence of frames of the preview (see `capture_frames`)
+    /// to `path`, which is either the directory that the numbered PNGs are
+    /// saved in, or the video file.
+    pub fn capture_frames_to(&mut self, mode: CaptureMode, path: &Path) {
+        let settings = self.capture_settings;
+        let time_range = self.timeline.get_loop_range();
+
+        let result = match settings.video {
+            Some(format) => Encoder::start(
+                settings.width,
+                settings.height,
+                settings.fps,
+                format,
+                path,
+            ).and_then(|mut encoder| {
+                let result = self.preview.capture(
+                    &self.renderer,
+                    mode,
+                    &settings,
+                    time_range,
+                    |_, framebuffer| encoder.write_frame(framebuffer),
+                );
+
+                // Wait for ffmpeg even if capturing failed, but report the
+                // first error, since it usually explains the second.
+                let finished = encoder.finish();
+                result.and(finished)
+            }),
+            None => fs::create_dir_all(path)
+                .map_err(|error| error.to_string())
+                .and_then(|_| {
+                    self.preview.capture(
+                        &self.renderer,
+                        mode,
+                        &settings,
+                        time_range,
+                        |index, framebuffer| {
+                            framebuffer.save_png(&capture::get_frame_path(path, index))
+                        },
+                    )
+                }),
+        };
+
+        let window = *self.renderer.get_size();
+        unsafe {
+            gl::Viewport(0, 0, window.x as i32, window.y as i32);
+        }
+
+        match result {
+            Ok(_) => console!(
+                "Saved {} frames ({}x{}) to {}",
+                settings.frames,
+                settings.width,
+                settings.height,
+                path.display()
+            ),
+            Err(error) => error!("Failed to capture frames: {}", error),
+        }
+    }
+
+    /// Benchmarks the preview (see `Preview::benchmark`) and saves the
+    /// report to the path in `benchmark`. Returns `false` if either one
+    /// fails.
+    pub fn benchmark(&mut self, benchmark: &Benchmark) -> bool {
+        let path = benchmark.get_output();
+        let result = self.preview
+            .benchmark(&self.renderer, benchmark)
+            .and_then(|report| report.save(path).map(|_| report));
+
+        let window = *self.renderer.get_size();
+        unsafe {
+            gl::Viewport(0, 0, window.x as i32, window.y as i32);
+        }
+
+        match result {
+            Ok(report) => {
+                console!("{}", report.get_summary());
+                console!("Saved the benchmark report to {}", path.display());
+                true
+            }
+            Err(error) => {
+                error!("Failed to benchmark the preview: {}", error);
+                false
+            }
+        }
+    }
+
+    /// Writes a vector version of the network (backdrops, edges, and
+    /// labeled ops) to `path` as an SVG document that is framed around
+    /// its contents.
+    pub fn export_svg(&self, path: &Path) {
+        let bounds = match self.get_content_bounds() {
+            Some(bounds) => Rect::expanded_from(
+                &bounds,
+                &Vector2::new(constants::EXPORT_MARGIN, constants::EXPORT_MARGIN),
+            ),
+            None => {
+                console!("There is nothing in the network to export");
+                return;
+            }
+        };
+
+        let mut svg = Svg::new(&bounds);
+        svg.rect(
+            &bounds,
+            0.0,
+            &Color::from_hex(
+                constants::NETWORK_BACKGROUND_COLOR,
+                constants::NETWORK_BACKGROUND_ALPHA,
+            ),
+            None,
+        );
+
+        for backdrop in self.backdrops.iter() {
+            let color = backdrop.get_color();
+            svg.rect(
+                &backdrop.bounds_body,
+                0.0,
+                &Color::new(color.r, color.g, color.b, constants::BACKDROP_ALPHA),
+                None,
+            );
+            svg.rect(&backdrop.bounds_title, 0.0, &color, None);
+            svg.text(
+                &backdrop.title,
+                &(backdrop.bounds_title.get_upper_left()
+                    + Vector2::new(
+                        constants::BACKDROP_TITLE_PADDING,
+                        (constants::BACKDROP_TITLE_HEIGHT - constants::SVG_FONT_SIZE) * 0.5,
+                    )),
+                constants::SVG_FONT_SIZE,
+                &Color::mono(0.9, 1.0),
+            );
+        }
+
+        for (src, edges) in self.graph.edges.iter().enumerate() {
+            for dst in edges.outputs.iter() {
+                let (mode, color) = match self.style_for_edge(src, *dst) {
+                    Some((mode, color, _)) => (mode, color),
+                    None => continue,
+                };
+                let segments = self.segments_for_edge(src, *dst);
+                let dashed = match mode {
+                    LineMode::Dashed => true,
+                    _ => false,
+                };
+                svg.curve(&segments, &color, constants::EDGE_LINE_WIDTH, dashed);
+
+                let (position, tangent) = routing::midpoint(&segments);
+                if let Some((left, tip, right)) = Network::arrow_points(&position, &tangent) {
+                    svg.polyline(&[left, tip, right], &color, constants::EDGE_LINE_WIDTH);
+                }
+            }
+        }
+
+        let border = Color::mono(0.0, 0.25);
+        let slot_color = Color::from_hex(constants::NETWORK_BACKGROUND_COLOR, 1.0);
+        for node in self.graph.get_nodes().iter() {
+            let op = &node.data;
+            svg.rect(
+                &op.bounds_body,
+                constants::OPERATOR_CORNER_RADIUS,
+                &self.color_for_op(op),
+                Some((&border, constants::OPERATOR_BORDER_WIDTH)),
+            );
+            for slot in [op.bounds_input, op.bounds_output].iter() {
+                svg.rect(slot, constants::OPERATOR_SLOT_CORNER_RADIUS, &slot_color, None);
+            }
+
+            // Ops are labeled with their names, since their icons are
+            // raster images.
+            let padding = constants::OPERATIVE_SLOT_SIZE.x;
+            svg.text(
+                &op.name,
+                &(op.bounds_body.get_upper_left()
+                    + Vector2::new(
+                        padding,
+                        (op.bounds_body.get_size().y - constants::SVG_FONT_SIZE) * 0.5,
+                    )),
+                constants::SVG_FONT_SIZE,
+                &Color::mono(0.9, 1.0),
+            );
+        }
+
+        match svg.save(path) {
+            Ok(_) => console!("Exported the network to {}", path.display()),
+            Err(error) => error!("Failed to export the network: {}", error),
+        }
+    }
+
+    /// Exports the op graph to a GraphViz (DOT) file at `path`, for
+    /// documentation or analysis of large graphs with external tools.
+    pub fn export_dot(&self, path: &Path) {
+        if self.graph.nodes.is_empty() {
+            console!("There is nothing in the network to export");
+            return;
+        }
+
+        match dot::save(&dot::generate(&self.graph, self.render_id), path) {
+            Ok(_) => console!("Exported the op graph to {}", path.display()),
+            Err(error) => error!("Failed to export the op graph: {}", error),
+        }
+    }
+
+    /// Raymarches the scene into the preview's offscreen framebuffer and
+    /// scales the result to fit the preview window. If the graph isn't
+    /// valid, a fall-back pattern is drawn instead.
+    fn draw_preview(&mut self) {
+        self.preview.set_pixel_cost(self.perf.get_preview_cost());
+        self.preview.set_recording_steps(self.show_perf);
+        self.perf.begin_preview();
+        let valid = self.preview.render(&self.renderer);
+        self.perf.end_preview(self.preview.get_rendered_pixels());
+
+        self.renderer.set_layer(Layer::Overlays);
+        self.renderer.draw(
+            DrawParams::Shadow(
+                &Network::shadow_for(self.preview.get_bounds()),
+                0.0,
+                constants::SHADOW_BLUR,
+            ),
+            &Color::mono(0.0, constants::SHADOW_ALPHA),
+            None,
+            None,
+        );
+
+        // Clip the preview (and anything that is drawn on top of it) to
+        // its bounds, so that it never bleeds into the rest of the editor.
+        let region = self.renderer.get_region(self.preview.get_bounds());
+        self.renderer.set_clip(Some(region));
+
+        self.renderer.draw(
+            DrawParams::Rectangle(self.preview.get_bounds()),
+            &Color::white(),
+            Some(self.preview.get_color_attachment()),
+            None,
+        );
+
+        if valid {
+            // Draw the line that splits the active and comparison graphs.
+            if let Some(x) = self.preview.get_split() {
+                let bounds = self.preview.get_bounds();
+                let split = Rect::new(
+                    Vector2::new(x - 1.0, bounds.get_upper_left().y),
+                    Vector2::new(2.0, bounds.get_size().y),
+                );
+                self.renderer.draw(
+                    DrawParams::Rectangle(&split),
+                    &Color::from_hex(0xC77832, 1.0),
+                    None,
+                    None,
+                );
+            }
+
+            // Separate the panes of the quad view.
+            if self.preview.is_quad_view() {
+                let bounds = self.preview.get_bounds();
+                let center = bounds.get_upper_left() + bounds.get_size() * 0.5;
+                let dividers = [
+                    Rect::new(
+                        Vector2::new(center.x - 1.0, bounds.get_upper_left().y),
+                        Vector2::new(2.0, bounds.get_size().y),
+                    ),
+                    Rect::new(
+                        Vector2::new(bounds.get_upper_left().x, center.y - 1.0),
+                        Vector2::new(bounds.get_size().x, 2.0),
+                    ),
+                ];
+                for divider in dividers.iter() {
+                    self.renderer.draw(
+                        DrawParams::Rectangle(divider),
+                        &Color::from_hex(0x373737, 1.0),
+                        None,
+                        None,
+                    );
+                }
+            }
+        }
+
+        self.renderer.set_clip(None);
+    }
+
+    /// Draws the results of the most recent surface probe in the
+    /// lower-left corner of the preview window.
+    fn draw_probe(&self) {
+        self.renderer.set_layer(Layer::Overlays);
+
+        if !self.preview.is_probing() {
+            return;
+        }
+
+        let text = match self.preview.get_probe() {
+            Some(probe) => probe.get_text(),
+            None => "click to probe the surface".to_string(),
+        };
+
+        let bounds_preview = self.preview.get_bounds();
+        let size = self.renderer.get_font().measure(&text, constants::FONT_SCALE)
+            + constants::TOOLTIP_PADDING * 2.0;
+        let upper_left = bounds_preview.get_upper_left()
+            + Vector2::new(0.0, bounds_preview.get_size().y - size.y);
+        let bounds_background = Rect::new(upper_left, size);
+
+        self.renderer.draw(
+            DrawParams::RoundedRectangle(
+                &bounds_background,
+                Rounding::new(constants::PANEL_CORNER_RADIUS),
+            ),
+            &Color::from_hex(0x373737, 0.9),
+            None,
+            None,
+        );
+
+        self.renderer.draw(
+            DrawParams::Text(
+                &text,
+                upper_left + constants::TOOLTIP_PADDING,
+                constants::FONT_SCALE,
+            ),
+            &Color::mono(0.85, 1.0),
+            None,
+            None,
+        );
+    }
+
+    /// Pick a draw color based on the current interaction state of this
+    /// operator and the op type.
+    fn color_for_op(&self, op: &Op) -> Color {
+        let mut color = match op.family {
+            OpFamily::Domain(domain) => Color::from_hex(0x515151, 1.0),
+            OpFamily::Primitive(primitive) => match primitive {
+                PrimitiveType::Sphere
+                | PrimitiveType::Box
+                | PrimitiveType::Plane
+                | PrimitiveType::Torus
+                | PrimitiveType::Custom => Color::from_hex(0x8F719D, 1.0),
+                PrimitiveType::Defined(_) if op.family.is_generator() => {
+                    Color::from_hex(0x8F719D, 1.0)
+                }
+                PrimitiveType::Defined(_) => Color::from_hex(0x8A7BA4, 1.0),
+                PrimitiveType::Union
+                | PrimitiveType::Subtraction
+                | PrimitiveType::Intersection
+                | PrimitiveType::SmoothMinimum => Color::from_hex(0x8A7BA4, 1.0),
+                PrimitiveType::Render => Color::from_hex(0xC77832, 1.0),
+            },
+        };
+
+        // Tint any op whose code caused the shader graph to fail to compile.
+        if self.error_ops.contains(&op.uuid) {
+            color = Color::from_hex(constants::OPERATOR_ERROR_COLOR, 1.0);
+        }
+
+        // Add a contribution based on the op's current interaction state.
+        if let InteractionState::Hover = op.state {
+            color += Color::mono(0.05, 0.0);
+        }
+        color
+    }
+
+    /// Draws all backdrops in the network.
+    fn draw_all_backdrops(&self) {
+        self.renderer.set_layer(Layer::Backdrops);
+
+        for backdrop in self.backdrops.iter() {
+            let color = backdrop.get_color();
+
+            // Draw the body of the backdrop.
+            self.renderer.draw(
+                DrawParams::Rectangle(&backdrop.bounds_body),
+                &Color::new(color.r, color.g, color.b, constants::BACKDROP_ALPHA),
+                None,
+                None,
+            );
+
+            // Draw the title bar, which is highlighted if the backdrop
+            // is selected.
+            let title_color = match backdrop.state {
+                InteractionState::Selected => Color::from_hex(0x76B264, 1.0),
+                _ => color,
+            };
+            self.renderer.draw(
+                DrawParams::Rectangle(&backdrop.bounds_title),
+                &title_color,
+                None,
+                None,
+            );
+
+            // Draw the resize handle.
+            self.renderer.draw(
+                DrawParams::Rectangle(&backdrop.bounds_handle),
+                &Color::mono(0.75, 0.5),
+                None,
+                None,
+            );
+
+            // Draw the title, vertically centered in the title bar.
+            let text_height = self.renderer
+                .get_font()
+                .measure(&backdrop.title, constants::FONT_SCALE)
+                .y;
+            let offset = Vector2::new(
+                constants::BACKDROP_TITLE_PADDING,
+                (constants::BACKDROP_TITLE_HEIGHT - text_height) * 0.5,
+            );
+            self.renderer.draw(
+                DrawParams::Text(
+                    &backdrop.title,
+                    backdrop.bounds_title.get_upper_left() + offset,
+                    constants::FONT_SCALE,
+                ),
+                &Color::mono(0.9, 1.0),
+                None,
+                None,
+            );
+        }
+    }
+
+    /// Draws all ops in the network.
+    fn draw_all_nodes(&mut self) {
+        self.renderer.set_layer(Layer::Nodes);
+
+        // Gather the rectangles that make up each op into batches that
+        // share the same textures, so that the network can be drawn with a
+        // handful of draw calls per band: shadows first, then selection
+        // outlines, then op bodies, then slots, then icons. Ops are drawn
+        // back-to-front in graph order, so an op that overlaps one that is
+        // already in the current band starts a new band.
+        let mut band = NodeBand::default();
+
+        for node in self.graph.get_nodes().iter() {
+            let op = &node.data;
+            if !self.is_visible(&op.bounds_body) {
+                continue;
+            }
+
+            let extent = Network::extent_of(&op.bounds_body);
+            if band.extents.iter().any(|other| other.intersects(&extent)) {
+                self.draw_node_band(&band);
+                band = NodeBand::default();
+            }
+            band.extents.push(extent);
+
+            // If the op is selected, draw a selection outline behind it.
+            if let InteractionState::Selected = op.state {
+                band.selected.push(op.bounds_body);
+            }
+
+            // Draw the body of the op.
+            let draw_color = self.color_for_op(op);
+            band.bodies.push(Instance::new(op.bounds_body, draw_color));
+
+            // Draw the op's connection slots, highlighting the appropriate
+            // one if the op is being used as a connection source or
+            // destination.
+            let slot_color = Color::from_hex(constants::NETWORK_BACKGROUND_COLOR, 1.0);
+            let highlight_color = Color::from_hex(0x373737, 1.0);
+            let (input_color, output_color) = match op.state {
+                InteractionState::ConnectDestination => (highlight_color, slot_color),
+                InteractionState::ConnectSource => (slot_color, highlight_color),
+                _ => (slot_color, slot_color),
+            };
+            let (input, output) = match op.family.get_connectivity() {
+                Connectivity::InputOutput => (true, true),
+                Connectivity::Input => (true, false),
+                Connectivity::Output => (false, true),
+            };
+            if input {
+                band.slots.push(Instance::new(op.bounds_input, input_color));
+            }
+            if output {
+                band.slots.push(Instance::new(op.bounds_output, output_color));
+            }
+
+            // Draw the icon on top of the op (if one exists), preferring
+            // the op's thumbnail if it has one.
+            let thumbnail = if self.show_thumbnails {
+                self.thumbnails.get(&op.uuid)
+            } else {
+                None
+            };
+            let color_map = thumbnail.unwrap_or_else(|| self.get_icon(op.family));
+            Network::add_to_batch(
+                &mut band.icons,
+                color_map,
+                Instance::new(op.bounds_icon, draw_color),
+            );
+        }
+        self.draw_node_band(&band);
+    }
+
+    /// Draws the ops in `band`, none of which overlap one another.
+    fn draw_node_band(&self, band: &NodeBand) {
+        let shadows: Vec<Instance> = band
+            .bodies
+            .iter()
+            .map(|body| {
+                Instance::new(
+                    Network::shadow_for(&body.bounds),
+                    Color::mono(0.0, constants::SHADOW_ALPHA),
+                )
+            })
+            .collect();
+        self.renderer.draw_shadows_instanced(
+            &shadows,
+            constants::OPERATOR_CORNER_RADIUS,
+            constants::SHADOW_BLUR,
+        );
+        for bounds in band.selected.iter() {
+            self.draw_outline(
+                bounds,
+                constants::OPERATOR_SELECTION_WIDTH,
+                &Color::from_hex(0x76B264, 1.0),
+            );
+        }
+        self.renderer.draw_rounded_instanced(
+            &band.bodies,
+            Rounding::with_border(
+                constants::OPERATOR_CORNER_RADIUS,
+                constants::OPERATOR_BORDER_WIDTH,
+                Color::mono(0.0, 0.25),
+            ),
+        );
+        self.renderer.draw_rounded_instanced(
+            &band.slots,
+            Rounding::new(constants::OPERATOR_SLOT_CORNER_RADIUS),
+        );
+        for &(color_map, ref instances) in band.icons.iter() {
+            self.renderer.draw_instanced(instances, Some(color_map), None);
+        }
+    }
+
+    /// Returns `true` if anything drawn within `bounds` (or just outside of
+    /// it, like shadows and outlines) might be visible, and `false` if it
+    /// can be skipped.
+    fn is_visible(&self, bounds: &Rect) -> bool {
+        let visible = self.renderer.get_view().get_bounds();
+        Network::extent_of(bounds).intersects(&visible)
+    }
+
+    /// Returns the area that anything drawn within `bounds` (including
+    /// shadows and outlines) might cover.
+    fn extent_of(bounds: &Rect) -> Rect {
+        let padding = Vector2::new(1.0, 1.0) * constants::NETWORK_CULL_PADDING * 2.0;
+        Rect::expanded_from(bounds, &padding)
+    }
+
+    /// Returns the rectangle that casts the drop shadow of `bounds`.
+    fn shadow_for(bounds: &Rect) -> Rect {
+        let mut shadow = *bounds;
+        shadow.translate(&constants::SHADOW_OFFSET);
+        shadow
+    }
+
+    /// Draws a solid outline that is `width` pixels wide and centered on
+    /// the edges of `bounds`.
+    fn draw_outline(&self, bounds: &Rect, width: f32, color: &Color) {
+        let ul = *bounds.get_upper_left();
+        let lr = ul + *bounds.get_size();
+
+        // Start (and end) the loop in the middle of the top edge, so that
+        // every corner is mitered.
+        let mid = (ul.x + lr.x) * 0.5;
+        let corners = [
+            (mid, ul.y),
+            (lr.x, ul.y),
+            (lr.x, lr.y),
+            (ul.x, lr.y),
+            (ul.x, ul.y),
+            (mid, ul.y),
+        ];
+
+        let mut points = Vec::with_capacity(corners.len() * 4);
+        for (i, &(x, y)) in corners.iter().enumerate() {
+            let t = (i as f32) / ((corners.len() - 1) as f32);
+            points.extend_from_slice(&[x, y, t, t]);
+        }
+
+        self.renderer.draw(
+            DrawParams::Line(&points, LineMode::Solid, LineConnectivity::Strip, width),
+            color,
+            None,
+            None,
+        );
+    }
+
+    /// Adds `instance` to the batch in `batches` that is drawn with
+    /// `texture`, starting a new batch if there isn't one yet.
+    fn add_to_batch<'a>(
+        batches: &mut Vec<(&'a Texture, Vec<Instance>)>,
+        texture: &'a Texture,
+        instance: Instance,
+    ) {
+        if let Some(batch) = batches
+            .iter_mut()
+            .find(|batch| ptr::eq(batch.0, texture))
+        {
+            batch.1.push(instance);
+            return;
+        }
+        batches.push((texture, vec![instance]));
+    }
+
+    /// Draws a strip of icons along the bottom of the network editor
+    /// that shows the most recently used op families. The left-most
+    /// icon will be re-created by the quick-add command.
+    fn draw_recent_ops(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        if self.recent_families.is_empty() {
+            return;
+        }
+
+        let size = constants::OPERATOR_ICON_SIZE;
+        let padding = constants::RECENT_OPS_PADDING;
+        let count = self.recent_families.len() as f32;
+
+        // Anchor the strip to the lower-left corner of the network editor.
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(
+            -half_size.x + padding,
+            half_size.y - constants::STATUS_BAR_HEIGHT - constants::TIMELINE_HEIGHT - size.y
+                - padding * 3.0,
+        );
+        let bounds_strip = Rect::new(
+            upper_left,
+            Vector2::new(count * (size.x + padding) + padding, size.y + padding * 2.0),
+        );
+        self.renderer.draw(
+            DrawParams::RoundedRectangle(
+                &bounds_strip,
+                Rounding::new(constants::PANEL_CORNER_RADIUS),
+            ),
+            &Color::from_hex(0x373737, 0.9),
+            None,
+            None,
+        );
+
+        for (i, family) in self.recent_families.iter().enumerate() {
+            let offset = Vector2::new(padding + i as f32 * (size.x + padding), padding);
+            let bounds_icon = Rect::new(upper_left + offset, size);
+
+            // Highlight the op family that will be re-created next.
+            if i == 0 {
+                let bounds_select = Rect::expanded_from(&bounds_icon, &Vector2::new(4.0, 4.0));
+                self.renderer.draw(
+                    DrawParams::Rectangle(&bounds_select),
+                    &Color::from_hex(0x76B264, 1.0),
+                    None,
+                    None,
+                );
+            }
+
+            let color_map = self.get_icon(*family);
+            self.renderer.draw(
+                DrawParams::Rectangle(&bounds_icon),
+                &Color::white(),
+                Some(color_map),
+                None,
+            );
+        }
+    }
+
+    /// Gathers the draw data required to draw a curve through `samples`
+    /// (see `routing::sample`).
+    fn curve_points(samples: &[Vector2<f32>]) -> Vec<f32> {
+        let mut points = Vec::with_capacity(samples.len() * 4);
+        for (i, point) in samples.iter().enumerate() {
+            // Texture coordinates increase from the start of the curve
+            // towards the end, so that dashes flow in the direction of
+            // the connection.
+            let t = (i as f32) / ((samples.len() - 1) as f32);
+            points.extend_from_slice(&[point.x, point.y, t, t]);
         }
+        points
+    }
 
-        if let (Some(src), Some(dst)) = (src, dst) {
-            let src_family = self.graph.get_node(src).unwrap().data.family;
-            let dst_family = self.graph.get_node(dst).unwrap().data.family;
+    /// Draws a curve (or arrowhead) whose draw data was gathered by
+    /// `curve_points` (or `arrow_data`).
+    fn draw_curve(&self, points: &Vec<f32>, mode: LineMode, color: &Color) {
+        if points.is_empty() {
+            return;
+        }
+
+        self.renderer.draw(
+            DrawParams::Line(
+                points,
+                mode,
+                LineConnectivity::Strip,
+                constants::EDGE_LINE_WIDTH,
+            ),
+            color,
+            None,
+            None,
+        );
+    }
 
-            if dst_family.has_inputs() {
+    /// Gathers the draw data required to draw a small arrowhead centered
+    /// at `position` that points along `direction`, which indicates the
+    /// flow of data through an edge. The data is empty if `direction` is
+    /// zero.
+    fn arrow_data(position: &Vector2<f32>, direction: &Vector2<f32>) -> Vec<f32> {
+        match Network::arrow_points(position, direction) {
+            Some((left, tip, right)) => vec![
+                left.x, left.y, 0.0, 0.0,
+                tip.x, tip.y, 0.5, 0.5,
+                right.x, right.y, 1.0, 1.0,
+            ],
+            None => Vec::new(),
+        }
+    }
 
-                if src_family.can_connect_to(dst_family) {
-                    println!("Valid connection between ops with IDs: {}, {}", src, dst);
-                    self.add_connection(src, dst);
+    /// Returns the left corner, tip, and right corner of an arrowhead
+    /// centered at `position` that points along `direction`, or `None`
+    /// if `direction` is zero.
+    fn arrow_points(
+        position: &Vector2<f32>,
+        direction: &Vector2<f32>,
+    ) -> Option<(Vector2<f32>, Vector2<f32>, Vector2<f32>)> {
+        if direction.magnitude2() == 0.0 {
+            return None;
+        }
+
+        let forward = direction.normalize() * constants::EDGE_ARROW_SIZE;
+        let side = Vector2::new(-forward.y, forward.x) * 0.5;
+
+        let tip = position + forward * 0.5;
+        Some((tip - forward + side, tip, tip - forward - side))
+    }
+
+    /// Draws all edges between ops in the network. The geometry of each
+    /// edge is only regenerated when it moves (see `EdgeCache`), and edges
+    /// that look the same are drawn back to back, so that the renderer
+    /// batches them into a handful of draw calls.
+    fn draw_all_edges(&mut self) {
+        self.renderer.set_layer(Layer::Edges);
+
+        let bodies = self.graph.nodes.iter().map(|node| node.data.bounds_body);
+        self.edge_cache.begin(bodies);
+
+        let mut order = mem::replace(&mut self.edge_order, Vec::new());
+        order.clear();
+        for (src, edges) in self.graph.edges.iter().enumerate() {
+            for &dst in edges.outputs.iter() {
+                let mode = match self.style_for_edge(src, dst) {
+                    Some((mode, ..)) => mode,
+                    None => continue,
+                };
+
+                let (from, to) = match self.endpoints_for_edge(src, dst) {
+                    Some(endpoints) => endpoints,
+                    None => continue,
+                };
+                let (src_family, dst_family) =
+                    match (self.graph.get_node(src), self.graph.get_node(dst)) {
+                        (Some(src_node), Some(dst_node)) => {
+                            (src_node.data.family, dst_node.data.family)
+                        }
+                        _ => continue,
+                    };
+                let routed = match src_family.get_connection_type(dst_family) {
+                    ConnectionType::Direct => true,
+                    _ => false,
+                };
+                if self.edge_cache.mark(src, dst, from, to, routed) {
+                    let geometry = self.geometry_for_edge(src, dst);
+                    self.edge_cache.insert(src, dst, from, to, routed, geometry);
                 }
+
+                // Edges that are entirely offscreen aren't drawn.
+                if !self.is_visible(&self.edge_cache.get(src, dst).unwrap().bounds) {
+                    continue;
+                }
+
+                // An edge's color only depends on its mode and whether or
+                // not it is selected.
+                let selected = self.edge_selection == Some((src, dst));
+                order.push((selected, mode as u32, src, dst));
+            }
+        }
+        self.edge_cache.end();
+        order.sort();
+
+        // Draw all of the curves, followed by all of the arrowheads, which
+        // sit halfway along each edge and point along its tangent.
+        for &(_, _, src, dst) in order.iter() {
+            let (mode, color, _) = self.style_for_edge(src, dst).unwrap();
+            let geometry = self.edge_cache.get(src, dst).unwrap();
+            self.draw_curve(&geometry.curve, mode, &color);
+        }
+        for &(_, _, src, dst) in order.iter() {
+            let (_, color, _) = self.style_for_edge(src, dst).unwrap();
+            let geometry = self.edge_cache.get(src, dst).unwrap();
+            self.draw_curve(&geometry.arrow, LineMode::Solid, &color);
+        }
+
+        // Label the selected edge with its connection type (the cache only
+        // holds edges that were just drawn).
+        if let Some((src, dst)) = self.edge_selection {
+            if let Some(geometry) = self.edge_cache.get(src, dst) {
+                let (_, color, label) = self.style_for_edge(src, dst).unwrap();
+                self.renderer.draw(
+                    DrawParams::Text(
+                        label,
+                        geometry.midpoint + constants::TOOLTIP_OFFSET,
+                        constants::FONT_SCALE,
+                    ),
+                    &color,
+                    None,
+                    None,
+                );
             }
         }
+        self.edge_order = order;
+    }
 
-        self.preview.handle_interaction(&mouse);
+    /// Generates the draw data for the edge between ops `src` and `dst`.
+    fn geometry_for_edge(&self, src: usize, dst: usize) -> EdgeGeometry {
+        let segments = self.segments_for_edge(src, dst);
+        let samples = routing::sample(&segments, constants::EDGE_CURVE_LOD);
+        let (midpoint, tangent) = routing::midpoint(&segments);
+
+        EdgeGeometry {
+            curve: Network::curve_points(&samples),
+            arrow: Network::arrow_data(&midpoint, &tangent),
+            midpoint,
+            bounds: routing::get_bounds(&samples),
+        }
     }
 
-    /// Draws all of the operators and edges that make
-    /// up this graph.
-    pub fn draw(&mut self) {
-        self.draw_grid();
-        self.draw_all_edges();
-        self.draw_all_nodes();
+    /// Returns the centroids of the output slot of op `src` and the input
+    /// slot of op `dst`, which the edge between them connects, or `None`
+    /// if either op doesn't exist.
+    fn endpoints_for_edge(&self, src: usize, dst: usize) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let src_node = self.graph.get_node(src)?;
+        let dst_node = self.graph.get_node(dst)?;
+        Some((
+            src_node.data.bounds_output.centroid(),
+            dst_node.data.bounds_input.centroid(),
+        ))
+    }
 
-        if self.show_preview {
-            self.gather_params();
+    /// Returns the line mode, color, and label of the edge between ops
+    /// `src` and `dst`, or `None` if the connection is invalid.
+    fn style_for_edge(&self, src: usize, dst: usize) -> Option<(LineMode, Color, &'static str)> {
+        let src_family = self.graph.get_node(src)?.data.family;
+        let dst_family = self.graph.get_node(dst)?.data.family;
+
+        let (mode, mut color, label) = match src_family.get_connection_type(dst_family) {
+            // Draw a bezier curve between these two operators, routed
+            // around the bodies of any other ops that are in the way.
+            ConnectionType::Direct => (LineMode::Solid, Color::mono(0.75, 1.0), "direct"),
+
+            // Draw a straight, dashed line (export) between these
+            // two operators.
+            ConnectionType::Indirect => (LineMode::Dashed, Color::mono(0.75, 0.25), "indirect"),
 
-            self.preview.prepare(self.renderer.get_projection());
-            self.renderer.draw_rect_inner();
+            // An invalid connection - this should never happen, in practice.
+            _ => return None,
+        };
+        if self.edge_selection == Some((src, dst)) {
+            color = Color::from_hex(0x76B264, 1.0);
         }
+        Some((mode, color, label))
     }
 
-    /// Pick a draw color based on the current interaction state of this
-    /// operator and the op type.
-    fn color_for_op(&self, op: &Op) -> Color {
-        let mut color = match op.family {
-            OpFamily::Domain(domain) => Color::from_hex(0x515151, 1.0),
-            OpFamily::Primitive(primitive) => match primitive {
-                PrimitiveType::Sphere
-                | PrimitiveType::Box
-                | PrimitiveType::Plane
-                | PrimitiveType::Torus => Color::from_hex(0x8F719D, 1.0),
-                PrimitiveType::Union
-                | PrimitiveType::Subtraction
-                | PrimitiveType::Intersection
-                | PrimitiveType::SmoothMinimum => Color::from_hex(0x8A7BA4, 1.0),
-                PrimitiveType::Render => Color::from_hex(0xC77832, 1.0),
-            },
+    /// Returns the segments that make up the edge between ops `src`
+    /// and `dst`. Direct connections are routed around the bodies of
+    /// any other ops that are in the way, while indirect connections
+    /// are drawn as straight lines. If either op doesn't exist, there
+    /// are no segments.
+    fn segments_for_edge(&self, src: usize, dst: usize) -> Vec<Segment> {
+        let (src_node, dst_node) = match (self.graph.get_node(src), self.graph.get_node(dst)) {
+            (Some(src_node), Some(dst_node)) => (src_node, dst_node),
+            _ => return Vec::new(),
         };
-
-        // Add a contribution based on the op's current interaction state.
-        if let InteractionState::Hover = op.state {
-            color += Color::mono(0.05, 0.0);
+        let src_centroid = src_node.data.bounds_output.centroid();
+        let dst_centroid = dst_node.data.bounds_input.centroid();
+
+        match src_node.data.family.get_connection_type(dst_node.data.family) {
+            ConnectionType::Direct => {
+                let obstacles: Vec<Rect> = self.graph
+                    .nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|&(index, _)| index != src && index != dst)
+                    .map(|(_, node)| node.data.bounds_body)
+                    .collect();
+
+                routing::route(src_centroid, dst_centroid, &obstacles)
+            }
+            _ => vec![routing::straight(src_centroid, dst_centroid)],
         }
-        color
     }
 
-    /// Draws all ops in the network.
-    fn draw_all_nodes(&mut self) {
-        for node in self.graph.get_nodes().iter() {
-            let op = &node.data;
+    /// Returns the text that will be displayed in the tooltip of the
+    /// op at `index`, or `None` if there is no such op.
+    fn tooltip_for_op(&self, index: usize) -> Option<String> {
+        let op = &self.graph.get_node(index)?.data;
+        let edges = &self.graph.edges[index];
+
+        let mut lines = vec![
+            op.name.clone(),
+            format!(
+                "family: {} ({})",
+                op.family.get_family_name(),
+                op.family.to_string()
+            ),
+            format!(
+                "inputs: {}/{}",
+                edges.inputs.len(),
+                op.get_input_capacity()
+            ),
+            format!("outputs: {}", edges.outputs.len()),
+        ];
+        if let Some(ref custom) = op.custom {
+            lines.push(format!("code: {}", custom.file_name));
+        }
+        if op.baked {
+            lines.push("baked".to_string());
+        }
 
-            // Draw the op and other components:
-            // - If the op is selected, draw a selection box behind it
-            // - If the op is being used as a connection source or
-            //   destination, draw the appropriate connection slot
-            let slot_color = Color::from_hex(0x373737, 1.0);
-            match op.state {
-                InteractionState::Selected => {
-                    let bounds_select =
-                        Rect::expanded_from(&op.bounds_body, &Vector2::new(6.0, 6.0));
-                    self.renderer.draw(
-                        DrawParams::Rectangle(&bounds_select),
-                        &Color::from_hex(0x76B264, 1.0),
-                        None,
-                        None,
-                    );
-                }
-                InteractionState::ConnectSource => self.renderer.draw(
-                    DrawParams::Rectangle(&op.bounds_output),
-                    &slot_color,
+        // Only list parameter components that have a name.
+        let params = op.get_params();
+        for (name, value) in params.get_names().iter().zip(params.get_data().iter()) {
+            if !name.is_empty() {
+                lines.push(format!("{}: {:.2}", name, value));
+            }
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// Draws a tooltip below the op that the mouse is hovering over,
+    /// once the mouse has been still for long enough.
+    fn draw_tooltip(&self) {
+        self.renderer.set_layer(Layer::Tooltips);
+
+        if let Some(index) = self.hover.expired(constants::TOOLTIP_DELAY) {
+            let node = self.graph.get_node(index);
+            if let (Some(node), Some(text)) = (node, self.tooltip_for_op(index)) {
+
+                // Place the tooltip just below the op's body, which is in
+                // world space (unlike the tooltip itself).
+                let body = &node.data.bounds_body;
+                let lower_left = body.get_upper_left() + Vector2::new(0.0, body.get_size().y);
+                let upper_left =
+                    self.renderer.get_view().to_screen(&lower_left) + constants::TOOLTIP_OFFSET;
+
+                let size = self.renderer.get_font().measure(&text, constants::FONT_SCALE)
+                    + constants::TOOLTIP_PADDING * 2.0;
+                let bounds_background = Rect::new(upper_left, size);
+
+                self.renderer.draw(
+                    DrawParams::RoundedRectangle(
+                        &bounds_background,
+                        Rounding::new(constants::PANEL_CORNER_RADIUS),
+                    ),
+                    &Color::from_hex(0x373737, 0.9),
                     None,
                     None,
-                ),
-                InteractionState::ConnectDestination => self.renderer.draw(
-                    DrawParams::Rectangle(&op.bounds_input),
-                    &slot_color,
+                );
+
+                self.renderer.draw(
+                    DrawParams::Text(
+                        &text,
+                        upper_left + constants::TOOLTIP_PADDING,
+                        constants::FONT_SCALE,
+                    ),
+                    &Color::mono(0.85, 1.0),
                     None,
                     None,
-                ),
-                _ => (),
+                );
             }
+        }
+    }
 
-            // Draw the body of the op.
-            let draw_color = self.color_for_op(op);
-            let alpha_key = match op.family.get_connectivity() {
-                Connectivity::InputOutput => "alpha_input_output".to_string(),
-                Connectivity::Input => "alpha_input".to_string(),
-                Connectivity::Output => "alpha_output".to_string(),
+    /// Draws the timeline, which shows the loop range, the playhead, and
+    /// the current time.
+    fn draw_timeline(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        let bounds = self.timeline_bounds();
+        self.renderer.draw(
+            DrawParams::Rectangle(&bounds),
+            &Color::from_hex(0x2D2D2D, 1.0),
+            None,
+            None,
+        );
+
+        // Converts a time (in seconds) to a horizontal position.
+        let to_x = |time: f32| {
+            bounds.get_upper_left().x + time / constants::TIMELINE_DURATION * bounds.get_size().x
+        };
+
+        let (loop_start, loop_end) = self.timeline.get_loop_range();
+        let bounds_loop = Rect::new(
+            Vector2::new(to_x(loop_start), bounds.get_upper_left().y),
+            Vector2::new(to_x(loop_end) - to_x(loop_start), bounds.get_size().y),
+        );
+        self.renderer.draw(
+            DrawParams::Rectangle(&bounds_loop),
+            &Color::from_hex(0x4A4A4A, 1.0),
+            None,
+            None,
+        );
+
+        let bounds_playhead = Rect::new(
+            Vector2::new(to_x(self.timeline.get_time()) - 1.0, bounds.get_upper_left().y),
+            Vector2::new(2.0, bounds.get_size().y),
+        );
+        self.renderer.draw(
+            DrawParams::Rectangle(&bounds_playhead),
+            &Color::from_hex(0xC77832, 1.0),
+            None,
+            None,
+        );
+
+        let text = format!(
+            "{:.2}s{}",
+            self.timeline.get_time(),
+            if self.timeline.is_playing() { "" } else { " (paused)" }
+        );
+        let text_height = self.renderer
+            .get_font()
+            .measure(&text, constants::FONT_SCALE)
+            .y;
+        let offset = Vector2::new(
+            constants::STATUS_BAR_PADDING,
+            (constants::TIMELINE_HEIGHT - text_height) * 0.5,
+        );
+        self.renderer.draw(
+            DrawParams::Text(&text, bounds.get_upper_left() + offset, constants::FONT_SCALE),
+            &Color::mono(0.75, 1.0),
+            None,
+            None,
+        );
+    }
+
+    /// Draws a thin bar along the bottom of the network editor that
+    /// shows various statistics about the network.
+    fn draw_status_bar(&mut self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        self.status.tick();
+
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(-half_size.x, half_size.y - constants::STATUS_BAR_HEIGHT);
+        let bounds_bar = Rect::new(
+            upper_left,
+            Vector2::new(half_size.x * 2.0, constants::STATUS_BAR_HEIGHT),
+        );
+        self.renderer.draw(
+            DrawParams::Rectangle(&bounds_bar),
+            &Color::from_hex(0x373737, 1.0),
+            None,
+            None,
+        );
+
+        let connections = self.graph
+            .get_edges()
+            .iter()
+            .map(|edges| edges.inputs.len())
+            .sum();
+        let text = self.status.get_text(
+            self.graph.get_nodes().len(),
+            connections,
+            &self.frame_settings,
+        );
+
+        // Center the text vertically within the status bar.
+        let text_height = self.renderer
+            .get_font()
+            .measure(&text, constants::FONT_SCALE)
+            .y;
+        let offset = Vector2::new(
+            constants::STATUS_BAR_PADDING,
+            (constants::STATUS_BAR_HEIGHT - text_height) * 0.5,
+        );
+        self.renderer.draw(
+            DrawParams::Text(&text, upper_left + offset, constants::FONT_SCALE),
+            &Color::mono(0.75, 1.0),
+            None,
+            None,
+        );
+    }
+
+    /// Draws a panel whose rows hold `rows`, where row `0` is the panel's
+    /// title, each at the bounding box returned by `get_bounds`. The row
+    /// at `highlighted` (if any) is drawn in the selection color.
+    fn draw_panel_rows<F>(&self, rows: &[String], highlighted: Option<usize>, get_bounds: F)
+    where
+        F: Fn(usize) -> Rect,
+    {
+        for (index, row) in rows.iter().enumerate() {
+            let bounds_row = get_bounds(index);
+
+            let background = if highlighted == Some(index) {
+                Color::from_hex(0x76B264, 0.9)
+            } else {
+                Color::from_hex(0x373737, 0.9)
             };
-            let alpha_map = self.assets.get(&alpha_key).unwrap();
             self.renderer.draw(
-                DrawParams::Rectangle(&op.bounds_body),
-                &draw_color,
+                DrawParams::RoundedRectangle(
+                    &bounds_row,
+                    Rounding::new(constants::PANEL_CORNER_RADIUS),
+                ),
+                &background,
+                None,
                 None,
-                Some(alpha_map),
             );
 
-            // Draw the icon on top of the op (if one exists).
-            let color_map = self.assets.get(op.family.to_string()).unwrap();
+            let color = if index == 0 {
+                Color::mono(0.9, 1.0)
+            } else {
+                Color::mono(0.7, 1.0)
+            };
             self.renderer.draw(
-                DrawParams::Rectangle(&op.bounds_icon),
-                &draw_color,
-                Some(color_map),
+                DrawParams::Text(
+                    row,
+                    bounds_row.get_upper_left() + constants::PRESET_PANEL_PADDING,
+                    constants::FONT_SCALE,
+                ),
+                &color,
+                None,
                 None,
             );
         }
     }
 
-    /// Gathers the draw data required to draw a curve between
-    /// `a` and `b`.
-    fn curve_between(
-        &self,
-        a: &Vector2<f32>,
-        b: &Vector2<f32>,
-        c: &Vector2<f32>,
-        d: &Vector2<f32>,
-    ) {
-        const LOD: usize = 20;
-        let mut points = Vec::with_capacity(LOD * 4);
-        for i in 0..LOD {
-            let t = (i as f32) / (LOD as f32);
-            let t_inv = 1.0 - t;
+    /// Draws the library panel, which lists all of the available presets.
+    fn draw_presets(&self) {
+        self.renderer.set_layer(Layer::Panels);
 
-            // Coefficients for a cubic polynomial.
-            let b0 = t * t * t;
-            let b1 = 3.0 * t * t * t_inv;
-            let b2 = 3.0 * t * t_inv * t_inv;
-            let b3 = t_inv * t_inv * t_inv;
+        if !self.show_presets {
+            return;
+        }
 
-            let point = a * b0 + b * b1 + c * b2 + d * b3;
+        let mut rows = vec!["presets".to_string()];
+        rows.extend(self.presets.iter().map(|preset| preset.name.clone()));
 
-            points.extend_from_slice(&[point.x, point.y, t, t]);
+        // Highlight the preset that will be dropped at the next click.
+        let armed = self.armed_preset.map(|index| index + 1);
+        self.draw_panel_rows(&rows, armed, |index| self.preset_row_bounds(index));
+    }
+
+    /// Draws the panel of recent networks, which lists the path of each
+    /// one (shortened from the left, since the file name matters most).
+    fn draw_recent_projects(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        if !self.show_recent_projects {
+            return;
+        }
+
+        let mut rows = vec!["recent networks".to_string()];
+        for path in self.recent_projects.get_paths() {
+            let text = path.display().to_string();
+            let count = text.chars().count();
+            rows.push(if count > constants::RECENT_PANEL_MAX_CHARS {
+                let skipped = count - constants::RECENT_PANEL_MAX_CHARS + 3;
+                format!("...{}", text.chars().skip(skipped).collect::<String>())
+            } else {
+                text
+            });
+        }
+
+        // Highlight the network that is currently open.
+        let current = self.recent_projects
+            .get_paths()
+            .iter()
+            .position(|path| *path == self.project_path)
+            .map(|index| index + 1);
+        self.draw_panel_rows(&rows, current, |index| self.recent_project_row_bounds(index));
+    }
+
+    /// Draws the gallery, which lists the name of each example network.
+    fn draw_gallery(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        if !self.show_gallery {
+            return;
         }
 
-        // Add the first point.
-        points.extend_from_slice(&[a.x, a.y, 0.0, 0.0]);
+        let mut rows = vec!["examples (ctrl + g)".to_string()];
+        rows.extend(self.examples.iter().map(|example| example.name.clone()));
+        self.draw_panel_rows(&rows, None, |index| self.gallery_row_bounds(index));
+    }
+
+    /// Draws the performance overlay in the lower-right corner of the
+    /// network editor, which lists the average of each timing and graphs
+    /// its recent history against the budget of a 60 Hz frame. Below that,
+    /// it shows a histogram of the number of steps that the raymarcher
+    /// took through each pixel of the preview (if they were recorded).
+    fn draw_perf(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        if !self.show_perf {
+            return;
+        }
 
+        let series = [
+            ("cpu", &self.perf.cpu),
+            ("preview", &self.perf.preview),
+            ("interface", &self.perf.interface),
+        ];
+        let line_height = self.renderer.get_font().get_line_height(constants::FONT_SCALE);
+        let padding = constants::PERF_PADDING;
+        let steps = self.preview.get_step_statistics();
+        let steps_height = if steps.is_some() {
+            line_height + constants::PERF_HISTOGRAM_HEIGHT + padding.y * 2.0
+        } else {
+            0.0
+        };
+        let size = Vector2::new(
+            constants::PERF_PANEL_WIDTH,
+            line_height * series.len() as f32
+                + constants::PERF_GRAPH_HEIGHT
+                + padding.y * 3.0
+                + steps_height,
+        );
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(
+            half_size.x - size.x - padding.x,
+            half_size.y
+                - constants::STATUS_BAR_HEIGHT
+                - constants::TIMELINE_HEIGHT
+                - size.y
+                - padding.y,
+        );
+        let bounds = Rect::new(upper_left, size);
         self.renderer.draw(
-            DrawParams::Line(&points, LineMode::Solid, LineConnectivity::Strip),
-            &Color::mono(0.75, 1.0),
+            DrawParams::RoundedRectangle(&bounds, Rounding::new(constants::PANEL_CORNER_RADIUS)),
+            &Color::from_hex(0x373737, 0.9),
+            None,
+            None,
+        );
+
+        // Every graph shares the same scale, which always fits the budget.
+        let scale = series
+            .iter()
+            .map(|&(_, history)| history.get_max())
+            .fold(constants::PERF_BUDGET_MS, f32::max);
+        let graph = Rect::new(
+            Vector2::new(
+                upper_left.x + padding.x,
+                upper_left.y + line_height * series.len() as f32 + padding.y * 2.0,
+            ),
+            Vector2::new(size.x - padding.x * 2.0, constants::PERF_GRAPH_HEIGHT),
+        );
+        let to_y = |milliseconds: f32| {
+            graph.get_upper_left().y + graph.get_size().y * (1.0 - milliseconds / scale)
+        };
+
+        let budget_y = to_y(constants::PERF_BUDGET_MS);
+        let budget = vec![
+            graph.get_upper_left().x, budget_y, 0.0, 0.0,
+            graph.get_upper_left().x + graph.get_size().x, budget_y, 1.0, 1.0,
+        ];
+        self.renderer.draw(
+            DrawParams::Line(&budget, LineMode::Dashed, LineConnectivity::Segment, 1.0),
+            &Color::mono(0.5, 1.0),
+            None,
+            None,
+        );
+
+        for (i, &(name, history)) in series.iter().enumerate() {
+            let color = Color::from_hex(constants::PERF_COLORS[i], 1.0);
+            let text = format!(
+                "{}: {:.2} ms (max {:.2})",
+                name,
+                history.get_average(),
+                history.get_max()
+            );
+            self.renderer.draw(
+                DrawParams::Text(
+                    &text,
+                    upper_left + padding + Vector2::new(0.0, line_height * i as f32),
+                    constants::FONT_SCALE,
+                ),
+                &color,
+                None,
+                None,
+            );
+
+            let samples = history.get_samples();
+            if samples.len() < 2 {
+                continue;
+            }
+            // The newest measurement is on the right.
+            let step = graph.get_size().x / (constants::PERF_HISTORY_LENGTH - 1) as f32;
+            let right = graph.get_upper_left().x + graph.get_size().x;
+            let mut points = Vec::with_capacity(samples.len() * 4);
+            for (j, &milliseconds) in samples.iter().enumerate() {
+                let t = (j as f32) / ((samples.len() - 1) as f32);
+                let x = right - step * (samples.len() - 1 - j) as f32;
+                points.extend_from_slice(&[x, to_y(milliseconds), t, t]);
+            }
+            self.renderer.draw(
+                DrawParams::Line(&points, LineMode::Solid, LineConnectivity::Strip, 1.5),
+                &color,
+                None,
+                None,
+            );
+        }
+
+        let steps = match steps {
+            Some(steps) => steps,
+            None => return,
+        };
+        let top = graph.get_upper_left().y + graph.get_size().y + padding.y;
+        let text = format!("steps: {:.1} per pixel (max {})", steps.mean, steps.max);
+        self.renderer.draw(
+            DrawParams::Text(
+                &text,
+                Vector2::new(upper_left.x + padding.x, top),
+                constants::FONT_SCALE,
+            ),
+            &Color::mono(0.8, 1.0),
+            None,
+            None,
+        );
+
+        // Each bar's height is relative to the fullest bin.
+        let fullest = steps.bins.iter().cloned().max().unwrap_or(0).max(1);
+        let width = graph.get_size().x / steps.bins.len() as f32;
+        let bottom = top + line_height + padding.y + constants::PERF_HISTOGRAM_HEIGHT;
+        for (i, &count) in steps.bins.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let height = constants::PERF_HISTOGRAM_HEIGHT * count as f32 / fullest as f32;
+            let bar = Rect::new(
+                Vector2::new(graph.get_upper_left().x + width * i as f32, bottom - height),
+                Vector2::new((width - 1.0).max(1.0), height),
+            );
+            self.renderer.draw(
+                DrawParams::Rectangle(&bar),
+                &Color::from_hex(constants::PERF_COLORS[1], 1.0),
+                None,
+                None,
+            );
+        }
+    }
+
+    /// Draws the console in the upper-left corner of the network editor.
+    /// When collapsed, only the console's title (and the number of
+    /// messages it holds) is drawn.
+    fn draw_console(&self) {
+        self.renderer.set_layer(Layer::Panels);
+
+        let padding = constants::CONSOLE_PADDING;
+        let line_height = self.renderer.get_font().get_line_height(constants::FONT_SCALE);
+
+        let mut lines = vec![format!("console ({})", console::len())];
+        if self.show_console {
+            for message in console::get_recent(constants::CONSOLE_VISIBLE_MESSAGES) {
+                // Clip long messages (i.e. shader error logs) to the width of the console.
+                lines.push(message.chars().take(constants::CONSOLE_MAX_LINE_LENGTH).collect());
+            }
+        }
+
+        let half_size = self.renderer.get_size() * 0.5;
+        let upper_left = Vector2::new(-half_size.x, -half_size.y) + padding;
+
+        let width = if self.show_console {
+            self.renderer
+                .get_font()
+                .measure(&"#".repeat(constants::CONSOLE_MAX_LINE_LENGTH), constants::FONT_SCALE)
+                .x
+        } else {
+            self.renderer.get_font().measure(&lines[0], constants::FONT_SCALE).x
+        };
+        let size = Vector2::new(width, line_height * lines.len() as f32) + padding * 2.0;
+        self.renderer.draw(
+            DrawParams::RoundedRectangle(
+                &Rect::new(upper_left, size),
+                Rounding::new(constants::PANEL_CORNER_RADIUS),
+            ),
+            &Color::from_hex(0x373737, 0.9),
             None,
             None,
         );
+
+        for (i, line) in lines.iter().enumerate() {
+            let origin = upper_left + padding + Vector2::new(0.0, line_height * i as f32);
+
+            // Draw the title slightly brighter than the messages.
+            let color = if i == 0 {
+                Color::mono(0.9, 1.0)
+            } else {
+                Color::mono(0.7, 1.0)
+            };
+            self.renderer.draw(
+                DrawParams::Text(line, origin, constants::FONT_SCALE),
+                &color,
+                None,
+                None,
+            );
+        }
     }
 
-    /// Gathers the draw data required to draw a straight line between
-    /// `a` and `b`.
-    fn line_between(&self, a: &Vector2<f32>, b: &Vector2<f32>) {
-        let points = vec![a.x, a.y, 0.0, 0.0, b.x, b.y, 1.0, 1.0];
+    /// Draws the knife stroke that the user is currently drawing (if any).
+    fn draw_knife_stroke(&self) {
+        self.renderer.set_layer(Layer::Overlays);
+
+        if self.knife_stroke.len() < 2 {
+            return;
+        }
+
+        // The stroke is recorded in world space, but drawn above the ops
+        // in screen space.
+        let view = self.renderer.get_view();
+        let mut points = Vec::with_capacity(self.knife_stroke.len() * 4);
+        for (i, point) in self.knife_stroke.iter().map(|p| view.to_screen(p)).enumerate() {
+            let t = (i as f32) / ((self.knife_stroke.len() - 1) as f32);
+            points.extend_from_slice(&[point.x, point.y, t, t]);
+        }
 
         self.renderer.draw(
-            DrawParams::Line(&points, LineMode::Dashed, LineConnectivity::Segment),
-            &Color::mono(0.75, 0.25),
+            DrawParams::Line(
+                &points,
+                LineMode::Dashed,
+                LineConnectivity::Strip,
+                constants::KNIFE_LINE_WIDTH,
+            ),
+            &Color::from_hex(constants::OPERATOR_ERROR_COLOR, 1.0),
             None,
             None,
         );
     }
 
-    /// Draws all edges between ops in the network.
-    fn draw_all_edges(&self) {
-        for (src, edges) in self.graph.edges.iter().enumerate() {
-            for dst in edges.outputs.iter() {
-                let src_node = self.graph.get_node(src).unwrap();
-                let dst_node = self.graph.get_node(*dst).unwrap();
-                let src_family = src_node.data.family;
-                let dst_family = dst_node.data.family;
-                let src_centroid = src_node.data.bounds_output.centroid();
-                let dst_centroid = dst_node.data.bounds_input.centroid();
-
-                match src_family.get_connection_type(dst_family) {
-                    // Draw a bezier curve between these two operators.
-                    ConnectionType::Direct => {
-                        let a = src_centroid;
-                        let d = dst_centroid;
-                        let mid = (a + d) * 0.5;
-
-                        let b = Vector2::new(mid.x, a.y);
-                        let c = Vector2::new(mid.x, d.y);
-                        self.curve_between(&a, &b, &c, &d);
-                    }
-                    // Draw a straight, dashed line (export) between these
-                    // two operators.
-                    ConnectionType::Indirect => {
-                        self.line_between(&src_centroid, &dst_centroid);
-                    }
-                    // An invalid connection - this should never happen, in practice.
-                    _ => (),
-                }
+    /// Draws a dashed curve from the output slot of the op that the
+    /// user is dragging a connection from to the current mouse position.
+    fn draw_pending_connection(&self) {
+        self.renderer.set_layer(Layer::Edges);
+
+        if let Some((src, cursor)) = self.pending_connection {
+            if let Some(node) = self.graph.get_node(src) {
+                let segment = routing::direct(node.data.bounds_output.centroid(), cursor);
+                let samples = routing::sample(&[segment], constants::EDGE_CURVE_LOD);
+                let points = Network::curve_points(&samples);
+                self.draw_curve(&points, LineMode::Dashed, &Color::mono(0.75, 0.75));
             }
         }
     }
 
     /// Draws a grid in the network editor.
     fn draw_grid(&mut self) {
+        self.renderer.set_layer(Layer::Grid);
+        self.grid.cover(&self.renderer.get_view().get_bounds());
+
         let draw_color = Color::from_hex(0x373737, 0.25);
 
         self.renderer.draw(
@@ -591,6 +3656,7 @@ impl Network {
                 &self.grid.points_vertical,
                 LineMode::Solid,
                 LineConnectivity::Segment,
+                constants::NETWORK_GRID_LINE_WIDTH,
             ),
             &draw_color,
             None,
@@ -602,6 +3668,7 @@ impl Network {
                 &self.grid.points_horizontal,
                 LineMode::Solid,
                 LineConnectivity::Segment,
+                constants::NETWORK_GRID_LINE_WIDTH,
             ),
             &draw_color,
             None,
@@ -609,28 +3676,59 @@ impl Network {
         );
     }
 
-    /// Aggregates all of the operator parameters.
-    fn gather_params(&self) {
-        let mut all_params = Vec::new();
+    /// Aggregates all of the operator parameters, followed by the bounding
+    /// spheres of the union hierarchy.
+    fn gather_params(&mut self) {
+        self.params_scratch.clear();
         for node in self.graph.nodes.iter() {
-            all_params.extend_from_slice(node.data.params.get_data());
-            //all_params.push(node.data.params.data);
+            self.params_scratch.extend_from_slice(node.data.params.get_data());
         }
+        self.hierarchy.refit(&self.graph, &mut self.params_scratch);
 
-        self.preview.update_params(all_params);
+        self.preview.update_params(&self.params_scratch);
     }
 
-    /// Loads all texture assets.
-    fn load_assets(&mut self) {
-        for entry in fs::read_dir("assets").unwrap() {
-            let path = entry.unwrap().path();
-            let file = path.file_stem().unwrap();
-            let ext = path.extension();
-
-            if ext == Some(OsStr::new("png")) {
-                self.assets
-                    .insert(file.to_str().unwrap().to_string(), Texture::new(&path));
+    /// Loads all texture assets. Icons that can't be loaded are skipped
+    /// (with a message), except for the one that is shared by custom ops,
+    /// which every other op type falls back to.
+    fn load_assets(&mut self) -> Result<(), Error> {
+        let directory = assets::get_directory();
+        let entries =
+            fs::read_dir(&directory).map_err(|error| Error::Io(directory.clone(), error))?;
+
+        // Op types that were defined in data files may come with icons.
+        let definitions = fs::read_dir(assets::get_path(constants::OP_DEFINITIONS_PATH))
+            .into_iter()
+            .flatten();
+        for entry in entries.chain(definitions).filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension() != Some(OsStr::new("png")) {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            match Texture::new(&path) {
+                Ok(texture) => {
+                    self.assets.insert(name, texture);
+                }
+                Err(error) => warn!("Failed to load icon: {}", error),
             }
         }
+
+        if !self.assets.contains_key("custom") {
+            return Err(Error::MissingAsset(directory.join("custom.png")));
+        }
+        Ok(())
+    }
+
+    /// Returns the icon of `family`. Op types that were defined in data
+    /// files without an icon of their own (or whose icon failed to load)
+    /// share the icon of custom ops, which always exists.
+    fn get_icon(&self, family: OpFamily) -> &Texture {
+        self.assets
+            .get(family.to_string())
+            .unwrap_or_else(|| self.assets.get("custom").unwrap())
     }
 }
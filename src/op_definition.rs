@@ -0,0 +1,332 @@
+use constants;
+use operator::{Connectivity, DomainType, OpFamily, Parameters, PrimitiveType};
+
+use toml::Value;
+
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// The role that an op type that was defined in a data file plays in the
+/// shader graph, which determines what it can be connected to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// Transforms the domain (i.e. `p_NAME` and `s_NAME`) of its input
+    Domain,
+
+    /// Produces a distance field from the domain of its input
+    Generator,
+
+    /// Combines the distance fields of its inputs
+    Combinator,
+}
+
+/// An op type that was defined in a data file, rather than being built in.
+/// Definitions live in `assets/ops/*.toml`:
+///
+/// ```text
+/// name = "capsule"
+/// family = "generator"
+/// shortcut = "K"
+/// template = """
+/// vec3 q_NAME = p_INPUT_A;
+/// q_NAME.y -= clamp(q_NAME.y, -params[INDEX].y, params[INDEX].y);
+/// float NAME = (length(q_NAME) - params[INDEX].x) * s_INPUT_A;
+/// """
+///
+/// [[param]]
+/// name = "radius"
+/// default = 0.5
+/// min = 0.1
+/// max = 2.0
+/// step = 0.1
+/// ```
+///
+/// The `family` is one of `domain`, `generator`, or `combinator`. The number
+/// of `inputs` and the `connectivity` (`input_output`, `input`, or `output`)
+/// are optional, and default to those of the built-in ops of the same family.
+/// Holding `shift` and pressing the key named by the optional `shortcut` adds
+/// an op of this type to the network.
+#[derive(Clone)]
+pub struct OpDefinition {
+    /// The name of the op type
+    pub name: String,
+
+    /// The role of the op type in the shader graph
+    pub kind: Kind,
+
+    /// Whether ops of this type accept inputs, outputs, or both
+    pub connectivity: Connectivity,
+
+    /// The number of ops that can be connected to the input slot
+    pub inputs: usize,
+
+    /// The default parameters of ops of this type (the names of which are
+    /// only filled in once the op type is registered)
+    pub params: Parameters,
+
+    /// The name of each of the parameters that were declared
+    pub param_names: Vec<String>,
+
+    /// The code template, which uses the same placeholders as the templates
+    /// of the built-in ops
+    pub template: String,
+
+    /// The name of the key that adds an op of this type, if any
+    pub shortcut: Option<String>,
+}
+
+thread_local! {
+    /// The op types that were loaded at startup, in the order that they
+    /// were registered, along with their names.
+    static DEFINITIONS: RefCell<Vec<(&'static str, OpDefinition)>> = RefCell::new(Vec::new());
+}
+
+/// Returns the string stored under `key` in `table`, if there is one.
+fn get_string(table: &Value, key: &str) -> Result<Option<String>, String> {
+    match table.get(key) {
+        Some(value) => value
+            .as_str()
+            .map(|value| Some(value.to_string()))
+            .ok_or_else(|| format!("expected `{}` to be a string", key)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the number stored under `key` in `table`, if there is one.
+/// Both integers and floats are accepted.
+fn get_number(table: &Value, key: &str) -> Result<Option<f32>, String> {
+    match table.get(key) {
+        Some(value) => value
+            .as_float()
+            .or_else(|| value.as_integer().map(|value| value as f64))
+            .map(|value| Some(value as f32))
+            .ok_or_else(|| format!("expected `{}` to be a number", key)),
+        None => Ok(None),
+    }
+}
+
+impl OpDefinition {
+    /// Parses the definition of an op type from the contents of a data file.
+    pub fn parse(text: &str) -> Result<OpDefinition, String> {
+        let root: Value = text.parse().map_err(|error: toml::de::Error| error.to_string())?;
+
+        let name = get_string(&root, "name")?.ok_or("missing `name`")?;
+        let valid = name.chars().enumerate().all(|(i, c)| {
+            c == '_' || c.is_ascii_lowercase() || (i > 0 && c.is_ascii_digit())
+        });
+        if name.is_empty() || !valid {
+            return Err(format!("`{}` isn't a valid op name", name));
+        }
+
+        let family = get_string(&root, "family")?.ok_or("missing `family`")?;
+        let (kind, default_inputs) = match &family[..] {
+            "domain" => (Kind::Domain, 1),
+            "generator" => (Kind::Generator, 1),
+            "combinator" => (Kind::Combinator, 2),
+            _ => return Err(format!("unknown family: {}", family)),
+        };
+
+        let inputs = match get_number(&root, "inputs")? {
+            Some(inputs) if (0.0..=2.0).contains(&inputs) && inputs.fract() == 0.0 => {
+                inputs as usize
+            }
+            Some(_) => return Err("expected 0, 1, or 2 `inputs`".to_string()),
+            None => default_inputs,
+        };
+
+        let connectivity = match get_string(&root, "connectivity")? {
+            Some(ref connectivity) => match &connectivity[..] {
+                "input_output" => Connectivity::InputOutput,
+                "input" => Connectivity::Input,
+                "output" => Connectivity::Output,
+                _ => return Err(format!("unknown connectivity: {}", connectivity)),
+            },
+            None if inputs == 0 => Connectivity::Output,
+            None => Connectivity::InputOutput,
+        };
+
+        let template = get_string(&root, "template")?.ok_or("missing `template`")?;
+        let template = template.trim_matches('\n').to_string();
+
+        // Each parameter corresponds to a component of `params[INDEX]`.
+        let declared = match root.get("param") {
+            Some(params) => &params.as_array().ok_or("expected `[[param]]` tables")?[..],
+            None => &[][..],
+        };
+        if declared.len() > constants::PARAMETER_CAPACITY {
+            return Err(format!(
+                "ops can have at most {} parameters",
+                constants::PARAMETER_CAPACITY
+            ));
+        }
+        let mut data = [0.0; constants::PARAMETER_CAPACITY];
+        let mut param_names = Vec::new();
+        let mut min = [0.0; constants::PARAMETER_CAPACITY];
+        let mut max = [0.0; constants::PARAMETER_CAPACITY];
+        let mut step = [0.0; constants::PARAMETER_CAPACITY];
+        for (i, param) in declared.iter().enumerate() {
+            param_names.push(get_string(param, "name")?.ok_or("missing parameter `name`")?);
+            data[i] = get_number(param, "default")?.unwrap_or(0.0);
+            min[i] = get_number(param, "min")?.unwrap_or(0.0);
+            max[i] = get_number(param, "max")?.unwrap_or(0.0);
+            step[i] = get_number(param, "step")?.unwrap_or(0.0);
+        }
+
+        Ok(OpDefinition {
+            name,
+            kind,
+            connectivity,
+            inputs,
+            params: Parameters::new(data, [""; constants::PARAMETER_CAPACITY], 0, min, max, step),
+            param_names,
+            template,
+            shortcut: get_string(&root, "shortcut")?,
+        })
+    }
+
+    /// Returns the op family that corresponds to the op type that was
+    /// registered at `index`, given its `kind`.
+    fn get_family(kind: Kind, index: usize) -> OpFamily {
+        match kind {
+            Kind::Domain => OpFamily::Domain(DomainType::Defined(index)),
+            _ => OpFamily::Primitive(PrimitiveType::Defined(index)),
+        }
+    }
+}
+
+/// Adds `definition` to the op types that can be used in the network,
+/// failing if its name is already taken, and returns its op family.
+pub fn register(mut definition: OpDefinition) -> Result<OpFamily, String> {
+    if OpFamily::from_string(&definition.name).is_some() {
+        return Err(format!("an op named `{}` already exists", definition.name));
+    }
+
+    // The names of an op type and its parameters are shared by every op of
+    // this type for the remainder of the program.
+    let name: &'static str = Box::leak(definition.name.clone().into_boxed_str());
+    let mut names = [""; constants::PARAMETER_CAPACITY];
+    for (i, param_name) in definition.param_names.iter().enumerate() {
+        names[i] = Box::leak(param_name.clone().into_boxed_str());
+    }
+    definition.params = Parameters::new(
+        *definition.params.get_data(),
+        names,
+        0,
+        *definition.params.get_min(),
+        *definition.params.get_max(),
+        *definition.params.get_step(),
+    );
+
+    DEFINITIONS.with(|definitions| {
+        let mut definitions = definitions.borrow_mut();
+        let family = OpDefinition::get_family(definition.kind, definitions.len());
+        definitions.push((name, definition));
+        Ok(family)
+    })
+}
+
+/// Calls `f` with the op type that was registered at `index`.
+pub fn with<F, R>(index: usize, f: F) -> R
+where
+    F: FnOnce(&OpDefinition) -> R,
+{
+    DEFINITIONS.with(|definitions| f(&definitions.borrow()[index].1))
+}
+
+/// Returns the name of the op type that was registered at `index`.
+pub fn get_name(index: usize) -> &'static str {
+    DEFINITIONS.with(|definitions| definitions.borrow()[index].0)
+}
+
+/// Returns the op family of the registered op type named `name`, if any.
+pub fn find(name: &str) -> Option<OpFamily> {
+    DEFINITIONS.with(|definitions| {
+        definitions
+            .borrow()
+            .iter()
+            .position(|&(registered, _)| registered == name)
+            .map(|index| OpDefinition::get_family(definitions.borrow()[index].1.kind, index))
+    })
+}
+
+/// Returns the op family of the registered op type whose shortcut is the
+/// key named `key`, if any.
+pub fn find_by_shortcut(key: &str) -> Option<OpFamily> {
+    DEFINITIONS.with(|definitions| {
+        definitions
+            .borrow()
+            .iter()
+            .enumerate()
+            .find(|&(_, entry)| entry.1.shortcut.as_ref().map_or(false, |s| s == key))
+            .map(|(index, entry)| OpDefinition::get_family(entry.1.kind, index))
+    })
+}
+
+/// Loads and registers the op types defined in each of the data files in
+/// the directory at `path`, skipping (and logging) any that are invalid.
+pub fn load_all(path: &Path) {
+    let mut paths: Vec<_> = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(_) => return,
+    };
+
+    // Register op types in a stable order.
+    paths.sort();
+    for path in paths {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+            continue;
+        }
+
+        let mut text = String::new();
+        let result = File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|error| error.to_string())
+            .and_then(|_| OpDefinition::parse(&text))
+            .and_then(register);
+
+        match result {
+            Ok(family) => console!("Loaded op: {}", family.to_string()),
+            Err(error) => error!("Failed to load op {}: {}", path.display(), error),
+        }
+    }
+}
+
+#[test]
+fn test_parse_op_definition() {
+    let text = "# A capsule.\nname = \"capsule\"\nfamily = \"generator\" # comment\n\
+                template = \"\"\"\nfloat NAME = 1.0;\n\"\"\"\n\n\
+                [[param]]\nname = \"radius\"\ndefault = 0.5\nmax = 2\n";
+    let definition = OpDefinition::parse(text).unwrap();
+    assert_eq!(definition.name, "capsule");
+    assert_eq!(definition.kind, Kind::Generator);
+    assert_eq!(definition.inputs, 1);
+    assert_eq!(definition.template, "float NAME = 1.0;");
+    assert_eq!(definition.param_names, ["radius"]);
+    assert_eq!(definition.params.get_data()[0], 0.5);
+    assert_eq!(definition.params.get_max()[0], 2.0);
+
+    let family = register(definition).unwrap();
+    assert_eq!(family.to_string(), "capsule");
+    assert_eq!(family.get_default_params().get_names()[0], "radius");
+    assert!(family == OpFamily::Primitive(PrimitiveType::Defined(0)));
+    assert!(OpFamily::from_string("capsule") == Some(family));
+
+    // Names can't be reused, and families must be known.
+    assert!(register(OpDefinition::parse(text).unwrap()).is_err());
+    let unknown = text.replace("generator", "shape");
+    assert!(OpDefinition::parse(&unknown).is_err());
+}
+
+#[test]
+fn test_bundled_op_definitions() {
+    use assets;
+
+    for entry in fs::read_dir(assets::get_path(constants::OP_DEFINITIONS_PATH)).unwrap() {
+        let path = entry.unwrap().path();
+        let mut text = String::new();
+        File::open(&path).unwrap().read_to_string(&mut text).unwrap();
+        assert!(OpDefinition::parse(&text).is_ok(), "failed to parse {}", path.display());
+    }
+}
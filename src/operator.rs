@@ -1,7 +1,9 @@
 use bounds::{Edge, Rect};
 use constants;
+use custom::CustomCode;
 use graph::Connected;
 use interaction::InteractionState;
+use op_definition::{self, Kind};
 use renderer::{DrawParams, Drawable};
 
 use cgmath::{Vector2, Vector3, Vector4, Zero};
@@ -11,6 +13,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 
 static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+#[derive(Copy, Clone)]
 pub enum Connectivity {
     InputOutput,
     Input,
@@ -134,6 +137,10 @@ pub enum DomainType {
     Transform,
     Twist,
     Bend,
+
+    /// An op type that was defined in a data file, which refers to its
+    /// index in the registry of op definitions
+    Defined(usize),
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -158,6 +165,11 @@ pub enum PrimitiveType {
     Intersection,
     SmoothMinimum,
     Render,
+    Custom,
+
+    /// An op type that was defined in a data file, which refers to its
+    /// index in the registry of op definitions
+    Defined(usize),
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -186,6 +198,7 @@ impl OpFamily {
                 DomainType::Transform => "transform",
                 DomainType::Twist => "twist",
                 DomainType::Bend => "bend",
+                DomainType::Defined(index) => op_definition::get_name(index),
             },
             OpFamily::Primitive(primitive) => match primitive {
                 PrimitiveType::Sphere => "sphere",
@@ -197,14 +210,51 @@ impl OpFamily {
                 PrimitiveType::Intersection => "intersection",
                 PrimitiveType::SmoothMinimum => "smooth_minimum",
                 PrimitiveType::Render => "render",
+                PrimitiveType::Custom => "custom",
+                PrimitiveType::Defined(index) => op_definition::get_name(index),
             },
         }
     }
 
+    /// Converts a human-readable string (as returned by `to_string()`)
+    /// back into the corresponding op family, if there is one.
+    pub fn from_string(name: &str) -> Option<OpFamily> {
+        match name {
+            "root" => Some(OpFamily::Domain(DomainType::Root)),
+            "transform" => Some(OpFamily::Domain(DomainType::Transform)),
+            "twist" => Some(OpFamily::Domain(DomainType::Twist)),
+            "bend" => Some(OpFamily::Domain(DomainType::Bend)),
+            "sphere" => Some(OpFamily::Primitive(PrimitiveType::Sphere)),
+            "box" => Some(OpFamily::Primitive(PrimitiveType::Box)),
+            "plane" => Some(OpFamily::Primitive(PrimitiveType::Plane)),
+            "torus" => Some(OpFamily::Primitive(PrimitiveType::Torus)),
+            "union" => Some(OpFamily::Primitive(PrimitiveType::Union)),
+            "subtraction" => Some(OpFamily::Primitive(PrimitiveType::Subtraction)),
+            "intersection" => Some(OpFamily::Primitive(PrimitiveType::Intersection)),
+            "smooth_minimum" => Some(OpFamily::Primitive(PrimitiveType::SmoothMinimum)),
+            "render" => Some(OpFamily::Primitive(PrimitiveType::Render)),
+            "custom" => Some(OpFamily::Primitive(PrimitiveType::Custom)),
+            _ => op_definition::find(name),
+        }
+    }
+
+    /// Returns the name of the parent family of this op (i.e. "domain"
+    /// or "primitive").
+    pub fn get_family_name(&self) -> &'static str {
+        match *self {
+            OpFamily::Domain(_) => "domain",
+            OpFamily::Primitive(_) => "primitive",
+        }
+    }
+
     /// Returns an enum that describes the connectivity of this op family
     /// (whether it accepts inputs, outputs, or both).
     pub fn get_connectivity(&self) -> Connectivity {
         match *self {
+            OpFamily::Domain(DomainType::Defined(index))
+            | OpFamily::Primitive(PrimitiveType::Defined(index)) => {
+                op_definition::with(index, |d| d.connectivity)
+            }
             OpFamily::Domain(domain) => match domain {
                 DomainType::Root => Connectivity::Output,
                 _ => Connectivity::InputOutput,
@@ -219,9 +269,13 @@ impl OpFamily {
     /// Returns the maximum number of ops that can be connected to this
     /// op's input slot. Note that there is no equivalent `get_output_capacity`
     /// method, since an op's output slot can be connected to a potentially
-    /// unbounded number of other ops.
+    /// unbounded number of other ops. Custom ops may declare fewer inputs.
     pub fn get_input_capacity(&self) -> usize {
         match *self {
+            OpFamily::Domain(DomainType::Defined(index))
+            | OpFamily::Primitive(PrimitiveType::Defined(index)) => {
+                op_definition::with(index, |d| d.inputs)
+            }
             OpFamily::Domain(domain) => match domain {
                 DomainType::Root => 0,
                 _ => 1,
@@ -230,7 +284,8 @@ impl OpFamily {
                 PrimitiveType::Union
                 | PrimitiveType::Subtraction
                 | PrimitiveType::Intersection
-                | PrimitiveType::SmoothMinimum => 2,
+                | PrimitiveType::SmoothMinimum
+                | PrimitiveType::Custom => 2,
                 _ => 1,
             },
         }
@@ -245,12 +300,38 @@ impl OpFamily {
     /// Returns `true` if this op's output slot can be connected to another
     /// op's input slot and `false` otherwise.
     pub fn has_outputs(&self) -> bool {
+        match self.get_connectivity() {
+            Connectivity::Input => false,
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if this op family produces a distance field from a
+    /// domain (i.e. a sphere) and `false` otherwise.
+    pub fn is_generator(&self) -> bool {
         match *self {
-            OpFamily::Domain(domain) => true,
             OpFamily::Primitive(primitive) => match primitive {
-                PrimitiveType::Render => false,
-                _ => true,
+                PrimitiveType::Sphere
+                | PrimitiveType::Box
+                | PrimitiveType::Plane
+                | PrimitiveType::Torus => true,
+                PrimitiveType::Defined(index) => {
+                    op_definition::with(index, |d| d.kind == Kind::Generator)
+                }
+                _ => false,
             },
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the distance field of an op of this family can be
+    /// baked into a 3D texture (see `Bakes`), which is the case for every
+    /// op that produces one, except for the render operator.
+    pub fn is_bakeable(&self) -> bool {
+        match *self {
+            OpFamily::Primitive(PrimitiveType::Render) => false,
+            OpFamily::Primitive(_) => true,
+            _ => false,
         }
     }
 
@@ -276,6 +357,7 @@ impl OpFamily {
                     float s_NAME = s_INPUT_A;
                     vec3 p_NAME = domain_bend(p_INPUT_A, params[INDEX].x);"
                     .to_string(),
+                DomainType::Defined(index) => op_definition::with(index, |d| d.template.clone()),
             },
             OpFamily::Primitive(primitive) => match primitive {
                 PrimitiveType::Sphere => {
@@ -290,17 +372,33 @@ impl OpFamily {
                 PrimitiveType::Torus => {
                     "float NAME = sdf_torus(p_INPUT_A, vec2(1.0, 0.5)) * s_INPUT_A;".to_string()
                 }
-                PrimitiveType::Union => "float NAME = op_union(INPUT_A, INPUT_B);".to_string(),
-                PrimitiveType::Subtraction => {
-                    "float NAME = op_subtract(INPUT_A, INPUT_B);".to_string()
-                }
-                PrimitiveType::Intersection => {
-                    "float NAME = op_intersect(INPUT_A, INPUT_B);".to_string()
-                }
-                PrimitiveType::SmoothMinimum => {
-                    "float NAME = op_smooth_min(INPUT_A, INPUT_B, params[INDEX].x);".to_string()
-                }
-                PrimitiveType::Render => "float NAME = INPUT_A;".to_string(),
+                // Combinators take the object ID of whichever input
+                // determines the resulting distance.
+                PrimitiveType::Union => "
+                    float NAME = op_union(INPUT_A, INPUT_B);
+                    float id_NAME = INPUT_A < INPUT_B ? id_INPUT_A : id_INPUT_B;"
+                    .to_string(),
+                PrimitiveType::Subtraction => "
+                    float NAME = op_subtract(INPUT_A, INPUT_B);
+                    float id_NAME = -INPUT_A > INPUT_B ? id_INPUT_A : id_INPUT_B;"
+                    .to_string(),
+                PrimitiveType::Intersection => "
+                    float NAME = op_intersect(INPUT_A, INPUT_B);
+                    float id_NAME = INPUT_A > INPUT_B ? id_INPUT_A : id_INPUT_B;"
+                    .to_string(),
+                PrimitiveType::SmoothMinimum => "
+                    float NAME = op_smooth_min(INPUT_A, INPUT_B, params[INDEX].x);
+                    float id_NAME = INPUT_A < INPUT_B ? id_INPUT_A : id_INPUT_B;"
+                    .to_string(),
+                PrimitiveType::Render => "
+                    float NAME = INPUT_A;
+                    float id_NAME = id_INPUT_A;
+                    cone_scale = params[INDEX].x;"
+                    .to_string(),
+                // The code of each custom op is supplied by the user: until
+                // it has been loaded, the op doesn't produce a surface.
+                PrimitiveType::Custom => "float NAME = 1e10;".to_string(),
+                PrimitiveType::Defined(index) => op_definition::with(index, |d| d.template.clone()),
             },
         }
     }
@@ -312,13 +410,8 @@ impl OpFamily {
             // This operator is a domain operator.
             OpFamily::Domain(domain) => match other {
                 OpFamily::Domain(other_domain) => return true,
-                OpFamily::Primitive(other_primitive) => match other_primitive {
-                    PrimitiveType::Sphere
-                    | PrimitiveType::Box
-                    | PrimitiveType::Plane
-                    | PrimitiveType::Torus => return true,
-                    _ => return false,
-                },
+                OpFamily::Primitive(PrimitiveType::Custom) => return true,
+                OpFamily::Primitive(_) => return other.is_generator(),
             },
             // This operator is a primitive operator.
             OpFamily::Primitive(primitive) => match other {
@@ -326,13 +419,7 @@ impl OpFamily {
                 // Generators such as spheres, boxes, planes, and toruses can
                 // only be used as the source operator in primitive -> primitive
                 // interactions.
-                OpFamily::Primitive(other_primitive) => match other_primitive {
-                    PrimitiveType::Sphere
-                    | PrimitiveType::Box
-                    | PrimitiveType::Plane
-                    | PrimitiveType::Torus => return false,
-                    _ => return true,
-                },
+                OpFamily::Primitive(_) => return !other.is_generator(),
             },
         }
     }
@@ -357,6 +444,10 @@ impl OpFamily {
     /// Returns the default parameters for this op family.
     pub fn get_default_params(&self) -> Parameters {
         match *self {
+            OpFamily::Domain(DomainType::Defined(index))
+            | OpFamily::Primitive(PrimitiveType::Defined(index)) => {
+                op_definition::with(index, |d| d.params)
+            }
             OpFamily::Domain(domain) => match domain {
                 DomainType::Transform => Parameters::new(
                     [0.0, 0.0, 0.0, 1.0],
@@ -393,6 +484,14 @@ impl OpFamily {
                     [1.0, 0.0, 0.0, 0.0],
                     [0.1, 0.0, 0.0, 0.0],
                 ),
+                PrimitiveType::Render => Parameters::new(
+                    [0.0, 0.0, 0.0, 0.0],
+                    ["cone_aa", "", "", ""],
+                    0,
+                    [0.0, 0.0, 0.0, 0.0],
+                    [4.0, 0.0, 0.0, 0.0],
+                    [0.25, 0.0, 0.0, 0.0],
+                ),
                 _ => Parameters::default(),
             },
         }
@@ -429,6 +528,13 @@ pub struct Op {
 
     /// This op's parameters, which may or may not be used by the shader
     pub params: Parameters,
+
+    /// The user-supplied code of this op, if it is a custom op
+    pub custom: Option<CustomCode>,
+
+    /// Whether the distance field of the sub-graph that ends at this op is
+    /// baked into a 3D texture, which replaces its code (see `Bakes`)
+    pub baked: bool,
 }
 
 impl Op {
@@ -461,6 +567,39 @@ impl Op {
             name,
             family,
             params: family.get_default_params(),
+            custom: None,
+            baked: false,
+        }
+    }
+
+    /// Replaces the code of this (custom) op with `custom`. The values of
+    /// the op's parameters are kept, unless the new code declares different
+    /// parameters, in which case they are reset to their defaults.
+    pub fn set_custom(&mut self, custom: CustomCode) {
+        let mut params = custom.get_default_params();
+        params.set_index(self.params.get_index());
+        if params.get_names() == self.params.get_names() {
+            *params.get_data_mut() = *self.params.get_data();
+        }
+        self.params = params;
+        self.custom = Some(custom);
+    }
+
+    /// Returns the maximum number of ops that can be connected to this
+    /// op's input slot, which for custom ops is declared by their code.
+    pub fn get_input_capacity(&self) -> usize {
+        match self.custom {
+            Some(ref custom) => custom.inputs,
+            None => self.family.get_input_capacity(),
+        }
+    }
+
+    /// Returns the formattable string of shader code that corresponds to
+    /// this op, which for custom ops is supplied by the user.
+    pub fn get_code_template(&self) -> String {
+        match self.custom {
+            Some(ref custom) => custom.template.clone(),
+            None => self.family.get_code_template(),
         }
     }
 
@@ -478,8 +617,14 @@ impl Op {
     /// this op after it has been connected to `input_a` and
     /// `input_b` (both of which are optional).
     pub fn get_code(&self, input_a: Option<&str>, input_b: Option<&str>) -> String {
-        let mut code = self.family.get_code_template();
-        code = code.replace("NAME", &self.name);
+        self.get_code_as(&self.name, input_a, input_b)
+    }
+
+    /// Like `get_code`, but with `name` in place of this op's name,
+    /// which is used to compare the code that two ops would generate.
+    pub fn get_code_as(&self, name: &str, input_a: Option<&str>, input_b: Option<&str>) -> String {
+        let mut code = self.get_code_template();
+        code = code.replace("NAME", name);
 
         code = code.replace("INDEX", &self.params.index.to_string());
 
@@ -505,7 +650,7 @@ impl Op {
 
 impl Connected for Op {
     fn has_inputs(&self) -> bool {
-        self.family.has_inputs()
+        self.get_input_capacity() > 0
     }
 
     fn has_outputs(&self) -> bool {
@@ -513,7 +658,7 @@ impl Connected for Op {
     }
 
     fn get_number_of_available_inputs(&self) -> usize {
-        self.family.get_input_capacity() - self.active_inputs
+        self.get_input_capacity().saturating_sub(self.active_inputs)
     }
 
     fn update_active_inputs_count(&mut self, count: usize) {
@@ -0,0 +1,82 @@
+use constants;
+
+use std::time::Duration;
+
+/// Options that control how often the main window is redrawn, which are
+/// parsed from the command line (see `cli::Options`). While nothing is
+/// changing, the window isn't redrawn at all, regardless of these.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FrameSettings {
+    /// Whether swapping buffers waits for the display to refresh
+    /// (`--vsync=<on|off>`), which can only be chosen when the window is
+    /// created
+    pub vsync: bool,
+
+    /// The most frames that are drawn per second, or `None` if there isn't
+    /// a limit (`--fps-cap=<n>`, where `0` removes the limit)
+    pub fps_cap: Option<u32>,
+}
+
+impl FrameSettings {
+    pub fn new() -> FrameSettings {
+        FrameSettings {
+            vsync: constants::WINDOW_VSYNC,
+            fps_cap: Some(constants::WINDOW_FPS_CAP),
+        }
+    }
+
+    /// Sets the frame rate limit from a number of frames per second, where
+    /// `0` removes the limit.
+    pub fn set_fps_cap(&mut self, fps: u32) {
+        self.fps_cap = if fps == 0 { None } else { Some(fps) };
+    }
+
+    /// Switches to the next of the frame rate limits in `WINDOW_FPS_CAPS`.
+    pub fn cycle_fps_cap(&mut self) {
+        let caps = constants::WINDOW_FPS_CAPS;
+        let current = self.fps_cap.unwrap_or(0);
+        let next = match caps.iter().position(|&cap| cap == current) {
+            Some(index) => caps[(index + 1) % caps.len()],
+            None => caps[0],
+        };
+        self.set_fps_cap(next);
+        console!("Frame rate limit: {}", self.get_description());
+    }
+
+    /// Returns the shortest time that each frame should take, or `None` if
+    /// frames should be drawn as fast as possible.
+    pub fn get_frame_interval(&self) -> Option<Duration> {
+        self.fps_cap.map(|fps| Duration::from_secs(1) / fps.max(1))
+    }
+
+    /// Returns a short description of the settings for the status bar
+    /// (i.e. `cap 60, vsync`).
+    pub fn get_description(&self) -> String {
+        let cap = match self.fps_cap {
+            Some(fps) => format!("cap {}", fps),
+            None => "uncapped".to_string(),
+        };
+        if self.vsync {
+            format!("{}, vsync", cap)
+        } else {
+            cap
+        }
+    }
+}
+
+#[test]
+fn test_fps_cap() {
+    let mut settings = FrameSettings::new();
+    settings.set_fps_cap(50);
+    assert_eq!(settings.get_frame_interval(), Some(Duration::from_millis(20)));
+
+    settings.set_fps_cap(0);
+    assert_eq!(settings.get_frame_interval(), None);
+    settings.vsync = false;
+    assert_eq!(settings.get_description(), "uncapped");
+
+    // Limits that aren't among the presets start over at the first one.
+    settings.set_fps_cap(75);
+    settings.cycle_fps_cap();
+    assert_eq!(settings.fps_cap, Some(constants::WINDOW_FPS_CAPS[0]));
+}
@@ -0,0 +1,213 @@
+use gl::{self, types::*};
+
+use constants;
+use profile::Profile;
+
+use std::mem;
+use std::ptr;
+use std::os::raw::c_void;
+
+/// Rounds `size` up to the next multiple of `alignment`.
+fn align(size: usize, alignment: usize) -> usize {
+    let alignment = alignment.max(1);
+    (size + alignment - 1) / alignment * alignment
+}
+
+/// The buffer that holds all of the op parameters, which is an SSBO (or a
+/// UBO, depending on the profile).
+///
+/// With OpenGL 4.5, the buffer is split into `PARAMETER_RING_REGIONS`
+/// regions and mapped once, persistently and coherently, so that new
+/// parameters are copied straight into memory that the GPU can see. Each
+/// update moves on to the next region, so the CPU never writes to a region
+/// that a frame in flight might still be reading: a fence is placed behind
+/// the draw calls that used a region when the ring moves past it, and the
+/// ring only waits on that fence when it comes back around. Older contexts
+/// upload the parameters that changed with `glBufferSubData` instead.
+pub struct ParamsBuffer {
+    /// The OpenGL handle to the buffer
+    buffer: GLuint,
+
+    /// The target that the buffer is bound to
+    target: GLenum,
+
+    /// The size of each region, in bytes
+    region_size: usize,
+
+    /// The start of the persistent mapping, or null if the buffer isn't
+    /// mapped (in which case it has a single region)
+    mapping: *mut f32,
+
+    /// The region that holds the current parameters
+    region: usize,
+
+    /// For each region, a fence that is signaled once the GPU has finished
+    /// the commands that read from it (if any are outstanding)
+    fences: Vec<Option<GLsync>>,
+}
+
+impl ParamsBuffer {
+    pub fn new(profile: Profile) -> ParamsBuffer {
+        let capacity = constants::PARAMETER_SSBO_CAPACITY * constants::PARAMETER_CAPACITY;
+        let target = profile.get_params_target();
+
+        let mut buffer = 0;
+        let mut region_size = capacity * mem::size_of::<f32>();
+        let mut mapping = ptr::null_mut();
+        let mut regions = 1;
+        unsafe {
+            if profile.supports_persistent_mapping() {
+                // Every region has to start at an offset that the buffer can
+                // be bound at.
+                let mut alignment = 0;
+                gl::GetIntegerv(gl::SHADER_STORAGE_BUFFER_OFFSET_ALIGNMENT, &mut alignment);
+                region_size = align(region_size, alignment as usize);
+                regions = constants::PARAMETER_RING_REGIONS;
+
+                let size = (region_size * regions) as GLsizeiptr;
+                let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+                gl::CreateBuffers(1, &mut buffer);
+                gl::NamedBufferStorage(buffer, size, ptr::null(), flags);
+                mapping = gl::MapNamedBufferRange(buffer, 0, size, flags) as *mut f32;
+                if mapping.is_null() {
+                    warn!("Failed to map the parameter buffer: falling back to uploads");
+                    gl::DeleteBuffers(1, &buffer);
+                    region_size = capacity * mem::size_of::<f32>();
+                    regions = 1;
+                }
+            }
+
+            if mapping.is_null() {
+                let size = region_size as GLsizeiptr;
+                if profile.uses_storage_buffer() {
+                    gl::CreateBuffers(1, &mut buffer);
+                    gl::NamedBufferStorage(buffer, size, ptr::null(), gl::DYNAMIC_STORAGE_BIT);
+                } else {
+                    // Direct state access requires OpenGL 4.5.
+                    gl::GenBuffers(1, &mut buffer);
+                    gl::BindBuffer(gl::UNIFORM_BUFFER, buffer);
+                    gl::BufferData(gl::UNIFORM_BUFFER, size, ptr::null(), gl::DYNAMIC_DRAW);
+                    gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+                }
+            }
+        }
+
+        ParamsBuffer {
+            buffer,
+            target,
+            region_size,
+            mapping,
+            region: 0,
+            fences: vec![None; regions],
+        }
+    }
+
+    /// Returns `true` if the buffer is persistently mapped.
+    pub fn is_mapped(&self) -> bool {
+        !self.mapping.is_null()
+    }
+
+    /// Writes the op parameters in `data` to the buffer. A mapped buffer
+    /// moves on to its next region and receives all of `data`, since that
+    /// region still holds the parameters from a few updates ago: otherwise,
+    /// only the floats in `range` are uploaded.
+    pub fn write(&mut self, data: &[f32], range: (usize, usize)) {
+        let capacity = self.region_size / mem::size_of::<f32>();
+        if data.len() > capacity {
+            warn!(
+                "Too many op parameters ({}): only the first {} are used",
+                data.len(),
+                capacity
+            );
+        }
+
+        if self.is_mapped() {
+            self.advance();
+            let count = data.len().min(capacity);
+            unsafe {
+                let region = self.mapping.add(self.region * capacity);
+                ptr::copy_nonoverlapping(data.as_ptr(), region, count);
+            }
+            return;
+        }
+
+        let (start, end) = (range.0.min(capacity), range.1.min(capacity));
+        if start >= end {
+            return;
+        }
+        unsafe {
+            let offset = (start * mem::size_of::<f32>()) as GLintptr;
+            let data_size = ((end - start) * mem::size_of::<f32>()) as GLsizeiptr;
+            let data_ptr = data[start..end].as_ptr() as *const c_void;
+            if self.target == gl::SHADER_STORAGE_BUFFER {
+                gl::NamedBufferSubData(self.buffer, offset, data_size, data_ptr);
+            } else {
+                gl::BindBuffer(gl::UNIFORM_BUFFER, self.buffer);
+                gl::BufferSubData(gl::UNIFORM_BUFFER, offset, data_size, data_ptr);
+                gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            }
+        }
+    }
+
+    /// Fences off the current region, which covers every command that has
+    /// been issued so far (and so every draw call that read from it), then
+    /// moves on to the next region and waits until the GPU is done with it.
+    fn advance(&mut self) {
+        unsafe {
+            if let Some(fence) = self.fences[self.region].take() {
+                gl::DeleteSync(fence);
+            }
+            self.fences[self.region] = Some(gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+
+            self.region = (self.region + 1) % self.fences.len();
+            if let Some(fence) = self.fences[self.region].take() {
+                loop {
+                    let status = gl::ClientWaitSync(
+                        fence,
+                        gl::SYNC_FLUSH_COMMANDS_BIT,
+                        constants::PARAMETER_FENCE_TIMEOUT,
+                    );
+                    if status != gl::TIMEOUT_EXPIRED {
+                        break;
+                    }
+                }
+                gl::DeleteSync(fence);
+            }
+        }
+    }
+
+    /// Binds the region that holds the current parameters to binding
+    /// point `0`.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindBufferRange(
+                self.target,
+                0,
+                self.buffer,
+                (self.region * self.region_size) as GLintptr,
+                self.region_size as GLsizeiptr,
+            );
+        }
+    }
+}
+
+impl Drop for ParamsBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            for fence in self.fences.iter().filter_map(|fence| *fence) {
+                gl::DeleteSync(fence);
+            }
+            if self.is_mapped() {
+                gl::UnmapNamedBuffer(self.buffer);
+            }
+            gl::DeleteBuffers(1, &self.buffer);
+        }
+    }
+}
+
+#[test]
+fn test_align() {
+    assert_eq!(align(4096, 256), 4096);
+    assert_eq!(align(4100, 256), 4352);
+    assert_eq!(align(12, 0), 12);
+}
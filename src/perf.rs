@@ -0,0 +1,259 @@
+use gl;
+use gl::types::*;
+
+use constants;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Converts `duration` to (fractional) milliseconds.
+pub fn to_milliseconds(duration: &Duration) -> f32 {
+    duration.as_secs() as f32 * 1000.0 + duration.subsec_nanos() as f32 / 1_000_000.0
+}
+
+/// Measures how long the GPU takes to execute the commands that are issued
+/// between `begin` and `end`, with `GL_TIME_ELAPSED` queries. The results of
+/// a query only become available a frame or two later, so the timer cycles
+/// through several of them and never waits for one to finish. Timers can't
+/// be nested.
+pub struct GpuTimer {
+    /// The OpenGL handles to the queries that aren't in use
+    free: Vec<GLuint>,
+
+    /// The queries that are waiting for their results, oldest first
+    pending: VecDeque<GLuint>,
+
+    /// The query that is currently measuring (if there is one)
+    active: Option<GLuint>,
+}
+
+impl GpuTimer {
+    pub fn new() -> GpuTimer {
+        let mut free = vec![0; constants::PERF_QUERY_COUNT];
+        unsafe {
+            gl::CreateQueries(gl::TIME_ELAPSED, free.len() as GLsizei, free.as_mut_ptr());
+        }
+
+        GpuTimer {
+            free,
+            pending: VecDeque::new(),
+            active: None,
+        }
+    }
+
+    /// Starts measuring, unless all of the queries are still waiting for
+    /// their results (in which case this measurement is skipped and `false`
+    /// is returned).
+    pub fn begin(&mut self) -> bool {
+        if let Some(query) = self.free.pop() {
+            unsafe {
+                gl::BeginQuery(gl::TIME_ELAPSED, query);
+            }
+            self.active = Some(query);
+            return true;
+        }
+        false
+    }
+
+    /// Stops measuring.
+    pub fn end(&mut self) {
+        if let Some(query) = self.active.take() {
+            unsafe {
+                gl::EndQuery(gl::TIME_ELAPSED);
+            }
+            self.pending.push_back(query);
+        }
+    }
+
+    /// Returns the durations of the measurements that have finished since
+    /// the last call, in milliseconds and oldest first.
+    pub fn poll(&mut self) -> Vec<f32> {
+        self.collect(false)
+    }
+
+    /// Like `poll`, but waits for the measurements that haven't finished
+    /// yet rather than skipping them, which stalls the CPU.
+    pub fn finish(&mut self) -> Vec<f32> {
+        self.collect(true)
+    }
+
+    fn collect(&mut self, wait: bool) -> Vec<f32> {
+        let mut results = Vec::new();
+        while let Some(&query) = self.pending.front() {
+            let mut available = 0;
+            let mut nanoseconds = 0;
+            unsafe {
+                gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+                if available == 0 && !wait {
+                    break;
+                }
+                gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut nanoseconds);
+            }
+            results.push(nanoseconds as f32 / 1_000_000.0);
+            self.free.push(self.pending.pop_front().unwrap());
+        }
+        results
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        let queries: Vec<GLuint> = self.free
+            .iter()
+            .chain(self.pending.iter())
+            .chain(self.active.iter())
+            .cloned()
+            .collect();
+        unsafe {
+            gl::DeleteQueries(queries.len() as GLsizei, queries.as_ptr());
+        }
+    }
+}
+
+/// The most recent measurements of a single timing, in milliseconds.
+pub struct History {
+    samples: VecDeque<f32>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            samples: VecDeque::with_capacity(constants::PERF_HISTORY_LENGTH),
+        }
+    }
+
+    /// Adds a new measurement, dropping the oldest one if the history is
+    /// full.
+    pub fn push(&mut self, milliseconds: f32) {
+        if self.samples.len() == constants::PERF_HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(milliseconds);
+    }
+
+    /// Returns the measurements, oldest first.
+    pub fn get_samples(&self) -> &VecDeque<f32> {
+        &self.samples
+    }
+
+    /// Returns the average of the measurements, or `0.0` if there aren't
+    /// any.
+    pub fn get_average(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+
+    /// Returns the largest measurement, or `0.0` if there aren't any.
+    pub fn get_max(&self) -> f32 {
+        self.samples.iter().cloned().fold(0.0, f32::max)
+    }
+}
+
+/// How long each frame takes on the CPU, and how long the GPU takes to
+/// raymarch the preview and draw the rest of the interface, which are
+/// shown in the performance overlay.
+pub struct Performance {
+    /// The time spent on the CPU handling events, building shaders, and
+    /// issuing draw calls, excluding any time spent waiting for the next
+    /// frame
+    pub cpu: History,
+
+    /// The time that the GPU spends rendering the preview (which is zero
+    /// on frames where the previous render could be reused)
+    pub preview: History,
+
+    /// The time that the GPU spends drawing the network editor and panels
+    pub interface: History,
+
+    /// The timer around the preview's render pass
+    pub preview_timer: GpuTimer,
+
+    /// The timer around the network editor's draw calls
+    pub interface_timer: GpuTimer,
+
+    /// The number of pixels that the preview raymarched during each of the
+    /// measurements of `preview_timer` that haven't finished yet, oldest
+    /// first
+    preview_pixels: VecDeque<u32>,
+
+    /// A smoothed estimate of how long the GPU takes to raymarch a million
+    /// pixels of the preview, in milliseconds (if it has been measured)
+    preview_cost: Option<f32>,
+}
+
+impl Performance {
+    pub fn new() -> Performance {
+        Performance {
+            cpu: History::new(),
+            preview: History::new(),
+            interface: History::new(),
+            preview_timer: GpuTimer::new(),
+            interface_timer: GpuTimer::new(),
+            preview_pixels: VecDeque::new(),
+            preview_cost: None,
+        }
+    }
+
+    /// Starts measuring the preview's render pass.
+    pub fn begin_preview(&mut self) {
+        if self.preview_timer.begin() {
+            self.preview_pixels.push_back(0);
+        }
+    }
+
+    /// Stops measuring the preview's render pass, during which `pixels`
+    /// pixels were raymarched.
+    pub fn end_preview(&mut self, pixels: u32) {
+        self.preview_timer.end();
+        if let Some(last) = self.preview_pixels.back_mut() {
+            *last = pixels;
+        }
+    }
+
+    /// Returns how long the GPU takes to raymarch a million pixels of the
+    /// preview, in milliseconds, if it has been measured yet.
+    pub fn get_preview_cost(&self) -> Option<f32> {
+        self.preview_cost
+    }
+
+    /// Records how long the CPU spent on the last frame.
+    pub fn record_frame(&mut self, duration: Duration) {
+        self.cpu.push(to_milliseconds(&duration));
+    }
+
+    /// Collects the results of any GPU measurements that have finished.
+    pub fn update(&mut self) {
+        for milliseconds in self.preview_timer.poll() {
+            self.preview.push(milliseconds);
+
+            // Frames where the previous image was reused say nothing about
+            // the cost of raymarching it.
+            let pixels = self.preview_pixels.pop_front().unwrap_or(0);
+            if pixels > 0 {
+                let cost = milliseconds * 1_000_000.0 / pixels as f32;
+                self.preview_cost = Some(match self.preview_cost {
+                    Some(previous) => previous + (cost - previous) * constants::PERF_COST_SMOOTHING,
+                    None => cost,
+                });
+            }
+        }
+        for milliseconds in self.interface_timer.poll() {
+            self.interface.push(milliseconds);
+        }
+    }
+}
+
+#[test]
+fn test_history() {
+    let mut history = History::new();
+    assert_eq!(history.get_average(), 0.0);
+
+    for i in 0..constants::PERF_HISTORY_LENGTH + 2 {
+        history.push(i as f32);
+    }
+    assert_eq!(history.get_samples().len(), constants::PERF_HISTORY_LENGTH);
+    assert_eq!(history.get_samples()[0], 2.0);
+    assert_eq!(history.get_max(), (constants::PERF_HISTORY_LENGTH + 1) as f32);
+}
@@ -0,0 +1,251 @@
+use assets;
+use constants;
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// GLSL source code whose `#include "<file>"` directives have been
+/// replaced with the contents of the included files, along with the file
+/// and line that each line of the expanded code came from, so that errors
+/// reported by the driver can be traced back to the file that caused them.
+pub struct Source {
+    /// The expanded source code, where every line ends with a newline
+    pub code: String,
+
+    /// The name of the file and (1-based) line within that file that
+    /// each line of `code` came from
+    origins: Vec<(String, usize)>,
+}
+
+impl Source {
+    /// Returns the number of lines in the expanded source code.
+    pub fn line_count(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// Returns the name of the file and the line within that file that
+    /// the (1-based) `line` of the expanded source code came from.
+    pub fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        if line == 0 {
+            return None;
+        }
+        self.origins
+            .get(line - 1)
+            .map(|&(ref name, line)| (&name[..], line))
+    }
+
+    /// Returns a copy of the source code with only the lines whose
+    /// (0-based) indices satisfy `keep`, which remember where they came from.
+    pub fn retain<F>(&self, keep: F) -> Source
+    where
+        F: Fn(usize) -> bool,
+    {
+        let mut source = Source {
+            code: String::new(),
+            origins: Vec::new(),
+        };
+        for (index, (line, origin)) in self.code.lines().zip(self.origins.iter()).enumerate() {
+            if keep(index) {
+                source.code.push_str(line);
+                source.code.push('\n');
+                source.origins.push(origin.clone());
+            }
+        }
+        source
+    }
+}
+
+/// The shaders that ship with the application, which are built into the
+/// binary so that it still runs if the shader directory is missing.
+static BUILTIN_SHADERS: &[(&str, &str)] = &[
+    ("bake.glsl", include_str!("../assets/shaders/bake.glsl")),
+    ("camera.glsl", include_str!("../assets/shaders/camera.glsl")),
+    ("grid_footer.glsl", include_str!("../assets/shaders/grid_footer.glsl")),
+    ("grid_header.glsl", include_str!("../assets/shaders/grid_header.glsl")),
+    ("map_header.glsl", include_str!("../assets/shaders/map_header.glsl")),
+    ("params_storage.glsl", include_str!("../assets/shaders/params_storage.glsl")),
+    ("params_uniform.glsl", include_str!("../assets/shaders/params_uniform.glsl")),
+    ("preview_footer.glsl", include_str!("../assets/shaders/preview_footer.glsl")),
+    ("preview_header.glsl", include_str!("../assets/shaders/preview_header.glsl")),
+    ("random.glsl", include_str!("../assets/shaders/random.glsl")),
+    ("raymarch.glsl", include_str!("../assets/shaders/raymarch.glsl")),
+    ("rounded_rectangle.glsl", include_str!("../assets/shaders/rounded_rectangle.glsl")),
+    ("sdf.glsl", include_str!("../assets/shaders/sdf.glsl")),
+    ("shading.glsl", include_str!("../assets/shaders/shading.glsl")),
+    ("shadertoy_footer.glsl", include_str!("../assets/shaders/shadertoy_footer.glsl")),
+    ("step_image.glsl", include_str!("../assets/shaders/step_image.glsl")),
+    ("steps.glsl", include_str!("../assets/shaders/steps.glsl")),
+];
+
+/// Returns the built-in copy of the shader called `name`.
+fn read_builtin(name: &str) -> io::Result<String> {
+    BUILTIN_SHADERS
+        .iter()
+        .find(|&&(builtin, _)| builtin == name)
+        .map(|&(_, src)| src.to_string())
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("no shader called \"{}\"", name))
+        })
+}
+
+/// Reads the shader called `name`, preferring the user's override (if
+/// there is one) over the copy in the shader directory, and falling back
+/// to the built-in copy if neither exists.
+fn read_shader(name: &str) -> io::Result<String> {
+    let directories = [
+        PathBuf::from(constants::SHADER_OVERRIDE_PATH),
+        assets::get_path(constants::SHADER_PATH),
+    ];
+    for (index, directory) in directories.iter().enumerate() {
+        let path = directory.join(name);
+        if path.is_file() {
+            if index == 0 {
+                console!("Using the override of {} at {}", name, path.display());
+            }
+            let mut src = String::new();
+            File::open(path)?.read_to_string(&mut src)?;
+            return Ok(src);
+        }
+    }
+    read_builtin(name)
+}
+
+/// Returns the name of the file referenced by `line`, if it is an
+/// `#include "<file>"` directive.
+fn parse_include(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if !line.starts_with("#include") {
+        return None;
+    }
+    let name = line["#include".len()..].trim();
+    if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
+        Some(&name[1..name.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Loads the shader called `name` (see `read_shader`) and expands any
+/// files that it includes, which are looked up in the same way.
+pub fn load(name: &str) -> Result<Source, String> {
+    let src = read_shader(name).map_err(|error| format!("Failed to load {}: {}", name, error))?;
+    process(name, &src)
+}
+
+/// Expands any files included by `src`, which is the source code of a
+/// shader that is referred to as `name` in error messages.
+pub fn process(name: &str, src: &str) -> Result<Source, String> {
+    process_with(name, src, &read_shader)
+}
+
+/// Like `process`, but includes files by calling `read` with their names.
+pub fn process_with<F>(name: &str, src: &str, read: &F) -> Result<Source, String>
+where
+    F: Fn(&str) -> io::Result<String>,
+{
+    let mut source = Source {
+        code: String::new(),
+        origins: Vec::new(),
+    };
+    let mut stack = vec![name.to_string()];
+    expand(src, &mut stack, read, &mut source)?;
+    Ok(source)
+}
+
+/// Appends `src`, which is the contents of the file at the top of `stack`,
+/// to `source`, recursively expanding any files that it includes. The
+/// stack holds the chain of files that are currently being included,
+/// which is used to detect cycles.
+fn expand<F>(
+    src: &str,
+    stack: &mut Vec<String>,
+    read: &F,
+    source: &mut Source,
+) -> Result<(), String>
+where
+    F: Fn(&str) -> io::Result<String>,
+{
+    let name = stack.last().unwrap().clone();
+
+    for (index, line) in src.lines().enumerate() {
+        let location = format!("{}:{}", name, index + 1);
+
+        match parse_include(line) {
+            Some(included) => {
+                if stack.iter().any(|entry| entry == included) {
+                    return Err(format!(
+                        "{}: circular include of \"{}\" ({} -> {})",
+                        location,
+                        included,
+                        stack.join(" -> "),
+                        included
+                    ));
+                }
+
+                let included_src = read(included).map_err(|error| {
+                    format!("{}: failed to include \"{}\": {}", location, included, error)
+                })?;
+
+                stack.push(included.to_string());
+                expand(&included_src, stack, read, source)?;
+                stack.pop();
+            }
+            None => {
+                if line.trim().starts_with("#include") {
+                    return Err(format!("{}: malformed include directive", location));
+                }
+                source.code.push_str(line);
+                source.code.push('\n');
+                source.origins.push((name.clone(), index + 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_process_includes() {
+    let read = |name: &str| match name {
+        "a.glsl" => Ok("float a;\n#include \"b.glsl\"\n".to_string()),
+        "b.glsl" => Ok("float b;".to_string()),
+        "cycle.glsl" => Ok("#include \"main.glsl\"".to_string()),
+        _ => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+    };
+
+    let src = "#version 430\n#include \"a.glsl\"\nvoid main() {}";
+    let source = process_with("main.glsl", src, &read).unwrap();
+    assert_eq!(source.code, "#version 430\nfloat a;\nfloat b;\nvoid main() {}\n");
+    assert_eq!(source.line_count(), 4);
+    assert_eq!(source.locate(3), Some(("b.glsl", 1)));
+    assert_eq!(source.locate(4), Some(("main.glsl", 3)));
+
+    let cycle = process_with("main.glsl", "#include \"cycle.glsl\"", &read);
+    assert!(cycle.err().unwrap().contains("circular include"));
+
+    let missing = process_with("main.glsl", "\n#include \"missing.glsl\"", &read);
+    assert!(missing.err().unwrap().starts_with("main.glsl:2: failed to include"));
+}
+
+#[test]
+fn test_builtin_shaders() {
+    use profile::Profile;
+    use shader_builder::ShaderBuilder;
+    use steps;
+
+    for &(name, src) in BUILTIN_SHADERS.iter() {
+        assert!(process_with(name, src, &read_builtin).is_ok(), "{}", name);
+    }
+
+    // Shaders that are only included by code that is assembled at runtime
+    // have to be built in as well.
+    for &name in ["330", "410", "430", "450"].iter() {
+        let profile = Profile::from_name(name).unwrap();
+        for &header in ["preview_header.glsl", "grid_header.glsl"].iter() {
+            let prelude = ShaderBuilder::get_prelude(profile, header);
+            assert!(process_with("prelude", &prelude, &read_builtin).is_ok(), "{}", name);
+        }
+    }
+    let prelude = steps::get_prelude(Profile::Glsl430);
+    assert!(process_with("prelude", &prelude, &read_builtin).is_ok());
+}
@@ -0,0 +1,136 @@
+use serde_json::{self, Value};
+
+use project::Snippet;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// A small, reusable group of connected ops that can be dropped into
+/// the network. Presets are stored on disk in the same JSON format as
+/// snippets (see `Snippet`), with the name of the preset added:
+///
+/// ```text
+/// {
+///   "name": "smooth blended blob",
+///   "version": 2,
+///   "ops": [...],
+///   "connections": [...],
+///   "sources": {}
+/// }
+/// ```
+pub struct Preset {
+    /// The name of the preset as it will appear in the library panel
+    pub name: String,
+
+    /// The ops that make up this preset, along with their connections
+    pub snippet: Snippet,
+}
+
+impl Preset {
+    /// Returns the name of the file that holds the preset named `name`.
+    pub fn get_file_name(name: &str) -> String {
+        format!("{}.preset", name.replace(' ', "_"))
+    }
+
+    /// Parses a preset from `text`, returning an error if it isn't one.
+    pub fn parse(text: &str) -> Result<Preset, String> {
+        let value: Value = serde_json::from_str(text).map_err(|error| error.to_string())?;
+        let name = value
+            .get("name")
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| "expected a string: name".to_string())?;
+
+        Ok(Preset {
+            name: name.to_string(),
+            snippet: Snippet::from_json(&value)?,
+        })
+    }
+
+    /// Converts the preset into the JSON format described above.
+    pub fn serialize(&self) -> String {
+        let mut value = self.snippet.to_json();
+        value["name"] = Value::from(self.name.clone());
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    }
+
+    /// Loads all of the presets in the directory at `path`, skipping
+    /// (and logging) any files that fail to parse.
+    pub fn load_all(path: &Path) -> Vec<Preset> {
+        let mut presets = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("preset") {
+                    continue;
+                }
+
+                let mut text = String::new();
+                let parsed = File::open(&path)
+                    .and_then(|mut file| file.read_to_string(&mut text))
+                    .map_err(|error| error.to_string())
+                    .and_then(|_| Preset::parse(&text));
+
+                match parsed {
+                    Ok(preset) => presets.push(preset),
+                    Err(error) => error!("Failed to load preset {}: {}", path.display(), error),
+                }
+            }
+        }
+
+        // Keep the library panel in a stable order.
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        presets
+    }
+
+    /// Saves the preset to a file named after the preset (see
+    /// `get_file_name`) inside of the directory at `path`, without
+    /// overwriting an existing file. Returns the path of the file.
+    pub fn save(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(path)?;
+        let path = path.join(Preset::get_file_name(&self.name));
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        file.write_all(self.serialize().as_bytes())?;
+        Ok(path)
+    }
+}
+
+#[test]
+fn test_preset_round_trip() {
+    use assets;
+    use cgmath::Vector2;
+    use constants;
+    use operator::{OpFamily, PrimitiveType};
+    use project::ProjectOp;
+
+    let preset = Preset {
+        name: "smooth blob".to_string(),
+        snippet: Snippet {
+            ops: vec![ProjectOp {
+                name: "sphere_0".to_string(),
+                family: OpFamily::Primitive(PrimitiveType::Sphere),
+                position: Vector2::new(140.0, -20.0),
+                params: [0.5, 0.0, 0.0, 1.0],
+                code: None,
+                baked: false,
+            }],
+            connections: Vec::new(),
+            sources: Vec::new(),
+        },
+    };
+    assert_eq!(Preset::get_file_name(&preset.name), "smooth_blob.preset");
+
+    let parsed = Preset::parse(&preset.serialize()).unwrap();
+    assert_eq!(parsed.name, preset.name);
+    assert_eq!(parsed.snippet.ops.len(), 1);
+    assert_eq!(parsed.snippet.ops[0].params, [0.5, 0.0, 0.0, 1.0]);
+
+    // Snippets without a name aren't presets.
+    assert!(Preset::parse(&preset.snippet.serialize()).is_err());
+
+    // The presets that ship with the application can all be loaded.
+    let path = assets::get_path(constants::PRESETS_PATH);
+    let count = fs::read_dir(&path).unwrap().count();
+    assert_eq!(Preset::load_all(&path).len(), count);
+}
@@ -1,18 +1,74 @@
 use gl::{self, types::*};
-use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector2, Vector3, Vector4,
-             Zero};
+use cgmath::{self, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Vector2, Vector3,
+             Vector4, Zero};
 
+use benchmark::{FrameTiming, Report};
 use bounds::Rect;
+use capture::{CaptureMode, CaptureSettings};
+use cli::Benchmark;
 use color::Color;
 use constants;
+use error::Error;
+use framebuffer::Framebuffer;
 use interaction::{MouseInfo, Panel};
+use params_buffer::ParamsBuffer;
+use perf::{self, GpuTimer};
+use profile::Profile;
 use program::Program;
+use renderer::{Region, Renderer};
+use shadertoy::{self, Snapshot};
+use steps::{StepHistogram, StepStatistics};
+use texture::Texture;
 
 use std::mem;
+use std::path::Path;
 use std::ptr;
 use std::os::raw::c_void;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+/// The information gathered along a single ray that was cast
+/// through the preview.
 #[derive(Copy, Clone)]
+pub struct Probe {
+    /// The point where the ray hit the surface
+    pub position: Vector3<f32>,
+
+    /// The distance travelled along the ray before it hit the surface
+    pub distance: f32,
+
+    /// The surface normal at `position`
+    pub normal: Vector3<f32>,
+
+    /// The number of steps that the raymarcher took
+    pub steps: u32,
+
+    /// Whether or not the ray hit anything
+    pub hit: bool,
+}
+
+impl Probe {
+    /// Returns a human-readable description of the probe, one
+    /// measurement per line.
+    pub fn get_text(&self) -> String {
+        if !self.hit {
+            return format!("no hit\nsteps: {}", self.steps);
+        }
+        format!(
+            "position: ({:.3}, {:.3}, {:.3})\ndistance: {:.3}\n\
+             normal: ({:.3}, {:.3}, {:.3})\nsteps: {}",
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.distance,
+            self.normal.x,
+            self.normal.y,
+            self.normal.z,
+            self.steps
+        )
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
 pub enum Shading {
     /// Display the z-depth of each fragment
     Depth,
@@ -28,6 +84,223 @@ pub enum Shading {
 
     /// Display the scene with diffuse lighting
     Diffuse,
+
+    /// Display the scene with progressive, path-traced lighting
+    PathTraced,
+}
+
+impl Shading {
+    /// Converts the shading mode into a human-readable string format.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Shading::Depth => "depth",
+            Shading::Steps => "steps",
+            Shading::AmbientOcclusion => "ambient_occlusion",
+            Shading::Normals => "normals",
+            Shading::Diffuse => "diffuse",
+            Shading::PathTraced => "path_traced",
+        }
+    }
+
+    /// Converts a human-readable string (as returned by `name()`) back
+    /// into the corresponding shading mode, if there is one.
+    pub fn from_name(name: &str) -> Option<Shading> {
+        match name {
+            "depth" => Some(Shading::Depth),
+            "steps" => Some(Shading::Steps),
+            "ambient_occlusion" => Some(Shading::AmbientOcclusion),
+            "normals" => Some(Shading::Normals),
+            "diffuse" => Some(Shading::Diffuse),
+            "path_traced" => Some(Shading::PathTraced),
+            _ => None,
+        }
+    }
+}
+
+/// The point of view of one of the panes of the preview.
+#[derive(Copy, Clone, PartialEq)]
+pub enum View {
+    /// Look through the virtual camera
+    Perspective,
+
+    /// Look down the negative z-axis with an orthographic projection
+    Front,
+
+    /// Look down the negative y-axis with an orthographic projection
+    Top,
+
+    /// Look down the negative x-axis with an orthographic projection
+    Side,
+}
+
+/// Describes which orientation helpers are drawn on top of the scene.
+#[derive(Copy, Clone)]
+pub enum Gizmos {
+    /// Don't draw any helpers
+    None,
+
+    /// Draw a world-space axis tripod at the origin
+    Axes,
+
+    /// Draw the axis tripod as well as a grid on the floor (`y = 0`)
+    AxesAndGrid,
+}
+
+impl Gizmos {
+    /// Converts the set of helpers into a human-readable string format.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Gizmos::None => "none",
+            Gizmos::Axes => "axes",
+            Gizmos::AxesAndGrid => "axes_and_grid",
+        }
+    }
+
+    /// Converts a human-readable string (as returned by `name()`) back
+    /// into the corresponding set of helpers, if there is one.
+    pub fn from_name(name: &str) -> Option<Gizmos> {
+        match name {
+            "none" => Some(Gizmos::None),
+            "axes" => Some(Gizmos::Axes),
+            "axes_and_grid" => Some(Gizmos::AxesAndGrid),
+            _ => None,
+        }
+    }
+}
+
+/// Describes how the user navigates around the scene.
+#[derive(Copy, Clone, PartialEq)]
+pub enum CameraMode {
+    /// Left-drag orbits around the focus point, right-drag pans the
+    /// focus point, and scrolling dollies the camera
+    Orbit,
+
+    /// WASD moves the camera, left-drag looks around, and scrolling
+    /// changes the movement speed
+    Fly,
+}
+
+/// The directions that the camera can move in while flying.
+#[derive(Copy, Clone)]
+pub enum Movement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+}
+
+/// The camera and view settings of the preview that are saved along
+/// with a network (see `Project`).
+#[derive(Copy, Clone)]
+pub struct PreviewSettings {
+    /// The point that the camera orbits around
+    pub camera_focus: Point3<f32>,
+
+    /// The distance between the camera and its focus point
+    pub camera_distance: f32,
+
+    /// The horizontal and vertical angles of the camera
+    pub camera_yaw: f32,
+    pub camera_pitch: f32,
+
+    /// The shading mode
+    pub shading: Shading,
+
+    /// The camera's field of view, in degrees
+    pub fov: f32,
+
+    /// The maximum distance that rays will travel
+    pub max_trace_distance: f32,
+
+    /// The orientation helpers that are drawn on top of the scene
+    pub gizmos: Gizmos,
+
+    /// The range and scale of the step count heatmap
+    pub steps_min: f32,
+    pub steps_max: f32,
+    pub steps_log: bool,
+
+    /// Whether or not isodistance bands are drawn on top of surfaces
+    pub isolines: bool,
+}
+
+/// Returns the UTC date and time that is `seconds` after the Unix epoch,
+/// formatted so that it can be part of a file name (i.e.
+/// `2018-03-21_17-45-09`).
+fn format_timestamp(seconds: u64) -> String {
+    let time = seconds % 86400;
+
+    // Converts the number of days since the epoch into a date on the
+    // (proleptic) Gregorian calendar, in eras of 400 years that start
+    // on March 1st, 0000.
+    let days = seconds / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time / 60) % 60,
+        time % 60
+    )
+}
+
+/// Returns the range of `new` that differs from `old` (i.e. the op
+/// parameters that were uploaded last), widened to whole ops, or `None` if
+/// nothing changed. Parameters that were only removed from the end don't
+/// need to be uploaded.
+fn get_changed_range(old: &[f32], new: &[f32]) -> Option<(usize, usize)> {
+    let differs = |index: usize| index >= old.len() || old[index] != new[index];
+    let first = (0..new.len()).find(|&index| differs(index))?;
+    let last = (first..new.len()).rev().find(|&index| differs(index)).unwrap_or(first);
+
+    let capacity = constants::PARAMETER_CAPACITY;
+    let start = first / capacity * capacity;
+    let end = ((last / capacity + 1) * capacity).min(new.len());
+    Some((start, end))
+}
+
+/// Splits a framebuffer with `resolution` into square tiles that are
+/// `PREVIEW_TILE_SIZE` pixels across (or smaller, along its top and right
+/// edges), ordered by the distance from their centers to `focus` (in
+/// pixels), closest first.
+fn get_tiles(resolution: &Vector2<f32>, focus: &Vector2<f32>) -> Vec<Region> {
+    let size = constants::PREVIEW_TILE_SIZE;
+    let (width, height) = (resolution.x as i32, resolution.y as i32);
+
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(size as usize) {
+        for x in (0..width).step_by(size as usize) {
+            tiles.push(Region::new(x, y, size.min(width - x), size.min(height - y)));
+        }
+    }
+
+    let (fx, fy) = (focus.x as i32, focus.y as i32);
+    tiles.sort_by_key(|tile| {
+        let dx = tile.x + tile.width / 2 - fx;
+        let dy = tile.y + tile.height / 2 - fy;
+        dx * dx + dy * dy
+    });
+    tiles
+}
+
+/// Returns the current UTC date and time, formatted by `format_timestamp`.
+pub fn get_timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    format_timestamp(seconds)
 }
 
 struct VirtualCamera {
@@ -48,24 +321,79 @@ struct VirtualCamera {
 
     /// The horizontal angle of the camera
     yaw: f32,
+
+    /// The point that the camera orbits around
+    focus: Point3<f32>,
+
+    /// The distance between the camera and its focus point
+    distance: f32,
 }
 
 impl VirtualCamera {
     fn new() -> VirtualCamera {
-        VirtualCamera {
-            position: Point3::new(0.0, 0.0, 5.0),
+        let mut camera = VirtualCamera {
+            position: Point3::origin(),
             up: Vector3::unit_y(),
             front: Vector3::new(0.0, 0.0, -1.0),
             right: Vector3::unit_x(),
             pitch: 0.0,
             yaw: -90.0,
-        }
+            focus: Point3::origin(),
+            distance: constants::PREVIEW_HOME_DISTANCE,
+        };
+        camera.rebuild_basis();
+        camera
     }
 
     fn home(&mut self) {
-        self.position = Point3::new(0.0, 0.0, 5.0);
+        self.focus = Point3::origin();
+        self.distance = constants::PREVIEW_HOME_DISTANCE;
         self.pitch = 0.0;
         self.yaw = -90.0;
+        self.rebuild_basis();
+    }
+
+    /// Rotates the camera around its focus point.
+    fn orbit(&mut self, offset: &Vector2<f32>) {
+        self.yaw += offset.x * constants::PREVIEW_ROTATION_SENSITIVITY;
+        self.pitch += offset.y * constants::PREVIEW_ROTATION_SENSITIVITY;
+        self.pitch = self.pitch.min(89.0).max(-89.0);
+        self.rebuild_basis();
+    }
+
+    /// Rotates the camera in place, dragging its focus point along
+    /// with it.
+    fn look(&mut self, offset: &Vector2<f32>) {
+        let position = self.position;
+        self.orbit(offset);
+
+        self.focus = position + self.front * self.distance;
+        self.position = position;
+    }
+
+    /// Moves the camera (and its focus point) by `offset`.
+    fn fly(&mut self, offset: &Vector3<f32>) {
+        self.focus += *offset;
+        self.rebuild_basis();
+    }
+
+    /// Moves the focus point (and the camera along with it) parallel
+    /// to the camera's image plane. Panning is faster when the camera
+    /// is further away from its focus point.
+    fn pan(&mut self, offset: &Vector2<f32>) {
+        let speed = constants::PREVIEW_TRANSLATION_SENSITIVITY * self.distance;
+        let camera_up = self.right.cross(self.front);
+
+        self.focus += self.right * offset.x * speed;
+        self.focus += camera_up * -offset.y * speed;
+        self.rebuild_basis();
+    }
+
+    /// Moves the camera towards (negative `amount`) or away from
+    /// (positive `amount`) its focus point.
+    fn dolly(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 + amount)).max(constants::PREVIEW_MIN_DISTANCE);
+        self.rebuild_basis();
     }
 
     fn rebuild_basis(&mut self) {
@@ -75,7 +403,10 @@ impl VirtualCamera {
             self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
         ).normalize();
 
-        self.right = self.front.cross(self.up).normalize()
+        self.right = self.front.cross(self.up).normalize();
+
+        // The camera always looks at its focus point.
+        self.position = self.focus + self.front * -self.distance;
     }
 }
 
@@ -83,6 +414,17 @@ pub struct Preview {
     /// The valid shader program, if one exists
     program_valid: Option<Program>,
 
+    /// The program built from the second render op that is being
+    /// compared against the first (if any), which is drawn to the right
+    /// of the split line
+    program_compare: Option<Program>,
+
+    /// The horizontal position of the split line, in texture coordinates
+    split: f32,
+
+    /// A flag that is set while the user is dragging the split line
+    dragging_split: bool,
+
     /// The fallback program that will be used if `program_valid`
     /// is `None`
     program_error: Program,
@@ -90,21 +432,180 @@ pub struct Preview {
     /// The bounding box of the preview window
     bounds: Rect,
 
+    /// The bounding box of the preview window when it isn't
+    /// covering the entire network editor
+    bounds_docked: Rect,
+
+    /// A flag that controls whether or not the preview covers
+    /// the entire network editor
+    fullscreen: bool,
+
+    /// The offscreen framebuffer that the scene is raymarched into,
+    /// before being scaled to fit the preview window
+    framebuffer: Framebuffer,
+
+    /// A flag that controls whether the preview is split into four panes
+    /// (perspective, front, top, and side) or shows a single view
+    quad_view: bool,
+
+    /// A lower resolution framebuffer that the scene is raymarched into
+    /// while the user is interacting with it
+    framebuffer_interactive: Framebuffer,
+
+    /// The index into `constants::PREVIEW_INTERACTIVE_SCALES` of the
+    /// resolution of `framebuffer_interactive`, relative to `framebuffer`
+    interactive_scale_index: usize,
+
+    /// The time at which the user last moved the camera or changed a
+    /// parameter (if ever)
+    last_interaction: Option<SystemTime>,
+
+    /// A flag that is set if the most recent frame was rendered into
+    /// `framebuffer_interactive`
+    rendered_interactive: bool,
+
+    /// A tiny framebuffer that is used to read back the results of
+    /// rays cast through the preview (i.e. for picking or probing)
+    framebuffer_pick: Framebuffer,
+
+    /// A float framebuffer that jittered frames are averaged into while
+    /// nothing in the scene is changing
+    framebuffer_accumulation: Framebuffer,
+
+    /// A flag that controls whether or not ambient occlusion is
+    /// accumulated progressively over several frames
+    accumulate: bool,
+
+    /// The number of frames that have been averaged into
+    /// `framebuffer_accumulation`
+    accumulated_frames: u32,
+
+    /// A snapshot of everything that affects the rendered image, which
+    /// is used to detect when accumulation must start over
+    accumulation_signature: Vec<f32>,
+
+    /// A snapshot of the scene the last time it was rendered without
+    /// accumulation: if nothing has changed since then, the previous
+    /// image is presented again rather than raymarching the same scene
+    rendered_signature: Vec<f32>,
+
+    /// The op parameters that were most recently uploaded
+    params: Vec<f32>,
+
+    /// A flag that controls whether clicking in the preview probes
+    /// the surface rather than selecting an op
+    probing: bool,
+
+    /// The results of the most recent probe (if any)
+    probe: Option<Probe>,
+
+    /// The index into `constants::PREVIEW_RENDER_SCALES` of the ratio
+    /// between the framebuffer's resolution and the preview's size
+    render_scale_index: usize,
+
+    /// The index into `constants::PREVIEW_SAMPLE_COUNTS` of the number of
+    /// rays that are traced per pixel, for anti-aliasing
+    sample_count_index: usize,
+
     /// The virtual camera that will be used to view the scene
     camera: VirtualCamera,
 
     /// The current shading mode that will be applied to the scene
     shading: Shading,
 
-    /// The OpenGL handle to the shader storage buffer object (SSBO)
-    /// that will hold all of the op parameters
-    ssbo: GLuint,
+    /// The current time of the application's timeline, in seconds
+    time: f32,
+
+    /// The aspect ratio of the frames that are being captured (if any),
+    /// which overrides the aspect ratio of the preview window
+    capture_aspect: Option<f32>,
+
+    /// The horizontal field of view of the camera, in degrees
+    fov: f32,
+
+    /// The distance after which rays are considered to have missed
+    /// the scene
+    max_trace_distance: f32,
+
+    /// The orientation helpers that will be drawn on top of the scene
+    gizmos: Gizmos,
+
+    /// The step counts that map to the lowest and highest colors of the
+    /// heatmap in the `Steps` shading mode
+    steps_min: f32,
+    steps_max: f32,
+
+    /// A flag that controls whether or not the step count heatmap uses
+    /// a logarithmic scale
+    steps_log: bool,
+
+    /// A flag that controls whether or not isodistance bands will be
+    /// drawn on top of the scene
+    isolines: bool,
+
+    /// How the user navigates around the scene
+    camera_mode: CameraMode,
+
+    /// Whether or not each of the movement keys is held down, indexed
+    /// by `Movement`
+    movement: [bool; 4],
+
+    /// The speed at which the camera flies, in units per second
+    fly_speed: f32,
+
+    /// A flag that is set while the mouse is inside of the preview
+    /// window, which is when the movement keys are active
+    focused: bool,
+
+    /// A flag that controls whether or not the camera slowly orbits
+    /// around its focus point
+    turntable: bool,
+
+    /// The speed at which the camera orbits in turntable mode, in
+    /// degrees per second
+    turntable_speed: f32,
+
+    /// The time at which the camera was last updated
+    last_update: SystemTime,
+
+    /// The scroll status of the mouse during the last interaction,
+    /// which is used to dolly the camera
+    last_scroll: f32,
+
+    /// The OpenGL feature level that the preview's shaders target
+    profile: Profile,
+
+    /// The buffer that will hold all of the op parameters
+    params_buffer: ParamsBuffer,
+
+    /// The tiles of the current image that haven't been raymarched yet,
+    /// in the order that they will be drawn (see `render`)
+    tiles: Vec<Region>,
+
+    /// The texture coordinates of the mouse cursor, if it is over the
+    /// preview window
+    cursor: Option<Vector2<f32>>,
+
+    /// An estimate of how long the GPU takes to raymarch a million pixels
+    /// of the preview, in milliseconds (if it has been measured yet)
+    pixel_cost: Option<f32>,
+
+    /// The number of pixels that were raymarched during the most recent
+    /// call to `render`
+    rendered_pixels: u32,
+
+    /// Reduces the step counts of each rendered image into a histogram
+    steps: StepHistogram,
+
+    /// A flag that controls whether or not the step counts of each image
+    /// are recorded (i.e. while the performance overlay is shown)
+    recording_steps: bool,
 }
 
 impl Preview {
-    pub fn new() -> Preview {
+    pub fn new(profile: Profile) -> Result<Preview, Error> {
         static FALLBACK_VS_SRC: &'static str = "
-        #version 430
+        #version 410
 
         layout(location = 0) in vec2 position;
         layout(location = 1) in vec2 texcoord;
@@ -120,7 +621,7 @@ impl Preview {
         }";
 
         static FALLBACK_FS_SRC: &'static str = "
-        #version 430
+        #version 410
 
         layout (location = 0) in vec2 vs_texcoord;
         layout (location = 0) out vec4 o_color;
@@ -138,25 +639,309 @@ impl Preview {
         }";
 
         let program_error =
-            Program::new(FALLBACK_VS_SRC.to_string(), FALLBACK_FS_SRC.to_string()).unwrap();
+            Program::new(FALLBACK_VS_SRC.to_string(), FALLBACK_FS_SRC.to_string())?;
 
-        let mut ssbo = 0;
-        unsafe {
-            let ssbo_size = (constants::PARAMETER_SSBO_CAPACITY * mem::size_of::<Vector4<f32>>()) as GLsizeiptr;
+        let params_buffer = ParamsBuffer::new(profile);
+        let render_scale_index = constants::PREVIEW_DEFAULT_RENDER_SCALE;
+        let resolution = constants::PREVIEW_RESOLUTION
+            * constants::PREVIEW_RENDER_SCALES[render_scale_index];
+        let interactive_scale =
+            constants::PREVIEW_INTERACTIVE_SCALES[constants::PREVIEW_DEFAULT_INTERACTIVE_SCALE];
 
-            gl::CreateBuffers(1, &mut ssbo);
-            gl::NamedBufferStorage(ssbo, ssbo_size, ptr::null(), gl::DYNAMIC_STORAGE_BIT);
-        }
-        Preview {
+        Ok(Preview {
             program_valid: None,
+            program_compare: None,
+            split: 0.5,
+            dragging_split: false,
             program_error,
             bounds: Rect::new(Vector2::new(400.0, 50.0), constants::PREVIEW_RESOLUTION),
+            bounds_docked: Rect::new(Vector2::new(400.0, 50.0), constants::PREVIEW_RESOLUTION),
+            fullscreen: false,
+            framebuffer: Framebuffer::new(resolution.x as u32, resolution.y as u32),
+            framebuffer_interactive: Framebuffer::new(
+                (resolution.x * interactive_scale) as u32,
+                (resolution.y * interactive_scale) as u32,
+            ),
+            interactive_scale_index: constants::PREVIEW_DEFAULT_INTERACTIVE_SCALE,
+            quad_view: false,
+            last_interaction: None,
+            rendered_interactive: false,
+            framebuffer_pick: Framebuffer::with_format(2, 1, gl::RGBA32F),
+            framebuffer_accumulation: Framebuffer::with_format(
+                resolution.x as u32,
+                resolution.y as u32,
+                gl::RGBA32F,
+            ),
+            accumulate: false,
+            accumulated_frames: 0,
+            accumulation_signature: Vec::new(),
+            rendered_signature: Vec::new(),
+            params: Vec::new(),
+            probing: false,
+            probe: None,
+            render_scale_index,
+            sample_count_index: 0,
             camera: VirtualCamera::new(),
             shading: Shading::Normals,
-            ssbo,
+            time: 0.0,
+            capture_aspect: None,
+            fov: constants::PREVIEW_FOV,
+            max_trace_distance: constants::PREVIEW_MAX_TRACE_DISTANCE,
+            gizmos: Gizmos::None,
+            steps_min: 0.0,
+            steps_max: constants::PREVIEW_MAX_STEPS,
+            steps_log: false,
+            isolines: false,
+            camera_mode: CameraMode::Orbit,
+            movement: [false; 4],
+            fly_speed: constants::PREVIEW_FLY_SPEED,
+            focused: false,
+            turntable: false,
+            turntable_speed: constants::PREVIEW_TURNTABLE_SPEED,
+            last_update: SystemTime::now(),
+            last_scroll: 1.0,
+            profile,
+            params_buffer,
+            tiles: Vec::new(),
+            cursor: None,
+            pixel_cost: None,
+            rendered_pixels: 0,
+            steps: StepHistogram::new(profile),
+            recording_steps: false,
+        })
+    }
+
+    /// Re-creates the offscreen framebuffer so that its resolution
+    /// matches the current size of the preview and render scale.
+    fn rebuild_framebuffer(&mut self) {
+        let resolution =
+            self.bounds.get_size() * constants::PREVIEW_RENDER_SCALES[self.render_scale_index];
+        self.framebuffer = Framebuffer::new(
+            (resolution.x as u32).max(1),
+            (resolution.y as u32).max(1),
+        );
+        self.framebuffer_accumulation = Framebuffer::with_format(
+            (resolution.x as u32).max(1),
+            (resolution.y as u32).max(1),
+            gl::RGBA32F,
+        );
+        self.accumulated_frames = 0;
+        self.rendered_signature.clear();
+
+        let resolution =
+            resolution * constants::PREVIEW_INTERACTIVE_SCALES[self.interactive_scale_index];
+        self.framebuffer_interactive = Framebuffer::new(
+            (resolution.x as u32).max(1),
+            (resolution.y as u32).max(1),
+        );
+    }
+
+    /// Switches to the next resolution that is used while the user is
+    /// interacting with the scene: full, half, or quarter.
+    pub fn cycle_interactive_scale(&mut self) {
+        self.interactive_scale_index =
+            (self.interactive_scale_index + 1) % constants::PREVIEW_INTERACTIVE_SCALES.len();
+        self.rebuild_framebuffer();
+
+        console!(
+            "Preview interactive resolution scale: {}",
+            constants::PREVIEW_INTERACTIVE_SCALES[self.interactive_scale_index]
+        );
+    }
+
+    /// Returns `true` if the preview is split into four panes.
+    pub fn is_quad_view(&self) -> bool {
+        self.quad_view
+    }
+
+    /// Switches between a single perspective view and four panes that
+    /// also show the scene from the front, top, and side.
+    pub fn toggle_quad_view(&mut self) {
+        self.quad_view = !self.quad_view;
+    }
+
+    /// Returns each of the views that make up the preview, along with the
+    /// area of the framebuffer that it covers (as `x`, `y`, `width`, and
+    /// `height`, in texture coordinates).
+    fn get_panes(&self) -> Vec<(View, Vector4<f32>)> {
+        if !self.quad_view {
+            return vec![(View::Perspective, Vector4::new(0.0, 0.0, 1.0, 1.0))];
+        }
+        vec![
+            (View::Perspective, Vector4::new(0.0, 0.5, 0.5, 0.5)),
+            (View::Front, Vector4::new(0.5, 0.5, 0.5, 0.5)),
+            (View::Top, Vector4::new(0.0, 0.0, 0.5, 0.5)),
+            (View::Side, Vector4::new(0.5, 0.0, 0.5, 0.5)),
+        ]
+    }
+
+    /// Returns the view under the texture coordinates `uv`, along with
+    /// the texture coordinates relative to that view's pane.
+    fn get_view_at(&self, uv: &Vector2<f32>) -> (View, Vector2<f32>) {
+        for (view, area) in self.get_panes() {
+            let local = Vector2::new((uv.x - area.x) / area.z, (uv.y - area.y) / area.w);
+            if local.x >= 0.0 && local.x <= 1.0 && local.y >= 0.0 && local.y <= 1.0 {
+                return (view, local);
+            }
+        }
+        (View::Perspective, *uv)
+    }
+
+    /// Sets the camera uniforms that correspond to `view`. The orthographic
+    /// views are centered on the camera's focus point and framed so that
+    /// they roughly match the perspective view.
+    fn set_view_uniforms(&self, program: &Program, view: View) {
+        let (front, up) = match view {
+            View::Perspective => {
+                program.uniform_3f("u_camera_position", &self.camera.position.to_vec());
+                program.uniform_3f("u_camera_front", &self.camera.front);
+                program.uniform_3f("u_camera_up", &Vector3::unit_y());
+                program.uniform_1i("u_orthographic", 0);
+                return;
+            }
+            View::Front => (-Vector3::unit_z(), Vector3::unit_y()),
+            View::Top => (-Vector3::unit_y(), -Vector3::unit_z()),
+            View::Side => (-Vector3::unit_x(), Vector3::unit_y()),
+        };
+
+        let position = self.camera.focus + front * -(self.max_trace_distance * 0.5);
+        let scale = self.camera.distance * (self.fov.to_radians() * 0.5).tan();
+        program.uniform_3f("u_camera_position", &position.to_vec());
+        program.uniform_3f("u_camera_front", &front);
+        program.uniform_3f("u_camera_up", &up);
+        program.uniform_1i("u_orthographic", 1);
+        program.uniform_1f("u_ortho_scale", scale);
+    }
+
+    /// Records that the user just moved the camera or changed a parameter.
+    fn mark_interaction(&mut self) {
+        self.last_interaction = Some(SystemTime::now());
+    }
+
+    /// Returns `true` if the preview will change on its own from one frame
+    /// to the next (i.e. the camera is moving, frames are still being
+    /// accumulated, some tiles of the image haven't been raymarched yet,
+    /// or a full resolution frame is pending after the user stopped
+    /// interacting), even if no new events arrive.
+    pub fn is_animating(&self) -> bool {
+        let accumulating = self.is_accumulating()
+            && self.accumulated_frames < constants::PREVIEW_MAX_ACCUMULATED_FRAMES;
+        let flying = self.camera_mode == CameraMode::Fly
+            && (self.focused || self.fullscreen)
+            && self.movement.iter().any(|&held| held);
+        self.turntable
+            || flying
+            || accumulating
+            || self.is_interacting()
+            || self.rendered_interactive
+            || !self.tiles.is_empty()
+    }
+
+    /// Returns `true` if the user has moved the camera or changed a
+    /// parameter recently, in which case the preview is rendered at a
+    /// lower resolution to stay responsive.
+    fn is_interacting(&self) -> bool {
+        if constants::PREVIEW_INTERACTIVE_SCALES[self.interactive_scale_index] >= 1.0 {
+            return false;
+        }
+        match self.last_interaction.map(|time| time.elapsed()) {
+            Some(Ok(elapsed)) => {
+                let seconds = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1e9;
+                seconds < constants::PREVIEW_IDLE_DELAY
+            }
+            _ => false,
+        }
+    }
+
+    /// Switches to the next render scale, i.e. from a cheap, low
+    /// resolution interactive mode to a supersampled one.
+    pub fn cycle_render_scale(&mut self) {
+        self.render_scale_index =
+            (self.render_scale_index + 1) % constants::PREVIEW_RENDER_SCALES.len();
+        self.rebuild_framebuffer();
+
+        let resolution = self.framebuffer.get_resolution();
+        console!("Preview render resolution: {}x{}", resolution.x, resolution.y);
+    }
+
+    /// Switches to the next number of rays traced per pixel, which
+    /// smooths out hard edges at the cost of performance.
+    pub fn cycle_sample_count(&mut self) {
+        self.sample_count_index =
+            (self.sample_count_index + 1) % constants::PREVIEW_SAMPLE_COUNTS.len();
+
+        console!(
+            "Preview samples per pixel: {}",
+            constants::PREVIEW_SAMPLE_COUNTS[self.sample_count_index]
+        );
+    }
+
+    /// Returns the bounding box of the preview window.
+    pub fn get_bounds(&self) -> &Rect {
+        &self.bounds
+    }
+
+    /// Returns the texture that the scene is raymarched into.
+    pub fn get_color_attachment(&self) -> &Texture {
+        if self.program_valid.is_none() {
+            return self.framebuffer.get_color_attachment();
+        }
+        if self.is_accumulating() {
+            return self.framebuffer_accumulation.get_color_attachment();
+        }
+        if self.rendered_interactive {
+            return self.framebuffer_interactive.get_color_attachment();
+        }
+        self.framebuffer.get_color_attachment()
+    }
+
+    /// Turns the progressive accumulation of ambient occlusion on or off.
+    pub fn toggle_accumulation(&mut self) {
+        self.accumulate = !self.accumulate;
+        self.accumulated_frames = 0;
+    }
+
+    /// Returns `true` if frames are currently being accumulated, which
+    /// only happens in the shading modes that use ambient occlusion
+    /// (if enabled) or path tracing (always).
+    fn is_accumulating(&self) -> bool {
+        match self.shading {
+            Shading::AmbientOcclusion | Shading::Diffuse => self.accumulate,
+            Shading::PathTraced => true,
+            _ => false,
         }
     }
 
+    /// Returns a snapshot of everything that affects the accumulated
+    /// image: if any of it changes, accumulation starts over.
+    fn get_signature(&self) -> Vec<f32> {
+        let mut signature = vec![
+            self.camera.position.x,
+            self.camera.position.y,
+            self.camera.position.z,
+            self.camera.front.x,
+            self.camera.front.y,
+            self.camera.front.z,
+            self.time,
+            self.fov,
+            self.max_trace_distance,
+            self.shading as u32 as f32,
+            self.gizmos as u32 as f32,
+            self.isolines as u32 as f32,
+            self.sample_count_index as f32,
+            self.quad_view as u32 as f32,
+            self.program_compare.is_some() as u32 as f32,
+            self.split,
+            self.steps_min,
+            self.steps_max,
+            self.steps_log as u32 as f32,
+            self.bounds.get_size().x,
+            self.bounds.get_size().y,
+        ];
+        signature.extend_from_slice(&self.params);
+        signature
+    }
+
     /// Sets the shader program that will be used to render a
     /// miniature preview window in the lower right-hand corner
     /// of the network.
@@ -166,15 +951,59 @@ impl Preview {
     /// current graph.
     pub fn set_valid_program(&mut self, program: Option<Program>) {
         self.program_valid = program;
+        self.accumulated_frames = 0;
+        self.rendered_signature.clear();
+    }
+
+    /// Sets the shader program that will be drawn to the right of the
+    /// split line, for comparison against the valid program. If `program`
+    /// is `None`, the valid program fills the entire preview.
+    pub fn set_compare_program(&mut self, program: Option<Program>) {
+        self.program_compare = program;
+        self.accumulated_frames = 0;
+        self.rendered_signature.clear();
+    }
+
+    /// Returns the position of the split line in network editor
+    /// coordinates, if a comparison program has been assigned.
+    pub fn get_split(&self) -> Option<f32> {
+        self.program_compare
+            .as_ref()
+            .map(|_| self.bounds.get_upper_left().x + self.split * self.bounds.get_size().x)
+    }
+
+    /// Returns `true` if `point` (in network editor coordinates) is close
+    /// enough to the split line to grab it.
+    pub fn is_over_split(&self, point: &Vector2<f32>) -> bool {
+        match self.get_split() {
+            Some(x) => {
+                self.bounds.inside(point)
+                    && (point.x - x).abs() < constants::PREVIEW_SPLIT_HANDLE_WIDTH
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` if the texture coordinates `uv` lie to the right of
+    /// the split line, where the comparison program is drawn.
+    fn is_compare_at(&self, uv: &Vector2<f32>) -> bool {
+        self.program_compare.is_some() && uv.x > self.split
     }
 
     /// Writes `data` to the OpenGL buffer that this preview
-    /// will use to populate shader parameters during rendering.
-    pub fn update_params(&self, data: Vec<f32>) {
-        unsafe {
-            let data_size = (data.len() * mem::size_of::<f32>()) as GLsizeiptr;
-            gl::NamedBufferSubData(self.ssbo, 0, data_size, data.as_ptr() as *const c_void);
+    /// will use to populate shader parameters during rendering. Only the
+    /// parameters of the ops that changed since the last call are
+    /// uploaded (if any).
+    pub fn update_params(&mut self, data: &[f32]) {
+        if self.params.as_slice() == data {
+            return;
         }
+        if let Some(range) = get_changed_range(&self.params, data) {
+            self.params_buffer.write(data, range);
+            self.mark_interaction();
+        }
+        self.params.clear();
+        self.params.extend_from_slice(data);
     }
 
     /// Sets the shading mode.
@@ -182,63 +1011,923 @@ impl Preview {
         self.shading = shading;
     }
 
+    /// Sets the time that is passed to the shader graph as `u_time`.
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Returns the current op parameters, camera, and view settings, i.e.
+    /// for baking them into an exported shader. Exported shaders always use
+    /// the perspective camera.
+    pub fn get_snapshot(&self) -> Snapshot {
+        let samples = constants::PREVIEW_SAMPLE_COUNTS[self.sample_count_index];
+        Snapshot {
+            params: self.params.clone(),
+            camera_position: self.camera.position,
+            camera_focus: self.camera.focus,
+            constants: vec![
+                ("u_orthographic", "false".to_string()),
+                ("u_ortho_scale", shadertoy::float(1.0)),
+                ("u_shading", format!("{}u", self.shading as u32)),
+                ("u_gizmos", format!("{}u", self.gizmos as u32)),
+                ("u_fov", shadertoy::float(self.fov)),
+                ("u_max_trace_distance", shadertoy::float(self.max_trace_distance)),
+                ("u_picking", "false".to_string()),
+                ("u_probing", "false".to_string()),
+                ("u_isolines", self.isolines.to_string()),
+                ("u_samples", format!("{}u", samples)),
+                ("u_jitter", "false".to_string()),
+                ("u_steps_min", shadertoy::float(self.steps_min)),
+                ("u_steps_max", shadertoy::float(self.steps_max)),
+                ("u_steps_log", self.steps_log.to_string()),
+                ("u_pick_uv", "vec2(0.0)".to_string()),
+            ],
+        }
+    }
+
+    /// Returns the camera and view settings that are saved along with
+    /// a network.
+    pub fn get_settings(&self) -> PreviewSettings {
+        PreviewSettings {
+            camera_focus: self.camera.focus,
+            camera_distance: self.camera.distance,
+            camera_yaw: self.camera.yaw,
+            camera_pitch: self.camera.pitch,
+            shading: self.shading,
+            fov: self.fov,
+            max_trace_distance: self.max_trace_distance,
+            gizmos: self.gizmos,
+            steps_min: self.steps_min,
+            steps_max: self.steps_max,
+            steps_log: self.steps_log,
+            isolines: self.isolines,
+        }
+    }
+
+    /// Restores the camera and view `settings` of a saved network.
+    pub fn apply_settings(&mut self, settings: &PreviewSettings) {
+        self.camera.focus = settings.camera_focus;
+        self.camera.distance = settings.camera_distance.max(constants::PREVIEW_MIN_DISTANCE);
+        self.camera.yaw = settings.camera_yaw;
+        self.camera.pitch = settings.camera_pitch.min(89.0).max(-89.0);
+        self.camera.rebuild_basis();
+
+        self.shading = settings.shading;
+        self.fov = settings
+            .fov
+            .max(constants::PREVIEW_MIN_FOV)
+            .min(constants::PREVIEW_MAX_FOV);
+        self.max_trace_distance = settings.max_trace_distance.max(1.0);
+        self.gizmos = settings.gizmos;
+        self.steps_min = settings.steps_min;
+        self.steps_max = settings.steps_max;
+        self.steps_log = settings.steps_log;
+        self.isolines = settings.isolines;
+        self.mark_interaction();
+    }
+
+    /// Returns `true` if the preview covers the entire network editor
+    /// and `false` otherwise.
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    /// Expands the preview so that it covers a network editor of
+    /// dimensions `size` or, if it is already expanded, returns it
+    /// to its original position.
+    pub fn toggle_fullscreen(&mut self, size: &Vector2<f32>) {
+        self.fullscreen = !self.fullscreen;
+        self.bounds = if self.fullscreen {
+            Rect::new(size * -0.5, *size)
+        } else {
+            self.bounds_docked
+        };
+        self.rebuild_framebuffer();
+    }
+
+    /// Changes the camera's field of view by `delta` degrees.
+    pub fn adjust_fov(&mut self, delta: f32) {
+        self.fov = (self.fov + delta)
+            .max(constants::PREVIEW_MIN_FOV)
+            .min(constants::PREVIEW_MAX_FOV);
+        console!("Field of view: {} degrees", self.fov);
+    }
+
+    /// Changes the maximum distance that rays will travel by `delta`.
+    pub fn adjust_max_trace_distance(&mut self, delta: f32) {
+        self.max_trace_distance = (self.max_trace_distance + delta).max(1.0);
+        console!("Max trace distance: {}", self.max_trace_distance);
+    }
+
+    /// Changes the step counts that map to the lowest and highest colors
+    /// of the heatmap by `delta_min` and `delta_max`. This only applies
+    /// while the `Steps` shading mode is active.
+    pub fn adjust_steps_range(&mut self, delta_min: f32, delta_max: f32) {
+        if self.shading != Shading::Steps {
+            return;
+        }
+        let increment = constants::PREVIEW_STEPS_INCREMENT;
+        self.steps_max = (self.steps_max + delta_max)
+            .max(increment)
+            .min(constants::PREVIEW_MAX_STEPS);
+        self.steps_min = (self.steps_min + delta_min)
+            .max(0.0)
+            .min(self.steps_max - increment);
+        console!("Step heatmap range: {} to {}", self.steps_min, self.steps_max);
+    }
+
+    /// Switches the step count heatmap between a linear and logarithmic
+    /// scale. This only applies while the `Steps` shading mode is active.
+    pub fn toggle_steps_log(&mut self) {
+        if self.shading != Shading::Steps {
+            return;
+        }
+        self.steps_log = !self.steps_log;
+        console!("Step heatmap scale: {}", if self.steps_log { "log" } else { "linear" });
+    }
+
+    /// Switches to the next set of orientation helpers: none, then
+    /// the axis tripod, then the axis tripod and floor grid.
+    pub fn cycle_gizmos(&mut self) {
+        self.gizmos = match self.gizmos {
+            Gizmos::None => Gizmos::Axes,
+            Gizmos::Axes => Gizmos::AxesAndGrid,
+            Gizmos::AxesAndGrid => Gizmos::None,
+        };
+    }
+
+    /// Shows or hides the isodistance bands that are drawn on top of
+    /// surfaces, which help visualize how well-behaved the distance
+    /// field is.
+    pub fn toggle_isolines(&mut self) {
+        self.isolines = !self.isolines;
+    }
+
+    /// Switches between the orbit and fly camera modes.
+    pub fn toggle_camera_mode(&mut self) {
+        self.camera_mode = match self.camera_mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+        self.movement = [false; 4];
+    }
+
+    /// Starts or stops orbiting the camera around its focus point.
+    pub fn toggle_turntable(&mut self) {
+        self.turntable = !self.turntable;
+    }
+
+    /// Changes the speed of the turntable by `delta` degrees per second.
+    /// Negative speeds orbit in the opposite direction.
+    pub fn adjust_turntable_speed(&mut self, delta: f32) {
+        self.turntable_speed += delta;
+        console!("Turntable speed: {} degrees/second", self.turntable_speed);
+    }
+
+    /// Records whether or not the key that moves the camera in
+    /// `direction` is held down.
+    pub fn set_movement(&mut self, direction: Movement, held: bool) {
+        self.movement[direction as usize] = held;
+    }
+
+    /// Moves the camera according to the movement keys that are held
+    /// down (and the turntable, if it is enabled). This should be called
+    /// once per frame.
+    pub fn update(&mut self) {
+        let elapsed = match self.last_update.elapsed() {
+            Ok(elapsed) => elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1e9,
+            Err(_) => 0.0,
+        };
+        self.last_update = SystemTime::now();
+
+        if self.turntable {
+            self.camera.yaw += self.turntable_speed * elapsed;
+            self.camera.rebuild_basis();
+        }
+
+        if self.camera_mode != CameraMode::Fly || !(self.focused || self.fullscreen) {
+            return;
+        }
+
+        let mut direction = Vector3::zero();
+        if self.movement[Movement::Forward as usize] {
+            direction += self.camera.front;
+        }
+        if self.movement[Movement::Backward as usize] {
+            direction -= self.camera.front;
+        }
+        if self.movement[Movement::Left as usize] {
+            direction -= self.camera.right;
+        }
+        if self.movement[Movement::Right as usize] {
+            direction += self.camera.right;
+        }
+
+        if direction.magnitude2() > 0.0 {
+            self.camera.fly(&(direction.normalize() * self.fly_speed * elapsed));
+            self.mark_interaction();
+        }
+    }
+
     /// Homes the virtual preview camera.
     pub fn home(&mut self) {
         self.camera.home();
+
+        // The network resets the mouse's scroll status when the
+        // camera is homed.
+        self.last_scroll = 1.0;
     }
 
-    /// If a preview program has be assigned, render a miniature
-    /// preview window in the lower right-hand corner of the
-    /// network.
-    pub fn prepare(&self, projection: &Matrix4<f32>) {
-        if let Some(ref program) = self.program_valid {
+    /// Raymarches the scene into `framebuffer` with the valid program,
+    /// after setting any extra uniforms via `set_uniforms`. If `view` is
+    /// `None`, each of the preview's panes is drawn (split between the
+    /// valid and comparison programs, if the latter exists): otherwise,
+    /// `view` fills the entire framebuffer and `compare` selects which
+    /// program is used. Returns `false` if there is no valid program and
+    /// `true` otherwise.
+    fn render_into<F>(
+        &self,
+        renderer: &Renderer,
+        framebuffer: &Framebuffer,
+        view: Option<View>,
+        compare: bool,
+        set_uniforms: F,
+    ) -> bool
+    where
+        F: Fn(&Program),
+    {
+        let program = match self.program_valid {
+            Some(ref program) => program,
+            None => return false,
+        };
+
+        let resolution = framebuffer.get_resolution();
+        let region = Region::new(0, 0, resolution.x as i32, resolution.y as i32);
+        region.clip(|| {
+            framebuffer.bind();
             self.bind_transforms();
-            program.bind();
-            program.uniform_3f("u_camera_position", &self.camera.position.to_vec());
-            program.uniform_3f("u_camera_front", &self.camera.front);
-            program.uniform_1ui("u_shading", self.shading as u32);
-            program.uniform_matrix_4f("u_model_matrix", &self.bounds.get_model_matrix());
-            program.uniform_matrix_4f("u_projection_matrix", &projection);
+
+            match self.program_compare {
+                // Draw the valid program to the left of the split line and the
+                // comparison program to the right of it.
+                Some(ref program_compare) if view.is_none() => {
+                    let left = Vector4::new(0.0, 0.0, self.split, 1.0);
+                    let right = Vector4::new(self.split, 0.0, 1.0 - self.split, 1.0);
+                    region.subregion(&left).clip(|| {
+                        self.draw_program(renderer, program, framebuffer, view, &set_uniforms)
+                    });
+                    region.subregion(&right).clip(|| {
+                        self.draw_program(
+                            renderer,
+                            program_compare,
+                            framebuffer,
+                            view,
+                            &set_uniforms,
+                        )
+                    });
+                }
+                Some(ref program_compare) if compare => {
+                    self.draw_program(renderer, program_compare, framebuffer, view, &set_uniforms)
+                }
+                _ => self.draw_program(renderer, program, framebuffer, view, &set_uniforms),
+            }
+
+            framebuffer.unbind();
+        });
+        true
+    }
+
+    /// Draws `view` (or each of the preview's panes, if `view` is `None`)
+    /// into the currently bound `framebuffer` with `program`.
+    fn draw_program<F>(
+        &self,
+        renderer: &Renderer,
+        program: &Program,
+        framebuffer: &Framebuffer,
+        view: Option<View>,
+        set_uniforms: &F,
+    ) where
+        F: Fn(&Program),
+    {
+        // The quad that the raymarcher is drawn onto spans `[0..1]`,
+        // and it is flipped vertically so that the result is upright
+        // when sampled as a color map.
+        let projection = cgmath::ortho(0.0, 1.0, 0.0, 1.0, -1.0, 1.0);
+
+        program.bind();
+        program.validate_once();
+        program.uniform_1ui("u_shading", self.shading as u32);
+        program.uniform_1ui("u_gizmos", self.gizmos as u32);
+        program.uniform_1i("u_isolines", self.isolines as i32);
+        program.uniform_1ui(
+            "u_samples",
+            constants::PREVIEW_SAMPLE_COUNTS[self.sample_count_index],
+        );
+        program.uniform_1f("u_steps_min", self.steps_min);
+        program.uniform_1f("u_steps_max", self.steps_max);
+        program.uniform_1i("u_steps_log", self.steps_log as i32);
+        program.uniform_1f("u_time", self.time);
+        program.uniform_1f("u_fov", self.fov);
+        program.uniform_1f("u_max_trace_distance", self.max_trace_distance);
+        program.uniform_1i("u_recording_steps", 0);
+
+        // Rays are generated based on the aspect ratio of the preview.
+        let size = self.bounds.get_size();
+        let aspect = self.capture_aspect.unwrap_or(size.x / size.y);
+        program.uniform_1f("u_aspect", aspect);
+        program.uniform_matrix_4f("u_model_matrix", &Matrix4::identity());
+        program.uniform_matrix_4f("u_projection_matrix", &projection);
+        set_uniforms(program);
+
+        let panes = match view {
+            Some(view) => vec![(view, Vector4::new(0.0, 0.0, 1.0, 1.0))],
+            None => self.get_panes(),
+        };
+        let resolution = framebuffer.get_resolution();
+        for (view, area) in panes {
+            unsafe {
+                gl::Viewport(
+                    (area.x * resolution.x) as i32,
+                    (area.y * resolution.y) as i32,
+                    (area.z * resolution.x) as i32,
+                    (area.w * resolution.y) as i32,
+                );
+            }
+            self.set_view_uniforms(program, view);
+            renderer.draw_rect_inner();
+        }
+        program.unbind();
+    }
+
+    /// Sets the estimate of how long the GPU takes to raymarch a million
+    /// pixels of the preview, in milliseconds (see `Performance`).
+    pub fn set_pixel_cost(&mut self, cost: Option<f32>) {
+        self.pixel_cost = cost;
+    }
+
+    /// Returns the number of pixels that were raymarched during the most
+    /// recent call to `render`, which is zero if the previous image was
+    /// reused.
+    pub fn get_rendered_pixels(&self) -> u32 {
+        self.rendered_pixels
+    }
+
+    /// Turns the recording of step counts on or off (see `StepHistogram`),
+    /// which does nothing if the profile doesn't support it. When turned
+    /// on, the current image is raymarched again to record them.
+    pub fn set_recording_steps(&mut self, recording: bool) {
+        let recording = recording && self.steps.is_supported();
+        if recording && !self.recording_steps {
+            self.rendered_signature.clear();
+            self.accumulated_frames = 0;
+        }
+        self.recording_steps = recording;
+    }
+
+    /// Returns the histogram of the step counts of the most recent image
+    /// whose step counts were recorded, if any.
+    pub fn get_step_statistics(&self) -> Option<&StepStatistics> {
+        if !self.recording_steps {
+            return None;
+        }
+        self.steps.get_statistics()
+    }
+
+    /// Returns the tiles that a new image should be raymarched in: a single
+    /// tile that covers the entire framebuffer, unless that is expected to
+    /// take longer than `PREVIEW_TILE_BUDGET_MS`, in which case the image is
+    /// split into smaller tiles around the mouse cursor (or the center of
+    /// the preview) that are spread across several frames.
+    fn get_image_tiles(&self) -> Vec<Region> {
+        let resolution = if self.rendered_interactive {
+            self.framebuffer_interactive.get_resolution()
+        } else {
+            self.framebuffer.get_resolution()
+        };
+
+        let cost = self.pixel_cost.unwrap_or(0.0) * resolution.x * resolution.y / 1_000_000.0;
+        if cost <= constants::PREVIEW_TILE_BUDGET_MS {
+            return vec![Region::new(0, 0, resolution.x as i32, resolution.y as i32)];
+        }
+
+        let focus = self.cursor.unwrap_or(Vector2::new(0.5, 0.5));
+        get_tiles(resolution, &Vector2::new(focus.x * resolution.x, focus.y * resolution.y))
+    }
+
+    /// Returns the number of tiles that can be raymarched this frame
+    /// without exceeding `PREVIEW_TILE_BUDGET_MS` (but always at least one).
+    fn get_tiles_per_frame(&self) -> usize {
+        let size = constants::PREVIEW_TILE_SIZE as f32;
+        match self.pixel_cost {
+            Some(cost) if cost > 0.0 => {
+                let tile = cost * size * size / 1_000_000.0;
+                ((constants::PREVIEW_TILE_BUDGET_MS / tile) as usize).max(1)
+            }
+            _ => self.tiles.len(),
+        }
+    }
+
+    /// If a preview program has been assigned, raymarches the scene
+    /// into the preview's offscreen framebuffer and returns `true`.
+    /// Otherwise, returns `false`.
+    ///
+    /// If the scene is expensive to raymarch, each image is drawn in tiles
+    /// over several calls, starting under the mouse cursor, so that the
+    /// rest of the interface stays responsive. While accumulating, each
+    /// call averages one more jittered frame into the accumulation
+    /// framebuffer, until either the scene changes or enough frames have
+    /// been gathered.
+    pub fn render(&mut self, renderer: &Renderer) -> bool {
+        self.rendered_pixels = 0;
+        self.steps.poll();
+        if self.program_valid.is_none() {
+            self.render_fallback(renderer);
+            return false;
+        }
+
+        if !self.is_accumulating() {
+            // Start a new image if the scene has changed: otherwise, finish
+            // drawing the previous one (if any of its tiles are left) or
+            // reuse it.
+            let interacting = self.is_interacting();
+            let signature = self.get_signature();
+            if signature != self.rendered_signature || interacting != self.rendered_interactive {
+                self.rendered_signature = signature;
+                self.rendered_interactive = interacting;
+                self.tiles = self.get_image_tiles();
+            }
+
+            let count = self.get_tiles_per_frame().min(self.tiles.len());
+            let tiles: Vec<Region> = self.tiles.drain(..count).collect();
+            self.rendered_pixels = tiles.iter().map(|tile| (tile.width * tile.height) as u32).sum();
+
+            let recording = self.recording_steps && !tiles.is_empty();
+            if recording {
+                let resolution = if self.rendered_interactive {
+                    self.framebuffer_interactive.get_resolution()
+                } else {
+                    self.framebuffer.get_resolution()
+                };
+                self.steps.bind_image(resolution.x as u32, resolution.y as u32);
+            }
+
+            let framebuffer = if self.rendered_interactive {
+                &self.framebuffer_interactive
+            } else {
+                &self.framebuffer
+            };
+            for tile in tiles.iter() {
+                tile.clip(|| {
+                    self.render_into(renderer, framebuffer, None, false, |program| {
+                        program.uniform_1i("u_picking", 0);
+                        program.uniform_1i("u_probing", 0);
+                        program.uniform_1i("u_jitter", 0);
+                        program.uniform_1i("u_recording_steps", recording as i32);
+                    })
+                });
+            }
+
+            // The histogram covers whole images, so wait for the last tile.
+            if recording && self.tiles.is_empty() {
+                self.steps.reduce();
+            }
+            return true;
+        }
+
+        let signature = self.get_signature();
+        if signature != self.accumulation_signature {
+            self.accumulation_signature = signature;
+            self.accumulated_frames = 0;
+        }
+        if self.accumulated_frames >= constants::PREVIEW_MAX_ACCUMULATED_FRAMES {
+            return self.program_valid.is_some();
+        }
+
+        // Only the step counts of the first frame are recorded, since the
+        // jittered frames that follow take about as many steps.
+        let frame = self.accumulated_frames;
+        let recording = self.recording_steps && frame == 0;
+        if recording {
+            let resolution = self.framebuffer_accumulation.get_resolution();
+            self.steps.bind_image(resolution.x as u32, resolution.y as u32);
+        }
+
+        // Blend the new frame with a weight of `1 / (n + 1)`, which
+        // keeps a running average of all of the frames so far.
+        unsafe {
+            gl::BlendColor(0.0, 0.0, 0.0, 1.0 / (frame + 1) as f32);
+            gl::BlendFunc(gl::CONSTANT_ALPHA, gl::ONE_MINUS_CONSTANT_ALPHA);
+        }
+        let framebuffer = &self.framebuffer_accumulation;
+        let rendered = self.render_into(renderer, framebuffer, None, false, |program| {
+            program.uniform_1i("u_picking", 0);
+            program.uniform_1i("u_probing", 0);
+            program.uniform_1i("u_jitter", 1);
+            program.uniform_1ui("u_frame", frame);
+            program.uniform_1i("u_recording_steps", recording as i32);
+        });
+        unsafe {
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        }
+
+        if rendered && recording {
+            self.steps.reduce();
+        }
+        if rendered {
+            self.accumulated_frames += 1;
+            let resolution = self.framebuffer_accumulation.get_resolution();
+            self.rendered_pixels = (resolution.x * resolution.y) as u32;
+        }
+        rendered
+    }
+
+    /// Saves the scene to a PNG at `path`, at the full resolution of the
+    /// offscreen framebuffer (rather than the size of the preview window).
+    /// While the camera is moving, the preview shows a frame that was
+    /// rendered at a lower resolution, so the scene is raymarched again.
+    /// Note that the caller is responsible for restoring the viewport.
+    pub fn save_screenshot(&mut self, renderer: &Renderer, path: &Path) -> Result<(), String> {
+        if self.program_valid.is_none() {
+            return Err("the shader graph isn't valid".to_string());
+        }
+
+        if self.is_accumulating() {
+            // The accumulated frames are stored as linear, floating-point
+            // colors, which are encoded when copying them.
+            self.framebuffer_accumulation.blit_to(&self.framebuffer);
+        } else {
+            self.render_into(renderer, &self.framebuffer, None, false, |program| {
+                program.uniform_1i("u_picking", 0);
+                program.uniform_1i("u_probing", 0);
+                program.uniform_1i("u_jitter", 0);
+            });
+        }
+        self.framebuffer.save_png(path)
+    }
+
+    /// Renders a sequence of `settings.frames` frames at the resolution in
+    /// `settings` through the perspective camera, and passes each one to
+    /// `save_frame` along with its index. In turntable mode, the camera
+    /// makes one full orbit at the current time. In timeline mode, time
+    /// advances from the start of `time_range` towards its end. In both
+    /// cases, the last frame stops one step short of the first, so that
+    /// the sequence loops seamlessly. The camera and time are restored
+    /// afterwards.
+    pub fn capture<F>(
+        &mut self,
+        renderer: &Renderer,
+        mode: CaptureMode,
+        settings: &CaptureSettings,
+        time_range: (f32, f32),
+        mut save_frame: F,
+    ) -> Result<(), String>
+    where
+        F: FnMut(u32, &Framebuffer) -> Result<(), String>,
+    {
+        if self.program_valid.is_none() {
+            return Err("the shader graph isn't valid".to_string());
+        }
+
+        let framebuffer = Framebuffer::new(settings.width, settings.height);
+        let yaw = self.camera.yaw;
+        let time = self.time;
+        self.capture_aspect = Some(settings.width as f32 / settings.height as f32);
+
+        let mut result = Ok(());
+        for index in 0..settings.frames {
+            let progress = index as f32 / settings.frames as f32;
+            match mode {
+                CaptureMode::Turntable => {
+                    self.camera.yaw = yaw + 360.0 * progress;
+                    self.camera.rebuild_basis();
+                }
+                CaptureMode::Timeline => {
+                    self.time = time_range.0 + (time_range.1 - time_range.0) * progress;
+                }
+            }
+
+            let view = Some(View::Perspective);
+            self.render_into(renderer, &framebuffer, view, false, |program| {
+                program.uniform_1i("u_picking", 0);
+                program.uniform_1i("u_probing", 0);
+                program.uniform_1i("u_jitter", 0);
+            });
+            result = save_frame(index, &framebuffer);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.camera.yaw = yaw;
+        self.camera.rebuild_basis();
+        self.time = time;
+        self.capture_aspect = None;
+        result
+    }
+
+    /// Raymarches the scene through the perspective camera at the
+    /// resolution in `benchmark`, timing each of its frames on the CPU and
+    /// the GPU, and then casts a grid of rays through the same view to
+    /// gather step counts. A few frames are rendered before any are timed,
+    /// so that the driver can finish any work that it defers until a
+    /// program is first used. Nothing else about the scene changes.
+    pub fn benchmark(
+        &mut self,
+        renderer: &Renderer,
+        benchmark: &Benchmark,
+    ) -> Result<Report, String> {
+        if self.program_valid.is_none() {
+            return Err("the shader graph isn't valid".to_string());
+        }
+
+        let framebuffer = Framebuffer::new(benchmark.width, benchmark.height);
+        self.capture_aspect = Some(benchmark.width as f32 / benchmark.height as f32);
+        let view = Some(View::Perspective);
+        let set_uniforms = |program: &Program| {
+            program.uniform_1i("u_picking", 0);
+            program.uniform_1i("u_probing", 0);
+            program.uniform_1i("u_jitter", 0);
+        };
+
+        for _ in 0..constants::BENCHMARK_WARMUP_FRAMES {
+            self.render_into(renderer, &framebuffer, view, false, set_uniforms);
+        }
+        unsafe {
+            gl::Finish();
+        }
+
+        // Waiting for each frame to finish keeps the frames from overlapping,
+        // so that every one of them is timed on its own.
+        let mut report = Report::new(benchmark);
+        let mut timer = GpuTimer::new();
+        for _ in 0..benchmark.frames {
+            let start = Instant::now();
+            timer.begin();
+            self.render_into(renderer, &framebuffer, view, false, set_uniforms);
+            timer.end();
+            let cpu = perf::to_milliseconds(&start.elapsed());
+
+            let gpu = timer.finish().iter().sum();
+            report.frames.push(FrameTiming { cpu, gpu });
+        }
+
+        let grid = constants::BENCHMARK_PROBE_GRID;
+        for y in 0..grid {
+            for x in 0..grid {
+                let uv = Vector2::new(
+                    (x as f32 + 0.5) / grid as f32,
+                    (y as f32 + 0.5) / grid as f32,
+                );
+                if let Some(probe) = self.cast_probe(renderer, View::Perspective, false, &uv) {
+                    report.steps.push(probe.steps);
+                    if probe.hit {
+                        report.hits += 1;
+                    }
+                }
+            }
+        }
+
+        self.capture_aspect = None;
+        Ok(report)
+    }
+
+    /// Returns the texture coordinates of `point` (in network editor
+    /// coordinates) within the preview window, if it lies inside.
+    pub fn get_texcoords(&self, point: &Vector2<f32>) -> Option<Vector2<f32>> {
+        if !self.bounds.inside(point) {
+            return None;
+        }
+
+        // The top of the preview window corresponds to `t = 1`.
+        let uv = point - self.bounds.get_upper_left();
+        let size = self.bounds.get_size();
+        Some(Vector2::new(uv.x / size.x, 1.0 - uv.y / size.y))
+    }
+
+    /// Casts a ray through the preview at `point` (in network editor
+    /// coordinates) and returns the parameter index of the primitive
+    /// that it hits (if any).
+    pub fn pick(&self, renderer: &Renderer, point: &Vector2<f32>) -> Option<usize> {
+        let uv = self.get_texcoords(point)?;
+        let compare = self.is_compare_at(&uv);
+        let (view, uv) = self.get_view_at(&uv);
+
+        let framebuffer = &self.framebuffer_pick;
+        let rendered = self.render_into(renderer, framebuffer, Some(view), compare, |program| {
+            program.uniform_1i("u_picking", 1);
+            program.uniform_1i("u_probing", 0);
+            program.uniform_1i("u_jitter", 0);
+            program.uniform_2f("u_pick_uv", &uv);
+        });
+        if !rendered {
+            return None;
+        }
+
+        let id = self.framebuffer_pick.read_pixel(0, 0).x;
+        if id >= 0.0 {
+            Some(id as usize)
         } else {
+            None
+        }
+    }
+
+    /// Returns `true` if clicking in the preview probes the surface.
+    pub fn is_probing(&self) -> bool {
+        self.probing
+    }
+
+    /// Switches between probing the surface and selecting ops when the
+    /// user clicks in the preview.
+    pub fn toggle_probing(&mut self) {
+        self.probing = !self.probing;
+        self.probe = None;
+    }
+
+    /// Returns the results of the most recent probe (if any).
+    pub fn get_probe(&self) -> Option<&Probe> {
+        self.probe.as_ref()
+    }
+
+    /// Casts a ray through the preview at `point` (in network editor
+    /// coordinates) and records where (and how quickly) it hit the
+    /// surface.
+    pub fn probe(&mut self, renderer: &Renderer, point: &Vector2<f32>) {
+        let uv = match self.get_texcoords(point) {
+            Some(uv) => uv,
+            None => return,
+        };
+        let compare = self.is_compare_at(&uv);
+        let (view, uv) = self.get_view_at(&uv);
+        self.probe = self.cast_probe(renderer, view, compare, &uv);
+    }
+
+    /// Casts a ray through `view` at the texture coordinates `uv` (with
+    /// the comparison program, if `compare` is set) and returns where it
+    /// hit the surface, or `None` if there is no valid program.
+    fn cast_probe(
+        &self,
+        renderer: &Renderer,
+        view: View,
+        compare: bool,
+        uv: &Vector2<f32>,
+    ) -> Option<Probe> {
+        let framebuffer = &self.framebuffer_pick;
+        let rendered = self.render_into(renderer, framebuffer, Some(view), compare, |program| {
+            program.uniform_1i("u_picking", 0);
+            program.uniform_1i("u_probing", 1);
+            program.uniform_1i("u_jitter", 0);
+            program.uniform_2f("u_pick_uv", uv);
+        });
+        if !rendered {
+            return None;
+        }
+
+        let first = self.framebuffer_pick.read_pixel(0, 0);
+        let second = self.framebuffer_pick.read_pixel(1, 0);
+        Some(Probe {
+            position: first.truncate(),
+            distance: first.w,
+            normal: second.truncate(),
+            steps: second.w as u32,
+            hit: first.w >= 0.0,
+        })
+    }
+
+    /// Draws the fall-back shader, which indicates the error state of
+    /// the current graph, into the preview's offscreen framebuffer.
+    fn render_fallback(&self, renderer: &Renderer) {
+        let projection = cgmath::ortho(0.0, 1.0, 0.0, 1.0, -1.0, 1.0);
+        let resolution = self.framebuffer.get_resolution();
+        let region = Region::new(0, 0, resolution.x as i32, resolution.y as i32);
+        region.clip(|| {
+            self.framebuffer.bind();
             self.program_error.bind();
             self.program_error
-                .uniform_matrix_4f("u_model_matrix", &self.bounds.get_model_matrix());
+                .uniform_matrix_4f("u_model_matrix", &Matrix4::identity());
             self.program_error
                 .uniform_matrix_4f("u_projection_matrix", &projection);
-        }
+
+            // The fall-back shader writes every pixel, and its alpha is
+            // applied when the framebuffer is drawn in the network editor.
+            unsafe {
+                gl::Disable(gl::BLEND);
+            }
+            renderer.draw_rect_inner();
+            unsafe {
+                gl::Enable(gl::BLEND);
+            }
+
+            self.program_error.unbind();
+            self.framebuffer.unbind();
+        });
     }
 
     pub fn handle_interaction(&mut self, mouse: &MouseInfo) {
-        if self.bounds.inside(&mouse.curr) {
-            let offset = -mouse.velocity();
+        let scroll = mouse.scroll - self.last_scroll;
+        self.last_scroll = mouse.scroll;
 
-            // Handle camera rotation.
-            if mouse.ldown {
-                self.camera.yaw += offset.x * constants::PREVIEW_ROTATION_SENSITIVITY;
-                self.camera.pitch += offset.y * constants::PREVIEW_ROTATION_SENSITIVITY;
-                self.camera.pitch.min(89.0).max(-89.0);
-                self.camera.rebuild_basis();
+        self.focused = self.bounds.inside(&mouse.curr);
+        self.cursor = self.get_texcoords(&mouse.curr);
+        if !self.focused {
+            return;
+        }
+
+        // Dragging the split line moves it, rather than the camera.
+        if mouse.ldown {
+            if mouse.clicked == mouse.curr && self.is_over_split(&mouse.curr) {
+                self.dragging_split = true;
+            }
+            if self.dragging_split {
+                let x = mouse.curr.x - self.bounds.get_upper_left().x;
+                self.split = (x / self.bounds.get_size().x).max(0.0).min(1.0);
+                return;
+            }
+        } else {
+            self.dragging_split = false;
+        }
+
+        let offset = -mouse.velocity();
+        if mouse.ldown || mouse.rdown || scroll != 0.0 {
+            self.mark_interaction();
+        }
+
+        match self.camera_mode {
+            CameraMode::Orbit => {
+                // Left-drag orbits the camera around its focus point.
+                if mouse.ldown {
+                    self.camera.orbit(&offset);
+                }
+
+                // Right-drag pans the focus point.
+                if mouse.rdown {
+                    self.camera.pan(&offset);
+                }
+
+                // Scrolling dollies the camera towards or away from the focus point.
+                if scroll != 0.0 {
+                    self.camera.dolly(scroll);
+                }
             }
+            CameraMode::Fly => {
+                // Left-drag looks around.
+                if mouse.ldown {
+                    self.camera.look(&offset);
+                }
 
-            // Handle camera translation.
-            if mouse.rdown {
-                self.camera.position += self.camera.right * offset.x * constants::PREVIEW_TRANSLATION_SENSITIVITY;
-                self.camera.position += self.camera.front * offset.y * constants::PREVIEW_TRANSLATION_SENSITIVITY;
+                // Scrolling changes how fast the camera flies.
+                if scroll != 0.0 {
+                    self.fly_speed = (self.fly_speed * (1.0 - scroll * 2.0))
+                        .max(constants::PREVIEW_MIN_FLY_SPEED);
+                }
             }
         }
     }
 
-    fn bind_transforms(&self) {
-        unsafe {
-            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, self.ssbo);
-        }
+    /// Binds the buffer that holds all of the op parameters.
+    pub fn bind_transforms(&self) {
+        self.params_buffer.bind();
     }
 }
 
-impl Drop for Preview {
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.ssbo);
-        }
-    }
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01_00-00-00");
+    assert_eq!(format_timestamp(1521654309), "2018-03-21_17-45-09");
+    assert_eq!(format_timestamp(951782400), "2000-02-29_00-00-00");
+}
+
+#[test]
+fn test_get_changed_range() {
+    let old = vec![0.0; 12];
+    assert_eq!(get_changed_range(&old, &old), None);
+    assert_eq!(get_changed_range(&old, &old[..8]), None);
+
+    // Changes are widened to the parameters of whole ops.
+    let mut new = old.clone();
+    new[5] = 1.0;
+    assert_eq!(get_changed_range(&old, &new), Some((4, 8)));
+    new[10] = 1.0;
+    assert_eq!(get_changed_range(&old, &new), Some((4, 12)));
+
+    // Everything is uploaded the first time.
+    assert_eq!(get_changed_range(&[], &old), Some((0, 12)));
+    new.extend_from_slice(&[0.0; 4]);
+    assert_eq!(get_changed_range(&old, &new), Some((4, 16)));
+}
+
+#[test]
+fn test_get_tiles() {
+    let size = constants::PREVIEW_TILE_SIZE;
+    let resolution = Vector2::new((size * 2 + 10) as f32, size as f32);
+
+    // The tile under the focus comes first, followed by the narrow one at
+    // the right edge (whose center is closer than the one to the left).
+    let tiles = get_tiles(&resolution, &Vector2::new(size as f32 * 1.5, 0.0));
+    assert_eq!(tiles.len(), 3);
+    assert_eq!(tiles[0], Region::new(size, 0, size, size));
+    assert_eq!(tiles[2], Region::new(0, 0, size, size));
+    assert_eq!(tiles[1], Region::new(size * 2, 0, 10, size));
+
+    let pixels: i32 = tiles.iter().map(|tile| tile.width * tile.height).sum();
+    assert_eq!(pixels, resolution.x as i32 * resolution.y as i32);
 }
@@ -0,0 +1,161 @@
+use gl::{self, types::*};
+
+/// The GLSL version (and profile) that the shader graph is generated for,
+/// which decides how op parameters are handed to it and which layout
+/// qualifiers it may use.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Profile {
+    /// GLSL 3.30, which can't assign locations to the outputs of one stage
+    /// and the inputs of the next
+    Glsl330,
+
+    /// GLSL 4.10 (i.e. macOS)
+    Glsl410,
+
+    /// GLSL 4.30, the first version with shader storage buffer objects
+    Glsl430,
+
+    /// GLSL 4.50
+    Glsl450,
+
+    /// GLSL ES 3.10, whose fragment shaders aren't guaranteed to have
+    /// access to shader storage buffer objects
+    Es310,
+}
+
+impl Profile {
+    /// Returns the profile with the given `name` (i.e. `"410"` or `"es310"`),
+    /// as passed on the command line.
+    pub fn from_name(name: &str) -> Option<Profile> {
+        match name {
+            "330" => Some(Profile::Glsl330),
+            "410" => Some(Profile::Glsl410),
+            "430" => Some(Profile::Glsl430),
+            "450" => Some(Profile::Glsl450),
+            "es310" => Some(Profile::Es310),
+            _ => None,
+        }
+    }
+
+    /// Returns the newest profile supported by an OpenGL `major.minor` context.
+    pub fn from_version(major: GLint, minor: GLint) -> Profile {
+        if (major, minor) >= (4, 5) {
+            Profile::Glsl450
+        } else if (major, minor) >= (4, 3) {
+            Profile::Glsl430
+        } else if (major, minor) >= (4, 1) {
+            Profile::Glsl410
+        } else {
+            Profile::Glsl330
+        }
+    }
+
+    /// Returns the newest profile supported by the current OpenGL context.
+    pub fn detect() -> Profile {
+        let mut major = 0;
+        let mut minor = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+            gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+        }
+
+        let profile = Profile::from_version(major, minor);
+        if !profile.uses_storage_buffer() {
+            warn!(
+                "OpenGL {}.{} doesn't support SSBOs: falling back to a uniform buffer",
+                major,
+                minor
+            );
+        }
+        profile
+    }
+
+    /// Returns `true` if the op parameters live in a shader storage buffer
+    /// object (SSBO) and `false` if they live in a uniform buffer object
+    /// (UBO) of fixed size.
+    pub fn uses_storage_buffer(&self) -> bool {
+        match *self {
+            Profile::Glsl430 | Profile::Glsl450 => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the op parameters can live in a buffer that is
+    /// mapped persistently, which requires immutable storage (OpenGL 4.4)
+    /// and direct state access (OpenGL 4.5).
+    pub fn supports_persistent_mapping(&self) -> bool {
+        *self == Profile::Glsl450
+    }
+
+    /// Returns the `#version` directive that generated shaders start with.
+    pub fn get_version_directive(&self) -> &'static str {
+        match *self {
+            Profile::Glsl330 => "#version 330",
+            Profile::Glsl410 => "#version 410",
+            Profile::Glsl430 => "#version 430",
+            Profile::Glsl450 => "#version 450",
+            Profile::Es310 => "#version 310 es",
+        }
+    }
+
+    /// Returns the lines that every stage of a generated program starts
+    /// with: the `#version` directive, default precisions (which GLSL ES
+    /// requires), and the `INTERSTAGE_LOCATION(n)` macro, which expands to
+    /// a location layout qualifier wherever one is allowed on the outputs
+    /// of one stage and the inputs of the next.
+    pub fn get_prelude(&self) -> String {
+        let precision = match *self {
+            Profile::Es310 => "precision highp float;\nprecision highp int;\n",
+            _ => "",
+        };
+        let location = match *self {
+            Profile::Glsl330 => "",
+            _ => "layout (location = n)",
+        };
+        format!(
+            "{}\n{}#define INTERSTAGE_LOCATION(n) {}",
+            self.get_version_directive(),
+            precision,
+            location
+        )
+    }
+
+    /// Returns the name of the shader file that declares the `params`
+    /// array of op parameters.
+    pub fn get_params_file(&self) -> &'static str {
+        if self.uses_storage_buffer() {
+            "params_storage.glsl"
+        } else {
+            "params_uniform.glsl"
+        }
+    }
+
+    /// Returns the buffer target that the op parameters are bound to.
+    pub fn get_params_target(&self) -> GLenum {
+        if self.uses_storage_buffer() {
+            gl::SHADER_STORAGE_BUFFER
+        } else {
+            gl::UNIFORM_BUFFER
+        }
+    }
+}
+
+#[test]
+fn test_profile_from_version() {
+    assert_eq!(Profile::from_version(4, 6), Profile::Glsl450);
+    assert_eq!(Profile::from_version(4, 3), Profile::Glsl430);
+    assert_eq!(Profile::from_version(4, 2), Profile::Glsl410);
+    assert_eq!(Profile::from_version(3, 3), Profile::Glsl330);
+    assert!(Profile::from_version(4, 3).uses_storage_buffer());
+    assert!(!Profile::from_version(4, 1).uses_storage_buffer());
+    assert!(!Profile::from_version(4, 3).supports_persistent_mapping());
+}
+
+#[test]
+fn test_profile_from_name() {
+    assert_eq!(Profile::from_name("410"), Some(Profile::Glsl410));
+    assert_eq!(Profile::from_name("es310"), Some(Profile::Es310));
+    assert_eq!(Profile::from_name("420"), None);
+    assert!(!Profile::Es310.uses_storage_buffer());
+    assert!(Profile::Es310.get_prelude().starts_with("#version 310 es\nprecision"));
+}
@@ -4,10 +4,100 @@ use gl::types::*;
 use cgmath;
 use cgmath::{Array, Matrix, Matrix4, Vector2, Vector3, Vector4};
 
+use constants;
+use shader_error::{self, Diagnostic, ShaderError, Stage};
+
+use std::mem;
 use std::ptr;
 use std::str;
-use std::ffi::CString;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::os::raw::{c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The `COMPLETION_STATUS_KHR` query from `KHR_parallel_shader_compile`,
+/// which isn't part of the generated bindings.
+const COMPLETION_STATUS: GLenum = 0x91B1;
+
+/// Whether or not the driver compiles and links programs in the
+/// background, in which case their completion can be polled.
+static PARALLEL_COMPILE: AtomicBool = AtomicBool::new(false);
+
+/// Lets the driver compile and link programs on as many background
+/// threads as it likes, if it supports `KHR_parallel_shader_compile` (or
+/// the equivalent ARB extension). `get_proc_address` should return the
+/// address of the OpenGL function with the specified name.
+pub fn enable_parallel_compile<F>(get_proc_address: F)
+where
+    F: Fn(&str) -> *const c_void,
+{
+    let mut count = 0;
+    unsafe {
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    }
+    let extensions: Vec<String> = (0..count)
+        .map(|i| unsafe {
+            let extension = gl::GetStringi(gl::EXTENSIONS, i as GLuint);
+            CStr::from_ptr(extension as *const c_char)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+
+    let candidates = [
+        ("GL_KHR_parallel_shader_compile", "glMaxShaderCompilerThreadsKHR"),
+        ("GL_ARB_parallel_shader_compile", "glMaxShaderCompilerThreadsARB"),
+    ];
+    for &(extension, function) in candidates.iter() {
+        let address = get_proc_address(function);
+        if !extensions.iter().any(|name| name == extension) || address.is_null() {
+            continue;
+        }
+
+        unsafe {
+            let max_shader_compiler_threads: extern "system" fn(GLuint) =
+                mem::transmute(address);
+
+            // Let the driver decide how many threads to use.
+            max_shader_compiler_threads(0xFFFFFFFF);
+        }
+        PARALLEL_COMPILE.store(true, Ordering::Relaxed);
+        console!("Compiling shaders in the background ({})", extension);
+        return;
+    }
+}
+
+/// Hashes each of `parts` with the 64-bit FNV-1a hash, which (unlike the
+/// hasher in the standard library) is guaranteed to be stable between
+/// runs and compiler versions, so it can be used to name cache files.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts.iter() {
+        for byte in part.iter().chain(&[0u8]) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Returns one of the strings that describe the current OpenGL
+/// implementation, i.e. `gl::RENDERER`.
+fn get_gl_string(name: GLenum) -> String {
+    unsafe {
+        let string = gl::GetString(name);
+        if string.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(string as *const c_char)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
 
 pub struct Uniform {
     location: i32,
@@ -21,86 +111,151 @@ pub struct Program {
     vs_src: String,
     fs_src: String,
     uniforms: HashMap<String, Uniform>,
+
+    /// The names of any uniforms that were set but aren't active in this
+    /// program, so that each one is only reported once
+    missing_uniforms: RefCell<HashSet<String>>,
+
+    /// The warnings that the compiler and linker reported, which are only
+    /// known if the program wasn't loaded from the program cache
+    pub warnings: Vec<Diagnostic>,
+
+    /// Whether or not `validate_once` has been called
+    validated: Cell<bool>,
 }
 
 impl Program {
-    /// Compiles a shader of type `stage` from the source held in `src`.
-    fn compile_shader(src: &String, stage: GLenum) -> Result<GLuint, String> {
-        let shader;
+    /// Starts compiling a shader of type `stage` from the source held in
+    /// `src`. The driver may finish compiling it in the background.
+    fn compile_shader(src: &String, stage: GLenum) -> GLuint {
         unsafe {
-            shader = gl::CreateShader(stage);
+            let shader = gl::CreateShader(stage);
 
             // Attempt to compile the shader.
             let c_str = CString::new(src.as_bytes()).unwrap();
             gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
             gl::CompileShader(shader);
+            shader
+        }
+    }
 
-            // Get the compile status.
-            let mut status = gl::FALSE as GLint;
-            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    /// Returns the info log of `shader`, which holds any errors or warnings
+    /// that the compiler reported (and may be empty).
+    fn get_shader_log(shader: GLuint) -> String {
+        unsafe {
+            let mut len = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+            if len <= 1 {
+                return String::new();
+            }
+            let mut buffer = Vec::with_capacity(len as usize);
 
-            // Fail on error
-            if status != (gl::TRUE as GLint) {
-                let mut len = 0;
-                gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buffer = Vec::with_capacity(len as usize);
-
-                // Subtract 1 to skip the trailing null character.
-                buffer.set_len((len as usize) - 1);
-
-                gl::GetShaderInfoLog(
-                    shader,
-                    len,
-                    ptr::null_mut(),
-                    buffer.as_mut_ptr() as *mut GLchar,
-                );
+            // Subtract 1 to skip the trailing null character.
+            buffer.set_len((len as usize) - 1);
 
-                let error = String::from_utf8(buffer)
-                    .ok()
-                    .expect("ShaderInfoLog not valid utf8");
-                return Err(error);
-            }
+            gl::GetShaderInfoLog(
+                shader,
+                len,
+                ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut GLchar,
+            );
+
+            String::from_utf8_lossy(&buffer).into_owned()
+        }
+    }
+
+    /// Returns the info log of `shader` if it failed to compile, which
+    /// blocks until the driver has finished compiling it.
+    fn get_compile_error(shader: GLuint) -> Option<String> {
+        // Get the compile status.
+        let mut status = gl::FALSE as GLint;
+        unsafe {
+            gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
         }
 
-        Ok(shader)
+        // Fail on error
+        if status != (gl::TRUE as GLint) {
+            return Some(Program::get_shader_log(shader));
+        }
+        None
     }
 
-    fn link_program(vs: GLuint, fs: GLuint) -> Result<GLuint, String> {
+    /// Starts linking the shaders `vs` and `fs` into a new program. The
+    /// driver may finish linking it in the background.
+    fn link_program(vs: GLuint, fs: GLuint) -> GLuint {
         unsafe {
             let program = gl::CreateProgram();
             gl::AttachShader(program, vs);
             gl::AttachShader(program, fs);
+
+            // Allow the linked binary to be saved to the program cache.
+            gl::ProgramParameteri(program, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as GLint);
             gl::LinkProgram(program);
+            program
+        }
+    }
 
-            // Get the link status.
-            let mut status = gl::FALSE as GLint;
+    /// Returns the info log of `program`, which holds any errors or
+    /// warnings that the linker (or validation) reported.
+    fn get_program_log(program: GLuint) -> String {
+        unsafe {
+            let mut len: GLint = 0;
+            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+            if len <= 1 {
+                return String::new();
+            }
+            let mut buffer = Vec::with_capacity(len as usize);
+
+            // Subtract 1 to skip the trailing null character.
+            buffer.set_len((len as usize) - 1);
+
+            gl::GetProgramInfoLog(
+                program,
+                len,
+                ptr::null_mut(),
+                buffer.as_mut_ptr() as *mut GLchar,
+            );
+
+            String::from_utf8_lossy(&buffer).into_owned()
+        }
+    }
+
+    /// Returns the info log of `program` if it failed to link, which
+    /// blocks until the driver has finished linking it.
+    fn get_link_error(program: GLuint) -> Option<String> {
+        // Get the link status.
+        let mut status = gl::FALSE as GLint;
+        unsafe {
             gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+        }
 
-            // If there was an error, return the error string.
-            if status != (gl::TRUE as GLint) {
-                let mut len: GLint = 0;
-                gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buffer = Vec::with_capacity(len as usize);
-
-                // Subtract 1 to skip the trailing null character.
-                buffer.set_len((len as usize) - 1);
-
-                gl::GetProgramInfoLog(
-                    program,
-                    len,
-                    ptr::null_mut(),
-                    buffer.as_mut_ptr() as *mut GLchar,
-                );
-                gl::DeleteShader(fs);
-                gl::DeleteShader(vs);
+        // If there was an error, return the error string.
+        if status != (gl::TRUE as GLint) {
+            return Some(Program::get_program_log(program));
+        }
+        None
+    }
 
-                let error = String::from_utf8(buffer)
-                    .ok()
-                    .expect("ProgramInfoLog not valid utf8");
-                return Err(error);
-            }
+    /// Validates the program against the current OpenGL state (i.e. the
+    /// bound buffers and textures) the first time that it's called, and
+    /// reports any problems to the console. Calling this right before the
+    /// program is first drawn with also warms it up, since drivers tend
+    /// to defer some of their work until then.
+    pub fn validate_once(&self) {
+        if self.validated.replace(true) {
+            return;
+        }
 
-            Ok(program)
+        let mut status = gl::FALSE as GLint;
+        unsafe {
+            gl::ValidateProgram(self.id);
+            gl::GetProgramiv(self.id, gl::VALIDATE_STATUS, &mut status);
+        }
+        let log = Program::get_program_log(self.id);
+        if status != (gl::TRUE as GLint) {
+            warn!("Program {} failed validation:\n{}", self.id, log.trim());
+        } else if !log.trim().is_empty() {
+            debug!("Program {} validation log:\n{}", self.id, log.trim());
         }
     }
 
@@ -135,60 +290,233 @@ impl Program {
 
                 // Convert the byte array to a string.
                 name_bytes.set_len(name_length as usize);
-                let name = String::from_utf8(name_bytes).unwrap();
+                let name = String::from_utf8_lossy(&name_bytes).into_owned();
 
                 // Finally, get the uniform's location.
                 let location =
                     gl::GetUniformLocation(self.id, CString::new(name.clone()).unwrap().as_ptr());
 
-                println!(
-                    "Uniform Entry with name {:?}: size {}, type {}, location {}",
-                    name, size, ty, location
+                trace!(
+                    "Uniform {:?} in program {}: size {}, type {}, location {}",
+                    name,
+                    self.id,
+                    size,
+                    ty,
+                    location
                 );
                 self.uniforms.insert(name, Uniform { location, size, ty });
             }
         }
     }
 
-    pub fn new(vs_src: String, fs_src: String) -> Option<Program> {
-        // Make sure that compiling each of the shaders was successful.
-        let compile_vs_res = Program::compile_shader(&vs_src, gl::VERTEX_SHADER);
-        let compile_fs_res = Program::compile_shader(&fs_src, gl::FRAGMENT_SHADER);
+    /// Compiles and links a new shader program, returning the errors of
+    /// any stages that failed. This blocks until the program is ready.
+    pub fn new(vs_src: String, fs_src: String) -> Result<Program, ShaderError> {
+        Program::submit(vs_src, fs_src).finish()
+    }
 
-        match (compile_vs_res, compile_fs_res) {
-            (Ok(vs_id), Ok(fs_id)) => {
-                // Make sure that linking the shader program was successful.
-                if let Ok(id) = Program::link_program(vs_id, fs_id) {
-                    // If everything went ok, return the shader program.
-                    let mut valid_program = Program {
-                        id,
-                        vs_src,
-                        fs_src,
-                        uniforms: HashMap::new(),
-                    };
-                    valid_program.perform_reflection();
-
-                    return Some(valid_program);
-                } else {
-                    return None;
-                }
+    /// Like `new`, but goes through the on-disk program cache (see
+    /// `submit_cached`).
+    pub fn new_cached(vs_src: String, fs_src: String) -> Result<Program, ShaderError> {
+        Program::submit_cached(vs_src, fs_src).finish()
+    }
+
+    /// Compiles and links a new compute program, returning the compiler's
+    /// (or linker's) log if it fails. This blocks until the program is ready.
+    pub fn new_compute(cs_src: String) -> Result<Program, String> {
+        let cs = Program::compile_shader(&cs_src, gl::COMPUTE_SHADER);
+        if let Some(log) = Program::get_compile_error(cs) {
+            unsafe {
+                gl::DeleteShader(cs);
             }
-            // Both shader stages resulted in an error.
-            (Err(vs_err), Err(fs_err)) => {
-                println!("{}", vs_err);
-                println!("{}", fs_err);
-                return None;
+            return Err(log);
+        }
+
+        let id = unsafe {
+            let id = gl::CreateProgram();
+            gl::AttachShader(id, cs);
+            gl::LinkProgram(id);
+            gl::DeleteShader(cs);
+            id
+        };
+        if let Some(log) = Program::get_link_error(id) {
+            unsafe {
+                gl::DeleteProgram(id);
             }
-            // The vertex shader resulted in an error.
-            (Err(vs_err), Ok(_)) => {
-                println!("{}", vs_err);
-                return None;
+            return Err(log);
+        }
+
+        // Compute programs don't have a vertex shader, and their only
+        // shader takes the place of the fragment shader.
+        Ok(Program::from_id(id, String::new(), cs_src))
+    }
+
+    /// Hands the sources of a new shader program off to the driver, which
+    /// may compile and link it in the background (see `PendingProgram`).
+    pub fn submit(vs_src: String, fs_src: String) -> PendingProgram {
+        let vs = Program::compile_shader(&vs_src, gl::VERTEX_SHADER);
+        let fs = Program::compile_shader(&fs_src, gl::FRAGMENT_SHADER);
+        let id = Program::link_program(vs, fs);
+
+        PendingProgram {
+            id,
+            vs,
+            fs,
+            vs_src,
+            fs_src,
+            cache_path: None,
+        }
+    }
+
+    /// Like `submit`, but first looks for a binary of a program that was
+    /// previously linked from the same sources in the on-disk program
+    /// cache. Programs that do have to be compiled are added to the cache,
+    /// so that (for example) reopening a network or toggling a connection
+    /// back and forth doesn't pay the full compile cost every time.
+    pub fn submit_cached(vs_src: String, fs_src: String) -> PendingProgram {
+        let path = Program::get_cache_path(&vs_src, &fs_src);
+
+        if let Some(ref path) = path {
+            if let Some(id) = Program::load_binary(path) {
+                return PendingProgram {
+                    id,
+                    vs: 0,
+                    fs: 0,
+                    vs_src,
+                    fs_src,
+                    cache_path: None,
+                };
             }
-            // The fragment shader resulted in an error.
-            (Ok(_), Err(fs_err)) => {
-                println!("{}", fs_err);
+        }
+
+        let mut pending = Program::submit(vs_src, fs_src);
+        pending.cache_path = path;
+        pending
+    }
+
+    /// Wraps the linked program `id` and reflects its uniforms.
+    fn from_id(id: GLuint, vs_src: String, fs_src: String) -> Program {
+        let mut program = Program {
+            id,
+            vs_src,
+            fs_src,
+            uniforms: HashMap::new(),
+            missing_uniforms: RefCell::new(HashSet::new()),
+            warnings: Vec::new(),
+            validated: Cell::new(false),
+        };
+        program.perform_reflection();
+        program
+    }
+
+    /// Returns the path of the program cache entry for the specified
+    /// sources, or `None` if the driver doesn't support program binaries.
+    fn get_cache_path(vs_src: &str, fs_src: &str) -> Option<PathBuf> {
+        let mut formats = 0;
+        unsafe {
+            gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+        }
+        if formats == 0 {
+            return None;
+        }
+
+        // Binaries are only valid for the driver that produced them, so
+        // they are keyed by the driver as well as the sources.
+        let renderer = get_gl_string(gl::RENDERER);
+        let version = get_gl_string(gl::VERSION);
+        let hash = fnv1a(&[
+            renderer.as_bytes(),
+            version.as_bytes(),
+            vs_src.as_bytes(),
+            fs_src.as_bytes(),
+        ]);
+
+        Some(Path::new(constants::PROGRAM_CACHE_PATH).join(format!("{:016x}.bin", hash)))
+    }
+
+    /// Creates a program from the binary stored at `path`, which consists
+    /// of the binary format (as a little-endian `u32`) followed by the
+    /// binary itself. Returns `None` if there is no such entry or if the
+    /// driver rejects it, in which case the stale entry is removed.
+    fn load_binary(path: &Path) -> Option<GLuint> {
+        let mut bytes = Vec::new();
+        File::open(path).ok()?.read_to_end(&mut bytes).ok()?;
+        if bytes.len() <= 4 {
+            return None;
+        }
+
+        let format = bytes[..4]
+            .iter()
+            .rev()
+            .fold(0u32, |format, byte| (format << 8) | *byte as u32);
+        let binary = &bytes[4..];
+
+        unsafe {
+            let id = gl::CreateProgram();
+            gl::ProgramBinary(
+                id,
+                format,
+                binary.as_ptr() as *const GLvoid,
+                binary.len() as GLsizei,
+            );
+
+            let mut status = gl::FALSE as GLint;
+            gl::GetProgramiv(id, gl::LINK_STATUS, &mut status);
+            if status != (gl::TRUE as GLint) {
+                gl::DeleteProgram(id);
+                let _ = fs::remove_file(path);
                 return None;
             }
+            Some(id)
+        }
+    }
+
+    /// Writes this program's binary to `path` (see `load_binary`).
+    fn save_binary(&self, path: &Path) {
+        let mut length = 0;
+        unsafe {
+            gl::GetProgramiv(self.id, gl::PROGRAM_BINARY_LENGTH, &mut length);
+        }
+        if length <= 0 {
+            return;
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut written = 0;
+        let mut format = 0;
+        unsafe {
+            gl::GetProgramBinary(
+                self.id,
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+        binary.truncate(written as usize);
+
+        let mut bytes = Vec::with_capacity(binary.len() + 4);
+        bytes.extend((0..4).map(|i| (format >> (i * 8)) as u8));
+        bytes.extend(binary);
+
+        let result = fs::create_dir_all(constants::PROGRAM_CACHE_PATH)
+            .and_then(|_| File::create(path))
+            .and_then(|mut file| file.write_all(&bytes));
+        if let Err(error) = result {
+            warn!("Failed to cache program binary {}: {}", path.display(), error);
+        }
+    }
+
+    /// Assigns the uniform block called `name` to the uniform buffer
+    /// binding point `binding`, which (before GLSL 4.20) can't be done
+    /// with a layout qualifier in the shader itself.
+    pub fn bind_uniform_block(&self, name: &str, binding: GLuint) {
+        let c_str = CString::new(name).unwrap();
+        unsafe {
+            let index = gl::GetUniformBlockIndex(self.id, c_str.as_ptr());
+            if index != gl::INVALID_INDEX {
+                gl::UniformBlockBinding(self.id, index, binding);
+            }
         }
     }
 
@@ -204,105 +532,240 @@ impl Program {
         }
     }
 
+    /// Returns the location of the uniform called `name`, which was cached
+    /// during reflection. Uniforms that aren't active in this program (i.e.
+    /// because they were optimized out) are reported the first time they
+    /// are set, after which they are silently ignored.
+    fn get_uniform_location(&self, name: &str) -> GLint {
+        // Reflection reports arrays by the name of their first element.
+        if let Some(uniform) = self.uniforms
+            .get(name)
+            .or_else(|| self.uniforms.get(&format!("{}[0]", name)))
+        {
+            return uniform.location;
+        }
+
+        if self.missing_uniforms.borrow_mut().insert(name.to_string()) {
+            debug!("Uniform {:?} is not active in program {}", name, self.id);
+        }
+
+        // OpenGL silently ignores any attempts to set location -1.
+        -1
+    }
+
     pub fn uniform_1i(&self, name: &str, value: i32) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform1i(self.id, location, value as gl::types::GLint);
         }
     }
 
     pub fn uniform_2i(&self, name: &str, value: &cgmath::Vector2<i32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform2iv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_3i(&self, name: &str, value: &cgmath::Vector3<i32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform3iv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_4i(&self, name: &str, value: &cgmath::Vector4<i32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform4iv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_1ui(&self, name: &str, value: u32) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform1ui(self.id, location, value as gl::types::GLuint);
         }
     }
 
     pub fn uniform_2ui(&self, name: &str, value: &cgmath::Vector2<u32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform2uiv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_3ui(&self, name: &str, value: &cgmath::Vector3<u32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform3uiv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_4ui(&self, name: &str, value: &cgmath::Vector4<u32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform4uiv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_1f(&self, name: &str, value: f32) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform1f(self.id, location, value as gl::types::GLfloat);
         }
     }
 
     pub fn uniform_2f(&self, name: &str, value: &cgmath::Vector2<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform2fv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_3f(&self, name: &str, value: &cgmath::Vector3<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform3fv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_4f(&self, name: &str, value: &cgmath::Vector4<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniform4fv(self.id, location, 1, value.as_ptr());
         }
     }
 
     pub fn uniform_matrix_3f(&self, name: &str, value: &cgmath::Matrix3<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniformMatrix3fv(self.id, location, 1, gl::FALSE, value.as_ptr());
         }
     }
 
     pub fn uniform_matrix_4f(&self, name: &str, value: &cgmath::Matrix4<f32>) {
         unsafe {
-            let location = gl::GetUniformLocation(self.id, CString::new(name).unwrap().as_ptr());
+            let location = self.get_uniform_location(name);
             gl::ProgramUniformMatrix4fv(self.id, location, 1, gl::FALSE, value.as_ptr());
         }
     }
 }
 
+/// A shader program that has been handed off to the driver, which may
+/// still be compiling and linking it in the background. Polling `is_ready`
+/// until it returns `true` before calling `finish` avoids stalling the
+/// render loop while the driver does its work.
+pub struct PendingProgram {
+    /// The OpenGL handle to the program
+    id: GLuint,
+
+    /// The OpenGL handles to the vertex and fragment shaders, which are
+    /// zero if the program was loaded from the program cache
+    vs: GLuint,
+    fs: GLuint,
+
+    vs_src: String,
+    fs_src: String,
+
+    /// Where the program's binary should be cached once it has been
+    /// linked, if anywhere
+    cache_path: Option<PathBuf>,
+}
+
+impl PendingProgram {
+    /// Returns `true` if the driver has finished compiling and linking
+    /// the program (i.e. `finish` won't block) and `false` otherwise.
+    pub fn is_ready(&self) -> bool {
+        // Without parallel compilation, the driver does all of the work
+        // as soon as the status of the program is queried.
+        if !PARALLEL_COMPILE.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        let mut status = gl::TRUE as GLint;
+        unsafe {
+            gl::GetProgramiv(self.id, COMPLETION_STATUS, &mut status);
+        }
+        status == (gl::TRUE as GLint)
+    }
+
+    /// Waits for the driver to finish compiling and linking the program,
+    /// returning the errors of any stages that failed.
+    pub fn finish(mut self) -> Result<Program, ShaderError> {
+        // Take ownership of the OpenGL objects, so that they aren't
+        // deleted when `self` is dropped.
+        let (id, vs, fs) = (self.id, self.vs, self.fs);
+        self.id = 0;
+        self.vs = 0;
+        self.fs = 0;
+        let vs_src = mem::replace(&mut self.vs_src, String::new());
+        let fs_src = mem::replace(&mut self.fs_src, String::new());
+
+        // Programs that were loaded from the cache have already been linked.
+        let mut warnings = Vec::new();
+        if vs != 0 {
+            let vs_err = Program::get_compile_error(vs)
+                .map(|log| ShaderError::new(Stage::Vertex, &log, Some(&vs_src)));
+            let fs_err = Program::get_compile_error(fs)
+                .map(|log| ShaderError::new(Stage::Fragment, &log, Some(&fs_src)));
+
+            // The compiler may report warnings even if it succeeded.
+            let vs_log = Program::get_shader_log(vs);
+            let fs_log = Program::get_shader_log(fs);
+            warnings.extend(shader_error::parse_warnings(Stage::Vertex, &vs_log, Some(&vs_src)));
+            warnings.extend(shader_error::parse_warnings(Stage::Fragment, &fs_log, Some(&fs_src)));
+            unsafe {
+                gl::DeleteShader(vs);
+                gl::DeleteShader(fs);
+            }
+
+            let error = match (vs_err, fs_err) {
+                // Both shader stages resulted in an error.
+                (Some(vs_err), Some(fs_err)) => Some(vs_err.merge(fs_err)),
+
+                // One of the shader stages resulted in an error.
+                (Some(err), None) | (None, Some(err)) => Some(err),
+
+                // Make sure that linking the shader program was successful.
+                (None, None) => Program::get_link_error(id)
+                    .map(|log| ShaderError::new(Stage::Link, &log, None)),
+            };
+            if let Some(error) = error {
+                unsafe {
+                    gl::DeleteProgram(id);
+                }
+                return Err(error);
+            }
+
+            let link_log = Program::get_program_log(id);
+            warnings.extend(shader_error::parse_warnings(Stage::Link, &link_log, None));
+        }
+
+        let mut program = Program::from_id(id, vs_src, fs_src);
+        program.warnings = warnings;
+        if let Some(ref path) = self.cache_path {
+            program.save_binary(path);
+        }
+        Ok(program)
+    }
+}
+
+impl Drop for PendingProgram {
+    fn drop(&mut self) {
+        // The program was abandoned before it was finished.
+        unsafe {
+            if self.vs != 0 {
+                gl::DeleteShader(self.vs);
+                gl::DeleteShader(self.fs);
+            }
+            if self.id != 0 {
+                gl::DeleteProgram(self.id);
+            }
+        }
+    }
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
         unsafe {
@@ -310,3 +773,11 @@ impl Drop for Program {
         }
     }
 }
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(&[]), 0xcbf29ce484222325);
+
+    // Parts are separated, so moving bytes between them changes the hash.
+    assert_ne!(fnv1a(&[b"ab", b"c"]), fnv1a(&[b"a", b"bc"]));
+}
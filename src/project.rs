@@ -0,0 +1,589 @@
+use cgmath::{Point3, Vector2};
+use serde_json::{self, Value};
+
+use constants;
+use operator::OpFamily;
+use preview::{Gizmos, PreviewSettings, Shading};
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// The version of the format that projects (and snippets) are saved in,
+/// which is bumped whenever a change would prevent older versions of the
+/// application from loading them correctly. Each bump comes with a new
+/// entry in `MIGRATIONS`.
+pub const PROJECT_VERSION: u64 = 2;
+
+/// Upgrades the JSON of a project or snippet by a single version, or
+/// returns an error if that isn't possible.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations between consecutive versions of the format, where the
+/// one at index `i` upgrades files from version `i + 1` to `i + 2`. When
+/// op families are renamed, parameters are reordered, or fields change,
+/// a migration keeps older files loading.
+static MIGRATIONS: &[Migration] = &[add_sources];
+
+/// Version 2: projects include the code of their custom ops, like snippets
+/// already did, so that they don't depend on the files in `CUSTOM_OP_PATH`.
+fn add_sources(value: &mut Value) -> Result<(), String> {
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| "expected an object".to_string())?;
+    if !object.contains_key("sources") {
+        object.insert("sources".to_string(), Value::Object(serde_json::Map::new()));
+    }
+    Ok(())
+}
+
+/// A single op inside of a project.
+#[derive(Clone)]
+pub struct ProjectOp {
+    /// The name of the op
+    pub name: String,
+
+    /// The op family
+    pub family: OpFamily,
+
+    /// The position of the op's upper-left corner in the network editor
+    pub position: Vector2<f32>,
+
+    /// The op's parameters
+    pub params: [f32; constants::PARAMETER_CAPACITY],
+
+    /// The name of the file that holds the op's code, if it is a custom op
+    pub code: Option<String>,
+
+    /// Whether the op's distance field is baked into a 3D texture, which is
+    /// optional in the file (older files never bake anything)
+    pub baked: bool,
+}
+
+/// A single backdrop inside of a project.
+pub struct ProjectBackdrop {
+    /// The title of the backdrop
+    pub title: String,
+
+    /// The position of the backdrop's upper-left corner
+    pub position: Vector2<f32>,
+
+    /// The dimensions of the backdrop
+    pub size: Vector2<f32>,
+
+    /// The index into `constants::BACKDROP_COLORS` of the backdrop's color
+    pub color_index: usize,
+}
+
+/// Everything that is needed to restore a network (and its preview) after
+/// the application is closed. Projects are stored on disk as JSON:
+///
+/// ```text
+/// {
+///   "version": 2,
+///   "ops": [{ "name": "sphere_0", "family": "sphere", "code": null, "baked": false,
+///             "position": [40, 80], "params": [1, 0, 0, 0] }, ...],
+///   "connections": [[0, 1], ...],
+///   "render": 2,
+///   "backdrops": [{ "title": "backdrop_0", "position": [0, 0],
+///                   "size": [300, 200], "color": 0 }],
+///   "preview": { "camera": { "focus": [0, 0, 0], ... }, "shading": "normals", ... },
+///   "sources": { "blend.glsl": "// @inputs 2\n..." }
+/// }
+/// ```
+///
+/// Like in presets, connections are indices into `ops`, listed in the order
+/// that each op's inputs should be connected. Files that were saved in an
+/// older version of the format are migrated when they are loaded.
+pub struct Project {
+    /// The ops that make up the network
+    pub ops: Vec<ProjectOp>,
+
+    /// The connections between ops, as indices into `ops`
+    pub connections: Vec<(usize, usize)>,
+
+    /// The index of the active render op (if there is one)
+    pub render: Option<usize>,
+
+    /// The backdrops that are drawn behind groups of ops
+    pub backdrops: Vec<ProjectBackdrop>,
+
+    /// The camera and view settings of the preview
+    pub settings: PreviewSettings,
+
+    /// The code of the custom ops, keyed by the name of the file that
+    /// holds it
+    pub sources: Vec<(String, String)>,
+}
+
+/// Returns `value` as a JSON number, using the shortest representation
+/// that round-trips (i.e. `0.1` rather than `0.10000000149011612`).
+fn number(value: f32) -> Value {
+    Value::from(value.to_string().parse::<f64>().unwrap_or(0.0))
+}
+
+/// Returns the field `key` of the JSON object `value`.
+fn get<'a>(value: &'a Value, key: &str) -> Result<&'a Value, String> {
+    value.get(key).ok_or_else(|| format!("missing field: {}", key))
+}
+
+fn get_f32(value: &Value, key: &str) -> Result<f32, String> {
+    get(value, key)?
+        .as_f64()
+        .map(|number| number as f32)
+        .ok_or_else(|| format!("expected a number: {}", key))
+}
+
+fn get_usize(value: &Value, key: &str) -> Result<usize, String> {
+    get(value, key)?
+        .as_u64()
+        .map(|number| number as usize)
+        .ok_or_else(|| format!("expected an index: {}", key))
+}
+
+fn get_bool(value: &Value, key: &str) -> Result<bool, String> {
+    get(value, key)?
+        .as_bool()
+        .ok_or_else(|| format!("expected a boolean: {}", key))
+}
+
+fn get_str<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    get(value, key)?
+        .as_str()
+        .ok_or_else(|| format!("expected a string: {}", key))
+}
+
+fn get_array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>, String> {
+    get(value, key)?
+        .as_array()
+        .ok_or_else(|| format!("expected an array: {}", key))
+}
+
+/// Returns the field `key` of `value` as an array of exactly `count` numbers.
+fn get_numbers(value: &Value, key: &str, count: usize) -> Result<Vec<f32>, String> {
+    let numbers = get_array(value, key)?
+        .iter()
+        .map(|number| number.as_f64().map(|number| number as f32))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("expected an array of numbers: {}", key))?;
+    if numbers.len() != count {
+        return Err(format!("expected {} numbers: {}", count, key));
+    }
+    Ok(numbers)
+}
+
+fn get_vector2(value: &Value, key: &str) -> Result<Vector2<f32>, String> {
+    let numbers = get_numbers(value, key, 2)?;
+    Ok(Vector2::new(numbers[0], numbers[1]))
+}
+
+/// Upgrades `value` from the version of the format that it was saved in
+/// to `PROJECT_VERSION`. Returns an error if it is missing a version number
+/// or was saved by a newer version of the application.
+fn migrate(value: &mut Value) -> Result<(), String> {
+    let version = get(value, "version")?
+        .as_u64()
+        .ok_or_else(|| "expected a version number".to_string())?;
+    if version == 0 || version > PROJECT_VERSION {
+        return Err(format!(
+            "it was saved with an unsupported version of the format ({}, expected at most {})",
+            version, PROJECT_VERSION
+        ));
+    }
+
+    for migration in MIGRATIONS[(version - 1) as usize..].iter() {
+        migration(value)?;
+    }
+    value["version"] = Value::from(PROJECT_VERSION);
+    Ok(())
+}
+
+fn sources_to_json(sources: &[(String, String)]) -> Value {
+    let mut object = serde_json::Map::new();
+    for (file_name, text) in sources.iter() {
+        object.insert(file_name.clone(), Value::from(text.clone()));
+    }
+    Value::Object(object)
+}
+
+/// Parses the code of custom ops in the field `sources` of `value`.
+fn get_sources(value: &Value) -> Result<Vec<(String, String)>, String> {
+    let mut sources = Vec::new();
+    let object = get(value, "sources")?
+        .as_object()
+        .ok_or_else(|| "expected an object: sources".to_string())?;
+    for (file_name, text) in object.iter() {
+        let text = text.as_str()
+            .ok_or_else(|| format!("expected a string: {}", file_name))?;
+        sources.push((file_name.clone(), text.to_string()));
+    }
+    Ok(sources)
+}
+
+fn ops_to_json(ops: &[ProjectOp]) -> Vec<Value> {
+    ops.iter()
+        .map(|op| {
+            json!({
+                "name": op.name,
+                "family": op.family.to_string(),
+                "code": op.code,
+                "baked": op.baked,
+                "position": [number(op.position.x), number(op.position.y)],
+                "params": op.params.iter().map(|&value| number(value)).collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the ops in the field `ops` of `value`.
+fn get_ops(value: &Value) -> Result<Vec<ProjectOp>, String> {
+    let mut ops = Vec::new();
+    for op in get_array(value, "ops")?.iter() {
+        let family = get_str(op, "family")?;
+        let params = get_numbers(op, "params", constants::PARAMETER_CAPACITY)?;
+        ops.push(ProjectOp {
+            name: get_str(op, "name")?.to_string(),
+            family: OpFamily::from_string(family)
+                .ok_or_else(|| format!("unknown op family: {}", family))?,
+            position: get_vector2(op, "position")?,
+            params: [params[0], params[1], params[2], params[3]],
+            code: op.get("code")
+                .and_then(|code| code.as_str())
+                .map(|code| code.to_string()),
+            baked: op.get("baked")
+                .and_then(|baked| baked.as_bool())
+                .unwrap_or(false),
+        });
+    }
+    Ok(ops)
+}
+
+fn connections_to_json(connections: &[(usize, usize)]) -> Vec<Value> {
+    connections
+        .iter()
+        .map(|&(src, dst)| json!([src, dst]))
+        .collect()
+}
+
+/// Parses the connections in the field `connections` of `value`, between
+/// `count` ops.
+fn get_connections(value: &Value, count: usize) -> Result<Vec<(usize, usize)>, String> {
+    let mut connections = Vec::new();
+    for connection in get_array(value, "connections")?.iter() {
+        let indices = connection
+            .as_array()
+            .and_then(|pair| {
+                pair.iter()
+                    .map(|index| index.as_u64().map(|index| index as usize))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| "expected a pair of indices: connections".to_string())?;
+        if indices.len() != 2 || indices.iter().any(|&index| index >= count) {
+            return Err("invalid connection".to_string());
+        }
+        connections.push((indices[0], indices[1]));
+    }
+    Ok(connections)
+}
+
+impl Project {
+    /// Converts the project into the JSON format described above.
+    pub fn to_json(&self) -> Value {
+        let backdrops: Vec<Value> = self.backdrops
+            .iter()
+            .map(|backdrop| {
+                json!({
+                    "title": backdrop.title,
+                    "position": [number(backdrop.position.x), number(backdrop.position.y)],
+                    "size": [number(backdrop.size.x), number(backdrop.size.y)],
+                    "color": backdrop.color_index,
+                })
+            })
+            .collect();
+
+        let settings = &self.settings;
+        let focus = settings.camera_focus;
+        json!({
+            "version": PROJECT_VERSION,
+            "ops": ops_to_json(&self.ops),
+            "connections": connections_to_json(&self.connections),
+            "render": self.render,
+            "backdrops": backdrops,
+            "preview": {
+                "camera": {
+                    "focus": [number(focus.x), number(focus.y), number(focus.z)],
+                    "distance": number(settings.camera_distance),
+                    "yaw": number(settings.camera_yaw),
+                    "pitch": number(settings.camera_pitch),
+                },
+                "shading": settings.shading.name(),
+                "fov": number(settings.fov),
+                "max_trace_distance": number(settings.max_trace_distance),
+                "gizmos": settings.gizmos.name(),
+                "steps": {
+                    "min": number(settings.steps_min),
+                    "max": number(settings.steps_max),
+                    "log": settings.steps_log,
+                },
+                "isolines": settings.isolines,
+            },
+            "sources": sources_to_json(&self.sources),
+        })
+    }
+
+    /// Parses a project from `value` (migrating it, if necessary), returning
+    /// an error if it is malformed or was saved by a newer version of the
+    /// application.
+    pub fn from_json(value: &Value) -> Result<Project, String> {
+        let mut value = value.clone();
+        migrate(&mut value)?;
+        let value = &value;
+
+        let ops = get_ops(value)?;
+        let connections = get_connections(value, ops.len())?;
+
+        let render = match value.get("render").and_then(|render| render.as_u64()) {
+            Some(index) if index as usize >= ops.len() => {
+                return Err("invalid render op".to_string())
+            }
+            render => render.map(|index| index as usize),
+        };
+
+        let mut backdrops = Vec::new();
+        for backdrop in get_array(value, "backdrops")?.iter() {
+            backdrops.push(ProjectBackdrop {
+                title: get_str(backdrop, "title")?.to_string(),
+                position: get_vector2(backdrop, "position")?,
+                size: get_vector2(backdrop, "size")?,
+                color_index: get_usize(backdrop, "color")?,
+            });
+        }
+
+        let preview = get(value, "preview")?;
+        let camera = get(preview, "camera")?;
+        let steps = get(preview, "steps")?;
+        let focus = get_numbers(camera, "focus", 3)?;
+        let shading = get_str(preview, "shading")?;
+        let gizmos = get_str(preview, "gizmos")?;
+        let settings = PreviewSettings {
+            camera_focus: Point3::new(focus[0], focus[1], focus[2]),
+            camera_distance: get_f32(camera, "distance")?,
+            camera_yaw: get_f32(camera, "yaw")?,
+            camera_pitch: get_f32(camera, "pitch")?,
+            shading: Shading::from_name(shading)
+                .ok_or_else(|| format!("unknown shading mode: {}", shading))?,
+            fov: get_f32(preview, "fov")?,
+            max_trace_distance: get_f32(preview, "max_trace_distance")?,
+            gizmos: Gizmos::from_name(gizmos)
+                .ok_or_else(|| format!("unknown gizmos: {}", gizmos))?,
+            steps_min: get_f32(steps, "min")?,
+            steps_max: get_f32(steps, "max")?,
+            steps_log: get_bool(steps, "log")?,
+            isolines: get_bool(preview, "isolines")?,
+        };
+
+        Ok(Project {
+            ops,
+            connections,
+            render,
+            backdrops,
+            settings,
+            sources: get_sources(value)?,
+        })
+    }
+
+    /// Loads the project from the file at `path`.
+    pub fn load(path: &Path) -> Result<Project, String> {
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|error| error.to_string())?;
+
+        let value: Value = serde_json::from_str(&text).map_err(|error| error.to_string())?;
+        Project::from_json(&value)
+    }
+
+    /// Saves the project to the file at `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json())
+            .map_err(|error| error.to_string())?;
+        File::create(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// A group of connected ops that is shared through the clipboard, in the
+/// same JSON format as a project's ops and connections. The code of each
+/// custom op is included, so that snippets can be pasted into networks on
+/// other machines:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "ops": [...],
+///   "connections": [...],
+///   "sources": { "blend.glsl": "// @inputs 2\n..." }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Snippet {
+    /// The ops that make up the snippet, whose positions are relative to
+    /// the upper-left corner of the snippet
+    pub ops: Vec<ProjectOp>,
+
+    /// The connections between ops, as indices into `ops`
+    pub connections: Vec<(usize, usize)>,
+
+    /// The code of the custom ops, keyed by the name of the file that
+    /// holds it
+    pub sources: Vec<(String, String)>,
+}
+
+impl Snippet {
+    /// Converts the snippet into the JSON format described above.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": PROJECT_VERSION,
+            "ops": ops_to_json(&self.ops),
+            "connections": connections_to_json(&self.connections),
+            "sources": sources_to_json(&self.sources),
+        })
+    }
+
+    /// Parses a snippet from `value` (migrating it, if necessary), returning
+    /// an error if it is malformed or was saved by a newer version of the
+    /// application.
+    pub fn from_json(value: &Value) -> Result<Snippet, String> {
+        let mut value = value.clone();
+        migrate(&mut value)?;
+        let ops = get_ops(&value)?;
+        let connections = get_connections(&value, ops.len())?;
+        let sources = get_sources(&value)?;
+
+        Ok(Snippet {
+            ops,
+            connections,
+            sources,
+        })
+    }
+
+    /// Parses a snippet from `text`, returning an error if it isn't one.
+    pub fn parse(text: &str) -> Result<Snippet, String> {
+        let value: Value = serde_json::from_str(text).map_err(|error| error.to_string())?;
+        Snippet::from_json(&value)
+    }
+
+    /// Converts the snippet into the text that is placed on the clipboard.
+    pub fn serialize(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_project_round_trip() {
+    use operator::PrimitiveType;
+
+    let project = Project {
+        ops: vec![
+            ProjectOp {
+                name: "sphere_0".to_string(),
+                family: OpFamily::Primitive(PrimitiveType::Sphere),
+                position: Vector2::new(40.0, -20.5),
+                params: [0.1, 0.0, 0.0, 0.0],
+                code: None,
+                baked: true,
+            },
+            ProjectOp {
+                name: "custom_1".to_string(),
+                family: OpFamily::Primitive(PrimitiveType::Custom),
+                position: Vector2::new(180.0, -20.5),
+                params: [1.0, 2.0, 3.0, 4.0],
+                code: Some("blend.glsl".to_string()),
+                baked: false,
+            },
+        ],
+        connections: vec![(0, 1)],
+        render: Some(1),
+        backdrops: vec![ProjectBackdrop {
+            title: "group".to_string(),
+            position: Vector2::new(0.0, 0.0),
+            size: Vector2::new(300.0, 200.0),
+            color_index: 2,
+        }],
+        settings: PreviewSettings {
+            camera_focus: Point3::new(0.0, 1.0, 0.0),
+            camera_distance: 5.0,
+            camera_yaw: -45.0,
+            camera_pitch: 10.0,
+            shading: Shading::Diffuse,
+            fov: 60.0,
+            max_trace_distance: 20.0,
+            gizmos: Gizmos::AxesAndGrid,
+            steps_min: 0.0,
+            steps_max: 64.0,
+            steps_log: true,
+            isolines: false,
+        },
+        sources: vec![("blend.glsl".to_string(), "// @inputs 2\n".to_string())],
+    };
+
+    let text = serde_json::to_string_pretty(&project.to_json()).unwrap();
+    assert!(text.contains("\"params\": [\n        0.1,"));
+
+    let value: Value = serde_json::from_str(&text).unwrap();
+    let loaded = Project::from_json(&value).unwrap();
+    assert_eq!(loaded.ops.len(), 2);
+    assert_eq!(loaded.ops[0].name, "sphere_0");
+    assert_eq!(loaded.ops[0].position, Vector2::new(40.0, -20.5));
+    assert_eq!(loaded.ops[0].params, [0.1, 0.0, 0.0, 0.0]);
+    assert_eq!(loaded.ops[1].code, Some("blend.glsl".to_string()));
+    assert!(loaded.ops[0].baked && !loaded.ops[1].baked);
+    assert_eq!(loaded.connections, vec![(0, 1)]);
+    assert_eq!(loaded.render, Some(1));
+    assert_eq!(loaded.backdrops[0].color_index, 2);
+    assert!(loaded.settings.shading == Shading::Diffuse);
+    assert_eq!(loaded.settings.camera_focus, Point3::new(0.0, 1.0, 0.0));
+    assert!(loaded.settings.steps_log);
+
+    assert_eq!(loaded.sources, project.sources);
+
+    // Projects that were saved before custom op code was included can
+    // still be loaded.
+    let mut older = project.to_json();
+    older["version"] = Value::from(1);
+    older.as_object_mut().unwrap().remove("sources");
+    assert!(Project::from_json(&older).unwrap().sources.is_empty());
+    assert_eq!(MIGRATIONS.len() as u64 + 1, PROJECT_VERSION);
+
+    // Projects that were saved by a newer version can't be loaded.
+    let mut newer = project.to_json();
+    newer["version"] = Value::from(PROJECT_VERSION + 1);
+    assert!(Project::from_json(&newer).is_err());
+}
+
+#[test]
+fn test_snippet_round_trip() {
+    use operator::PrimitiveType;
+
+    let snippet = Snippet {
+        ops: vec![ProjectOp {
+            name: "custom_0".to_string(),
+            family: OpFamily::Primitive(PrimitiveType::Custom),
+            position: Vector2::new(0.0, 0.0),
+            params: [0.5, 0.0, 0.0, 0.0],
+            code: Some("blend.glsl".to_string()),
+            baked: false,
+        }],
+        connections: Vec::new(),
+        sources: vec![("blend.glsl".to_string(), "// @inputs 2\n".to_string())],
+    };
+
+    let parsed = Snippet::parse(&snippet.serialize()).unwrap();
+    assert_eq!(parsed.ops.len(), 1);
+    assert_eq!(parsed.ops[0].code, Some("blend.glsl".to_string()));
+    assert_eq!(parsed.sources, snippet.sources);
+
+    assert!(Snippet::parse("not a snippet").is_err());
+    assert!(Snippet::parse("{ \"version\": 1 }").is_err());
+}
@@ -0,0 +1,89 @@
+use constants;
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The networks that were most recently saved or loaded, ordered from most
+/// to least recent. The list is stored on disk as plain text, one path per
+/// line, so that it survives restarts.
+pub struct RecentProjects {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentProjects {
+    /// Parses a list of recent networks from `text`, skipping blank lines.
+    pub fn parse(text: &str) -> RecentProjects {
+        let paths = text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .take(constants::RECENT_PROJECTS_CAPACITY)
+            .collect();
+        RecentProjects { paths }
+    }
+
+    /// Converts the list into the text format described above.
+    pub fn serialize(&self) -> String {
+        self.paths
+            .iter()
+            .map(|path| format!("{}\n", path.display()))
+            .collect()
+    }
+
+    /// Loads the list from the file at `path`, skipping any networks that
+    /// no longer exist. The list is empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> RecentProjects {
+        let mut text = String::new();
+        let _ = File::open(path).and_then(|mut file| file.read_to_string(&mut text));
+
+        let mut recent = RecentProjects::parse(&text);
+        recent.paths.retain(|path| path.exists());
+        recent
+    }
+
+    /// Saves the list to the file at `path`.
+    pub fn save(&self, path: &Path) {
+        let result = File::create(path).and_then(|mut file| {
+            file.write_all(self.serialize().as_bytes())
+        });
+        if let Err(error) = result {
+            error!("Failed to save the list of recent networks: {}", error);
+        }
+    }
+
+    /// Moves the network at `path` to the front of the list, adding it if
+    /// it isn't already there. Paths are stored as absolute paths (where
+    /// possible), so that the list doesn't depend on the working directory.
+    pub fn add(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|recent| *recent != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(constants::RECENT_PROJECTS_CAPACITY);
+    }
+
+    /// Returns the paths of the recent networks, from most to least recent.
+    pub fn get_paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns `true` if there aren't any recent networks.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+#[test]
+fn test_recent_projects() {
+    let mut recent = RecentProjects::parse("/a.json\n\n/b.json\n");
+    assert_eq!(recent.get_paths().len(), 2);
+
+    recent.add(Path::new("/b.json"));
+    assert_eq!(recent.serialize(), "/b.json\n/a.json\n");
+
+    for index in 0..constants::RECENT_PROJECTS_CAPACITY {
+        recent.add(Path::new(&format!("/network_{}.json", index)));
+    }
+    assert_eq!(recent.get_paths().len(), constants::RECENT_PROJECTS_CAPACITY);
+    assert!(!recent.get_paths().contains(&PathBuf::from("/a.json")));
+}
@@ -1,24 +1,32 @@
 use gl::{self, types::*};
-use cgmath::{self, Matrix, Matrix4, One, PerspectiveFov, SquareMatrix, Vector2, Vector4, Zero};
+use cgmath::{self, InnerSpace, Matrix, Matrix4, One, PerspectiveFov, SquareMatrix, Vector2, Vector4,
+             Zero};
 
+use assets;
+use backend::{Backend, BufferHandle, BufferUsage, LayoutHandle, ProgramHandle, StepMode,
+              TextureHandle, Topology, UniformValue, VertexAttribute};
 use bounds::Rect;
 use color::Color;
-use program::Program;
+use constants;
+use error::Error;
+use font::Font;
+use gl_backend::GlBackend;
+use preprocessor;
 use texture::Texture;
+use view::View;
 
+use std::cell::{Cell, RefCell};
 use std::mem;
-use std::ptr;
-use std::os::raw::c_void;
-use std::ffi::CString;
+use std::ops::Range;
 use std::time::{Duration, SystemTime};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub enum LineMode {
     Solid,
     Dashed,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub enum LineConnectivity {
     Segment,
     Strip,
@@ -27,45 +35,338 @@ pub enum LineConnectivity {
 #[derive(Clone)]
 pub enum DrawParams<'a> {
     Rectangle(&'a Rect),
-    Line(&'a Vec<f32>, LineMode, LineConnectivity),
+
+    /// A set of line segments or a line strip, along with the width of
+    /// the line in pixels
+    Line(&'a Vec<f32>, LineMode, LineConnectivity, f32),
+
+    /// A string of text, along with the position of its upper-left
+    /// corner and the scale of each glyph
+    Text(&'a str, Vector2<f32>, f32),
+
+    /// A rectangle with rounded corners and an (optional) border
+    RoundedRectangle(&'a Rect, Rounding),
+
+    /// The soft shadow cast by a rectangle, along with the rectangle's
+    /// corner radius and the distance over which the shadow fades out
+    Shadow(&'a Rect, f32, f32),
+}
+
+/// The corner radius and border of a rounded rectangle, in the same units
+/// as the rectangle itself.
+#[derive(Copy, Clone)]
+pub struct Rounding {
+    /// The radius of each corner
+    pub radius: f32,
+
+    /// The width of the border, which is drawn along the inside of the
+    /// rectangle's edges
+    pub border_width: f32,
+
+    /// The color of the border, which is blended over the fill color
+    /// based on its alpha
+    pub border_color: Color,
+}
+
+impl Rounding {
+    /// Returns a rounding with corners of the given `radius` and no border.
+    pub fn new(radius: f32) -> Rounding {
+        Rounding::with_border(radius, 0.0, Color::mono(0.0, 0.0))
+    }
+
+    pub fn with_border(radius: f32, border_width: f32, border_color: Color) -> Rounding {
+        Rounding {
+            radius,
+            border_width,
+            border_color,
+        }
+    }
 }
 
 pub trait Drawable<'a> {
     fn get_draw_params(&'a self) -> DrawParams<'a>;
 }
 
+/// The layers that the network editor is drawn in, from back to front.
+/// Everything that is drawn on a layer appears on top of everything that
+/// is drawn on the layers behind it, regardless of the order in which the
+/// draw calls were made.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Layer {
+    Grid,
+    Backdrops,
+    Edges,
+    Nodes,
+    Overlays,
+    Panels,
+    Tooltips,
+}
+
+impl Layer {
+    /// Returns all of the layers, from back to front.
+    pub fn all() -> [Layer; 7] {
+        [
+            Layer::Grid,
+            Layer::Backdrops,
+            Layer::Edges,
+            Layer::Nodes,
+            Layer::Overlays,
+            Layer::Panels,
+            Layer::Tooltips,
+        ]
+    }
+
+    /// Returns `true` if this layer is drawn in world space (so that it
+    /// is panned and zoomed along with the network), and `false` if it
+    /// is drawn in screen space (see `View`).
+    pub fn is_world(&self) -> bool {
+        match *self {
+            Layer::Grid | Layer::Backdrops | Layer::Edges | Layer::Nodes => true,
+            Layer::Overlays | Layer::Panels | Layer::Tooltips => false,
+        }
+    }
+}
+
+/// An owned copy of the data referenced by `DrawParams` (or a batch of
+/// instances), which is kept around until its layer is flushed.
+enum Primitive {
+    Rectangle(Rect),
+
+    /// One or more lines that are drawn together, whose vertices are the
+    /// given ranges of `Renderer::line_data`
+    Line(Vec<Range<usize>>, LineMode, LineConnectivity, f32),
+    Text(String, Vector2<f32>, f32),
+    RoundedRectangle(Rect, Rounding),
+    Shadow(Rect, f32, f32),
+    Instances(Vec<Instance>, InstanceStyle),
+}
+
+/// The way that every rectangle in an instanced batch is drawn.
+#[derive(Copy, Clone)]
+enum InstanceStyle {
+    Plain,
+    Rounded(Rounding),
+    Shadow(f32, f32),
+}
+
+/// A draw call that has been submitted to a layer but not issued yet.
+struct Command {
+    /// The primitive that will be drawn
+    primitive: Primitive,
+
+    /// The color of the primitive
+    color: Color,
+
+    /// The color map (if any)
+    color_map: Option<TextureHandle>,
+
+    /// The alpha map (if any)
+    alpha_map: Option<TextureHandle>,
+
+    /// The region of the window that the primitive is clipped to (if any)
+    clip: Option<Region>,
+}
+
+impl Command {
+    /// Folds `other` into this command if both are lines that are drawn
+    /// in the same way, so that they are issued as a single draw call.
+    /// Returns `true` if they were merged.
+    fn merge(&mut self, other: &Command) -> bool {
+        if self.color != other.color || self.color_map != other.color_map
+            || self.alpha_map != other.alpha_map || self.clip != other.clip
+        {
+            return false;
+        }
+
+        match (&mut self.primitive, &other.primitive) {
+            (
+                &mut Primitive::Line(ref mut lines, mode, connectivity, width),
+                &Primitive::Line(ref other_lines, other_mode, other_connectivity, other_width),
+            ) if mode == other_mode && connectivity == other_connectivity && width == other_width =>
+            {
+                lines.extend(other_lines.iter().cloned());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A single rectangle that is drawn as part of an instanced batch (see
+/// `Renderer::draw_instanced`).
+#[derive(Copy, Clone)]
+pub struct Instance {
+    /// The bounding box of the rectangle
+    pub bounds: Rect,
+
+    /// The color of the rectangle
+    pub color: Color,
+}
+
+impl Instance {
+    pub fn new(bounds: Rect, color: Color) -> Instance {
+        Instance { bounds, color }
+    }
+}
+
+/// A rectangular region of the current render target, in pixels, with
+/// its origin at the lower-left corner (i.e. the same convention as
+/// `glViewport` and `glScissor`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Region {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the portion of this region that also lies inside of
+    /// `other`, which is empty if the two regions don't overlap.
+    pub fn intersection(&self, other: &Region) -> Region {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let top = (self.y + self.height).min(other.y + other.height);
+        Region::new(x, y, (right - x).max(0), (top - y).max(0))
+    }
+
+    /// Returns the part of this region that is covered by `area`, whose
+    /// components (x, y, width, height) are fractions of this region's
+    /// size.
+    pub fn subregion(&self, area: &Vector4<f32>) -> Region {
+        Region::new(
+            self.x + (area.x * self.width as f32) as i32,
+            self.y + (area.y * self.height as f32) as i32,
+            (area.z * self.width as f32) as i32,
+            (area.w * self.height as f32) as i32,
+        )
+    }
+
+    /// Clips all drawing to this region while `draw` runs, restoring the
+    /// previous viewport and scissor state afterwards (so `draw` is free
+    /// to change the viewport). If a region is already being clipped to,
+    /// drawing is clipped to the intersection of the two.
+    pub fn clip<F, R>(&self, draw: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let mut viewport = [0; 4];
+        let mut scissor = [0; 4];
+        let scissor_enabled;
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl::GetIntegerv(gl::SCISSOR_BOX, scissor.as_mut_ptr());
+            scissor_enabled = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE;
+        }
+
+        let clipped = if scissor_enabled {
+            self.intersection(&Region::new(scissor[0], scissor[1], scissor[2], scissor[3]))
+        } else {
+            *self
+        };
+        unsafe {
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(clipped.x, clipped.y, clipped.width, clipped.height);
+        }
+
+        let result = draw();
+
+        unsafe {
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+            gl::Scissor(scissor[0], scissor[1], scissor[2], scissor[3]);
+            if !scissor_enabled {
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+        }
+        result
+    }
+}
+
 pub struct Renderer {
+    /// The graphics API that every draw call goes through
+    backend: Box<dyn Backend>,
+
     /// The shader program that will be used to draw sprites
-    program_draw: Program,
+    program_draw: ProgramHandle,
+
+    /// The shader program that will be used to draw batches of
+    /// rectangles in a single draw call
+    program_instanced: ProgramHandle,
 
-    /// The projection matrix used to render the network orthographically
-    projection: Matrix4<f32>,
+    /// The vertex layout for sprite rendering
+    vao: LayoutHandle,
 
-    /// The VAO that contains vertex attribute descriptions for sprite
-    /// rendering
-    vao: u32,
+    /// The vertex layout for instanced rectangle rendering
+    vao_instanced: LayoutHandle,
 
     /// The VBO that contains the vertex data necessary for rendering
     /// rectangular sprites
-    vbo_rect: u32,
+    vbo_rect: BufferHandle,
+
+    /// The VBO that will be dynamically updated with per-instance
+    /// data (bounds and color) for instanced rendering
+    vbo_instance: BufferHandle,
 
     /// The VBO that will be dynamically updated with vertex data
     /// for rendering lines
-    vbo_line: u32,
+    vbo_line: BufferHandle,
+
+    /// The number of floats that `vbo_line` can currently hold, which
+    /// grows as larger batches of lines are drawn
+    vbo_line_capacity: Cell<usize>,
+
+    /// The VBO that will be dynamically updated with vertex data
+    /// for rendering text
+    vbo_text: BufferHandle,
 
-    /// The zoom of the network editor
-    zoom: f32,
+    /// The font that will be used to draw text, whose glyph atlas is
+    /// loaded once and shared by every subsystem that draws text
+    font: Font,
 
-    /// The resolution (in pixels) of the network editor
-    size: Vector2<f32>,
+    /// The part of the network editor that is visible, which determines
+    /// where layers that are drawn in world space end up on screen
+    view: View,
+
+    /// The number of world units per pixel of the layer that is being
+    /// flushed, which keeps lines the same width at every zoom
+    line_scale: Cell<f32>,
 
     /// An application timer
     time: SystemTime,
+
+    /// The layer that draw calls are currently submitted to
+    layer: Cell<Layer>,
+
+    /// The region that draw calls are currently clipped to (if any)
+    clip: Cell<Option<Region>>,
+
+    /// The draw calls that have been submitted to each layer since the
+    /// last flush, indexed by layer
+    commands: RefCell<Vec<Vec<Command>>>,
+
+    /// The vertices of every line that has been submitted since the last
+    /// flush, which is cleared (but not freed) after each flush
+    line_data: RefCell<Vec<f32>>,
+
+    /// The triangles that the line that is being drawn is expanded into,
+    /// which is reused from one line to the next
+    ribbon: RefCell<Vec<f32>>,
 }
 
 impl Renderer {
-    /// Constructs and returns a new renderer instance.
-    pub fn new(size: Vector2<f32>) -> Renderer {
+    /// Constructs and returns a new renderer instance, which fails if its
+    /// shaders don't compile or its font can't be loaded.
+    pub fn new(size: Vector2<f32>) -> Result<Renderer, Error> {
         static VERTEX_DATA: [GLfloat; 24] = [
             // Positions followed by texture coordinates.
 
@@ -87,22 +388,54 @@ impl Renderer {
         layout(location = 1) in vec2 texcoord;
 
         layout (location = 0) out vec2 vs_texcoord;
+        layout (location = 1) out vec4 vs_color;
+        layout (location = 2) out vec2 vs_size;
 
         uniform mat4 u_model_matrix;
         uniform mat4 u_projection_matrix;
+        uniform vec4 u_draw_color = vec4(1.0);
 
         void main()
         {
             vs_texcoord = texcoord;
+            vs_color = u_draw_color;
+
+            // rectangles are scaled to their size by the model matrix
+            vs_size = vec2(u_model_matrix[0][0], u_model_matrix[1][1]);
 
             gl_Position = u_projection_matrix * u_model_matrix * vec4(position, 0.0, 1.0);
         }";
 
+        static INSTANCED_VS_SRC: &'static str = "
+        #version 430
+
+        layout(location = 0) in vec2 position;
+        layout(location = 1) in vec2 texcoord;
+
+        // The upper-left corner (xy) and size (zw) of each rectangle
+        layout(location = 2) in vec4 instance_bounds;
+        layout(location = 3) in vec4 instance_color;
+
+        layout (location = 0) out vec2 vs_texcoord;
+        layout (location = 1) out vec4 vs_color;
+        layout (location = 2) out vec2 vs_size;
+
+        uniform mat4 u_projection_matrix;
+
+        void main()
+        {
+            vs_texcoord = texcoord;
+            vs_color = instance_color;
+            vs_size = instance_bounds.zw;
+
+            vec2 p = instance_bounds.xy + position * instance_bounds.zw;
+            gl_Position = u_projection_matrix * vec4(p, 0.0, 1.0);
+        }";
+
         static DRAW_FS_SRC: &'static str = "
         #version 430
 
         uniform float u_time;
-        uniform vec4 u_draw_color = vec4(1.0);
         uniform uint u_draw_mode = 0;
 
         layout(binding = 0) uniform sampler2D u_color_map;
@@ -110,23 +443,53 @@ impl Renderer {
         uniform bool u_use_color_map;
         uniform bool u_use_alpha_map;
 
+        uniform float u_corner_radius = 0.0;
+        uniform float u_border_width = 0.0;
+        uniform vec4 u_border_color = vec4(0.0);
+        uniform float u_shadow_blur = 0.0;
+
         layout (location = 0) in vec2 vs_texcoord;
+        layout (location = 1) in vec4 vs_color;
+        layout (location = 2) in vec2 vs_size;
 
         layout (location = 0) out vec4 o_color;
 
         const uint DRAW_MODE_RECTANGLES = 0;
         const uint DRAW_MODE_LINES_SOLID = 1;
         const uint DRAW_MODE_LINES_DASHED = 2;
+        const uint DRAW_MODE_TEXT = 3;
+        const uint DRAW_MODE_ROUNDED_RECTANGLES = 4;
+        const uint DRAW_MODE_SHADOWS = 5;
+
+        #include \"rounded_rectangle.glsl\"
 
         void main()
         {
             vec2 uv = vs_texcoord;
 
-            float alpha = u_draw_color.a;;
+            // text is drawn with the color map acting as a glyph mask
+            if (u_draw_mode == DRAW_MODE_TEXT)
+            {
+                float mask = texture(u_color_map, uv).r;
+                o_color = vec4(vs_color.rgb, vs_color.a * mask);
+                return;
+            }
+
+            float alpha = vs_color.a;
+            if (u_draw_mode == DRAW_MODE_LINES_SOLID || u_draw_mode == DRAW_MODE_LINES_DASHED)
+            {
+                // lines are drawn as ribbons whose t-coordinate runs from
+                // 0 to 1 across the ribbon, so fade out the outermost pixel
+                float edge = min(uv.t, 1.0 - uv.t) / max(fwidth(uv.t), 1e-5);
+                alpha *= clamp(edge, 0.0, 1.0);
+            }
             if (u_draw_mode == DRAW_MODE_LINES_DASHED)
             {
+                // scroll the dashes towards the end of the line, which
+                // indicates the direction of the connection
                 const float stripes = 10.0;
-                //alpha = max(step(0.5, fract(uv.s * stripes - u_time)), 0.25);
+                const float speed = 2.0;
+                alpha *= step(0.5, fract(uv.s * stripes - u_time * speed));
             }
 
             // the alpha map overrides the default alpha
@@ -144,145 +507,197 @@ impl Renderer {
             }
             else
             {
-                o_color = vec4(u_draw_color.rgb, alpha);
+                o_color = vec4(vs_color.rgb, alpha);
+            }
+
+            if (u_draw_mode == DRAW_MODE_ROUNDED_RECTANGLES)
+            {
+                vec2 p = (vs_texcoord - 0.5) * vs_size;
+                float radius = min(u_corner_radius, min(vs_size.x, vs_size.y) * 0.5);
+                float d = rounded_rectangle(p, vs_size * 0.5, radius);
+                float aa = max(fwidth(d), 1e-5);
+
+                // the border runs along the inside of the edge
+                if (u_border_width > 0.0)
+                {
+                    float border = clamp((d + u_border_width) / aa + 0.5, 0.0, 1.0);
+                    o_color.rgb = mix(o_color.rgb, u_border_color.rgb, u_border_color.a * border);
+                }
+                o_color.a *= clamp(0.5 - d / aa, 0.0, 1.0);
+            }
+
+            if (u_draw_mode == DRAW_MODE_SHADOWS)
+            {
+                // the quad extends past the shadow-casting rectangle by the
+                // blur distance on every side, which is where the shadow
+                // fades out completely
+                vec2 p = (vs_texcoord - 0.5) * vs_size;
+                vec2 b = max(vs_size * 0.5 - u_shadow_blur, vec2(0.0));
+                float radius = min(u_corner_radius, min(b.x, b.y));
+                float d = rounded_rectangle(p, b, radius);
+                o_color.a *= 1.0 - smoothstep(-u_shadow_blur, u_shadow_blur, d);
             }
         }";
 
-        // Compile the shader program.
-        let program_draw = Program::new(DRAW_VS_SRC.to_string(), DRAW_FS_SRC.to_string()).unwrap();
+        let backend: Box<dyn Backend> = Box::new(GlBackend::new());
+        backend.init_state();
 
-        // Setup buffers.
-        let mut vao = 0;
-        let mut vbo_rect = 0;
-        let mut vbo_line = 0;
+        // Compile the shader program.
+        let draw_fs_src = preprocessor::process("renderer.frag", DRAW_FS_SRC)
+            .map_err(Error::Source)?
+            .code;
+        let program_draw = backend.create_program(DRAW_VS_SRC.to_string(), draw_fs_src.clone())?;
+        let program_instanced =
+            backend.create_program(INSTANCED_VS_SRC.to_string(), draw_fs_src)?;
+
+        // Setup buffers: each vertex has 4 components (position followed
+        // by texture coordinates).
+        let vbo_rect =
+            backend.create_buffer(VERTEX_DATA.len(), Some(&VERTEX_DATA[..]), BufferUsage::Static);
+
+        // The buffer for lines can be reallocated when a larger batch of
+        // lines is drawn.
+        let vbo_line = backend.create_buffer(
+            constants::RENDERER_LINE_INITIAL_CAPACITY,
+            None,
+            BufferUsage::Stream,
+        );
 
-        unsafe {
-            // Enable alpha blending.
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-            // Create the VBO for rendering rectangles.
-            let vbo_rect_size = (VERTEX_DATA.len() * mem::size_of::<GLfloat>()) as GLsizeiptr;
-            gl::CreateBuffers(1, &mut vbo_rect);
-            gl::NamedBufferData(
-                vbo_rect,
-                vbo_rect_size,
-                mem::transmute(&VERTEX_DATA[0]),
-                gl::STATIC_DRAW,
-            );
+        // Each glyph is made up of 6 vertices.
+        let vbo_text =
+            backend.create_buffer(constants::TEXT_GLYPH_CAPACITY * 24, None, BufferUsage::Dynamic);
 
-            // Create the VBO for rendering lines.
-            let vbo_line_size = (1000 * mem::size_of::<GLfloat>()) as GLsizeiptr;
-            gl::CreateBuffers(1, &mut vbo_line);
-            gl::NamedBufferStorage(
-                vbo_line,
-                vbo_line_size,
-                ptr::null(),
-                gl::DYNAMIC_STORAGE_BIT,
-            );
+        // Each instance has 8 components (bounds followed by color).
+        let vbo_instance = backend.create_buffer(
+            constants::RENDERER_INSTANCE_CAPACITY * 8,
+            None,
+            BufferUsage::Dynamic,
+        );
 
-            // This is not strictly necessary, but we do it for completeness sake.
-            let num_pos_components: i32 = 2;
-            let num_tex_components: i32 = 2;
-            let pos_attr =
-                gl::GetAttribLocation(program_draw.id, CString::new("position").unwrap().as_ptr());
-            let tex_attr =
-                gl::GetAttribLocation(program_draw.id, CString::new("texcoord").unwrap().as_ptr());
-            let tex_offset = (num_pos_components as usize * mem::size_of::<GLfloat>()) as GLuint;
-
-            // Create the VAO and setup vertex attributes.
-            gl::CreateVertexArrays(1, &mut vao);
-
-            // Position attribute.
-            gl::EnableVertexArrayAttrib(vao, pos_attr as GLuint);
-            gl::VertexArrayAttribFormat(
-                vao,
-                pos_attr as GLuint,
-                num_pos_components,
-                gl::FLOAT,
-                gl::FALSE as GLboolean,
-                0,
-            );
-            gl::VertexArrayAttribBinding(vao, pos_attr as GLuint, 0);
-
-            // Texture coordinates attribute.
-            gl::EnableVertexArrayAttrib(vao, tex_attr as GLuint);
-            gl::VertexArrayAttribFormat(
-                vao,
-                tex_attr as GLuint,
-                num_tex_components,
-                gl::FLOAT,
-                gl::FALSE as GLboolean,
-                tex_offset,
-            );
-            gl::VertexArrayAttribBinding(vao, tex_attr as GLuint, 0);
-
-            // Associate the VBO with bind point 0.
-            gl::VertexArrayVertexBuffer(
-                vao,
-                0,
-                vbo_rect,
-                0,
-                ((num_pos_components + num_tex_components) as usize * mem::size_of::<GLfloat>()) as i32,
-            );
-        }
+        // The attribute locations match the layout qualifiers of the vertex
+        // shaders above.
+        let vertex_attributes = [
+            VertexAttribute {
+                location: 0,
+                components: 2,
+                offset: 0,
+                binding: 0,
+            },
+            VertexAttribute {
+                location: 1,
+                components: 2,
+                offset: 2,
+                binding: 0,
+            },
+        ];
+        let vao = backend.create_layout(&vertex_attributes, &[StepMode::Vertex]);
+        backend.set_vertex_buffer(vao, 0, vbo_rect, 4);
+
+        // For instanced rendering, the per-vertex attributes come from bind
+        // point 0 and the per-instance attributes come from bind point 1.
+        let instance_attributes = [
+            vertex_attributes[0],
+            vertex_attributes[1],
+            VertexAttribute {
+                location: 2,
+                components: 4,
+                offset: 0,
+                binding: 1,
+            },
+            VertexAttribute {
+                location: 3,
+                components: 4,
+                offset: 4,
+                binding: 1,
+            },
+        ];
+        let vao_instanced =
+            backend.create_layout(&instance_attributes, &[StepMode::Vertex, StepMode::Instance]);
+        backend.set_vertex_buffer(vao_instanced, 0, vbo_rect, 4);
+        backend.set_vertex_buffer(vao_instanced, 1, vbo_instance, 8);
 
-        let mut renderer = Renderer {
+        let renderer = Renderer {
+            backend,
             program_draw,
-            projection: Matrix4::zero(),
+            program_instanced,
             vao,
+            vao_instanced,
             vbo_rect,
+            vbo_instance,
             vbo_line,
-            zoom: 1.0,
-            size,
+            vbo_line_capacity: Cell::new(constants::RENDERER_LINE_INITIAL_CAPACITY),
+            vbo_text,
+            font: Font::new(&assets::get_path(constants::FONT_PATH))?,
+            view: View::new(size),
+            line_scale: Cell::new(1.0),
             time: SystemTime::now(),
+            layer: Cell::new(Layer::Grid),
+            clip: Cell::new(None),
+            commands: RefCell::new(Layer::all().iter().map(|_| Vec::new()).collect()),
+            line_data: RefCell::new(Vec::new()),
+            ribbon: RefCell::new(Vec::new()),
         };
-        renderer.zoom(1.0);
-        renderer
+        Ok(renderer)
     }
 
-    /// Returns the renderer's current projection matrix.
-    pub fn get_projection(&self) -> &Matrix4<f32> {
-        &self.projection
+    /// Returns the font that is used to draw text, which can be used to
+    /// measure strings before drawing them.
+    pub fn get_font(&self) -> &Font {
+        &self.font
     }
 
     /// Returns the internal size (width, height) of the render region.
     pub fn get_size(&self) -> &Vector2<f32> {
-        &self.size
+        self.view.get_size()
     }
 
-    /// Zooms the network in or out by modifying the underlying
-    /// projection matrix. If `zoom` is `1.0`, this is
-    /// effectively the "home" position.
-    pub fn zoom(&mut self, zoom: f32) {
-        self.zoom = zoom;
-        self.rebuild_projection_matrix();
+    /// Returns the part of the network editor that is currently visible.
+    pub fn get_view(&self) -> &View {
+        &self.view
     }
 
-    /// Resizes the network.
-    pub fn resize(&mut self, resolution: &Vector2<f32>) {
-        self.size = *resolution;
-        self.rebuild_projection_matrix();
+    /// Returns the visible part of the network editor, so that it can be
+    /// panned, zoomed, or resized.
+    pub fn get_view_mut(&mut self) -> &mut View {
+        &mut self.view
     }
 
-    /// Rebuild the projection matrix:
-    /// L, R, B, T, N, F
-    fn rebuild_projection_matrix(&mut self) {
-        self.projection = cgmath::ortho(
-            -(self.size.x * 0.5) * self.zoom,
-            (self.size.x * 0.5) * self.zoom,
-            (self.size.y * 0.5) * self.zoom,
-            -(self.size.y * 0.5) * self.zoom,
-            -1.0,
-            1.0,
-        );
+    /// Replaces the visible part of the network editor (i.e. to draw the
+    /// network offscreen with `View::framing`).
+    pub fn set_view(&mut self, view: View) {
+        self.view = view;
+    }
 
-        // Set the uniform.
-        self.program_draw
-            .uniform_matrix_4f("u_projection_matrix", &self.projection);
+    /// Returns the region of the window (in pixels) that is covered by
+    /// `bounds`, which is specified in screen space (see `View`).
+    pub fn get_region(&self, bounds: &Rect) -> Region {
+        self.view.get_region(bounds)
+    }
+
+    /// Uploads `projection` to both programs.
+    fn set_projection(&self, projection: Matrix4<f32>) {
+        for &program in [self.program_draw, self.program_instanced].iter() {
+            self.backend.set_uniform(
+                program,
+                "u_projection_matrix",
+                UniformValue::Mat4(projection),
+            );
+        }
     }
 
-    /// Draws a primitive.
+    /// Sets the layer that subsequent draw calls are submitted to.
+    pub fn set_layer(&self, layer: Layer) {
+        self.layer.set(layer);
+    }
+
+    /// Clips subsequent draw calls to `region` (or stops clipping them,
+    /// if `region` is `None`).
+    pub fn set_clip(&self, region: Option<Region>) {
+        self.clip.set(region);
+    }
+
+    /// Submits a primitive to the current layer. Nothing is actually
+    /// drawn until the next call to `flush`.
     pub fn draw(
         &self,
         params: DrawParams,
@@ -290,112 +705,490 @@ impl Renderer {
         color_map: Option<&Texture>,
         alpha_map: Option<&Texture>,
     ) {
-        self.program_draw.bind();
+        let primitive = match params {
+            DrawParams::Rectangle(bounds) => Primitive::Rectangle(*bounds),
+            DrawParams::Line(data, mode, connectivity, width) => {
+                let mut line_data = self.line_data.borrow_mut();
+                let start = line_data.len();
+                line_data.extend_from_slice(data);
+                let range = Range {
+                    start,
+                    end: line_data.len(),
+                };
+                Primitive::Line(vec![range], mode, connectivity, width)
+            }
+            DrawParams::Text(text, origin, scale) => {
+                Primitive::Text(text.to_string(), origin, scale)
+            }
+            DrawParams::RoundedRectangle(bounds, rounding) => {
+                Primitive::RoundedRectangle(*bounds, rounding)
+            }
+            DrawParams::Shadow(bounds, radius, blur) => {
+                Primitive::Shadow(Renderer::get_shadow_bounds(bounds, blur), radius, blur)
+            }
+        };
+        self.submit(primitive, color, color_map, alpha_map);
+    }
+
+    /// Submits a batch of rectangles that share the same color and alpha
+    /// maps to the current layer. When flushed, they are drawn with as few
+    /// draw calls as possible, rather than issuing one draw call (and a
+    /// round of uniform updates) per rectangle.
+    pub fn draw_instanced(
+        &self,
+        instances: &[Instance],
+        color_map: Option<&Texture>,
+        alpha_map: Option<&Texture>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        let primitive = Primitive::Instances(instances.to_vec(), InstanceStyle::Plain);
+        self.submit(primitive, &Color::white(), color_map, alpha_map);
+    }
+
+    /// Submits a batch of rounded rectangles that share the same `rounding`
+    /// to the current layer (see `draw_instanced`).
+    pub fn draw_rounded_instanced(&self, instances: &[Instance], rounding: Rounding) {
+        if instances.is_empty() {
+            return;
+        }
+        let style = InstanceStyle::Rounded(rounding);
+        let primitive = Primitive::Instances(instances.to_vec(), style);
+        self.submit(primitive, &Color::white(), None, None);
+    }
+
+    /// Submits the shadows cast by a batch of rectangles with corners of
+    /// the given `radius` to the current layer (see `DrawParams::Shadow`).
+    pub fn draw_shadows_instanced(&self, casters: &[Instance], radius: f32, blur: f32) {
+        if casters.is_empty() {
+            return;
+        }
+        let instances = casters
+            .iter()
+            .map(|caster| {
+                Instance::new(Renderer::get_shadow_bounds(&caster.bounds, blur), caster.color)
+            })
+            .collect();
+        let primitive = Primitive::Instances(instances, InstanceStyle::Shadow(radius, blur));
+        self.submit(primitive, &Color::white(), None, None);
+    }
+
+    /// Returns the region covered by the shadow of `bounds`, which fades
+    /// out over a distance of `blur`.
+    fn get_shadow_bounds(bounds: &Rect, blur: f32) -> Rect {
+        Rect::expanded_from(bounds, &Vector2::new(blur * 2.0, blur * 2.0))
+    }
+
+    /// Adds a draw call to the current layer's list of commands.
+    fn submit(
+        &self,
+        primitive: Primitive,
+        color: &Color,
+        color_map: Option<&Texture>,
+        alpha_map: Option<&Texture>,
+    ) {
+        let command = Command {
+            primitive,
+            color: color.to_linear(),
+            color_map: color_map.map(|texture| texture.get_handle()),
+            alpha_map: alpha_map.map(|texture| texture.get_handle()),
+            clip: self.clip.get(),
+        };
 
-        let mut model = Matrix4::identity();
-        if let DrawParams::Rectangle(bounds) = params {
-            model = *bounds.get_model_matrix();
+        // Consecutive lines that look the same are drawn all at once.
+        let mut commands = self.commands.borrow_mut();
+        let layer = &mut commands[self.layer.get() as usize];
+        if let Some(last) = layer.last_mut() {
+            if last.merge(&command) {
+                return;
+            }
         }
+        layer.push(command);
+    }
 
-        // Set shared uniforms.
-        self.program_draw
-            .uniform_matrix_4f("u_model_matrix", &model);
-        self.program_draw
-            .uniform_4f("u_draw_color", &(*color).into());
-        self.program_draw
-            .uniform_1f("u_time", self.get_elapsed_seconds());
-
-        // Bind the color map, if available.
-        if let Some(color_map) = color_map {
-            self.program_draw.uniform_1i("u_use_color_map", true as i32);
-            color_map.bind(0);
-        } else {
-            self.program_draw
-                .uniform_1i("u_use_color_map", false as i32);
+    /// Issues all of the draw calls that have been submitted since the
+    /// last flush, one layer at a time (from back to front). Within each
+    /// layer, draw calls are issued in the order that they were submitted.
+    pub fn flush(&self) {
+        for layer in Layer::all().iter() {
+            let mut commands =
+                mem::replace(&mut self.commands.borrow_mut()[*layer as usize], Vec::new());
+            if !commands.is_empty() {
+                if layer.is_world() {
+                    self.set_projection(self.view.get_world_projection());
+                    self.line_scale.set(self.view.get_zoom());
+                } else {
+                    self.set_projection(self.view.get_screen_projection());
+                    self.line_scale.set(1.0);
+                }
+            }
+            for command in commands.iter() {
+                match command.clip {
+                    Some(region) => region.clip(|| self.execute(command)),
+                    None => self.execute(command),
+                }
+            }
+
+            // Hand the (now empty) list back, so that its allocation is
+            // reused during the next frame.
+            commands.clear();
+            self.commands.borrow_mut()[*layer as usize] = commands;
         }
+        self.line_data.borrow_mut().clear();
 
-        // Bind the alpha map, if available.
-        if let Some(alpha_map) = alpha_map {
-            self.program_draw.uniform_1i("u_use_alpha_map", true as i32);
-            alpha_map.bind(1);
-        } else {
-            self.program_draw
-                .uniform_1i("u_use_alpha_map", false as i32);
+        // Subsequent draw calls start over at the back.
+        self.set_layer(Layer::Grid);
+        self.set_clip(None);
+    }
+
+    /// Issues the draw call described by `command`.
+    fn execute(&self, command: &Command) {
+        if let Primitive::Instances(ref instances, style) = command.primitive {
+            self.draw_instances_inner(instances, style, command.color_map, command.alpha_map);
+            return;
         }
 
+        let program = self.program_draw;
+        self.backend.bind_program(program);
+
+        let model = match command.primitive {
+            Primitive::Rectangle(ref bounds)
+            | Primitive::RoundedRectangle(ref bounds, _)
+            | Primitive::Shadow(ref bounds, ..) => *bounds.get_model_matrix(),
+            _ => Matrix4::identity(),
+        };
+
+        // Set shared uniforms.
+        let backend = &self.backend;
+        backend.set_uniform(program, "u_model_matrix", UniformValue::Mat4(model));
+        backend.set_uniform(program, "u_draw_color", UniformValue::Vec4(command.color.into()));
+        backend.set_uniform(program, "u_time", UniformValue::Float(self.get_elapsed_seconds()));
+        self.bind_maps(program, command.color_map, command.alpha_map);
+
         // Issue draw call.
-        match params {
-            DrawParams::Rectangle(_) => {
-                self.program_draw.uniform_1ui("u_draw_mode", 0);
+        match command.primitive {
+            Primitive::Rectangle(_) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(0));
+                self.draw_rect_inner();
+            }
+            Primitive::Line(ref lines, mode, connectivity, width) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(mode as u32 + 1));
+                self.draw_line_inner(lines, connectivity, width);
+            }
+            Primitive::Text(ref text, origin, scale) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(3));
+                backend.bind_texture(0, self.font.get_atlas().get_handle());
+                self.draw_text_inner(&self.font.layout(text, &origin, scale));
+            }
+            Primitive::RoundedRectangle(_, rounding) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(4));
+                self.set_rounding(program, &rounding);
                 self.draw_rect_inner();
             }
-            DrawParams::Line(data, mode, connectivity) => {
-                self.program_draw
-                    .uniform_1ui("u_draw_mode", mode as u32 + 1);
-                self.draw_line_inner(&data, connectivity);
+            Primitive::Shadow(_, radius, blur) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(5));
+                self.set_shadow(program, radius, blur);
+                self.draw_rect_inner();
             }
+            Primitive::Instances(..) => (),
         }
 
-        self.program_draw.unbind();
+        backend.unbind_program();
     }
 
-    /// Draws a rectangle.
-    pub fn draw_rect_inner(&self) {
-        unsafe {
-            gl::VertexArrayVertexBuffer(
-                self.vao,
-                0,
-                self.vbo_rect,
-                0,
-                (4 * mem::size_of::<GLfloat>()) as i32,
-            );
-
-            gl::BindVertexArray(self.vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+    /// Binds the color and alpha maps (if available) and tells `program`
+    /// which of the two to use.
+    fn bind_maps(
+        &self,
+        program: ProgramHandle,
+        color_map: Option<TextureHandle>,
+        alpha_map: Option<TextureHandle>,
+    ) {
+        let backend = &self.backend;
+        let use_color_map = UniformValue::Int(color_map.is_some() as i32);
+        let use_alpha_map = UniformValue::Int(alpha_map.is_some() as i32);
+        backend.set_uniform(program, "u_use_color_map", use_color_map);
+        backend.set_uniform(program, "u_use_alpha_map", use_alpha_map);
+        if let Some(color_map) = color_map {
+            backend.bind_texture(0, color_map);
+        }
+        if let Some(alpha_map) = alpha_map {
+            backend.bind_texture(1, alpha_map);
         }
     }
 
-    /// Draws a line (or polyline segment).
-    pub fn draw_line_inner(&self, data: &Vec<f32>, connectivity: LineConnectivity) {
-        unsafe {
-            // Upload the vertex data.
-            let data_size = (data.len() * mem::size_of::<GLfloat>()) as GLsizeiptr;
-            gl::NamedBufferSubData(self.vbo_line, 0, data_size, data.as_ptr() as *const c_void);
-
-            gl::VertexArrayVertexBuffer(
-                self.vao,
-                0,
-                self.vbo_line,
-                0,
-                (4 * mem::size_of::<GLfloat>()) as i32,
-            );
+    /// Sets the uniforms that describe the corners and border of a
+    /// rounded rectangle.
+    fn set_rounding(&self, program: ProgramHandle, rounding: &Rounding) {
+        let border_color = rounding.border_color.to_linear().into();
+        self.backend.set_uniform(program, "u_corner_radius", UniformValue::Float(rounding.radius));
+        self.backend.set_uniform(
+            program,
+            "u_border_width",
+            UniformValue::Float(rounding.border_width),
+        );
+        self.backend.set_uniform(program, "u_border_color", UniformValue::Vec4(border_color));
+    }
+
+    /// Sets the uniforms that describe the shadow of a rounded rectangle.
+    fn set_shadow(&self, program: ProgramHandle, radius: f32, blur: f32) {
+        self.backend.set_uniform(program, "u_corner_radius", UniformValue::Float(radius));
+        self.backend.set_uniform(program, "u_shadow_blur", UniformValue::Float(blur));
+    }
 
-            let primitive = match connectivity {
-                LineConnectivity::Segment => gl::LINES,
-                LineConnectivity::Strip => gl::LINE_STRIP,
-            };
+    /// Draws a batch of rectangles in the given `style` with the instanced
+    /// shader program.
+    fn draw_instances_inner(
+        &self,
+        instances: &[Instance],
+        style: InstanceStyle,
+        color_map: Option<TextureHandle>,
+        alpha_map: Option<TextureHandle>,
+    ) {
+        let backend = &self.backend;
+        let program = self.program_instanced;
+        backend.bind_program(program);
+        backend.set_uniform(program, "u_time", UniformValue::Float(self.get_elapsed_seconds()));
+        match style {
+            InstanceStyle::Plain => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(0))
+            }
+            InstanceStyle::Rounded(ref rounding) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(4));
+                self.set_rounding(program, rounding);
+            }
+            InstanceStyle::Shadow(radius, blur) => {
+                backend.set_uniform(program, "u_draw_mode", UniformValue::UInt(5));
+                self.set_shadow(program, radius, blur);
+            }
+        }
+        self.bind_maps(program, color_map, alpha_map);
+
+        // Upload and draw the instances, one buffer's worth at a time.
+        for chunk in instances.chunks(constants::RENDERER_INSTANCE_CAPACITY) {
+            let mut data = Vec::with_capacity(chunk.len() * 8);
+            for instance in chunk.iter() {
+                let upper_left = instance.bounds.get_upper_left();
+                let size = instance.bounds.get_size();
+                let color = instance.color.to_linear();
+                data.extend_from_slice(&[
+                    upper_left.x,
+                    upper_left.y,
+                    size.x,
+                    size.y,
+                    color.r,
+                    color.g,
+                    color.b,
+                    color.a,
+                ]);
+            }
 
-            gl::BindVertexArray(self.vao);
-            gl::DrawArrays(primitive, 0, (data.len() / 4) as i32);
+            backend.update_buffer(self.vbo_instance, &data);
+            backend.draw(self.vao_instanced, Topology::Triangles, 0..6, chunk.len() as u32);
         }
+
+        backend.unbind_program();
+    }
+
+    /// Draws a rectangle.
+    pub fn draw_rect_inner(&self) {
+        self.backend.set_vertex_buffer(self.vao, 0, self.vbo_rect, 4);
+        self.backend.draw(self.vao, Topology::Triangles, 0..6, 1);
+    }
+
+    /// Draws one or more lines (or polyline segments), whose vertices are
+    /// the given ranges of `line_data`, with a single draw call.
+    pub fn draw_line_inner(
+        &self,
+        lines: &[Range<usize>],
+        connectivity: LineConnectivity,
+        width: f32,
+    ) {
+        // Expand each line into a ribbon of triangles that is `width` pixels
+        // wide, regardless of the current zoom. The ribbon is padded by an
+        // extra pixel, which the fragment shader uses for anti-aliasing.
+        let half_width = (width + 1.0) * 0.5 * self.line_scale.get();
+        let line_data = self.line_data.borrow();
+        let mut ribbon = self.ribbon.borrow_mut();
+        ribbon.clear();
+        for range in lines.iter() {
+            expand_line(&line_data[range.clone()], connectivity, half_width, &mut ribbon);
+        }
+        let data = &ribbon;
+
+        // Grow the buffer (to the next power of two) if the data won't fit.
+        let capacity = self.vbo_line_capacity.get().max(data.len().next_power_of_two());
+        self.vbo_line_capacity.set(capacity);
+
+        // Orphan the buffer before uploading the vertex data, so that
+        // several batches of lines can be drawn per frame without waiting
+        // on draw calls that are still using the previous contents.
+        self.backend.resize_buffer(self.vbo_line, capacity);
+        self.backend.update_buffer(self.vbo_line, data);
+        self.backend.set_vertex_buffer(self.vao, 0, self.vbo_line, 4);
+
+        let topology = match connectivity {
+            LineConnectivity::Segment => Topology::Triangles,
+            LineConnectivity::Strip => Topology::TriangleStrip,
+        };
+        self.backend.draw(self.vao, topology, 0..(data.len() / 4) as u32, 1);
+    }
+
+    /// Draws a block of text, where `data` contains the glyph vertices
+    /// (see `Font::layout`).
+    pub fn draw_text_inner(&self, data: &[f32]) {
+        // Upload the vertex data, ignoring any glyphs that don't fit.
+        let count = data.len().min(constants::TEXT_GLYPH_CAPACITY * 24);
+        self.backend.update_buffer(self.vbo_text, &data[..count]);
+        self.backend.set_vertex_buffer(self.vao, 0, self.vbo_text, 4);
+        self.backend.draw(self.vao, Topology::Triangles, 0..(count / 4) as u32, 1);
     }
 
     /// Returns the number of seconds that have elapsed since the program
     /// was launched.
     fn get_elapsed_seconds(&self) -> f32 {
-        let elapsed = self.time.elapsed().unwrap();
+        let elapsed = self.time.elapsed().unwrap_or(Duration::from_secs(0));
         let milliseconds = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
 
         (milliseconds as f32) / 1000.0
     }
 }
 
+/// Expands the line described by `data` (4 floats - position followed by
+/// texture coordinates - per vertex) into a ribbon of triangles that
+/// extends `half_width` units to either side of the line. Segments
+/// become pairs of triangles, and strips become a single triangle strip
+/// with mitered joints. The t-coordinate of each output vertex is 0 on
+/// one side of the ribbon and 1 on the other. The triangles are appended
+/// to `ribbon`: if it already holds a strip, the two strips are joined by
+/// a pair of degenerate (zero-area) triangles.
+fn expand_line(
+    data: &[f32],
+    connectivity: LineConnectivity,
+    half_width: f32,
+    ribbon: &mut Vec<f32>,
+) {
+    let count = data.len() / 4;
+    let point = |i: usize| Vector2::new(data[i * 4], data[i * 4 + 1]);
+    let s = |i: usize| data[i * 4 + 2];
+
+    // Returns the unit normal of the segment from `a` to `b`.
+    let normal = |a: Vector2<f32>, b: Vector2<f32>| {
+        let direction = b - a;
+        if direction.magnitude2() == 0.0 {
+            Vector2::zero()
+        } else {
+            let direction = direction.normalize();
+            Vector2::new(-direction.y, direction.x)
+        }
+    };
+
+    match connectivity {
+        LineConnectivity::Segment => {
+            for i in (0..count / 2).map(|i| i * 2) {
+                let offset = normal(point(i), point(i + 1)) * half_width;
+                let corners = [
+                    (point(i) + offset, s(i), 0.0),
+                    (point(i) - offset, s(i), 1.0),
+                    (point(i + 1) + offset, s(i + 1), 0.0),
+                    (point(i + 1) - offset, s(i + 1), 1.0),
+                ];
+                for &index in [0, 1, 2, 2, 1, 3].iter() {
+                    let (p, s, t) = corners[index];
+                    ribbon.extend_from_slice(&[p.x, p.y, s, t]);
+                }
+            }
+        }
+        LineConnectivity::Strip => {
+            for i in 0..count {
+                let before = if i > 0 {
+                    normal(point(i - 1), point(i))
+                } else {
+                    Vector2::zero()
+                };
+                let after = if i + 1 < count {
+                    normal(point(i), point(i + 1))
+                } else {
+                    Vector2::zero()
+                };
+
+                // Miter the joint between the two adjacent segments, limiting
+                // the length of the miter at sharp corners.
+                let mut miter = before + after;
+                if miter.magnitude2() == 0.0 {
+                    miter = if before.magnitude2() > 0.0 { before } else { after };
+                }
+                let offset = if miter.magnitude2() == 0.0 {
+                    Vector2::zero()
+                } else {
+                    let miter = miter.normalize();
+                    let reference = if before.magnitude2() > 0.0 { before } else { after };
+                    miter * (half_width / miter.dot(reference).max(0.25))
+                };
+
+                let p = point(i) + offset;
+                let q = point(i) - offset;
+                if i == 0 && !ribbon.is_empty() {
+                    // Repeat the last vertex of the previous strip and the
+                    // first vertex of this one.
+                    let last = ribbon.len() - 4;
+                    for j in last..last + 4 {
+                        let value = ribbon[j];
+                        ribbon.push(value);
+                    }
+                    ribbon.extend_from_slice(&[p.x, p.y, s(i), 0.0]);
+                }
+                ribbon.extend_from_slice(&[p.x, p.y, s(i), 0.0, q.x, q.y, s(i), 1.0]);
+            }
+        }
+    }
+}
+
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.vbo_rect);
-            gl::DeleteBuffers(1, &self.vbo_line);
-            gl::DeleteVertexArrays(1, &self.vao);
+        for &buffer in [self.vbo_rect, self.vbo_instance, self.vbo_line, self.vbo_text].iter() {
+            self.backend.delete_buffer(buffer);
         }
+        self.backend.delete_layout(self.vao);
+        self.backend.delete_layout(self.vao_instanced);
+        self.backend.delete_program(self.program_draw);
+        self.backend.delete_program(self.program_instanced);
     }
 }
+
+#[test]
+fn test_expand_line_segments() {
+    let data = vec![0.0, 0.0, 0.0, 0.0, 10.0, 0.0, 1.0, 1.0];
+    let mut ribbon = Vec::new();
+    expand_line(&data, LineConnectivity::Segment, 2.0, &mut ribbon);
+
+    // Each segment becomes two triangles that extend 2 units above and
+    // below the original line.
+    assert_eq!(ribbon.len(), 6 * 4);
+    for vertex in ribbon.chunks(4) {
+        assert_eq!(vertex[1].abs(), 2.0);
+    }
+}
+
+#[test]
+fn test_region_intersection() {
+    let a = Region::new(0, 0, 100, 50);
+    let b = Region::new(50, 25, 100, 100);
+    assert_eq!(a.intersection(&b), Region::new(50, 25, 50, 25));
+    assert_eq!(a.intersection(&Region::new(200, 0, 10, 10)).width, 0);
+}
+
+#[test]
+fn test_expand_line_strips() {
+    let data = vec![0.0, 0.0, 0.0, 0.0, 10.0, 0.0, 1.0, 1.0];
+    let mut ribbon = Vec::new();
+    expand_line(&data, LineConnectivity::Strip, 2.0, &mut ribbon);
+    assert_eq!(ribbon.len(), 4 * 4);
+
+    // A second strip is joined to the first by two repeated vertices.
+    expand_line(&data, LineConnectivity::Strip, 2.0, &mut ribbon);
+    assert_eq!(ribbon.len(), 10 * 4);
+    assert_eq!(ribbon[12..16], ribbon[16..20]);
+    assert_eq!(ribbon[20..24], ribbon[24..28]);
+}
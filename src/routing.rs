@@ -0,0 +1,260 @@
+use cgmath::{InnerSpace, Vector2};
+
+use bounds::Rect;
+use constants;
+
+/// The four control points of a single cubic Bezier segment.
+pub type Segment = [Vector2<f32>; 4];
+
+/// Returns a segment that leaves `a` and enters `d` horizontally,
+/// which is the default shape of a connection between two ops.
+pub fn direct(a: Vector2<f32>, d: Vector2<f32>) -> Segment {
+    // Edges that point backwards (i.e. to an op on the left) still
+    // need to leave and enter their slots horizontally.
+    let tangent = ((d.x - a.x) * 0.5)
+        .abs()
+        .max(constants::EDGE_MIN_TANGENT_LENGTH);
+
+    [
+        a,
+        Vector2::new(a.x + tangent, a.y),
+        Vector2::new(d.x - tangent, d.y),
+        d,
+    ]
+}
+
+/// Returns a segment that runs in a straight line from `a` to `d`.
+pub fn straight(a: Vector2<f32>, d: Vector2<f32>) -> Segment {
+    [a, a + (d - a) / 3.0, a + (d - a) * (2.0 / 3.0), d]
+}
+
+/// Evaluates `segment` at `t`, which should be in the range `[0..1]`.
+pub fn evaluate(segment: &Segment, t: f32) -> Vector2<f32> {
+    let t_inv = 1.0 - t;
+
+    // Coefficients for a cubic polynomial.
+    let b0 = t_inv * t_inv * t_inv;
+    let b1 = 3.0 * t * t_inv * t_inv;
+    let b2 = 3.0 * t * t * t_inv;
+    let b3 = t * t * t;
+
+    segment[0] * b0 + segment[1] * b1 + segment[2] * b2 + segment[3] * b3
+}
+
+/// Evaluates the first derivative (tangent) of `segment` at `t`.
+pub fn derivative(segment: &Segment, t: f32) -> Vector2<f32> {
+    let t_inv = 1.0 - t;
+
+    (segment[1] - segment[0]) * (3.0 * t_inv * t_inv)
+        + (segment[2] - segment[1]) * (6.0 * t_inv * t)
+        + (segment[3] - segment[2]) * (3.0 * t * t)
+}
+
+/// Samples each of the `segments` `lod` times, returning a polyline that
+/// runs from the start of the first segment to the end of the last.
+pub fn sample(segments: &[Segment], lod: usize) -> Vec<Vector2<f32>> {
+    let mut points = Vec::with_capacity(segments.len() * lod + 1);
+    for segment in segments.iter() {
+        for i in 0..lod {
+            points.push(evaluate(segment, (i as f32) / (lod as f32)));
+        }
+    }
+
+    if let Some(last) = segments.last() {
+        points.push(last[3]);
+    }
+    points
+}
+
+/// Returns the position and tangent halfway along a chain of `segments`.
+pub fn midpoint(segments: &[Segment]) -> (Vector2<f32>, Vector2<f32>) {
+    let u = segments.len() as f32 * 0.5;
+    let index = (u.floor() as usize).min(segments.len() - 1);
+    let t = u - index as f32;
+
+    (
+        evaluate(&segments[index], t),
+        derivative(&segments[index], t),
+    )
+}
+
+/// Returns the bounding box of `points`, which must not be empty.
+pub fn get_bounds(points: &[Vector2<f32>]) -> Rect {
+    let mut min = points[0];
+    let mut max = points[0];
+    for point in points.iter() {
+        min = Vector2::new(min.x.min(point.x), min.y.min(point.y));
+        max = Vector2::new(max.x.max(point.x), max.y.max(point.y));
+    }
+    Rect::new(min, max - min)
+}
+
+/// Returns the shortest distance between `point` and the polyline `points`.
+pub fn distance_to(point: &Vector2<f32>, points: &[Vector2<f32>]) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| {
+            let edge = pair[1] - pair[0];
+            let length2 = edge.magnitude2();
+
+            // Project `point` onto this piece of the polyline.
+            let t = if length2 > 0.0 {
+                ((point - pair[0]).dot(edge) / length2).max(0.0).min(1.0)
+            } else {
+                0.0
+            };
+            (point - (pair[0] + edge * t)).magnitude()
+        })
+        .fold(::std::f32::MAX, f32::min)
+}
+
+/// Returns `true` if the line segments `a0 -> a1` and `b0 -> b1`
+/// cross one another and `false` otherwise.
+fn segments_intersect(
+    a0: &Vector2<f32>,
+    a1: &Vector2<f32>,
+    b0: &Vector2<f32>,
+    b1: &Vector2<f32>,
+) -> bool {
+    let cross = |u: Vector2<f32>, v: Vector2<f32>| u.x * v.y - u.y * v.x;
+
+    let r = a1 - a0;
+    let s = b1 - b0;
+    let denominator = cross(r, s);
+
+    // Parallel (or degenerate) segments are treated as disjoint.
+    if denominator == 0.0 {
+        return false;
+    }
+
+    let t = cross(b0 - a0, s) / denominator;
+    let u = cross(b0 - a0, r) / denominator;
+    (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
+}
+
+/// Returns `true` if the polylines `a` and `b` cross one another
+/// and `false` otherwise.
+pub fn intersects(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> bool {
+    a.windows(2).any(|pa| {
+        b.windows(2)
+            .any(|pb| segments_intersect(&pa[0], &pa[1], &pb[0], &pb[1]))
+    })
+}
+
+/// Returns the obstacles that the polyline `points` passes through.
+fn collisions<'a>(points: &[Vector2<f32>], obstacles: &'a [Rect]) -> Vec<&'a Rect> {
+    obstacles
+        .iter()
+        .filter(|obstacle| {
+            points.iter().any(|point| {
+                obstacle.inside_with_padding(point, constants::EDGE_ROUTING_PADDING)
+            })
+        })
+        .collect()
+}
+
+/// Returns the total length of the polyline `points`.
+fn length(points: &[Vector2<f32>]) -> f32 {
+    points
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).magnitude())
+        .sum()
+}
+
+/// Routes a connection from `a` to `d` so that it avoids passing through
+/// any of the `obstacles` (i.e. the bodies of other ops), if possible.
+///
+/// If the default curve is blocked, the route is split into two segments
+/// that pass through a waypoint either above or below the blocking
+/// obstacles, whichever collides with fewer obstacles (or is shorter).
+pub fn route(a: Vector2<f32>, d: Vector2<f32>, obstacles: &[Rect]) -> Vec<Segment> {
+    let lod = constants::EDGE_CURVE_LOD;
+
+    let default = vec![direct(a, d)];
+    let default_points = sample(&default, lod);
+    let blocking = collisions(&default_points, obstacles);
+    if blocking.is_empty() {
+        return default;
+    }
+
+    // Find the region spanned by all of the blocking obstacles.
+    let mut min = *blocking[0].get_upper_left();
+    let mut max = min + blocking[0].get_size();
+    for obstacle in blocking.iter() {
+        let upper_left = obstacle.get_upper_left();
+        let lower_right = upper_left + obstacle.get_size();
+        min.x = min.x.min(upper_left.x);
+        min.y = min.y.min(upper_left.y);
+        max.x = max.x.max(lower_right.x);
+        max.y = max.y.max(lower_right.y);
+    }
+
+    let x = (min.x + max.x) * 0.5;
+    let margin = constants::EDGE_ROUTING_PADDING * 2.0;
+    let waypoints = [
+        Vector2::new(x, min.y - margin),
+        Vector2::new(x, max.y + margin),
+    ];
+
+    let mut best = default;
+    let mut best_collisions = blocking.len();
+    let mut best_length = length(&default_points);
+
+    for waypoint in waypoints.iter() {
+        let candidate = vec![direct(a, *waypoint), direct(*waypoint, d)];
+        let points = sample(&candidate, lod);
+        let candidate_collisions = collisions(&points, obstacles).len();
+        let candidate_length = length(&points);
+
+        if candidate_collisions < best_collisions
+            || (candidate_collisions == best_collisions && candidate_length < best_length)
+        {
+            best = candidate;
+            best_collisions = candidate_collisions;
+            best_length = candidate_length;
+        }
+    }
+    best
+}
+
+#[test]
+fn test_route_unobstructed() {
+    let route = route(Vector2::new(0.0, 0.0), Vector2::new(200.0, 50.0), &[]);
+    assert_eq!(route.len(), 1);
+}
+
+#[test]
+fn test_distance_to() {
+    let points = [Vector2::new(0.0, 0.0), Vector2::new(100.0, 0.0)];
+    assert_eq!(distance_to(&Vector2::new(50.0, 10.0), &points), 10.0);
+    assert_eq!(distance_to(&Vector2::new(-30.0, 40.0), &points), 50.0);
+}
+
+#[test]
+fn test_get_bounds() {
+    let points = [Vector2::new(10.0, -5.0), Vector2::new(-10.0, 20.0), Vector2::new(0.0, 0.0)];
+    let bounds = get_bounds(&points);
+    assert_eq!(*bounds.get_upper_left(), Vector2::new(-10.0, -5.0));
+    assert_eq!(*bounds.get_size(), Vector2::new(20.0, 25.0));
+}
+
+#[test]
+fn test_intersects() {
+    let a = [Vector2::new(0.0, 0.0), Vector2::new(100.0, 100.0)];
+    let b = [Vector2::new(0.0, 100.0), Vector2::new(100.0, 0.0)];
+    let c = [Vector2::new(0.0, 50.0), Vector2::new(20.0, 90.0)];
+    assert!(intersects(&a, &b));
+    assert!(!intersects(&a, &c));
+}
+
+#[test]
+fn test_route_around_obstacle() {
+    let a = Vector2::new(0.0, 0.0);
+    let d = Vector2::new(400.0, 0.0);
+    let obstacles = [Rect::new(Vector2::new(150.0, -25.0), Vector2::new(100.0, 50.0))];
+
+    let route = route(a, d, &obstacles);
+    let points = sample(&route, constants::EDGE_CURVE_LOD);
+    assert_eq!(route.len(), 2);
+    assert!(collisions(&points, &obstacles).is_empty());
+}
@@ -1,415 +1,1328 @@
+use bake::BakedOp;
+use graph::Graph;
+use hierarchy::{Cluster, Hierarchy, Node};
+use isf;
+use library;
 use network::Network;
-use operator::{DomainType, Op, OpFamily, PrimitiveType};
-use program::Program;
+use operator::{Op, OpFamily, PrimitiveType};
+use preprocessor::{self, Source};
+use constants;
+use error::Error;
+use formatter::{self, Style};
+use profile::Profile;
+use program::{PendingProgram, Program};
+use shader_error::ShaderError;
+use shadertoy::{self, Snapshot};
+use spirv::{self, Environment};
+use wgsl;
 
 use uuid::Uuid;
 
-pub struct ShaderBuilder {
-    shader_code: String,
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// The vertex shader of every program that the shader graph is built
+/// into, which comes after the prelude of the target profile.
+static VS_SRC: &str = "
+        layout(location = 0) in vec2 position;
+        layout(location = 1) in vec2 texcoord;
+        INTERSTAGE_LOCATION(0) out vec2 vs_texcoord;
+
+        uniform mat4 u_model_matrix;
+        uniform mat4 u_projection_matrix;
+
+        void main() {
+            vs_texcoord = texcoord;
+
+            gl_Position = u_projection_matrix * u_model_matrix * vec4(position, 0.0, 1.0);
+        }";
+
+/// Which of the preview's programs a build of the shader graph replaces.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Target {
+    Valid,
+    Compare,
 }
 
-impl ShaderBuilder {
-    pub fn new() -> ShaderBuilder {
-        ShaderBuilder {
-            shader_code: String::new(),
+/// A build of the shader graph whose program the driver may still be
+/// compiling in the background.
+struct PendingBuild {
+    target: Target,
+
+    program: PendingProgram,
+
+    /// The code that comes before the generated code in the fragment
+    /// shader, without any unused library functions
+    header: Source,
+
+    /// The number of lines of generated code in the fragment shader
+    code_lines: usize,
+
+    /// Maps each (1-based) line of the generated fragment shader
+    /// to the UUID of the op that produced it
+    source_map: HashMap<usize, Uuid>,
+
+    /// When the build was started
+    start: SystemTime,
+}
+
+/// The result of a build of the shader graph.
+pub struct FinishedBuild {
+    /// The program that the build replaces
+    pub target: Target,
+
+    /// The new program, or `None` if it failed to compile
+    pub program: Option<Program>,
+
+    /// The UUIDs of the ops that caused the build to fail
+    pub error_ops: Vec<Uuid>,
+
+    /// How long it took to generate and compile the shader graph
+    pub build_time: Option<Duration>,
+}
+
+/// The reason that a shader graph couldn't be turned into shader code.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// There is no op at the specified index
+    MissingOp(usize),
+
+    /// The op with the specified name doesn't have all of the inputs
+    /// that it needs
+    MissingInputs(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::MissingOp(index) => write!(f, "there is no op at index {}", index),
+            BuildError::MissingInputs(ref name) => write!(f, "{} is missing inputs", name),
         }
     }
+}
 
-    /// Given a list of op indices in the proper post-order, builds
-    /// and returns the appropriate shader code.
-    pub fn build_sources(&mut self, network: &Network, indices: Vec<usize>) -> Option<Program> {
-        static HEADER: &str = "
-        #version 430
+/// The fragment shader generated from a shader graph, which hasn't
+/// been compiled yet.
+pub struct GeneratedSource {
+    /// The complete source code of the fragment shader
+    pub fs_src: String,
 
-        layout (location = 0) in vec2 vs_texcoord;
+    /// The generated body of the `map(..)` function
+    pub map: String,
 
-        layout (location = 0) out vec4 o_color;
+    /// Maps each (1-based) line of `fs_src` that was generated to the
+    /// UUID of the op that produced it
+    pub source_map: HashMap<usize, Uuid>,
 
-        uniform vec3 u_camera_position;
-        uniform vec3 u_camera_front;
-        uniform uint u_shading;
-        uniform float u_time;
+    /// The number of ops in the shader graph
+    pub op_count: usize,
 
-        // The SSBO that will contain a parameter vector for each op in
-        // the graph. Note that according to the spec, there can only be
-        // one array of variable size per SSBO.
-        layout (std430, binding = 0) buffer params_block
-        {
-            vec4 params[];
-        };
+    /// The code that comes before the generated code in `fs_src`
+    header: Source,
+}
 
-        const uint MAX_STEPS = 256u;
-        const float MAX_TRACE_DISTANCE = 64.0;
-        const float MIN_HIT_DISTANCE = 0.001;
+/// The generated body of the `map(..)` function.
+struct GeneratedMap {
+    code: String,
 
-        struct ray
-        {
-            vec3 o;
-            vec3 d;
-        };
+    /// The UUID of the op that produced each line of `code`
+    lines: Vec<Uuid>,
+
+    /// Maps the index of each op whose code would have been identical to
+    /// that of an earlier op to the index of the earlier op, whose
+    /// variables it shares
+    aliases: HashMap<usize, usize>,
+}
+
+impl GeneratedMap {
+    /// Returns the index of the op whose variables hold the result of
+    /// the op at `index`.
+    fn resolve(&self, index: usize) -> usize {
+        *self.aliases.get(&index).unwrap_or(&index)
+    }
+}
+
+pub struct ShaderBuilder {
+    /// The OpenGL feature level that generated shaders target
+    profile: Profile,
+
+    /// The source of the vertex shader, which starts with the same
+    /// prelude as the fragment shader
+    vs_src: String,
+
+    /// The code that comes before the generated body of the `map(..)`
+    /// function in every fragment shader
+    header: Source,
+
+    /// The code that comes after the generated body of the `map(..)`
+    /// function, which does the actual raymarching and shading
+    footer: Source,
+
+    /// The builds that haven't finished compiling yet, at most one
+    /// per target
+    pending: Vec<PendingBuild>,
+
+    /// The signature (see `get_signature`) of the shader graph that was
+    /// last built for each target, so that graphs whose structure hasn't
+    /// changed aren't rebuilt
+    signatures: Vec<(Target, u64)>,
+
+    /// The error that caused the last build of the valid program to fail,
+    /// which maps each diagnostic back to the op that produced the
+    /// offending line
+    last_error: Option<ShaderError>,
+
+    /// The ops whose code is replaced with a texture fetch in the preview
+    /// and thumbnails (but not in exports, see `Bakes`)
+    bakes: Vec<BakedOp>,
+
+    /// The bounding spheres around the branches of large unions, which are
+    /// used by the preview and thumbnails (but not by exports)
+    hierarchy: Hierarchy,
+}
+
+impl ShaderBuilder {
+    /// Constructs a new builder for `profile`, which fails if the shader
+    /// files that every fragment shader is assembled from can't be loaded.
+    pub fn new(profile: Profile) -> Result<ShaderBuilder, Error> {
+        Ok(ShaderBuilder {
+            profile,
+            vs_src: format!("{}\n{}", profile.get_prelude(), VS_SRC),
+            header: ShaderBuilder::load_header(profile, "preview_header.glsl")
+                .map_err(Error::Source)?,
+            footer: preprocessor::load("preview_footer.glsl").map_err(Error::Source)?,
+            pending: Vec::new(),
+            signatures: Vec::new(),
+            last_error: None,
+            bakes: Vec::new(),
+            hierarchy: Hierarchy::new(),
+        })
+    }
+
+    /// Reloads the shader files that every fragment shader is assembled
+    /// from, i.e. after they were edited. If any of them fail to load,
+    /// the previous versions are kept.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let header = ShaderBuilder::load_header(self.profile, "preview_header.glsl")
+            .map_err(Error::Source)?;
+        let footer = preprocessor::load("preview_footer.glsl").map_err(Error::Source)?;
+        self.header = header;
+        self.footer = footer;
+        self.signatures.clear();
+        Ok(())
+    }
+
+    /// Loads the code that comes before the generated body of the `map(..)`
+    /// function (see `get_prelude`).
+    fn load_header(profile: Profile, name: &str) -> Result<Source, String> {
+        preprocessor::process("prelude", &ShaderBuilder::get_prelude(profile, name))
+    }
 
-        struct result
-        {
-            float id;
-            float total_distance;
-            int total_steps;
+    /// Returns the code that comes before the generated body of the
+    /// `map(..)` function, before its includes are expanded: the prelude
+    /// and declaration of the op parameters that `profile` calls for,
+    /// followed by an include of the shader `name`.
+    pub fn get_prelude(profile: Profile, name: &str) -> String {
+        // Baking requires grid evaluation, and so shader storage buffers,
+        // as does building histograms of step counts (see `steps.rs`).
+        let bakes = if profile.uses_storage_buffer() {
+            format!(
+                "#define BAKE_CAPACITY {}\n#define BAKE_TEXTURE_UNIT {}\n#include \"bake.glsl\"\n\
+                 #define STEP_IMAGE_UNIT {}\n#include \"step_image.glsl\"\n",
+                constants::BAKE_CAPACITY,
+                constants::BAKE_TEXTURE_UNIT,
+                constants::STEP_IMAGE_UNIT
+            )
+        } else {
+            String::new()
         };
+        format!(
+            "{}\n#define PARAMETER_CAPACITY {}\n{}#include \"{}\"\n#include \"{}\"",
+            profile.get_prelude(),
+            constants::PARAMETER_SSBO_CAPACITY,
+            bakes,
+            profile.get_params_file(),
+            name
+        )
+    }
 
-        mat3 lookat(in vec3 t, in vec3 p)
-        {
-            vec3 k = normalize(t - p);
-            vec3 i = cross(k, vec3(0.0, 1.0, 0.0));
-            vec3 j = cross(i, k);
-            return mat3(i, j, k);
+    /// Prepares a newly built `program` for use by the preview, i.e. by
+    /// connecting its op parameters to the buffer that holds them.
+    fn prepare(&self, program: Program) -> Program {
+        if !self.profile.uses_storage_buffer() {
+            program.bind_uniform_block("params_block", 0);
         }
+        program
+    }
+
+    /// Returns the error that caused the last build of the valid program
+    /// to fail to compile, if it did.
+    pub fn get_last_error(&self) -> Option<&ShaderError> {
+        self.last_error.as_ref()
+    }
 
-        vec3 domain_twist(in vec3 p, float t)
-        {
-            float c = cos(t * p.y);
-            float s = sin(t * p.y);
-            mat2  m = mat2(c, -s, s, c);
-            vec3  q = vec3(m * p.xz, p.y);
-            return q;
+    /// Returns `true` if any builds are still compiling.
+    pub fn is_building(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Replaces the ops that are baked into 3D textures, which forces every
+    /// target to be rebuilt if they changed.
+    pub fn set_bakes(&mut self, bakes: Vec<BakedOp>) {
+        if bakes != self.bakes {
+            self.bakes = bakes;
+            self.signatures.clear();
         }
+    }
 
-        vec3 domain_bend(in vec3 p, float t)
-        {
-            float c = cos(t * p.y);
-            float s = sin(t * p.y);
-            mat2 m = mat2(c, -s, s, c);
-            vec3 q = vec3(m * p.xy, p.z);
-            return q;
+    /// Replaces the bounding sphere hierarchy of the network's unions, which
+    /// forces every target to be rebuilt if it changed.
+    pub fn set_hierarchy(&mut self, hierarchy: Hierarchy) {
+        if hierarchy != self.hierarchy {
+            self.hierarchy = hierarchy;
+            self.signatures.clear();
         }
+    }
+
+    /// Abandons all of the builds that are still compiling.
+    pub fn cancel_all(&mut self) {
+        self.pending.clear();
+        self.signatures.clear();
+    }
 
-        float op_union(float a, float b)
-        {
-            return min(a, b);
+    /// Abandons the build of `target` if it is still compiling, i.e. when
+    /// its program is no longer needed.
+    pub fn cancel(&mut self, target: Target) {
+        self.pending.retain(|build| build.target != target);
+        self.signatures.retain(|&(other, _)| other != target);
+    }
+
+    /// Returns a hash of everything about the shader graph that ends at the
+    /// op at `root` that affects its generated code: the name, family, code,
+    /// and parameter slot of each op, along with the order of its inputs.
+    /// Parameter values aren't included, since they are read from a buffer
+    /// at runtime.
+    pub fn get_signature<E>(graph: &Graph<Op, E>, root: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        root.hash(&mut hasher);
+        for index in graph.traverse(root) {
+            let op = match graph.get_node(index) {
+                Some(node) => &node.data,
+                None => continue,
+            };
+            index.hash(&mut hasher);
+            op.uuid.hash(&mut hasher);
+            op.name.hash(&mut hasher);
+            op.family.to_string().hash(&mut hasher);
+            op.get_code_template().hash(&mut hasher);
+            op.params.get_index().hash(&mut hasher);
+            graph.edges[index].inputs.hash(&mut hasher);
         }
+        hasher.finish()
+    }
+
+    /// Generates the fragment shader for the shader graph that ends at
+    /// the op at `root`, laid out in `style`, without touching OpenGL.
+    pub fn generate_source<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        style: Style,
+    ) -> Result<GeneratedSource, BuildError> {
+        self.generate_source_with(graph, root, style, &[], None)
+    }
 
-        float op_subtract(float a, float b)
-        {
-            return max(-a, b);
+    /// Like `generate_source`, but the code of the ops in `bakes` is
+    /// replaced with a fetch from their textures, and large unions skip
+    /// distant branches with the help of `hierarchy`.
+    fn generate_source_with<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        style: Style,
+        bakes: &[BakedOp],
+        hierarchy: Option<&Hierarchy>,
+    ) -> Result<GeneratedSource, BuildError> {
+        if graph.get_node(root).is_none() {
+            return Err(BuildError::MissingOp(root));
         }
+        let indices = ShaderBuilder::traverse(graph, root, bakes);
+        let generated = ShaderBuilder::generate_map(graph, &indices, style, bakes, hierarchy)?;
+
+        // The generated code starts on the line after the header.
+        let (fs_src, header) = self.assemble(&generated.code);
+        let first_line = header.line_count() + 1;
+        let source_map = generated
+            .lines
+            .into_iter()
+            .enumerate()
+            .map(|(offset, uuid)| (first_line + offset, uuid))
+            .collect();
+
+        Ok(GeneratedSource {
+            fs_src,
+            map: generated.code,
+            source_map,
+            op_count: indices.len(),
+            header,
+        })
+    }
 
-        float op_intersect(float a, float b)
-        {
-            return max(a, b);
+    /// Generates the shader code for the shader graph that ends at the op
+    /// at `root` and starts compiling it in the background, replacing any
+    /// unfinished build of the same `target`. If the shader graph can't be
+    /// generated (i.e. it's incomplete), nothing is built. Nothing is built
+    /// either if the structure of the graph hasn't changed since the last
+    /// build of `target` (i.e. only parameters did), in which case the
+    /// previous program (or build) is kept.
+    pub fn start_build<E>(
+        &mut self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        target: Target,
+    ) -> Result<(), BuildError> {
+        if graph.get_node(root).is_none() {
+            self.cancel(target);
+            return Err(BuildError::MissingOp(root));
         }
+        let signature = ShaderBuilder::get_signature(graph, root);
+        if self.signatures.contains(&(target, signature)) {
+            return Ok(());
+        }
+        self.cancel(target);
+        let start = SystemTime::now();
+
+        let source = self.generate_source_with(
+            graph,
+            root,
+            Style::Pretty,
+            &self.bakes,
+            Some(&self.hierarchy),
+        )?;
+        self.compile(source, target, start);
+        self.signatures.push((target, signature));
+        Ok(())
+    }
 
-        float op_smooth_min(float a, float b, float k)
-        {
-            float h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
-            return mix(b, a, h) - k * h * (1.0 - h);
+    /// Generates a compute shader that evaluates the distance field of the
+    /// shader graph that ends at the op at `root` at every point of a grid
+    /// (see `SampleGrid`), without touching OpenGL. This requires shader storage
+    /// buffers, which hold the results.
+    pub fn generate_grid_source<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+    ) -> Result<String, String> {
+        if !self.profile.uses_storage_buffer() {
+            return Err("grid evaluation requires GLSL 4.30 or newer".to_string());
         }
+        let node = graph
+            .get_node(root)
+            .ok_or_else(|| BuildError::MissingOp(root).to_string())?;
+        let indices = graph.traverse(root);
+        let mut generated = ShaderBuilder::generate_map(graph, &indices, Style::Pretty, &[], None)
+            .map_err(|error| error.to_string())?;
 
-        float sdf_sphere(in vec3 p, in vec3 center, float radius)
-        {
-            return length(center - p) - radius;
+        // Ops other than the render operator don't return from the `map(..)`
+        // function themselves (i.e. when baking them).
+        match node.data.family {
+            OpFamily::Domain(_) => {
+                return Err(format!("{} doesn't produce a distance field", node.data.name))
+            }
+            OpFamily::Primitive(PrimitiveType::Render) => (),
+            _ => {
+                let resolved = generated.resolve(root);
+                let name = &graph
+                    .get_node(resolved)
+                    .ok_or_else(|| BuildError::MissingOp(resolved).to_string())?
+                    .data
+                    .name;
+                generated
+                    .code
+                    .push_str(&format!("    return vec2(id_{0}, {0});\n", name));
+            }
         }
 
-        float sdf_box(in vec3 p, in vec3 b)
-        {
-          vec3 d = abs(p) - b;
-          return min(max(d.x, max(d.y, d.z)), 0.0) + length(max(d, 0.0));
+        let header = ShaderBuilder::load_header(self.profile, "grid_header.glsl")?;
+        let footer = preprocessor::load("grid_footer.glsl")?;
+        let (cs_src, _) = ShaderBuilder::assemble_with(&header, &generated.code, &footer);
+        Ok(cs_src)
+    }
+
+    /// Builds the compute program that evaluates the distance field of the
+    /// shader graph that ends at the op at `root` over a grid, which blocks
+    /// until the program is ready.
+    pub fn build_grid_program<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+    ) -> Result<Program, String> {
+        Program::new_compute(self.generate_grid_source(graph, root)?)
+    }
+
+    /// Writes the minified fragment shader for the shader graph that ends
+    /// at the op at `root` to the file at `path`.
+    pub fn export<E>(&self, graph: &Graph<Op, E>, root: usize, path: &Path) {
+        let result = self
+            .generate_source(graph, root, Style::Minified)
+            .map_err(|error| error.to_string())
+            .and_then(|source| {
+                let minified = formatter::minify(&source.fs_src);
+                File::create(path)
+                    .and_then(|mut file| file.write_all(minified.as_bytes()))
+                    .map_err(|error| error.to_string())
+            });
+
+        match result {
+            Ok(_) => console!("Exported shader to {}", path.display()),
+            Err(error) => error!("Failed to export shader: {}", error),
         }
+    }
+
+    /// Compiles the fragment shader for the shader graph that ends at the
+    /// op at `root` into a SPIR-V module at `path`, validating it in the
+    /// process.
+    pub fn export_spirv<E>(&self, graph: &Graph<Op, E>, root: usize, path: &Path) {
+        let result = self
+            .generate_source(graph, root, Style::Pretty)
+            .map_err(|error| error.to_string())
+            .and_then(|source| spirv::compile(&source.fs_src, path, Environment::OpenGl));
 
-        float sdf_plane(in vec3 p, in float h)
-        {
-            return p.y - h;
+        match result {
+            Ok(_) => console!("Exported SPIR-V module to {}", path.display()),
+            Err(error) => error!("Failed to export SPIR-V module: {}", error),
         }
+    }
 
-        float sdf_torus(in vec3 p, in vec2 t)
-        {
-            vec2 d = vec2(length(p.xz)- t.x, p.y);
-            return length(d) - t.y;
+    /// Generates a fragment shader for the shader graph that ends at the op
+    /// at `root`, which follows Shadertoy's conventions (i.e. its entry point
+    /// is `mainImage(..)`) and bakes in the view settings of the preview
+    /// `snapshot`, as well as its op parameters if `bake_params` is set (see
+    /// `shadertoy::get_prelude`).
+    pub fn generate_shadertoy<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        snapshot: &Snapshot,
+        bake_params: bool,
+    ) -> Result<String, String> {
+        if graph.get_node(root).is_none() {
+            return Err(BuildError::MissingOp(root).to_string());
         }
+        let indices = graph.traverse(root);
+        let generated = ShaderBuilder::generate_map(graph, &indices, Style::Pretty, &[], None)
+            .map_err(|error| error.to_string())?;
 
-        vec2 map(in vec3 p)
-        {
-            // start of generated code
-        ";
+        let prelude = shadertoy::get_prelude(snapshot, bake_params);
+        let header = preprocessor::process("shadertoy_prelude", &prelude)?;
+        let footer = preprocessor::process(
+            "shadertoy_epilogue",
+            "#include \"preview_footer.glsl\"\n#include \"shadertoy_footer.glsl\"",
+        )?;
+        let (fs_src, _) = ShaderBuilder::assemble_with(&header, &generated.code, &footer);
+        shadertoy::convert(&fs_src, &snapshot.constants)
+    }
 
-        static FOOTER: &str = "
+    /// Writes the Shadertoy version of the fragment shader for the shader
+    /// graph that ends at the op at `root` to the file at `path`.
+    pub fn export_shadertoy<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        snapshot: &Snapshot,
+        path: &Path,
+    ) {
+        let result = self.generate_shadertoy(graph, root, snapshot, true).and_then(|fs_src| {
+            File::create(path)
+                .and_then(|mut file| file.write_all(fs_src.as_bytes()))
+                .map_err(|error| error.to_string())
+        });
+
+        match result {
+            Ok(_) => console!("Exported Shadertoy shader to {}", path.display()),
+            Err(error) => error!("Failed to export Shadertoy shader: {}", error),
         }
+    }
+
+    /// Generates an ISF (Interactive Shader Format) shader for the shader
+    /// graph that ends at the op at `root`, i.e. for VJ software, which
+    /// publishes the op parameters as inputs.
+    pub fn generate_isf<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        snapshot: &Snapshot,
+    ) -> Result<String, String> {
+        let fs_src = self.generate_shadertoy(graph, root, snapshot, false)?;
+        Ok(isf::convert(&fs_src, graph, root))
+    }
 
-        vec3 calculate_normal(in vec3 p)
-        {
-            const vec3 e = vec3(0.001, 0.0, 0.0);
-            vec3 n = vec3(map(p + e.xyy).y - map(p - e.xyy).y,	// Gradient x
-                          map(p + e.yxy).y - map(p - e.yxy).y,	// Gradient y
-                          map(p + e.yyx).y - map(p - e.yyx).y); // Gradient z
+    /// Writes the ISF version of the fragment shader for the shader graph
+    /// that ends at the op at `root` to the file at `path`.
+    pub fn export_isf<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        snapshot: &Snapshot,
+        path: &Path,
+    ) {
+        let result = self.generate_isf(graph, root, snapshot).and_then(|fs_src| {
+            File::create(path)
+                .and_then(|mut file| file.write_all(fs_src.as_bytes()))
+                .map_err(|error| error.to_string())
+        });
 
-            return normalize(n);
+        match result {
+            Ok(_) => console!("Exported ISF shader to {}", path.display()),
+            Err(error) => error!("Failed to export ISF shader: {}", error),
         }
+    }
 
-        float ambient_occlusion(in vec3 p, in vec3 n)
-        {
-            const float attenuation = 0.5;
-            float ao;
-            float accum = 0.0;
-            float scale = 1.0;
-            for(int step = 0; step < 5; step++)
-            {
-                float hr = 0.01 + 0.02 * float(step * step);
-                vec3 aopos = n * hr + p;
-
-                float dist = map(aopos).y;
-                ao = -(dist - hr);
-                accum += ao * scale;
-                scale *= attenuation;
-            }
-            ao = 1.0 - clamp(accum, 0.0, 1.0);
+    /// Translates the Shadertoy version of the fragment shader for the
+    /// shader graph that ends at the op at `root` into WGSL, i.e. for
+    /// WebGPU, and writes it to the file at `path`.
+    pub fn export_wgsl<E>(
+        &self,
+        graph: &Graph<Op, E>,
+        root: usize,
+        snapshot: &Snapshot,
+        path: &Path,
+    ) {
+        let result = self
+            .generate_shadertoy(graph, root, snapshot, true)
+            .and_then(|fs_src| wgsl::translate(&fs_src, path));
 
-            return ao;
+        match result {
+            Ok(_) => console!("Exported WGSL shader to {}", path.display()),
+            Err(error) => error!("Failed to export WGSL shader: {}", error),
         }
+    }
 
-        result raymarch(in ray r)
-        {
-            result res = result(-1.0, 0.0, 0);
-            for (int i = 0; i < MAX_STEPS; ++i)
-            {
-                vec3 p = r.o + r.d * res.total_distance;
-                vec2 hit_info = map(p);
-                float hit_id = hit_info.x;
-                float hit_dist = hit_info.y;
-                res.total_distance += hit_dist;
-
-                if (hit_dist < MIN_HIT_DISTANCE)
-                {
-                    res.id = hit_id;
-                    break;
-                }
+    /// Starts compiling the generated `source` in the background.
+    fn compile(&mut self, source: GeneratedSource, target: Target, start: SystemTime) {
+        console!("Rebuilding shader graph with {} op(s)", source.op_count);
+        debug!("Final shader code:\n{}", source.map);
 
-                if(res.total_distance > MAX_TRACE_DISTANCE)
-                {
-                    res.total_distance = 0.0;
-                    break;
-                }
+        self.pending.push(PendingBuild {
+            target,
+            program: Program::submit_cached(self.vs_src.clone(), source.fs_src),
+            header: source.header,
+            code_lines: source.map.matches('\n').count(),
+            source_map: source.source_map,
+            start,
+        });
+    }
 
-                res.total_steps++;
-            }
-            return res;
+    /// Finishes any builds that the driver is done compiling, in the order
+    /// that they were started.
+    pub fn poll(&mut self) -> Vec<FinishedBuild> {
+        let (ready, pending): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|build| build.program.is_ready());
+        self.pending = pending;
+
+        ready.into_iter().map(|build| self.finish(build)).collect()
+    }
+
+    /// Finishes compiling `build`, mapping any errors back to the ops
+    /// (or shader files) that caused them.
+    fn finish(&mut self, build: PendingBuild) -> FinishedBuild {
+        let PendingBuild {
+            target,
+            program,
+            header,
+            code_lines,
+            source_map,
+            start,
+        } = build;
+
+        let result = program.finish();
+        let build_time = start.elapsed().ok();
+        if target == Target::Valid {
+            self.last_error = None;
         }
 
-        const uint SHADING_DEPTH = 0;
-        const uint SHADING_STEPS = 1;
-        const uint SHADING_AMBIENT_OCCLUSION = 2;
-        const uint SHADING_NORMALS = 3;
-        const uint SHADING_DIFFUSE = 4;
-
-        vec3 shading(in ray r, in result res)
-        {
-            vec3 hit = r.o + r.d * res.total_distance;
-            if (u_shading == SHADING_DEPTH)
-            {
-                float depth = hit.z / MAX_TRACE_DISTANCE;
-                return vec3(pow(depth, 0.5));
-            }
-            else if (u_shading == SHADING_STEPS)
-            {
-                float pct = float(res.total_steps) / MAX_STEPS;
-                const vec3 c_a = vec3(0.0, 0.0, 1.0);
-                const vec3 c_b = vec3(0.0, 1.0, 1.0);
-                const vec3 c_c = vec3(1.0, 1.0, 0.0);
-                const vec3 c_d = vec3(1.0, 0.0, 0.0);
-
-                const float a = 0.00;
-                const float b = 0.33;
-                const float c = 0.66;
-                const float d = 1.00;
-
-                vec3 color = mix(c_a, c_b, smoothstep(a, b, pct));
-                color = mix(color, c_c, smoothstep(b, c, pct));
-                color = mix(color, c_d, smoothstep(c, d, pct));
-                return color;
+        match result {
+            Ok(program) => {
+                for warning in program.warnings.iter() {
+                    warn!("Warning in shader graph: {}", warning);
+                }
+                FinishedBuild {
+                    target,
+                    program: Some(self.prepare(program)),
+                    error_ops: Vec::new(),
+                    build_time,
+                }
             }
-            else
-            {
-                // calculate normals
-                vec3 n = calculate_normal(hit);
-                if (u_shading == SHADING_AMBIENT_OCCLUSION)
-                {
-                    float ao = ambient_occlusion(hit, n);
-                    return vec3(pow(ao, 3.0));
+            Err(error) => {
+                let error = error.with_source_map(source_map);
+                error!("Failed to compile shader graph:\n{}", error);
+
+                // Find the op(s) responsible for the error(s), or the
+                // shader file that they came from.
+                let error_ops = error.get_ops();
+                for diagnostic in error.diagnostics.iter() {
+                    if error.get_op(diagnostic).is_some() {
+                        continue;
+                    }
+                    if let Some(line) = diagnostic.line {
+                        if let Some((file, line)) = self.locate(&header, line, code_lines) {
+                            error!("Error in {} at line {}", file, line);
+                        }
+                    }
                 }
-                else if (u_shading == SHADING_NORMALS)
-                {
-                    return n * 0.5 + 0.5;
+                if target == Target::Valid {
+                    self.last_error = Some(error);
                 }
-                else
-                {
-                    const vec3 l = vec3(0.0, 2.0, 3.0);
-                    vec3 to_light = normalize(l - hit);
-                    float d = max(0.0, dot(n, to_light));
-                    float ao = ambient_occlusion(hit, n);
-                    return vec3(d * pow(ao, 3.0));
+
+                FinishedBuild {
+                    target,
+                    program: None,
+                    error_ops,
+                    build_time,
                 }
             }
         }
+    }
+
+    /// Builds a shader program that renders the distance field produced
+    /// by the op at `index` (i.e. the sub-graph that ends at this op),
+    /// which is used to draw the op's thumbnail. Returns `None` if the
+    /// op doesn't produce a distance field or its sub-graph is incomplete.
+    pub fn build_thumbnail(&self, network: &Network, index: usize) -> Option<Program> {
+        let node = network.graph.get_node(index)?;
 
-        ray generate_ray()
-        {
-            // uv-coordinates in the range [-1..1]
-            vec2 uv = vs_texcoord * 2.0 - 1.0;
+        let indices = ShaderBuilder::traverse(&network.graph, index, &self.bakes);
+        let generated = ShaderBuilder::generate_map(
+            &network.graph,
+            &indices,
+            Style::Pretty,
+            &self.bakes,
+            Some(&self.hierarchy),
+        ).ok()?;
 
-            const float pi = 3.14159265359;
-            const float fov = 50.0;
-            const float fovx = pi * fov / 360.0;
-            float fovy = fovx * 1.0; // iResolution.y/iResolution.x;
-            float ulen = tan(fovx);
-            float vlen = tan(fovy);
+        // The op may share the variables of an identical op.
+        let name = &network.graph.get_node(generated.resolve(index))?.data.name;
+        let mut code = generated.code;
 
-            const vec3 camera_up = vec3(0.0, 1.0, 0.0);
-            vec2 cam_uv = uv;
-            vec3 camera_right = normalize(cross(camera_up, u_camera_front));
-            vec3 pixel = u_camera_position + u_camera_front + camera_right * cam_uv.x * ulen + camera_up * cam_uv.y * vlen;
+        match node.data.family {
+            // Domain operators don't produce a distance field.
+            OpFamily::Domain(_) => return None,
 
-            vec3 ro = u_camera_position;
-            vec3 rd = normalize(pixel - u_camera_position);
+            // The render operator already returns from the `map(..)` function.
+            OpFamily::Primitive(PrimitiveType::Render) => (),
 
-            return ray(ro, rd);
+            _ => code.push_str(&format!("    return vec2(id_{0}, {0});\n", name)),
         }
 
-        void main()
-        {
-            ray r = generate_ray();
-            result res = raymarch(r);
-
-            const vec3 background = vec3(0.0);
-            vec3 color = background;
-            switch(int(res.id))
-            {
-                case 0:
-                    color = shading(r, res);
-                    break;
-                case 1:
-                    // Placeholder
-                    break;
-                case 2:
-                    // Placeholder
-                    break;
-                    // etc...
-                default:
-                    color = background;
-                    break;
+        let (fs_src, _) = self.assemble(&code);
+        match Program::new_cached(self.vs_src.clone(), fs_src) {
+            Ok(program) => Some(self.prepare(program)),
+            Err(error) => {
+                error!("Failed to compile thumbnail for {}:\n{}", node.data.name, error);
+                None
             }
+        }
+    }
 
-            o_color = vec4(color, 1.0);
-        }";
+    /// Returns a complete fragment shader, with `code` as the body of
+    /// the `map(..)` function, along with the header that it starts with,
+    /// which only includes the library functions that are actually used.
+    fn assemble(&self, code: &str) -> (String, Source) {
+        ShaderBuilder::assemble_with(&self.header, code, &self.footer)
+    }
+
+    /// Like `assemble`, but with a different `header` and `footer`.
+    fn assemble_with(header: &Source, code: &str, footer: &Source) -> (String, Source) {
+        let header =
+            library::strip_unused(header, constants::SHADER_LIBRARIES, &[code, &footer.code]);
+
+        let mut fs_src = String::new();
+        fs_src.push_str(&header.code);
+        fs_src.push_str(code);
+        fs_src.push_str(&footer.code);
+        (fs_src, header)
+    }
+
+    /// Returns the shader file and line within that file that produced
+    /// the (1-based) `line` of a fragment shader that was assembled from
+    /// `header` and `code_lines` lines of generated code, if it isn't
+    /// generated code.
+    fn locate<'a>(
+        &'a self,
+        header: &'a Source,
+        line: usize,
+        code_lines: usize,
+    ) -> Option<(&'a str, usize)> {
+        let header_lines = header.line_count();
+        if line <= header_lines {
+            header.locate(line)
+        } else if line > header_lines + code_lines {
+            self.footer.locate(line - header_lines - code_lines)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the code that gives the generator `op` its object ID.
+    fn get_id_code(op: &Op) -> String {
+        format!("\nfloat id_{} = {}.0;", &op.name, op.params.get_index())
+    }
+
+    /// Generates the code of the union at the root of `cluster`, which takes
+    /// the minimum of every branch that feeds into the cluster. The
+    /// generators among those branches are nested inside of the cluster's
+    /// tree of bounding spheres, and only evaluated if their sphere is closer
+    /// than the closest surface found so far, since they can't be any closer
+    /// than that.
+    fn generate_cluster<E>(
+        graph: &Graph<Op, E>,
+        cluster: &Cluster,
+        generated: &GeneratedMap,
+    ) -> Result<String, BuildError> {
+        let leaves = cluster.nodes.iter().flat_map(|node| match *node {
+            Node::Leaf(ref leaves) => leaves.clone(),
+            _ => Vec::new(),
+        });
+        let ops: Vec<usize> = leaves
+            .chain(cluster.members.iter().cloned())
+            .chain(Some(cluster.root))
+            .collect();
+        for &index in ops.iter() {
+            let op = &graph.nodes[index].data;
+            if graph.edges[index].inputs.len() < op.get_input_capacity() {
+                return Err(BuildError::MissingInputs(op.name.clone()));
+            }
+        }
+
+        let name = &graph.nodes[cluster.root].data.name;
+        let mut code = format!("float {0} = 1e10;\nfloat id_{0} = -1.0;", name);
+        for &index in cluster.unbounded.iter() {
+            let input = &graph.nodes[generated.resolve(index)].data.name;
+            code.push_str(&ShaderBuilder::get_closest_code(name, input));
+        }
+        ShaderBuilder::generate_node(graph, cluster, 0, generated, "", &mut code);
+        Ok(code)
+    }
+
+    /// Appends the code of the node at `index` of the tree of `cluster` to
+    /// `code`, with each line prefixed by `indent`.
+    fn generate_node<E>(
+        graph: &Graph<Op, E>,
+        cluster: &Cluster,
+        index: usize,
+        generated: &GeneratedMap,
+        indent: &str,
+        code: &mut String,
+    ) {
+        let name = &graph.nodes[cluster.root].data.name;
+        code.push_str(&format!(
+            "\n{0}if (length(p - params[{1}].xyz) - params[{1}].w < {2}) {{",
+            indent,
+            cluster.get_param_index(index),
+            name
+        ));
+
+        let inner = format!("{}    ", indent);
+        match cluster.nodes[index] {
+            Node::Branch(left, right) => {
+                ShaderBuilder::generate_node(graph, cluster, left, generated, &inner, code);
+                ShaderBuilder::generate_node(graph, cluster, right, generated, &inner, code);
+            }
+            Node::Leaf(ref leaves) => for &leaf in leaves.iter() {
+                let op = &graph.nodes[leaf].data;
+                let input = graph.edges[leaf].inputs[0];
+                let input = &graph.nodes[generated.resolve(input)].data.name;
+
+                let mut leaf_code = formatter::dedent(&op.get_code(Some(input), None));
+                leaf_code.push_str(&ShaderBuilder::get_id_code(op));
+                leaf_code.push_str(&ShaderBuilder::get_closest_code(name, &op.name));
+                for line in leaf_code.lines() {
+                    code.push_str(&format!("\n{}{}", inner, line));
+                }
+            },
+        }
+        code.push_str(&format!("\n{}}}", indent));
+    }
+
+    /// Returns the code that replaces the distance (and ID) of the union
+    /// named `name` with those of `input`, if `input` is closer.
+    fn get_closest_code(name: &str, input: &str) -> String {
+        format!("\nif ({1} < {0}) {{ {0} = {1}; id_{0} = id_{1}; }}", name, input)
+    }
+
+    /// Returns the indices of the ops in the shader graph that ends at the
+    /// op at `root` in post-order, leaving out the ops that only feed into
+    /// the ops in `bakes`.
+    fn traverse<E>(graph: &Graph<Op, E>, root: usize, bakes: &[BakedOp]) -> Vec<usize> {
+        graph.traverse_until(root, |index| {
+            let uuid = graph.get_node(index).map(|node| node.data.uuid);
+            bakes.iter().any(|baked| Some(baked.uuid) == uuid)
+        })
+    }
+
+    /// Generates the body of the `map(..)` function from a list of op
+    /// indices in the proper post-order, failing if any of the ops are
+    /// missing inputs. The code of the ops in `bakes` is replaced with a
+    /// fetch from their textures, and the clusters of unions in `hierarchy`
+    /// only evaluate the branches that are close enough (see
+    /// `generate_cluster`).
+    fn generate_map<E>(
+        graph: &Graph<Op, E>,
+        indices: &[usize],
+        style: Style,
+        bakes: &[BakedOp],
+        hierarchy: Option<&Hierarchy>,
+    ) -> Result<GeneratedMap, BuildError> {
+        let mut generated = GeneratedMap {
+            code: String::new(),
+            lines: Vec::new(),
+            aliases: HashMap::new(),
+        };
+
+        // The code of each op that has been emitted so far (with the op's
+        // own name left as a placeholder), and the index of that op.
+        let mut emitted: HashMap<String, usize> = HashMap::new();
 
-        // Clear the cached shader code (if there was any).
-        self.shader_code = String::new();
+        // The clusters whose results are needed, which generate all of the
+        // ops inside of them at once.
+        let clusters: Vec<&Cluster> = hierarchy
+            .map(|hierarchy| {
+                hierarchy
+                    .clusters
+                    .iter()
+                    .filter(|cluster| indices.contains(&cluster.root))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         // Build the `map` function by traversing the graph of ops.
-        for index in indices {
-            if let Some(node) = network.graph.get_node(index) {
-                let mut formatted = match node.data.family {
-                    OpFamily::Domain(domain) => match domain {
-                        // Root operators have no inputs.
-                        DomainType::Root => node.data.get_code(None, None),
-
-                        // All other domain operators have a single input.
-                        _ => {
-                            if network.graph.edges[index].inputs.len() < 1 {
-                                return None;
-                            }
-                            let a = network.graph.edges[index].inputs[0];
-                            node.data
-                                .get_code(Some(&network.graph.get_node(a).unwrap().data.name), None)
-                        }
-                    },
-
-                    OpFamily::Primitive(primitive) => match primitive {
-                        // All generators have a single input, corresponding to
-                        // their (potentially transformed) root.
-                        PrimitiveType::Sphere
-                        | PrimitiveType::Box
-                        | PrimitiveType::Plane
-                        | PrimitiveType::Torus => {
-                            if network.graph.edges[index].inputs.len() < 1 {
-                                return None;
-                            }
-                            let a = network.graph.edges[index].inputs[0];
-                            node.data
-                                .get_code(Some(&network.graph.get_node(a).unwrap().data.name), None)
-                        }
+        for &index in indices.iter() {
+            if let Some(node) = graph.get_node(index) {
+                if clusters.iter().any(|cluster| cluster.contains(index)) {
+                    continue;
+                }
+                if let Some(cluster) = clusters.iter().find(|cluster| cluster.root == index) {
+                    let code = ShaderBuilder::generate_cluster(graph, cluster, &generated)?;
 
-                        // All combinators have two inputs.
-                        PrimitiveType::Union
-                        | PrimitiveType::Subtraction
-                        | PrimitiveType::Intersection
-                        | PrimitiveType::SmoothMinimum => {
-                            // If this operator doesn't have at least 2 inputs,
-                            // then we exit early, since this isn't a valid
-                            // shader graph.
-                            if network.graph.edges[index].inputs.len() < 2 {
-                                return None;
-                            }
-
-                            let a = network.graph.edges[index].inputs[0];
-                            let b = network.graph.edges[index].inputs[1];
-                            node.data.get_code(
-                                Some(&network.graph.get_node(a).unwrap().data.name),
-                                Some(&network.graph.get_node(b).unwrap().data.name),
-                            )
-                        }
+                    let formatted = formatter::format_op(&node.data.name, &code, style);
+                    generated.code.push_str(&formatted);
+                    for _ in formatted.lines() {
+                        generated.lines.push(node.data.uuid);
+                    }
+                    continue;
+                }
 
-                        // The render operator only has a single input.
-                        PrimitiveType::Render => {
-                            // If this operator doesn't have at least 1 input,
-                            // then we exit early, since this isn't a valid
-                            // shader graph.
-                            if network.graph.edges[index].inputs.len() < 1 {
-                                return None;
-                            }
-
-                            let a = network.graph.edges[index].inputs[0];
-                            let mut code = node.data.get_code(
-                                Some(&network.graph.get_node(a).unwrap().data.name),
-                                None,
-                            );
-
-                            // Add the final `return` in the `map(..)` function.
-                            code.push('\n');
-                            code.push('\t');
-                            code.push_str(&format!("return vec2(0.0, {});", &node.data.name));
-                            code
-                        }
-                    },
+                // A baked op samples its texture instead, so its inputs don't
+                // matter (and aren't in `indices`, unless other ops use them).
+                let baked = bakes.iter().find(|baked| baked.uuid == node.data.uuid);
+                if let Some(baked) = baked {
+                    let mut code = baked.get_code(&node.data.name);
+                    code.push_str(&ShaderBuilder::get_id_code(&node.data));
+
+                    let formatted = formatter::format_op(&node.data.name, &code, style);
+                    generated.code.push_str(&formatted);
+                    for _ in formatted.lines() {
+                        generated.lines.push(node.data.uuid);
+                    }
+                    continue;
+                }
+
+                // Refer to each input by the name of the op whose variables
+                // actually hold its result.
+                let inputs = graph.edges[index]
+                    .inputs
+                    .iter()
+                    .map(|&input| {
+                        let resolved = generated.resolve(input);
+                        graph
+                            .get_node(resolved)
+                            .map(|node| &node.data.name[..])
+                            .ok_or(BuildError::MissingOp(resolved))
+                    })
+                    .collect::<Result<Vec<&str>, BuildError>>()?;
+
+                // Every input slot has to be filled: root operators have no
+                // inputs, combinators have two, and all other operators (i.e.
+                // other domain operators, generators, and the render operator)
+                // have a single input, which for generators corresponds to
+                // their (potentially transformed) root.
+                let required = node.data.get_input_capacity();
+
+                // If this operator doesn't have enough inputs, then we
+                // exit early, since this isn't a valid shader graph.
+                if inputs.len() < required {
+                    return Err(BuildError::MissingInputs(node.data.name.clone()));
+                }
+                let input_a = if required > 0 { Some(inputs[0]) } else { None };
+                let input_b = if required > 1 { Some(inputs[1]) } else { None };
+
+                // An op that would generate exactly the same code as an
+                // earlier op (i.e. two identical primitives that share the
+                // same input) reuses the earlier op's variables instead of
+                // duplicating its code. The render operator is always
+                // emitted, since it returns from the `map(..)` function.
+                if node.data.family != OpFamily::Primitive(PrimitiveType::Render) {
+                    let key = node.data.get_code_as("NAME", input_a, input_b);
+                    if let Some(&earlier) = emitted.get(&key) {
+                        generated.aliases.insert(index, earlier);
+                        continue;
+                    }
+                    emitted.insert(key, index);
+                }
+
+                let mut code = formatter::dedent(&node.data.get_code(input_a, input_b));
+
+                // Add the final `return` in the `map(..)` function, along
+                // with the ID of the object that the distance belongs to.
+                if node.data.family == OpFamily::Primitive(PrimitiveType::Render) {
+                    code.push_str(&format!("\nreturn vec2(id_{0}, {0});", &node.data.name));
+                }
+
+                // Give each generator a distinct object ID, which is what the
+                // user picks in the preview. Custom ops count as generators
+                // unless they take the ID of one of their inputs.
+                let generator = match node.data.family {
+                    OpFamily::Primitive(PrimitiveType::Custom) => {
+                        !node.data.get_code_template().contains("id_NAME")
+                    }
+                    family => family.is_generator(),
                 };
+                if generator {
+                    code.push_str(&ShaderBuilder::get_id_code(&node.data));
+                }
 
-                // Add a tab indent before each new line of shader code and a newline
-                // character after.
-                self.shader_code.push('\t');
-                self.shader_code.push_str(&formatted);
-                self.shader_code.push('\n');
+                let formatted = formatter::format_op(&node.data.name, &code, style);
+                generated.code.push_str(&formatted);
+
+                for _ in formatted.lines() {
+                    generated.lines.push(node.data.uuid);
+                }
             }
         }
 
-        let mut fs_src = String::new();
-        fs_src.push_str(HEADER);
-        fs_src.push_str(&self.shader_code[..]);
-        fs_src.push_str(FOOTER);
-        println!("Final shader code:");
-        println!("{}", self.shader_code);
+        Ok(generated)
+    }
+}
 
-        let vs_src = "
-        #version 430
+/// Builds a graph out of ops of each of `families`, connected by the
+/// `(src, dst)` pairs of op indices in `edges`. Each op is named after its
+/// family and index, so that the generated code doesn't depend on the
+/// order in which tests run.
+#[cfg(test)]
+pub(crate) fn make_graph(families: &[OpFamily], edges: &[(usize, usize)]) -> Graph<Op, ()> {
+    use cgmath::Vector2;
 
-        layout(location = 0) in vec2 position;
-        layout(location = 1) in vec2 texcoord;
-        layout (location = 0) out vec2 vs_texcoord;
+    let mut graph = Graph::new();
+    for (index, &family) in families.iter().enumerate() {
+        let mut op = Op::new(family, Vector2::new(0.0, 0.0), Vector2::new(100.0, 50.0));
+        op.name = format!("{}_{}", family.to_string(), index);
+        op.params.set_index(index);
+        graph.add_node(op, ());
+    }
+    for &(src, dst) in edges.iter() {
+        graph.add_edge(src, dst);
+    }
+    graph
+}
 
-        uniform mat4 u_model_matrix;
-        uniform mat4 u_projection_matrix;
+/// Compares `map` against the golden file `tests/golden/<name>.glsl`, or
+/// overwrites the golden file if the `UPDATE_GOLDEN` environment variable
+/// is set.
+#[cfg(test)]
+fn check_golden(name: &str, map: &str) {
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::path::Path;
 
-        void main() {
-            vs_texcoord = texcoord;
+    let path = Path::new("tests/golden").join(format!("{}.glsl", name));
+    if env::var("UPDATE_GOLDEN").is_ok() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path).unwrap().write_all(map.as_bytes()).unwrap();
+        return;
+    }
 
-            gl_Position = u_projection_matrix * u_model_matrix * vec4(position, 0.0, 1.0);
-        }"
-            .to_string();
+    let mut expected = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut expected))
+        .expect("missing golden file: run with UPDATE_GOLDEN=1 to create it");
+    assert!(map == expected, "generated code doesn't match {}:\n{}", path.display(), map);
+}
+
+#[test]
+fn test_generate_source() {
+    use custom::CustomCode;
+    use operator::DomainType;
+
+    let builder = ShaderBuilder::new(Profile::Glsl430).unwrap();
+    let root = OpFamily::Domain(DomainType::Root);
+    let transform = OpFamily::Domain(DomainType::Transform);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let cube = OpFamily::Primitive(PrimitiveType::Box);
+    let union = OpFamily::Primitive(PrimitiveType::Union);
+    let smooth_minimum = OpFamily::Primitive(PrimitiveType::SmoothMinimum);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+
+    // A single primitive.
+    let graph = make_graph(&[root, sphere, render], &[(0, 1), (1, 2)]);
+    let source = builder.generate_source(&graph, 2, Style::Pretty).unwrap();
+    assert_eq!(source.op_count, 3);
+    assert!(source.fs_src.contains(&source.map));
+
+    // Only the library functions that the graph uses are included.
+    assert!(source.fs_src.contains("float sdf_sphere("));
+    assert!(!source.fs_src.contains("float sdf_torus("));
+    check_golden("sphere", &source.map);
+
+    // Minified code takes up a single line per op.
+    let minified = builder.generate_source(&graph, 2, Style::Minified).unwrap();
+    assert_eq!(minified.map.lines().count(), 3);
+    assert_eq!(minified.source_map.len(), 3);
+
+    // A transformed primitive combined with another primitive.
+    let graph = make_graph(
+        &[root, transform, cube, sphere, union, render],
+        &[(0, 1), (1, 2), (0, 3), (2, 4), (3, 4), (4, 5)],
+    );
+    check_golden("union", &builder.generate_source(&graph, 5, Style::Pretty).unwrap().map);
+
+    // Two identical primitives, which share their code.
+    let graph = make_graph(
+        &[root, sphere, sphere, smooth_minimum, render],
+        &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)],
+    );
+    check_golden("shared", &builder.generate_source(&graph, 4, Style::Pretty).unwrap().map);
+
+    // A combinator with a single input.
+    let graph = make_graph(&[root, sphere, union, render], &[(0, 1), (1, 2), (2, 3)]);
+    let error = builder.generate_source(&graph, 3, Style::Pretty).err().unwrap();
+    assert_eq!(error, BuildError::MissingInputs("union_2".to_string()));
+
+    // A custom op, which takes the ID of its first input.
+    let custom = OpFamily::Primitive(PrimitiveType::Custom);
+    let mut graph = make_graph(&[root, sphere, custom, render], &[(0, 1), (1, 2), (2, 3)]);
+    let code = "// @inputs 1\nfloat NAME = abs(INPUT_A) - 0.1;\nfloat id_NAME = id_INPUT_A;";
+    let shell = CustomCode::parse("shell.glsl", code).unwrap();
+    graph.get_node_mut(2).unwrap().data.set_custom(shell);
+    let map = builder.generate_source(&graph, 3, Style::Pretty).unwrap().map;
+    assert!(map.contains("float custom_2 = abs(sphere_1) - 0.1;"));
+    assert!(!map.contains("id_custom_2 = 2.0"));
+}
+
+#[test]
+fn test_preview_source() {
+    use operator::DomainType;
+
+    // The raymarcher and the camera live in files of their own, which
+    // every profile's preview has to pull in.
+    let root = OpFamily::Domain(DomainType::Root);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let graph = make_graph(&[root, sphere, render], &[(0, 1), (1, 2)]);
+    for &name in ["330", "410", "430", "450"].iter() {
+        let builder = ShaderBuilder::new(Profile::from_name(name).unwrap()).unwrap();
+        let fs_src = builder.generate_source(&graph, 2, Style::Pretty).unwrap().fs_src;
+        assert!(fs_src.contains("result raymarch(in ray r)"), "{}", name);
+        assert!(fs_src.contains("ray generate_ray(in vec2 offset)"), "{}", name);
+    }
+}
+
+#[test]
+fn test_generate_shadertoy() {
+    use cgmath::Point3;
+    use operator::DomainType;
+
+    let builder = ShaderBuilder::new(Profile::Glsl430).unwrap();
+    let root = OpFamily::Domain(DomainType::Root);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let graph = make_graph(&[root, sphere, render], &[(0, 1), (1, 2)]);
 
-        Program::new(vs_src, fs_src)
+    let mut snapshot = Snapshot {
+        params: vec![0.5; 3 * constants::PARAMETER_CAPACITY],
+        camera_position: Point3::new(0.0, 0.0, 5.0),
+        camera_focus: Point3::new(0.0, 0.0, 0.0),
+        constants: Vec::new(),
+    };
+
+    // Every uniform that Shadertoy doesn't provide must have a value.
+    assert!(builder.generate_shadertoy(&graph, 2, &snapshot, true).is_err());
+
+    for name in &[
+        "u_orthographic", "u_ortho_scale", "u_shading", "u_gizmos", "u_fov",
+        "u_max_trace_distance", "u_picking", "u_probing", "u_isolines", "u_samples", "u_jitter",
+        "u_steps_min", "u_steps_max", "u_steps_log", "u_pick_uv",
+    ] {
+        snapshot.constants.push((name, "VALUE".to_string()));
+    }
+    let fs_src = builder.generate_shadertoy(&graph, 2, &snapshot, true).unwrap();
+    assert!(!fs_src.lines().any(|line| line.starts_with("#version")));
+    assert!(!fs_src.contains("uniform "));
+    assert!(fs_src.contains("const vec4 params[3] = vec4[3]("));
+    assert!(fs_src.contains("const float u_fov = VALUE;"));
+    assert!(fs_src.contains("void preview_main()"));
+    assert!(fs_src.contains("void mainImage(out vec4 fragColor, in vec2 fragCoord)"));
+
+    // Exported to ISF, the named op parameters become inputs.
+    let isf = builder.generate_isf(&graph, 2, &snapshot).unwrap();
+    assert!(isf.starts_with("/*{"));
+    assert!(isf.contains("\"NAME\": \"render_2_cone_aa\""));
+    assert!(isf.contains("vec4 params[3];"));
+    assert!(isf.contains("    params[1] = vec4(0.0, 0.0, 0.0, 0.0);"));
+    assert!(isf.contains("    params[2] = vec4(render_2_cone_aa, 0.0, 0.0, 0.0);"));
+    assert!(isf.contains("mainImage(gl_FragColor, gl_FragCoord.xy);"));
+}
+
+#[test]
+fn test_generate_grid_source() {
+    use operator::DomainType;
+
+    let root = OpFamily::Domain(DomainType::Root);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let graph = make_graph(&[root, sphere, render], &[(0, 1), (1, 2)]);
+
+    let builder = ShaderBuilder::new(Profile::Glsl430).unwrap();
+    let cs_src = builder.generate_grid_source(&graph, 2).unwrap();
+    assert!(cs_src.contains("layout (local_size_x = 4, local_size_y = 4, local_size_z = 4) in;"));
+    assert!(cs_src.contains("float sdf_sphere("));
+    assert!(cs_src.contains("ids[index] = hit.x;"));
+
+    // Any other op that produces a distance field can be evaluated (i.e. to
+    // bake it), but a domain op can't.
+    let cs_src = builder.generate_grid_source(&graph, 1).unwrap();
+    assert!(cs_src.contains("return vec2(id_sphere_1, sphere_1);"));
+    assert!(builder.generate_grid_source(&graph, 0).is_err());
+
+    // Without storage buffers, there is nowhere to put the distances.
+    let builder = ShaderBuilder::new(Profile::Glsl330).unwrap();
+    assert!(builder.generate_grid_source(&graph, 2).is_err());
+}
+
+#[test]
+fn test_get_signature() {
+    use operator::DomainType;
+
+    let root = OpFamily::Domain(DomainType::Root);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let cube = OpFamily::Primitive(PrimitiveType::Box);
+    let union = OpFamily::Primitive(PrimitiveType::Union);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let mut graph = make_graph(
+        &[root, sphere, cube, union, render],
+        &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)],
+    );
+    let signature = ShaderBuilder::get_signature(&graph, 4);
+
+    // Parameters are read from a buffer, so changing them doesn't require
+    // a rebuild.
+    graph.nodes[1].data.params.get_data_mut()[0] = 2.0;
+    assert_eq!(ShaderBuilder::get_signature(&graph, 4), signature);
+
+    // Swapping the inputs of the union changes the generated code.
+    graph.edges[3].inputs.reverse();
+    assert!(ShaderBuilder::get_signature(&graph, 4) != signature);
+    graph.edges[3].inputs.reverse();
+
+    graph.nodes[2].data.name = "box_renamed".to_string();
+    assert!(ShaderBuilder::get_signature(&graph, 4) != signature);
+}
+
+#[test]
+fn test_generate_baked() {
+    use cgmath::Vector3;
+    use operator::DomainType;
+
+    let root = OpFamily::Domain(DomainType::Root);
+    let transform = OpFamily::Domain(DomainType::Transform);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let cube = OpFamily::Primitive(PrimitiveType::Box);
+    let union = OpFamily::Primitive(PrimitiveType::Union);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let graph = make_graph(
+        &[root, transform, cube, sphere, union, render],
+        &[(0, 1), (1, 2), (0, 3), (2, 4), (3, 4), (4, 5)],
+    );
+    let bakes = [BakedOp {
+        uuid: graph.nodes[4].data.uuid,
+        slot: 0,
+        min: Vector3::new(-1.0, -1.0, -1.0),
+        max: Vector3::new(1.0, 1.0, 1.0),
+    }];
+
+    // Everything upstream of the baked union is left out.
+    let builder = ShaderBuilder::new(Profile::Glsl430).unwrap();
+    let source = builder.generate_source_with(&graph, 5, Style::Pretty, &bakes, None).unwrap();
+    assert_eq!(source.op_count, 2);
+    assert!(source.map.contains("float union_4 = sdf_baked(baked[0], p,"));
+    assert!(source.map.contains("float id_union_4 = 4.0;"));
+    assert!(!source.map.contains("sdf_box"));
+    assert!(source.fs_src.contains("#define BAKE_CAPACITY 8"));
+
+    // Exports aren't affected.
+    assert!(!builder.generate_source(&graph, 5, Style::Pretty).unwrap().map.contains("baked"));
+}
+
+#[test]
+fn test_generate_hierarchy() {
+    use operator::DomainType;
+
+    // Four spheres, spread out along the x-axis, combined by a chain of
+    // unions.
+    let root = OpFamily::Domain(DomainType::Root);
+    let transform = OpFamily::Domain(DomainType::Transform);
+    let sphere = OpFamily::Primitive(PrimitiveType::Sphere);
+    let union = OpFamily::Primitive(PrimitiveType::Union);
+    let render = OpFamily::Primitive(PrimitiveType::Render);
+    let mut graph = make_graph(
+        &[
+            root, transform, transform, transform, transform, sphere, sphere, sphere, sphere,
+            union, union, union, render,
+        ],
+        &[
+            (0, 1), (0, 2), (0, 3), (0, 4), (1, 5), (2, 6), (3, 7), (4, 8),
+            (5, 9), (6, 9), (9, 10), (7, 10), (10, 11), (8, 11), (11, 12),
+        ],
+    );
+    for index in 1..5 {
+        let offset = index as f32 * 4.0;
+        *graph.nodes[index].data.params.get_data_mut() = [offset, 0.0, 0.0, 1.0];
     }
+
+    let hierarchy = Hierarchy::build(&graph);
+    assert_eq!(hierarchy.clusters.len(), 1);
+    let cluster = &hierarchy.clusters[0];
+    assert_eq!(cluster.root, 11);
+    assert_eq!(cluster.nodes.len(), 3);
+    assert_eq!(cluster.offset, graph.nodes.len());
+
+    // The spheres of the tree are stored after the op parameters.
+    let mut data = vec![0.0; graph.nodes.len() * constants::PARAMETER_CAPACITY];
+    hierarchy.refit(&graph, &mut data);
+    assert_eq!(data.len(), (graph.nodes.len() + 3) * constants::PARAMETER_CAPACITY);
+    assert_eq!(&data[data.len() - 4..], &[-6.0, 0.0, 0.0, 3.0]);
+
+    let builder = ShaderBuilder::new(Profile::Glsl430).unwrap();
+    let map = builder
+        .generate_source_with(&graph, 12, Style::Pretty, &[], Some(&hierarchy))
+        .unwrap()
+        .map;
+    assert!(map.contains("float union_11 = 1e10;"));
+    assert!(map.contains("if (length(p - params[13].xyz) - params[13].w < union_11) {"));
+    let closest = "if (sphere_5 < union_11) { union_11 = sphere_5; id_union_11 = id_sphere_5; }";
+    assert!(map.contains(closest));
+    assert!(!map.contains("union_9"));
+
+    // A union that is missing an input can't be generated either way.
+    graph.edges[9].inputs.pop();
+    let error = builder.generate_source_with(&graph, 12, Style::Pretty, &[], Some(&hierarchy));
+    assert_eq!(error.err().unwrap(), BuildError::MissingInputs("union_9".to_string()));
 }
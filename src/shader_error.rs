@@ -0,0 +1,244 @@
+use uuid::Uuid;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// The step of building a shader program that failed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    Link,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Stage::Vertex => "vertex shader",
+            Stage::Fragment => "fragment shader",
+            Stage::Link => "program",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A single error (or warning) from a shader compiler or linker log.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// The stage that reported the error
+    pub stage: Stage,
+
+    /// The (1-based) line of the stage's source that caused the error,
+    /// if the driver reported one
+    pub line: Option<usize>,
+
+    /// The (1-based) column of the error, which only some drivers report
+    pub column: Option<usize>,
+
+    /// The error message, without its location
+    pub message: String,
+
+    /// The (trimmed) line of source code that caused the error
+    pub excerpt: Option<String>,
+}
+
+/// An error that occurred while compiling or linking a shader program.
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    /// The first stage that failed
+    pub stage: Stage,
+
+    /// The raw info log(s) of the stage(s) that failed
+    pub log: String,
+
+    /// The errors parsed from the log(s)
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Maps (1-based) lines of the fragment shader to the op that
+    /// generated them, if the shader was generated from a graph of ops
+    source_map: HashMap<usize, Uuid>,
+}
+
+/// Splits a line of a GLSL info log into the line and column that it
+/// refers to and the rest of the message. Drivers format these
+/// differently, i.e. `0(12) : error ...` (NVIDIA), `0:12(5): error: ...`
+/// (Mesa) or `ERROR: 0:12: ...` (AMD and Intel).
+fn parse_location(entry: &str) -> (Option<usize>, Option<usize>, &str) {
+    // Skip the index of the source string, which is always zero, since
+    // each shader is built from a single string.
+    let entry = entry
+        .trim_start_matches("ERROR: ")
+        .trim_start_matches("WARNING: ");
+    if !entry.starts_with(|c: char| c.is_ascii_digit()) {
+        return (None, None, entry);
+    }
+    let rest = entry
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(&['(', ':'][..]);
+
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let line = digits.parse().ok();
+    let mut rest = &rest[digits.len()..];
+
+    // Mesa follows the line with the column, in parentheses.
+    let mut column = None;
+    if rest.starts_with('(') {
+        let digits: String = rest[1..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        column = digits.parse().ok();
+        rest = &rest[1 + digits.len()..];
+    }
+
+    let message = rest.trim_start_matches(&[')', ':', ' '][..]);
+    (line, column, message)
+}
+
+/// Parses each of the errors in the info `log` of `stage`, whose source
+/// code is `src`.
+fn parse_log(stage: Stage, log: &str, src: Option<&str>) -> Vec<Diagnostic> {
+    parse_entries(stage, log, src, "error")
+}
+
+/// Parses each of the warnings in the info `log` of `stage`, whose source
+/// code is `src`, i.e. implicit conversions or unused variables.
+pub fn parse_warnings(stage: Stage, log: &str, src: Option<&str>) -> Vec<Diagnostic> {
+    parse_entries(stage, log, src, "warning")
+}
+
+/// Parses each of the entries in the info `log` of `stage` that contain
+/// `keyword` (regardless of case).
+fn parse_entries(stage: Stage, log: &str, src: Option<&str>, keyword: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .filter(|entry| entry.to_lowercase().contains(keyword))
+        .map(|entry| {
+            let (line, column, message) = parse_location(entry.trim());
+            let excerpt = match (line, src) {
+                (Some(line), Some(src)) if line > 0 => {
+                    src.lines().nth(line - 1).map(|code| code.trim().to_string())
+                }
+                _ => None,
+            };
+            Diagnostic {
+                stage,
+                line,
+                column,
+                message: message.to_string(),
+                excerpt,
+            }
+        })
+        .collect()
+}
+
+impl ShaderError {
+    /// Creates an error from the info `log` of `stage`, whose source code
+    /// (if it has any) is `src`.
+    pub fn new(stage: Stage, log: &str, src: Option<&str>) -> ShaderError {
+        ShaderError {
+            stage,
+            log: log.to_string(),
+            diagnostics: parse_log(stage, log, src),
+            source_map: HashMap::new(),
+        }
+    }
+
+    /// Appends the errors of another stage that failed, i.e. when both the
+    /// vertex and fragment shaders fail to compile.
+    pub fn merge(mut self, other: ShaderError) -> ShaderError {
+        self.log.push('\n');
+        self.log.push_str(&other.log);
+        self.diagnostics.extend(other.diagnostics);
+        self
+    }
+
+    /// Attaches a map from (1-based) lines of the fragment shader to the op
+    /// that generated them, so that each error can be traced back to an op.
+    pub fn with_source_map(mut self, source_map: HashMap<usize, Uuid>) -> ShaderError {
+        self.source_map = source_map;
+        self
+    }
+
+    /// Returns the UUID of the op that generated the line of code that
+    /// caused `diagnostic`, if any.
+    pub fn get_op(&self, diagnostic: &Diagnostic) -> Option<Uuid> {
+        match (diagnostic.stage, diagnostic.line) {
+            (Stage::Fragment, Some(line)) => self.source_map.get(&line).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns the UUIDs of all of the ops responsible for errors, without
+    /// any duplicates.
+    pub fn get_ops(&self) -> Vec<Uuid> {
+        let mut ops = Vec::new();
+        for diagnostic in self.diagnostics.iter() {
+            if let Some(uuid) = self.get_op(diagnostic) {
+                if !ops.contains(&uuid) {
+                    ops.push(uuid);
+                }
+            }
+        }
+        ops
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.stage)?;
+        if let Some(line) = self.line {
+            write!(f, ", line {}", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ", column {}", column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(ref excerpt) = self.excerpt {
+            write!(f, "\n    {}", excerpt)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Fall back to the raw log if it couldn't be parsed.
+        if self.diagnostics.is_empty() {
+            return write!(f, "{} error:\n{}", self.stage, self.log);
+        }
+
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_log() {
+    let src = "#version 430\nfloat a = sphere_0;\nfloat b = box_1;";
+
+    let nvidia = "0(2) : error C1008: undefined variable \"sphere_0\"";
+    let diagnostics = parse_log(Stage::Fragment, nvidia, Some(src));
+    assert_eq!(diagnostics[0].line, Some(2));
+    assert_eq!(diagnostics[0].column, None);
+    assert_eq!(diagnostics[0].message, "error C1008: undefined variable \"sphere_0\"");
+    assert_eq!(diagnostics[0].excerpt, Some("float a = sphere_0;".to_string()));
+
+    let mesa = "0:3(11): error: `box_1' undeclared";
+    let diagnostics = parse_log(Stage::Fragment, mesa, Some(src));
+    assert_eq!(diagnostics[0].line, Some(3));
+    assert_eq!(diagnostics[0].column, Some(11));
+    assert_eq!(diagnostics[0].message, "error: `box_1' undeclared");
+
+    let intel = "ERROR: 0:98: 'torus_2' : undeclared identifier\nWARNING: 0:3: unused";
+    let diagnostics = parse_log(Stage::Fragment, intel, None);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, Some(98));
+    assert_eq!(diagnostics[0].excerpt, None);
+
+    let warnings = parse_warnings(Stage::Fragment, intel, Some(src));
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line, Some(3));
+    assert_eq!(warnings[0].message, "unused");
+}
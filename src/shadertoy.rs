@@ -0,0 +1,137 @@
+use cgmath::Point3;
+
+/// The uniforms of the preview that are set from Shadertoy's inputs (i.e.
+/// `iTime` and `iMouse`) inside of `mainImage(..)`, which become ordinary
+/// global variables when exporting.
+const DYNAMIC_UNIFORMS: &[&str] = &[
+    "u_time",
+    "u_aspect",
+    "u_frame",
+    "u_camera_position",
+    "u_camera_front",
+    "u_camera_up",
+];
+
+/// The state of the preview that is baked into a shader when exporting
+/// it to Shadertoy.
+pub struct Snapshot {
+    /// The values of all of the op parameters, four per op
+    pub params: Vec<f32>,
+
+    /// The position of the camera
+    pub camera_position: Point3<f32>,
+
+    /// The point that the camera looks at (and orbits around)
+    pub camera_focus: Point3<f32>,
+
+    /// The GLSL values of the uniforms that stay the same for every pixel
+    /// and frame, keyed by name
+    pub constants: Vec<(&'static str, String)>,
+}
+
+/// Returns `value` as a GLSL float literal.
+pub fn float(value: f32) -> String {
+    format!("{:?}", value)
+}
+
+/// Returns `point` as a GLSL `vec3` constructor.
+fn vec3(point: &Point3<f32>) -> String {
+    format!("vec3({}, {}, {})", float(point.x), float(point.y), float(point.z))
+}
+
+/// Returns the code that takes the place of the preview's prelude, which
+/// declares the op parameters of `snapshot` as an array (rather than a
+/// buffer) along with the initial camera, followed by the usual header.
+/// If `bake_params` is set, the array is constant and holds the current
+/// values, and otherwise it's up to the code that calls `mainImage(..)`
+/// to fill it in. There is no `#version` directive, since Shadertoy adds
+/// its own.
+pub fn get_prelude(snapshot: &Snapshot, bake_params: bool) -> String {
+    let vectors: Vec<String> = snapshot
+        .params
+        .chunks(4)
+        .map(|chunk| {
+            let components: Vec<String> = chunk.iter().map(|&value| float(value)).collect();
+            format!("    vec4({})", components.join(", "))
+        })
+        .collect();
+
+    let params = if bake_params {
+        format!(
+            "const vec4 params[{}] = vec4[{}](\n{}\n);",
+            vectors.len(),
+            vectors.len(),
+            vectors.join(",\n")
+        )
+    } else {
+        format!("vec4 params[{}];", vectors.len())
+    };
+
+    format!(
+        "{params}\n\n\
+         #define CAMERA_POSITION {position}\n\
+         #define CAMERA_FOCUS {focus}\n\n\
+         #include \"preview_header.glsl\"",
+        params = params,
+        position = vec3(&snapshot.camera_position),
+        focus = vec3(&snapshot.camera_focus)
+    )
+}
+
+/// Rewrites the preview's fragment shader `fs_src` into Shadertoy's
+/// conventions: uniforms become constants (with the values in `constants`)
+/// or global variables that are set by `mainImage(..)`, as do the shader's
+/// inputs and outputs, and `main()` is renamed to `preview_main()`. Fails
+/// if a uniform doesn't have a value.
+pub fn convert(fs_src: &str, constants: &[(&str, String)]) -> Result<String, String> {
+    let mut converted = String::new();
+    for line in fs_src.lines() {
+        let tokens: Vec<&str> = line.trim_end_matches(';').split_whitespace().collect();
+        let is_global = !line.starts_with(char::is_whitespace) && line.ends_with(';');
+
+        if is_global && tokens.len() == 3 && tokens[0] == "uniform" {
+            let name = tokens[2];
+            match constants.iter().find(|&&(constant, _)| constant == name) {
+                Some((_, value)) => {
+                    converted.push_str(&format!("const {} {} = {};", tokens[1], name, value))
+                }
+                None if DYNAMIC_UNIFORMS.contains(&name) => {
+                    converted.push_str(&format!("{} {};", tokens[1], name))
+                }
+                None => return Err(format!("uniform {} doesn't have a value", name)),
+            }
+        } else if is_global
+            && !line.trim_end_matches(';').ends_with(')')
+            && tokens.iter().any(|&token| token == "in" || token == "out")
+        {
+            // Inputs and outputs (i.e. `layout (location = 0) out vec4 o_color`,
+            // as opposed to function declarations) end with their type and name.
+            converted.push_str(&tokens[tokens.len() - 2..].join(" "));
+            converted.push(';');
+        } else if line.trim() == "void main()" {
+            converted.push_str("void preview_main()");
+        } else {
+            converted.push_str(line);
+        }
+        converted.push('\n');
+    }
+    Ok(converted)
+}
+
+#[test]
+fn test_convert() {
+    let fs_src = "INTERSTAGE_LOCATION(0) in vec2 vs_texcoord;\n\
+                  layout (location = 0) out vec4 o_color;\n\
+                  uniform float u_time;\n\
+                  uniform uint u_shading;\n\
+                  vec2 map(in vec3 p);\n\
+                  void main()\n{\n    o_color = vec4(1.0);\n}\n";
+    let converted = convert(fs_src, &[("u_shading", "2u".to_string())]).unwrap();
+    assert_eq!(
+        converted,
+        "vec2 vs_texcoord;\nvec4 o_color;\nfloat u_time;\nconst uint u_shading = 2u;\n\
+         vec2 map(in vec3 p);\nvoid preview_main()\n{\n    o_color = vec4(1.0);\n}\n"
+    );
+
+    assert!(convert("uniform bool u_unknown;\n", &[]).is_err());
+}
@@ -0,0 +1,69 @@
+use constants;
+
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The API that a SPIR-V module is meant for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Environment {
+    /// OpenGL (via `GL_ARB_gl_spirv`), which allows plain uniforms outside
+    /// of uniform blocks, like the ones that the preview's shaders use
+    OpenGl,
+
+    /// Vulkan, which is also what other translators (i.e. naga) expect
+    Vulkan,
+}
+
+/// Returns the arguments that compile a fragment shader (read from the
+/// standard input) into a SPIR-V module for `environment` at `path`. Any
+/// uniforms or interface variables without explicit locations or bindings
+/// are assigned one by the compiler.
+fn get_arguments(path: &Path, environment: Environment) -> Vec<String> {
+    let target = match environment {
+        Environment::OpenGl => "opengl",
+        Environment::Vulkan => "vulkan",
+    };
+    vec![
+        "-fshader-stage=frag".to_string(),
+        format!("--target-env={}", target),
+        "-fauto-bind-uniforms".to_string(),
+        "-fauto-map-locations".to_string(),
+        "-o".to_string(),
+        path.display().to_string(),
+        "-".to_string(),
+    ]
+}
+
+/// Compiles the fragment shader `fs_src` into a SPIR-V module for
+/// `environment` at `path` with shaderc's `glslc`, which also validates
+/// the module. Returns the compiler's diagnostics if it fails, or if it
+/// can't be run at all.
+pub fn compile(fs_src: &str, path: &Path, environment: Environment) -> Result<(), String> {
+    let mut child = Command::new(constants::SPIRV_COMPILER)
+        .args(get_arguments(path, environment))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| match error.kind() {
+            ErrorKind::NotFound => format!(
+                "{} wasn't found, so make sure that shaderc is installed",
+                constants::SPIRV_COMPILER
+            ),
+            _ => format!("failed to run {}: {}", constants::SPIRV_COMPILER, error),
+        })?;
+
+    // Closing the standard input (by dropping it) lets the compiler
+    // know that it has the entire shader.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(fs_src.as_bytes()).map_err(|error| error.to_string())?;
+    }
+
+    let output = child.wait_with_output().map_err(|error| error.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
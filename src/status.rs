@@ -0,0 +1,85 @@
+use pacing::FrameSettings;
+
+use std::time::{Duration, SystemTime};
+
+/// Converts `duration` to (fractional) milliseconds.
+fn to_milliseconds(duration: &Duration) -> f32 {
+    duration.as_secs() as f32 * 1000.0 + duration.subsec_nanos() as f32 / 1_000_000.0
+}
+
+/// Keeps track of the statistics that are shown in the status bar
+/// at the bottom of the network editor.
+pub struct Status {
+    /// The number of frames drawn since `sample_start`
+    frames: u32,
+
+    /// The time at which the current FPS sample started
+    sample_start: SystemTime,
+
+    /// The frame rate measured over the last (roughly one second) sample
+    fps: f32,
+
+    /// The zoom of the network editor
+    pub zoom: f32,
+
+    /// How long it took to build the most recent shader graph (if one
+    /// has been built)
+    pub build_time: Option<Duration>,
+
+    /// A flag that is set while the preview keeps showing the last program
+    /// that was built, because the current graph can't be built (i.e. an
+    /// op is missing inputs while it is being rewired)
+    pub stale: bool,
+}
+
+impl Status {
+    pub fn new() -> Status {
+        Status {
+            frames: 0,
+            sample_start: SystemTime::now(),
+            fps: 0.0,
+            zoom: 1.0,
+            build_time: None,
+            stale: false,
+        }
+    }
+
+    /// Records that a new frame has been drawn, updating the
+    /// frame rate about once per second.
+    pub fn tick(&mut self) {
+        self.frames += 1;
+
+        if let Ok(elapsed) = self.sample_start.elapsed() {
+            let milliseconds = to_milliseconds(&elapsed);
+            if milliseconds >= 1000.0 {
+                self.fps = self.frames as f32 * 1000.0 / milliseconds;
+                self.frames = 0;
+                self.sample_start = SystemTime::now();
+            }
+        }
+    }
+
+    /// Returns the text of the status bar, given the number of ops and
+    /// connections in the network and how often it is redrawn.
+    pub fn get_text(&self, ops: usize, connections: usize, frame: &FrameSettings) -> String {
+        let build_time = match self.build_time {
+            Some(ref duration) => format!("{:.1} ms", to_milliseconds(duration)),
+            None => "-".to_string(),
+        };
+        let build_time = if self.stale {
+            format!("{} (stale)", build_time)
+        } else {
+            build_time
+        };
+
+        format!(
+            "ops: {}  connections: {}  build: {}  zoom: {:.2}  fps: {:.0} ({})",
+            ops,
+            connections,
+            build_time,
+            self.zoom,
+            self.fps,
+            frame.get_description()
+        )
+    }
+}
@@ -0,0 +1,276 @@
+use cgmath::Vector2;
+use gl::{self, types::*};
+
+use constants;
+use preprocessor;
+use profile::Profile;
+use program::Program;
+
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+
+/// The number of invocations along each axis of a work group, which
+/// matches the `local_size_*` qualifiers in `steps.glsl`.
+const WORK_GROUP_SIZE: u32 = 16;
+
+/// The number of `u32`s in the histogram buffer: the sum of every pixel's
+/// step count, the largest step count, and then each bin.
+const BUFFER_LENGTH: usize = 2 + constants::STEP_HISTOGRAM_BINS;
+
+/// A summary of how many steps the raymarcher took through each pixel
+/// of the preview.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepStatistics {
+    /// The number of pixels that took each range of step counts, where
+    /// each bin spans `STEP_HISTOGRAM_RANGE / STEP_HISTOGRAM_BINS` steps
+    /// (and the last one also holds anything beyond)
+    pub bins: Vec<u32>,
+
+    /// The average number of steps per pixel
+    pub mean: f32,
+
+    /// The largest number of steps that any pixel took
+    pub max: u32,
+}
+
+impl StepStatistics {
+    /// Unpacks the contents of the histogram buffer (see `BUFFER_LENGTH`).
+    fn from_buffer(data: &[u32]) -> StepStatistics {
+        let bins = data[2..].to_vec();
+        let pixels: u32 = bins.iter().sum();
+        StepStatistics {
+            mean: if pixels > 0 {
+                data[0] as f32 / pixels as f32
+            } else {
+                0.0
+            },
+            max: data[1],
+            bins,
+        }
+    }
+}
+
+/// Reduces the step counts that the preview writes into an image (one per
+/// pixel, see `preview_footer.glsl`) into a histogram with a compute pass.
+/// The results are read back once the GPU is done with them, which may be
+/// a frame or two later, so the CPU never waits. Compute passes require
+/// shader storage buffers, so this is only supported from GLSL 4.30 on.
+pub struct StepHistogram {
+    /// The compute program that builds the histogram, or `None` if the
+    /// profile doesn't support it (or it failed to compile)
+    program: Option<Program>,
+
+    /// The OpenGL handle to the image that holds the step counts
+    image: GLuint,
+
+    /// The size of `image`, in pixels
+    resolution: (u32, u32),
+
+    /// The OpenGL handle to the buffer that the histogram is built in
+    buffer: GLuint,
+
+    /// The fence that signals when the most recent reduction is done, if
+    /// it hasn't been read back yet
+    fence: Option<GLsync>,
+
+    /// A flag that is set if the image changed while the last reduction
+    /// was still pending, in which case it is reduced again afterwards
+    stale: bool,
+
+    /// The most recent results (if any)
+    statistics: Option<StepStatistics>,
+}
+
+/// Returns the source of the compute shader that bins the step counts,
+/// before its includes are expanded.
+pub fn get_prelude(profile: Profile) -> String {
+    format!(
+        "{}\n#define STEP_IMAGE_UNIT {}\n#define STEP_HISTOGRAM_BINDING {}\n\
+         #define STEP_HISTOGRAM_BINS {}\n#define STEP_HISTOGRAM_RANGE {}\n\
+         #include \"steps.glsl\"",
+        profile.get_prelude(),
+        constants::STEP_IMAGE_UNIT,
+        constants::STEP_HISTOGRAM_BINDING,
+        constants::STEP_HISTOGRAM_BINS,
+        constants::STEP_HISTOGRAM_RANGE
+    )
+}
+
+impl StepHistogram {
+    pub fn new(profile: Profile) -> StepHistogram {
+        let program = if profile.uses_storage_buffer() {
+            let result = preprocessor::process("prelude", &get_prelude(profile))
+                .and_then(|source| Program::new_compute(source.code));
+            match result {
+                Ok(program) => Some(program),
+                Err(error) => {
+                    warn!("Failed to build the step histogram program: {}", error);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut buffer = 0;
+        unsafe {
+            gl::CreateBuffers(1, &mut buffer);
+            gl::NamedBufferStorage(
+                buffer,
+                (BUFFER_LENGTH * mem::size_of::<u32>()) as GLsizeiptr,
+                ptr::null(),
+                gl::DYNAMIC_STORAGE_BIT,
+            );
+        }
+
+        StepHistogram {
+            program,
+            image: 0,
+            resolution: (0, 0),
+            buffer,
+            fence: None,
+            stale: false,
+            statistics: None,
+        }
+    }
+
+    /// Returns `true` if step counts can be recorded with this profile.
+    pub fn is_supported(&self) -> bool {
+        self.program.is_some()
+    }
+
+    /// Returns the most recent results, if any have been read back.
+    pub fn get_statistics(&self) -> Option<&StepStatistics> {
+        self.statistics.as_ref()
+    }
+
+    /// Binds an image that is `width` by `height` pixels to the image unit
+    /// `STEP_IMAGE_UNIT`, re-creating it first if its size has changed.
+    pub fn bind_image(&mut self, width: u32, height: u32) {
+        unsafe {
+            if self.resolution != (width, height) {
+                gl::DeleteTextures(1, &self.image);
+                gl::CreateTextures(gl::TEXTURE_2D, 1, &mut self.image);
+                gl::TextureStorage2D(
+                    self.image,
+                    1,
+                    gl::R32UI,
+                    width as GLsizei,
+                    height as GLsizei,
+                );
+                self.resolution = (width, height);
+            }
+            gl::BindImageTexture(
+                constants::STEP_IMAGE_UNIT,
+                self.image,
+                0,
+                gl::FALSE,
+                0,
+                gl::READ_WRITE,
+                gl::R32UI,
+            );
+        }
+    }
+
+    /// Builds the histogram of the step counts in the image, once every
+    /// draw call that writes to it has been issued. While the results of
+    /// the last reduction are still pending, this is put off until they
+    /// have been read back (see `poll`).
+    pub fn reduce(&mut self) {
+        if self.fence.is_some() {
+            self.stale = true;
+            return;
+        }
+        let program = match self.program {
+            Some(ref program) => program,
+            None => return,
+        };
+        let (width, height) = self.resolution;
+        let groups = |count: u32| (count + WORK_GROUP_SIZE - 1) / WORK_GROUP_SIZE;
+
+        program.bind();
+        program.uniform_2ui("u_resolution", &Vector2::new(width, height));
+        unsafe {
+            gl::ClearNamedBufferData(
+                self.buffer,
+                gl::R32UI,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_INT,
+                ptr::null(),
+            );
+            gl::BindBufferBase(
+                gl::SHADER_STORAGE_BUFFER,
+                constants::STEP_HISTOGRAM_BINDING,
+                self.buffer,
+            );
+
+            // Make sure that the step counts are written before reading them.
+            gl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+            gl::DispatchCompute(groups(width), groups(height), 1);
+            gl::MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, constants::STEP_HISTOGRAM_BINDING, 0);
+            self.fence = Some(gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+        }
+        program.unbind();
+    }
+
+    /// Reads back the results of the last reduction, if the GPU is done
+    /// with it.
+    pub fn poll(&mut self) {
+        let fence = match self.fence {
+            Some(fence) => fence,
+            None => return,
+        };
+
+        let mut data = [0u32; BUFFER_LENGTH];
+        unsafe {
+            let status = gl::ClientWaitSync(fence, 0, 0);
+            if status == gl::TIMEOUT_EXPIRED {
+                return;
+            }
+            gl::DeleteSync(fence);
+            gl::GetNamedBufferSubData(
+                self.buffer,
+                0,
+                mem::size_of_val(&data) as GLsizeiptr,
+                data.as_mut_ptr() as *mut c_void,
+            );
+        }
+        self.fence = None;
+        self.statistics = Some(StepStatistics::from_buffer(&data));
+
+        if self.stale {
+            self.stale = false;
+            self.reduce();
+        }
+    }
+}
+
+impl Drop for StepHistogram {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(fence) = self.fence {
+                gl::DeleteSync(fence);
+            }
+            gl::DeleteTextures(1, &self.image);
+            gl::DeleteBuffers(1, &self.buffer);
+        }
+    }
+}
+
+#[test]
+fn test_from_buffer() {
+    let mut data = [0u32; BUFFER_LENGTH];
+    data[0] = 40;
+    data[1] = 25;
+    data[2] = 3;
+    data[3] = 1;
+
+    let statistics = StepStatistics::from_buffer(&data);
+    assert_eq!(statistics.bins.len(), constants::STEP_HISTOGRAM_BINS);
+    assert_eq!(statistics.mean, 10.0);
+    assert_eq!(statistics.max, 25);
+    assert_eq!(StepStatistics::from_buffer(&[0; BUFFER_LENGTH]).mean, 0.0);
+}
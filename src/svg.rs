@@ -0,0 +1,193 @@
+use cgmath::Vector2;
+
+use bounds::Rect;
+use color::Color;
+use routing::Segment;
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Returns `color` as an SVG hex code, i.e. `#76b264`. Alpha is written
+/// separately, as an opacity attribute.
+fn hex(color: &Color) -> String {
+    let channel = |value: f32| (value.max(0.0).min(1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+/// Escapes the characters in `text` that have a special meaning in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A simple, vector representation of the network that is built up
+/// element by element and written out as an SVG document. Coordinates
+/// are specified in the coordinate space of the network editor.
+pub struct Svg {
+    /// The region of the network that is visible in the document
+    view: Rect,
+
+    /// The elements of the document, in the order they should be painted
+    elements: Vec<String>,
+}
+
+impl Svg {
+    /// Creates an empty document that shows the region of the network
+    /// covered by `view`.
+    pub fn new(view: &Rect) -> Svg {
+        Svg {
+            view: *view,
+            elements: Vec::new(),
+        }
+    }
+
+    /// Adds a rectangle with rounded corners of the specified `radius`,
+    /// which is filled with `fill` and optionally outlined with a
+    /// `(color, width)` stroke.
+    pub fn rect(
+        &mut self,
+        bounds: &Rect,
+        radius: f32,
+        fill: &Color,
+        stroke: Option<(&Color, f32)>,
+    ) {
+        let ul = bounds.get_upper_left();
+        let size = bounds.get_size();
+        let stroke = match stroke {
+            Some((color, width)) => format!(
+                " stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\"",
+                hex(color),
+                color.a,
+                width
+            ),
+            None => String::new(),
+        };
+        self.elements.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" \
+             fill=\"{}\" fill-opacity=\"{}\"{}/>",
+            ul.x,
+            ul.y,
+            size.x,
+            size.y,
+            radius,
+            hex(fill),
+            fill.a,
+            stroke
+        ));
+    }
+
+    /// Adds a chain of cubic Bezier `segments`, stroked with `color`.
+    pub fn curve(&mut self, segments: &[Segment], color: &Color, width: f32, dashed: bool) {
+        let mut data = String::new();
+        if let Some(first) = segments.first() {
+            data.push_str(&format!("M {} {}", first[0].x, first[0].y));
+        }
+        for segment in segments.iter() {
+            data.push_str(&format!(
+                " C {} {} {} {} {} {}",
+                segment[1].x, segment[1].y, segment[2].x, segment[2].y, segment[3].x, segment[3].y
+            ));
+        }
+        self.path(&data, color, width, dashed);
+    }
+
+    /// Adds an open polyline through `points`, stroked with `color`.
+    pub fn polyline(&mut self, points: &[Vector2<f32>], color: &Color, width: f32) {
+        let data: Vec<String> = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let command = if i == 0 { "M" } else { "L" };
+                format!("{} {} {}", command, point.x, point.y)
+            })
+            .collect();
+        self.path(&data.join(" "), color, width, false);
+    }
+
+    /// Adds a line of `text` whose upper-left corner is at `position`.
+    pub fn text(&mut self, text: &str, position: &Vector2<f32>, size: f32, color: &Color) {
+        self.elements.push(format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" \
+             dominant-baseline=\"hanging\" fill=\"{}\" fill-opacity=\"{}\">{}</text>",
+            position.x,
+            position.y,
+            size,
+            hex(color),
+            color.a,
+            escape(text)
+        ));
+    }
+
+    /// Adds a stroked (but unfilled) path with the SVG path `data`.
+    fn path(&mut self, data: &str, color: &Color, width: f32, dashed: bool) {
+        let dashes = if dashed {
+            format!(" stroke-dasharray=\"{} {}\"", width * 4.0, width * 2.0)
+        } else {
+            String::new()
+        };
+        self.elements.push(format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"{}\" \
+             stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"{}/>",
+            data,
+            hex(color),
+            color.a,
+            width,
+            dashes
+        ));
+    }
+
+    /// Writes the SVG document to `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string().as_bytes())
+    }
+}
+
+impl fmt::Display for Svg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ul = self.view.get_upper_left();
+        let size = self.view.get_size();
+        writeln!(
+            f,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"{} {} {} {}\">",
+            size.x, size.y, ul.x, ul.y, size.x, size.y
+        )?;
+        for element in self.elements.iter() {
+            writeln!(f, "  {}", element)?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+#[test]
+fn test_svg_document() {
+    let view = Rect::new(Vector2::new(-50.0, -25.0), Vector2::new(100.0, 50.0));
+    let mut svg = Svg::new(&view);
+    svg.curve(
+        &[[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(3.0, 1.0),
+        ]],
+        &Color::white(),
+        2.0,
+        false,
+    );
+    svg.text("a < b", &Vector2::new(0.0, 0.0), 12.0, &Color::white());
+
+    let document = svg.to_string();
+    assert!(document.contains("viewBox=\"-50 -25 100 50\""));
+    assert!(document.contains("d=\"M 0 0 C 1 0 2 1 3 1\""));
+    assert!(document.contains(">a &lt; b</text>"));
+}
@@ -3,6 +3,9 @@ use gl::types::*;
 use image::{self, GenericImage};
 use cgmath::{self, Vector2};
 
+use backend::TextureHandle;
+use error::Error;
+
 use std::fs::File;
 use std::path::Path;
 use std::os::raw::c_void;
@@ -16,8 +19,12 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn new(path: &Path) -> Texture {
-        let image = image::open(path).unwrap().to_rgba();
+    /// Loads the image at `path`, which fails if it doesn't exist or can't
+    /// be decoded.
+    pub fn new(path: &Path) -> Result<Texture, Error> {
+        let image = image::open(path)
+            .map_err(|error| Error::Image(path.to_path_buf(), error))?
+            .to_rgba();
         let (w, h) = image.dimensions();
         let pixels: Vec<u8> = image.into_raw();
 
@@ -30,8 +37,9 @@ impl Texture {
             gl::TextureParameteri(id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
             gl::TextureParameteri(id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
 
-            // Allocate storage.
-            gl::TextureStorage2D(id, 1, gl::RGBA8, w as i32, h as i32);
+            // Allocate storage: images are stored in sRGB, so they are
+            // converted to linear space when sampled.
+            gl::TextureStorage2D(id, 1, gl::SRGB8_ALPHA8, w as i32, h as i32);
             gl::TextureSubImage2D(
                 id,
                 0,
@@ -51,7 +59,57 @@ impl Texture {
             id,
         };
         tex.generate_mip_maps();
-        tex
+        Ok(tex)
+    }
+
+    /// Returns the resolution (width and height) of the texture, in pixels.
+    pub fn get_resolution(&self) -> &Vector2<f32> {
+        &self.resolution
+    }
+
+    /// Sets the minification and magnification filters that will be used
+    /// when sampling from this texture.
+    pub fn set_filter(&self, min: GLenum, mag: GLenum) {
+        unsafe {
+            gl::TextureParameteri(self.id, gl::TEXTURE_MIN_FILTER, min as i32);
+            gl::TextureParameteri(self.id, gl::TEXTURE_MAG_FILTER, mag as i32);
+        }
+    }
+
+    /// Creates a new, uninitialized RGBA texture with the specified
+    /// dimensions, which is typically used as a render target.
+    pub fn empty(width: u32, height: u32) -> Texture {
+        Texture::empty_with_format(width, height, gl::RGBA8)
+    }
+
+    /// Creates an empty texture of the specified dimensions with
+    /// the specified internal format (i.e. `gl::RGBA32F`).
+    pub fn empty_with_format(width: u32, height: u32, format: GLenum) -> Texture {
+        let mut id = 0;
+        unsafe {
+            gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id);
+            gl::TextureParameteri(id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TextureParameteri(id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TextureParameteri(id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TextureParameteri(id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TextureStorage2D(id, 1, format, width as i32, height as i32);
+        }
+
+        Texture {
+            pixels: Vec::new(),
+            resolution: Vector2::new(width as f32, height as f32),
+            id,
+        }
+    }
+
+    /// Returns the OpenGL handle to this texture.
+    pub fn get_id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Returns the handle that a `Backend` binds this texture by.
+    pub fn get_handle(&self) -> TextureHandle {
+        TextureHandle(self.id)
     }
 
     pub fn bind(&self, unit: u32) {
@@ -72,3 +130,11 @@ impl Texture {
         }
     }
 }
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use gl;
+use cgmath::{self, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix};
+use uuid::Uuid;
+
+use constants;
+use framebuffer::Framebuffer;
+use preview::Shading;
+use program::Program;
+use renderer::Renderer;
+use texture::Texture;
+
+use std::collections::HashMap;
+
+/// A miniature raymarched preview of the distance field produced
+/// by a single op.
+struct Thumbnail {
+    /// The framebuffer that the thumbnail is rendered into
+    framebuffer: Framebuffer,
+
+    /// The shader program that evaluates the op's (partial) graph
+    program: Program,
+}
+
+/// Manages the thumbnails of all of the ops in the network, recycling
+/// framebuffers as ops are added and removed.
+pub struct Thumbnails {
+    /// The thumbnail of each op, keyed by the op's UUID
+    active: HashMap<Uuid, Thumbnail>,
+
+    /// Framebuffers that are no longer in use and can be handed out
+    /// to new thumbnails
+    pool: Vec<Framebuffer>,
+}
+
+impl Thumbnails {
+    pub fn new() -> Thumbnails {
+        Thumbnails {
+            active: HashMap::new(),
+            pool: Vec::new(),
+        }
+    }
+
+    /// Replaces the shader program of the thumbnail of the op with the
+    /// specified UUID, giving the op a thumbnail if it doesn't have one.
+    pub fn set_program(&mut self, uuid: Uuid, program: Program) {
+        let framebuffer = match self.active.remove(&uuid) {
+            Some(thumbnail) => thumbnail.framebuffer,
+            None => self.pool.pop().unwrap_or_else(|| {
+                Framebuffer::new(
+                    constants::THUMBNAIL_RESOLUTION,
+                    constants::THUMBNAIL_RESOLUTION,
+                )
+            }),
+        };
+        self.active.insert(
+            uuid,
+            Thumbnail {
+                framebuffer,
+                program,
+            },
+        );
+    }
+
+    /// Removes the thumbnail of the op with the specified UUID (if it has
+    /// one), returning its framebuffer to the pool.
+    pub fn remove(&mut self, uuid: &Uuid) {
+        if let Some(thumbnail) = self.active.remove(uuid) {
+            self.pool.push(thumbnail.framebuffer);
+        }
+    }
+
+    /// Removes the thumbnails of all of the ops whose UUIDs don't satisfy
+    /// `keep`, returning their framebuffers to the pool.
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: Fn(&Uuid) -> bool,
+    {
+        let stale: Vec<Uuid> = self.active.keys().filter(|uuid| !keep(uuid)).cloned().collect();
+        for uuid in stale.iter() {
+            self.remove(uuid);
+        }
+    }
+
+    /// Returns the texture containing the thumbnail of the op with
+    /// the specified UUID, if one exists.
+    pub fn get(&self, uuid: &Uuid) -> Option<&Texture> {
+        self.active
+            .get(uuid)
+            .map(|thumbnail| thumbnail.framebuffer.get_color_attachment())
+    }
+
+    /// Renders all of the thumbnails. Note that the SSBO of op parameters
+    /// must be bound before calling this function.
+    pub fn render(&self, renderer: &Renderer) {
+        // The quad that the raymarcher is drawn onto spans `[0..1]`, and it
+        // is flipped vertically so that the result is upright when sampled
+        // as a color map.
+        let projection = cgmath::ortho(0.0, 1.0, 0.0, 1.0, -1.0, 1.0);
+        let position = Point3::new(0.0, 1.5, 4.0);
+        let front = (Point3::origin() - position).normalize();
+
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+
+        for thumbnail in self.active.values() {
+            thumbnail.framebuffer.bind();
+
+            let program = &thumbnail.program;
+            program.bind();
+            program.uniform_3f("u_camera_position", &position.to_vec());
+            program.uniform_3f("u_camera_front", &front);
+            program.uniform_1ui("u_shading", Shading::Normals as u32);
+            program.uniform_1f("u_aspect", 1.0);
+            program.uniform_matrix_4f("u_model_matrix", &Matrix4::identity());
+            program.uniform_matrix_4f("u_projection_matrix", &projection);
+            renderer.draw_rect_inner();
+            program.unbind();
+
+            thumbnail.framebuffer.unbind();
+        }
+
+        unsafe {
+            gl::Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use constants;
+
+use std::time::SystemTime;
+
+/// The application's notion of time, which drives the `u_time` uniform
+/// in the preview. Time can be played, paused, and scrubbed, and playback
+/// loops over a user-defined range.
+pub struct Timeline {
+    /// The current time, in seconds
+    time: f32,
+
+    /// A flag that controls whether or not time is advancing
+    playing: bool,
+
+    /// The time at which playback starts over after reaching `loop_end`,
+    /// in seconds
+    loop_start: f32,
+
+    /// The time at which playback loops back to `loop_start`, in seconds
+    loop_end: f32,
+
+    /// The time at which the timeline was last updated
+    last_update: SystemTime,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            time: 0.0,
+            playing: false,
+            loop_start: 0.0,
+            loop_end: constants::TIMELINE_DURATION,
+            last_update: SystemTime::now(),
+        }
+    }
+
+    /// Returns the current time, in seconds.
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns `true` if time is advancing and `false` otherwise.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns the start and end of the loop range, in seconds.
+    pub fn get_loop_range(&self) -> (f32, f32) {
+        (self.loop_start, self.loop_end)
+    }
+
+    /// Plays or pauses the timeline.
+    pub fn toggle_playing(&mut self) {
+        self.playing = !self.playing;
+        self.last_update = SystemTime::now();
+    }
+
+    /// Advances time by however long it has been since the last update,
+    /// if the timeline is playing. This should be called once per frame.
+    pub fn update(&mut self) {
+        let elapsed = match self.last_update.elapsed() {
+            Ok(elapsed) => elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1e9,
+            Err(_) => 0.0,
+        };
+        self.last_update = SystemTime::now();
+
+        if self.playing {
+            self.advance(elapsed);
+        }
+    }
+
+    /// Advances time by `seconds`, wrapping around to the start of the
+    /// loop range after passing its end.
+    pub fn advance(&mut self, seconds: f32) {
+        self.time += seconds;
+        if self.time >= self.loop_end {
+            let length = self.loop_end - self.loop_start;
+            self.time = self.loop_start + (self.time - self.loop_end) % length;
+        }
+    }
+
+    /// Jumps to `time`, which is clamped to the length of the timeline.
+    pub fn scrub(&mut self, time: f32) {
+        self.time = time.max(0.0).min(constants::TIMELINE_DURATION);
+    }
+
+    /// Moves the start of the loop range to the current time.
+    pub fn set_loop_start(&mut self) {
+        self.loop_start = self.time
+            .min(self.loop_end - constants::TIMELINE_MIN_LOOP_LENGTH);
+        console!("Loop range: {:.2}s to {:.2}s", self.loop_start, self.loop_end);
+    }
+
+    /// Moves the end of the loop range to the current time.
+    pub fn set_loop_end(&mut self) {
+        self.loop_end = self.time
+            .max(self.loop_start + constants::TIMELINE_MIN_LOOP_LENGTH);
+        console!("Loop range: {:.2}s to {:.2}s", self.loop_start, self.loop_end);
+    }
+}
+
+#[test]
+fn test_timeline_loops() {
+    let mut timeline = Timeline::new();
+    timeline.scrub(2.0);
+    timeline.set_loop_start();
+    timeline.scrub(4.0);
+    timeline.set_loop_end();
+
+    timeline.scrub(3.5);
+    timeline.advance(1.0);
+    assert!((timeline.get_time() - 2.5).abs() < 1e-5);
+}
@@ -0,0 +1,149 @@
+use constants;
+use framebuffer::Framebuffer;
+
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// The container (and codec) that captured videos are encoded as.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VideoFormat {
+    /// H.264 in an MP4 container, which plays back almost anywhere
+    Mp4,
+
+    /// VP9 in a WebM container, which is friendlier to browsers
+    Webm,
+}
+
+impl VideoFormat {
+    /// Parses a format from its name, which is also its file extension
+    /// (i.e. `"webm"`).
+    pub fn from_name(name: &str) -> Option<VideoFormat> {
+        match name {
+            "mp4" => Some(VideoFormat::Mp4),
+            "webm" => Some(VideoFormat::Webm),
+            _ => None,
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match *self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+        }
+    }
+}
+
+/// Returns the arguments that encode raw RGBA frames of `width` by `height`
+/// pixels (read from the standard input) at `fps` frames per second into
+/// a video of `format` at `path`. OpenGL returns the rows of each frame
+/// bottom to top, so the frames are flipped by ffmpeg along the way.
+fn get_arguments(
+    width: u32,
+    height: u32,
+    fps: u32,
+    format: VideoFormat,
+    path: &Path,
+) -> Vec<String> {
+    let mut arguments = vec![
+        "-y".to_string(),
+        "-loglevel".to_string(),
+        "error".to_string(),
+        "-f".to_string(),
+        "rawvideo".to_string(),
+        "-pixel_format".to_string(),
+        "rgba".to_string(),
+        "-video_size".to_string(),
+        format!("{}x{}", width, height),
+        "-framerate".to_string(),
+        fps.to_string(),
+        "-i".to_string(),
+        "-".to_string(),
+        "-vf".to_string(),
+        "vflip".to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+    ];
+    let codec: &[&str] = match format {
+        VideoFormat::Mp4 => &["-c:v", "libx264", "-movflags", "+faststart"],
+        VideoFormat::Webm => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32"],
+    };
+    arguments.extend(codec.iter().map(|argument| argument.to_string()));
+    arguments.push(path.display().to_string());
+    arguments
+}
+
+/// An `ffmpeg` process that frames are streamed to, one at a time, as
+/// they are rendered.
+pub struct Encoder {
+    child: Child,
+}
+
+impl Encoder {
+    /// Starts encoding a video of `format` at `path`, with frames of
+    /// `width` by `height` pixels that play back at `fps` frames per
+    /// second. Fails if ffmpeg can't be run (i.e. it isn't on the `PATH`).
+    pub fn start(
+        width: u32,
+        height: u32,
+        fps: u32,
+        format: VideoFormat,
+        path: &Path,
+    ) -> Result<Encoder, String> {
+        let child = Command::new(constants::VIDEO_ENCODER)
+            .args(get_arguments(width, height, fps, format, path))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| match error.kind() {
+                ErrorKind::NotFound => format!(
+                    "{} wasn't found, so make sure that it is installed and on the PATH",
+                    constants::VIDEO_ENCODER
+                ),
+                _ => format!("failed to run {}: {}", constants::VIDEO_ENCODER, error),
+            })?;
+        Ok(Encoder { child })
+    }
+
+    /// Appends the contents of `framebuffer` to the video.
+    pub fn write_frame(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        let pixels = framebuffer.read_pixels();
+        match self.child.stdin {
+            Some(ref mut stdin) => stdin.write_all(&pixels).map_err(|error| {
+                format!("{} stopped accepting frames: {}", constants::VIDEO_ENCODER, error)
+            }),
+            None => Err("the encoder has already finished".to_string()),
+        }
+    }
+
+    /// Waits for ffmpeg to finish writing the video, and returns its
+    /// diagnostics if it fails.
+    pub fn finish(mut self) -> Result<(), String> {
+        // Closing the standard input (by dropping it) lets ffmpeg know
+        // that there aren't any more frames.
+        self.child.stdin.take();
+
+        let mut errors = String::new();
+        if let Some(ref mut stderr) = self.child.stderr {
+            let _ = stderr.read_to_string(&mut errors);
+        }
+        let status = self.child.wait().map_err(|error| error.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(errors.trim().to_string())
+        }
+    }
+}
+
+#[test]
+fn test_get_arguments() {
+    let arguments = get_arguments(640, 360, 24, VideoFormat::Webm, Path::new("orbit.webm"));
+    let video_size = arguments.iter().position(|argument| argument == "-video_size").unwrap();
+    assert_eq!(arguments[video_size + 1], "640x360");
+    assert!(arguments.contains(&"libvpx-vp9".to_string()));
+    assert_eq!(arguments.last().unwrap(), "orbit.webm");
+    assert_eq!(VideoFormat::from_name("mp4"), Some(VideoFormat::Mp4));
+    assert_eq!(VideoFormat::from_name("gif"), None);
+}
@@ -0,0 +1,190 @@
+use cgmath::{self, Matrix4, Vector2, Zero};
+
+use bounds::Rect;
+use constants;
+use interaction::MouseInfo;
+use renderer::Region;
+
+/// The part of the network editor that is visible in the window, which
+/// can be panned and zoomed.
+///
+/// There are three coordinate spaces, all of which are measured with the
+/// y-axis pointing down:
+///
+/// - window space, in pixels, with its origin at the upper-left corner
+///   of the window (i.e. the positions reported by `glutin`)
+/// - screen space, in pixels, with its origin at the center of the window,
+///   which is used by the preview and every panel that floats above the
+///   network (i.e. anything that stays put when the network is panned)
+/// - world space, which is used by ops, edges, and backdrops
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct View {
+    /// The size (in pixels) of the window
+    size: Vector2<f32>,
+
+    /// The point (in world space) at the center of the window
+    center: Vector2<f32>,
+
+    /// The number of world units per pixel, which is greater than `1.0`
+    /// when the network is zoomed out
+    zoom: f32,
+}
+
+impl View {
+    /// Returns the "home" view of a window that is `size` pixels wide and
+    /// tall, which is centered on the origin and isn't zoomed.
+    pub fn new(size: Vector2<f32>) -> View {
+        View {
+            size,
+            center: Vector2::zero(),
+            zoom: 1.0,
+        }
+    }
+
+    /// Returns a view that exactly fits `bounds` (in world space) into a
+    /// render target that is `resolution` pixels wide and tall, which is
+    /// used to draw the network offscreen.
+    pub fn framing(bounds: &Rect, resolution: &Vector2<f32>) -> View {
+        View {
+            size: *resolution,
+            center: bounds.centroid(),
+            zoom: bounds.get_size().x / resolution.x,
+        }
+    }
+
+    /// Returns the size (in pixels) of the window.
+    pub fn get_size(&self) -> &Vector2<f32> {
+        &self.size
+    }
+
+    /// Returns the number of world units per pixel.
+    pub fn get_zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Changes the size of the window, keeping the same point at its
+    /// center.
+    pub fn resize(&mut self, size: &Vector2<f32>) {
+        self.size = *size;
+    }
+
+    /// Returns to the "home" view (see `new`).
+    pub fn home(&mut self) {
+        self.center = Vector2::zero();
+        self.zoom = 1.0;
+    }
+
+    /// Moves the network by `delta` (in pixels), i.e. the distance that
+    /// the mouse moved while dragging it.
+    pub fn pan(&mut self, delta: &Vector2<f32>) {
+        self.center -= delta * self.zoom;
+    }
+
+    /// Changes the zoom, keeping whatever is under `point` (in screen
+    /// space) in place. The zoom is clamped to a reasonable range.
+    pub fn zoom_about(&mut self, point: &Vector2<f32>, zoom: f32) {
+        let anchor = self.to_world(point);
+        self.zoom = zoom.max(constants::NETWORK_MIN_ZOOM).min(constants::NETWORK_MAX_ZOOM);
+        self.center = anchor - point * self.zoom;
+    }
+
+    /// Converts a point from window space to screen space.
+    pub fn window_to_screen(self, point: &Vector2<f32>) -> Vector2<f32> {
+        point - self.size * 0.5
+    }
+
+    /// Converts a point from screen space to world space.
+    pub fn to_world(self, point: &Vector2<f32>) -> Vector2<f32> {
+        self.center + point * self.zoom
+    }
+
+    /// Converts a point from world space to screen space.
+    pub fn to_screen(self, point: &Vector2<f32>) -> Vector2<f32> {
+        (point - self.center) / self.zoom
+    }
+
+    /// Returns a copy of `mouse` whose positions are in world space,
+    /// which is what ops, edges, and backdrops are hit-tested against.
+    pub fn to_world_mouse(self, mouse: &MouseInfo) -> MouseInfo {
+        MouseInfo {
+            curr: self.to_world(&mouse.curr),
+            last: self.to_world(&mouse.last),
+            clicked: self.to_world(&mouse.clicked),
+            ldown: mouse.ldown,
+            rdown: mouse.rdown,
+            mdown: mouse.mdown,
+            scroll: mouse.scroll,
+            ctrl: mouse.ctrl,
+        }
+    }
+
+    /// Returns the region of the network (in world space) that is
+    /// currently visible.
+    pub fn get_bounds(&self) -> Rect {
+        let size = self.size * self.zoom;
+        Rect::new(self.center - size * 0.5, size)
+    }
+
+    /// Returns the projection matrix that maps world space to the window.
+    pub fn get_world_projection(&self) -> Matrix4<f32> {
+        let bounds = self.get_bounds();
+        let ul = bounds.get_upper_left();
+        let size = bounds.get_size();
+        cgmath::ortho(ul.x, ul.x + size.x, ul.y + size.y, ul.y, -1.0, 1.0)
+    }
+
+    /// Returns the projection matrix that maps screen space to the window.
+    pub fn get_screen_projection(&self) -> Matrix4<f32> {
+        let half_size = self.size * 0.5;
+        cgmath::ortho(-half_size.x, half_size.x, half_size.y, -half_size.y, -1.0, 1.0)
+    }
+
+    /// Returns the region of the window (in pixels) that is covered by
+    /// `bounds`, which is specified in screen space.
+    pub fn get_region(&self, bounds: &Rect) -> Region {
+        // Window coordinates start at the lower-left corner.
+        let ul = bounds.get_upper_left();
+        let size = bounds.get_size();
+        let half_size = self.size * 0.5;
+        Region::new(
+            (ul.x + half_size.x).round() as i32,
+            (half_size.y - ul.y - size.y).round() as i32,
+            size.x.round() as i32,
+            size.y.round() as i32,
+        )
+    }
+}
+
+#[test]
+fn test_conversions() {
+    let mut view = View::new(Vector2::new(800.0, 600.0));
+    let corner = view.window_to_screen(&Vector2::new(0.0, 0.0));
+    assert_eq!(corner, Vector2::new(-400.0, -300.0));
+    assert_eq!(view.to_world(&corner), corner);
+
+    view.pan(&Vector2::new(100.0, 50.0));
+    view.zoom_about(&Vector2::new(40.0, -20.0), 2.0);
+    let point = Vector2::new(40.0, -20.0);
+    assert_eq!(view.to_screen(&view.to_world(&point)), point);
+
+    // Zooming keeps the point under the cursor in place.
+    let anchor = view.to_world(&point);
+    view.zoom_about(&point, 0.5);
+    assert_eq!(view.to_world(&point), anchor);
+
+    let bounds = view.get_bounds();
+    assert_eq!(*bounds.get_size(), Vector2::new(400.0, 300.0));
+    assert_eq!(view.to_screen(bounds.get_upper_left()), corner);
+}
+
+#[test]
+fn test_framing() {
+    let bounds = Rect::new(Vector2::new(-100.0, 20.0), Vector2::new(400.0, 200.0));
+    let view = View::framing(&bounds, &Vector2::new(800.0, 400.0));
+    assert_eq!(view.get_zoom(), 0.5);
+    assert_eq!(*view.get_bounds().get_upper_left(), Vector2::new(-100.0, 20.0));
+
+    let region = View::new(Vector2::new(800.0, 600.0))
+        .get_region(&Rect::new(Vector2::new(-400.0, 200.0), Vector2::new(100.0, 100.0)));
+    assert_eq!(region, Region::new(0, 0, 100, 100));
+}
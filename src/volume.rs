@@ -0,0 +1,165 @@
+use grid::SampleGrid;
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The magic number at the start of a dense volume file (`SDFV`).
+const VOLUME_MAGIC: &[u8] = b"SDFV";
+
+/// The version of the dense volume format, which is bumped whenever the
+/// layout of the header changes.
+const VOLUME_VERSION: u32 = 1;
+
+/// The version of the MagicaVoxel format that is written.
+const VOX_VERSION: u32 = 150;
+
+/// The largest number of voxels along each axis of a MagicaVoxel model.
+const VOX_MAX_RESOLUTION: u32 = 256;
+
+/// The index of the palette entry that every voxel uses (the first color
+/// of MagicaVoxel's default palette, which is white).
+const VOX_COLOR_INDEX: u8 = 1;
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[
+        value as u8,
+        (value >> 8) as u8,
+        (value >> 16) as u8,
+        (value >> 24) as u8,
+    ]);
+}
+
+fn push_f32(bytes: &mut Vec<u8>, value: f32) {
+    push_u32(bytes, value.to_bits());
+}
+
+/// Returns a dense volume file that holds the `distances` that were
+/// evaluated over `grid` (see `SampleGrid::evaluate`). All values are
+/// little-endian, and the file is laid out as:
+///
+/// - the magic number `SDFV` and the version (`u32`)
+/// - the number of points along each axis (3 x `u32`)
+/// - the corners of the box that the grid spans (2 x 3 x `f32`)
+/// - the distance at every point (`f32`), where the x-coordinate varies
+///   fastest and the z-coordinate slowest
+pub fn generate_volume(grid: &SampleGrid, distances: &[f32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(44 + distances.len() * 4);
+    bytes.extend_from_slice(VOLUME_MAGIC);
+    push_u32(&mut bytes, VOLUME_VERSION);
+    push_u32(&mut bytes, grid.resolution.x);
+    push_u32(&mut bytes, grid.resolution.y);
+    push_u32(&mut bytes, grid.resolution.z);
+    for corner in [grid.min, grid.max].iter() {
+        push_f32(&mut bytes, corner.x);
+        push_f32(&mut bytes, corner.y);
+        push_f32(&mut bytes, corner.z);
+    }
+    for &distance in distances.iter() {
+        push_f32(&mut bytes, distance);
+    }
+    bytes
+}
+
+/// Appends a MagicaVoxel chunk with the identifier `id` and `content`
+/// (without any children) to `bytes`.
+fn push_chunk(bytes: &mut Vec<u8>, id: &[u8], content: &[u8]) {
+    bytes.extend_from_slice(id);
+    push_u32(bytes, content.len() as u32);
+    push_u32(bytes, 0);
+    bytes.extend_from_slice(content);
+}
+
+/// Returns a MagicaVoxel (`.vox`) file with a single model, where every
+/// point of `grid` that lies inside of the surface (according to the
+/// `distances`) is a solid voxel. MagicaVoxel's z-axis points up, so the
+/// grid is rotated to match. Fails if the grid has more points along any
+/// axis than a model can hold.
+pub fn generate_vox(grid: &SampleGrid, distances: &[f32]) -> Result<Vec<u8>, String> {
+    let resolution = grid.resolution;
+    if resolution.x > VOX_MAX_RESOLUTION
+        || resolution.y > VOX_MAX_RESOLUTION
+        || resolution.z > VOX_MAX_RESOLUTION
+    {
+        return Err(format!(
+            "models can't be larger than {0}x{0}x{0} voxels",
+            VOX_MAX_RESOLUTION
+        ));
+    }
+
+    let mut size = Vec::new();
+    push_u32(&mut size, resolution.x);
+    push_u32(&mut size, resolution.z);
+    push_u32(&mut size, resolution.y);
+
+    let mut voxels = Vec::new();
+    let mut count = 0;
+    for (index, &distance) in distances.iter().enumerate() {
+        if distance <= 0.0 {
+            let cell = grid.get_cell(index);
+            voxels.extend_from_slice(&[
+                cell.x as u8,
+                (resolution.z - 1 - cell.z) as u8,
+                cell.y as u8,
+                VOX_COLOR_INDEX,
+            ]);
+            count += 1;
+        }
+    }
+    let mut xyzi = Vec::with_capacity(4 + voxels.len());
+    push_u32(&mut xyzi, count);
+    xyzi.extend_from_slice(&voxels);
+
+    let mut children = Vec::new();
+    push_chunk(&mut children, b"SIZE", &size);
+    push_chunk(&mut children, b"XYZI", &xyzi);
+
+    let mut bytes = Vec::with_capacity(20 + children.len());
+    bytes.extend_from_slice(b"VOX ");
+    push_u32(&mut bytes, VOX_VERSION);
+    bytes.extend_from_slice(b"MAIN");
+    push_u32(&mut bytes, 0);
+    push_u32(&mut bytes, children.len() as u32);
+    bytes.extend_from_slice(&children);
+    Ok(bytes)
+}
+
+/// Writes the file `bytes` (a dense volume or a MagicaVoxel model) to `path`.
+pub fn save(bytes: &[u8], path: &Path) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)
+}
+
+#[test]
+fn test_generate() {
+    use cgmath::Vector3;
+
+    let grid = SampleGrid::new(
+        Vector3::new(-1.0, -1.0, -1.0),
+        Vector3::new(1.0, 1.0, 1.0),
+        Vector3::new(3, 3, 3),
+    );
+
+    // A single point inside of the surface, at the top of the grid
+    // (nearest to the viewer).
+    let mut distances = vec![1.0; grid.get_point_count()];
+    distances[1 + 3 * (2 + 3 * 2)] = -0.5;
+
+    let volume = generate_volume(&grid, &distances);
+    assert_eq!(&volume[0..4], b"SDFV");
+    assert_eq!(volume.len(), 44 + 27 * 4);
+    let mut inside = Vec::new();
+    push_f32(&mut inside, -0.5);
+    assert_eq!(&volume[44 + 25 * 4..44 + 26 * 4], &inside[..]);
+
+    let vox = generate_vox(&grid, &distances).unwrap();
+    assert_eq!(&vox[0..4], b"VOX ");
+    assert_eq!(&vox[8..12], b"MAIN");
+
+    // The model's only voxel is moved to the front (y = 0) and top (z = 2)
+    // of MagicaVoxel's coordinate system.
+    assert_eq!(&vox[vox.len() - 8..], &[1, 0, 0, 0, 1, 0, 2, VOX_COLOR_INDEX]);
+
+    let large = SampleGrid::new(grid.min, grid.max, Vector3::new(257, 1, 1));
+    assert!(generate_vox(&large, &vec![1.0; 257]).is_err());
+}
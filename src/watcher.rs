@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Returns the modification time of each of the files in `directory`.
+fn scan(directory: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut times = HashMap::new();
+    if let Ok(entries) = fs::read_dir(directory) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                times.insert(entry.path(), modified);
+            }
+        }
+    }
+    times
+}
+
+/// Watches the files in one or more directories for changes, by
+/// periodically checking their modification times on a background thread.
+pub struct Watcher {
+    /// The paths of the files that were added, modified or removed
+    changes: Receiver<PathBuf>,
+}
+
+impl Watcher {
+    /// Starts watching the files in each of `directories` (which don't
+    /// have to exist yet) every `interval`. Whenever any of them change,
+    /// `notify` is called from the background thread, i.e. to wake up the
+    /// events loop.
+    pub fn new<F>(directories: &[&Path], interval: Duration, notify: F) -> Watcher
+    where
+        F: Fn() + Send + 'static,
+    {
+        let (sender, changes) = mpsc::channel();
+        let directories: Vec<PathBuf> = directories.iter().map(|path| path.to_path_buf()).collect();
+        let scan_all = move || {
+            let mut times = HashMap::new();
+            for directory in directories.iter() {
+                times.extend(scan(directory));
+            }
+            times
+        };
+
+        thread::spawn(move || {
+            let mut times = scan_all();
+            loop {
+                thread::sleep(interval);
+
+                let current = scan_all();
+                let mut changed: Vec<PathBuf> = current
+                    .iter()
+                    .filter(|&(path, time)| times.get(path) != Some(time))
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                changed.extend(times.keys().filter(|path| !current.contains_key(*path)).cloned());
+                times = current;
+
+                if changed.is_empty() {
+                    continue;
+                }
+                for path in changed {
+                    // Stop watching once the watcher has been dropped.
+                    if sender.send(path).is_err() {
+                        return;
+                    }
+                }
+                notify();
+            }
+        });
+
+        Watcher { changes }
+    }
+
+    /// Returns the paths of the files that have changed since the last
+    /// call, without any duplicates.
+    pub fn get_changes(&self) -> Vec<PathBuf> {
+        let mut changes = Vec::new();
+        for path in self.changes.try_iter() {
+            if !changes.contains(&path) {
+                changes.push(path);
+            }
+        }
+        changes
+    }
+}
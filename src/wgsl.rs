@@ -0,0 +1,81 @@
+use constants;
+use spirv::{self, Environment};
+
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+/// The code that comes before a shader that follows Shadertoy's conventions,
+/// which declares Shadertoy's inputs. WebGPU applications fill these in
+/// through a uniform buffer (with the `std140` layout) at group 0, binding 0,
+/// where `iMouse` is at offset 0, `iResolution` at 16, `iTime` at 28, and
+/// `iFrame` at 32.
+static HEADER: &str = "#version 450
+
+layout (set = 0, binding = 0, std140) uniform shadertoy_inputs
+{
+    vec4 iMouse;
+    vec3 iResolution;
+    float iTime;
+    int iFrame;
+};
+
+";
+
+/// The code that comes after a shader that follows Shadertoy's conventions,
+/// which calls its `mainImage(..)` function for every pixel.
+static FOOTER: &str = "
+layout (location = 0) out vec4 out_color;
+
+void main()
+{
+    // Unlike in Shadertoy, the origin of `gl_FragCoord` is the upper left
+    // corner of the window.
+    mainImage(out_color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+}
+";
+
+/// Returns the Shadertoy shader `fs_src` as a standalone (Vulkan-flavored)
+/// fragment shader.
+pub fn wrap(fs_src: &str) -> String {
+    format!("{}{}{}", HEADER, fs_src, FOOTER)
+}
+
+/// Translates the Shadertoy shader `fs_src` into WGSL and writes it to the
+/// file at `path`. The shader is compiled to SPIR-V first, which naga then
+/// translates, since its SPIR-V front end supports more of GLSL than its
+/// GLSL front end does.
+pub fn translate(fs_src: &str, path: &Path) -> Result<(), String> {
+    // naga picks the format of each file based on its extension.
+    let module = env::temp_dir().join("sdfperf_export.spv");
+    spirv::compile(&wrap(fs_src), &module, Environment::Vulkan)?;
+
+    let output = Command::new(constants::WGSL_TRANSLATOR)
+        .arg(&module)
+        .arg(path)
+        .output();
+    let _ = fs::remove_file(&module);
+
+    let output = output.map_err(|error| match error.kind() {
+        ErrorKind::NotFound => format!(
+            "{} wasn't found, so make sure that naga-cli is installed",
+            constants::WGSL_TRANSLATOR
+        ),
+        _ => format!("failed to run {}: {}", constants::WGSL_TRANSLATOR, error),
+    })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[test]
+fn test_wrap() {
+    let wrapped = wrap("void mainImage(out vec4 fragColor, in vec2 fragCoord) {}\n");
+    assert!(wrapped.starts_with("#version 450\n"));
+    assert!(wrapped.contains("uniform shadertoy_inputs"));
+    assert!(wrapped.trim_end().ends_with('}'));
+}
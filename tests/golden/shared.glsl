@@ -0,0 +1,14 @@
+    // op: root_0
+    vec3 p_root_0 = p;
+    float s_root_0 = 1.0;
+    // op: sphere_1
+    float sphere_1 = sdf_sphere(p_root_0, vec3(0.0), 1.0) * s_root_0;
+    float id_sphere_1 = 1.0;
+    // op: smooth_minimum_3
+    float smooth_minimum_3 = op_smooth_min(sphere_1, sphere_1, params[3].x);
+    float id_smooth_minimum_3 = sphere_1 < sphere_1 ? id_sphere_1 : id_sphere_1;
+    // op: render_4
+    float render_4 = smooth_minimum_3;
+    float id_render_4 = id_smooth_minimum_3;
+    cone_scale = params[4].x;
+    return vec2(id_render_4, render_4);
@@ -0,0 +1,11 @@
+    // op: root_0
+    vec3 p_root_0 = p;
+    float s_root_0 = 1.0;
+    // op: sphere_1
+    float sphere_1 = sdf_sphere(p_root_0, vec3(0.0), 1.0) * s_root_0;
+    float id_sphere_1 = 1.0;
+    // op: render_2
+    float render_2 = sphere_1;
+    float id_render_2 = id_sphere_1;
+    cone_scale = params[2].x;
+    return vec2(id_render_2, render_2);
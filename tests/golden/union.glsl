@@ -0,0 +1,21 @@
+    // op: root_0
+    vec3 p_root_0 = p;
+    float s_root_0 = 1.0;
+    // op: transform_1
+    float s_transform_1 = params[1].w * s_root_0;
+    vec3 t_transform_1 = params[1].xyz;
+    vec3 p_transform_1 = p_root_0 / s_transform_1 + t_transform_1;
+    // op: box_2
+    float box_2 = sdf_box(p_transform_1, vec3(1.0)) * s_transform_1;
+    float id_box_2 = 2.0;
+    // op: sphere_3
+    float sphere_3 = sdf_sphere(p_root_0, vec3(0.0), 1.0) * s_root_0;
+    float id_sphere_3 = 3.0;
+    // op: union_4
+    float union_4 = op_union(box_2, sphere_3);
+    float id_union_4 = box_2 < sphere_3 ? id_box_2 : id_sphere_3;
+    // op: render_5
+    float render_5 = union_4;
+    float id_render_5 = id_union_4;
+    cone_scale = params[5].x;
+    return vec2(id_render_5, render_5);